@@ -1,6 +1,7 @@
 use std::path::PathBuf;
 use std::str::FromStr;
 
+use chrono::{DateTime, Duration, Utc};
 use jsonwebtoken::{DecodingKey, Validation};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
@@ -9,6 +10,7 @@ use thiserror::Error;
 use url::Url;
 
 pub use crate::models::environment_ref::{self, *};
+use crate::models::floxmeta::CommitIdentity;
 use crate::providers::catalog;
 
 pub static FLOX_VERSION: Lazy<String> =
@@ -26,7 +28,7 @@ pub static FLOX_SENTRY_ENV: Lazy<Option<String>> =
 /// [Flox] will provide a preconfigured instance of the Nix API.
 /// By default this nix API uses the nix CLI.
 /// Preconfiguration includes environment variables and flox specific arguments.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Flox {
     /// The directory pointing to the users flox configuration
     ///
@@ -53,6 +55,9 @@ pub struct Flox {
     /// Checking for [None] can be used to check if the use is logged in.
     pub floxhub_token: Option<FloxhubToken>,
 
+    /// Author and signing key for generations of managed environments
+    pub commit_identity: CommitIdentity,
+
     pub catalog_client: Option<catalog::Client>,
 }
 
@@ -65,6 +70,8 @@ pub static DEFAULT_FLOXHUB_URL: Lazy<Url> =
 struct FloxTokenClaims {
     #[serde(rename = "https://flox.dev/handle")]
     handle: String,
+    /// Expiry of the token in seconds since the unix epoch
+    exp: i64,
 }
 
 #[derive(Debug, Clone, DeserializeFromStr)]
@@ -88,6 +95,18 @@ impl FloxhubToken {
     pub fn handle(&self) -> &str {
         &self.token_data.handle
     }
+
+    /// Return the point in time at which the token expires
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.token_data.exp, 0).unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+
+    /// Whether the token expires within the given duration from now
+    ///
+    /// Used to refresh tokens shortly before they expire.
+    pub fn expires_within(&self, duration: Duration) -> bool {
+        self.expires_at() - Utc::now() < duration
+    }
 }
 
 impl Serialize for FloxhubToken {
@@ -306,6 +325,7 @@ pub mod test_helpers {
             )
             .unwrap(),
             floxhub_token: None,
+            commit_identity: Default::default(),
             catalog_client: Some(MockClient.into()),
         };
 
@@ -364,6 +384,14 @@ pub mod tests {
         assert_eq!(token_error, FloxhubTokenError::Expired);
     }
 
+    #[test]
+    fn test_token_expiry() {
+        let token = FloxhubToken::new(FAKE_TOKEN.to_string()).unwrap();
+        assert_eq!(token.expires_at().timestamp(), 9999999999);
+        assert!(!token.expires_within(Duration::days(1)));
+        assert!(token.expires_within(Duration::days(365 * 300)));
+    }
+
     #[test]
     fn test_derive_git_url() {
         assert_eq!(
@@ -0,0 +1,389 @@
+//! Matching packages against a vulnerability database
+//!
+//! The database is a local copy of [OSV](https://ossf.github.io/osv-schema/) entries,
+//! e.g. an unpacked dump from <https://osv.dev>.
+//! Packages are matched by name and version,
+//! there is no OSV ecosystem for nix packages.
+//! Since the same name may refer to different software in different ecosystems,
+//! the database can be restricted to one ecosystem, e.g. a Linux distribution.
+
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use super::sbom::Sbom;
+use super::versions::compare_versions;
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("could not read vulnerability database '{0}'")]
+    Read(PathBuf, #[source] std::io::Error),
+    #[error("could not parse vulnerability database '{0}'")]
+    Parse(PathBuf, #[source] serde_json::Error),
+}
+
+/// A vulnerability affecting a package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Vulnerability {
+    pub id: String,
+    pub summary: Option<String>,
+    /// Other identifiers, e.g. CVE numbers
+    pub aliases: Vec<String>,
+    /// A severity rating or CVSS vector
+    pub severity: Option<String>,
+    /// The first version the vulnerability is fixed in
+    pub fixed: Option<String>,
+}
+
+impl Vulnerability {
+    pub fn url(&self) -> String {
+        format!("https://osv.dev/vulnerability/{}", self.id)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OsvEntry {
+    id: String,
+    summary: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    affected: Vec<OsvAffected>,
+    #[serde(default)]
+    severity: Vec<OsvSeverity>,
+    database_specific: Option<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OsvAffected {
+    package: Option<OsvPackage>,
+    #[serde(default)]
+    ranges: Vec<OsvRange>,
+    #[serde(default)]
+    versions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OsvPackage {
+    name: String,
+    ecosystem: Option<String>,
+}
+
+impl OsvPackage {
+    /// Whether the package belongs to `ecosystem`,
+    /// ignoring the release of versioned ecosystems, e.g. `Debian:12`
+    fn in_ecosystem(&self, ecosystem: &str) -> bool {
+        self.ecosystem.as_deref().is_some_and(|own| {
+            own.eq_ignore_ascii_case(ecosystem)
+                || own
+                    .split_once(':')
+                    .is_some_and(|(base, _)| base.eq_ignore_ascii_case(ecosystem))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OsvRange {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    events: Vec<OsvEvent>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OsvEvent {
+    introduced: Option<String>,
+    fixed: Option<String>,
+    last_affected: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OsvSeverity {
+    score: String,
+}
+
+impl OsvRange {
+    /// Whether `version` is within the range,
+    /// and the version it is fixed in if known
+    fn affects(&self, version: &str) -> Option<Option<&str>> {
+        // commit ranges can't be matched against versions
+        if self.kind == "GIT" {
+            return None;
+        }
+
+        let mut introduced = None;
+        for event in &self.events {
+            if let Some(start) = &event.introduced {
+                introduced = Some(start.as_str());
+            }
+            let Some(start) = introduced else {
+                continue;
+            };
+            let after_start = start == "0" || compare_versions(version, start) != Ordering::Less;
+
+            if let Some(fixed) = &event.fixed {
+                if after_start && compare_versions(version, fixed) == Ordering::Less {
+                    return Some(Some(fixed));
+                }
+                introduced = None;
+            } else if let Some(last) = &event.last_affected {
+                if after_start && compare_versions(version, last) != Ordering::Greater {
+                    return Some(None);
+                }
+                introduced = None;
+            }
+        }
+
+        // an introduced version without an end affects all later versions
+        match introduced {
+            Some(start) if start == "0" || compare_versions(version, start) != Ordering::Less => {
+                Some(None)
+            },
+            _ => None,
+        }
+    }
+}
+
+/// A set of OSV entries loaded from disk
+#[derive(Debug, Clone, Default)]
+pub struct VulnerabilityDatabase {
+    entries: Vec<OsvEntry>,
+}
+
+impl VulnerabilityDatabase {
+    /// Load OSV entries from a file or a directory of `.json` files
+    ///
+    /// A file may contain a single entry, a list of entries,
+    /// or an OSV API response with a `vulns` list.
+    pub fn load(path: &Path) -> Result<Self, AuditError> {
+        let mut entries = Vec::new();
+
+        if path.is_dir() {
+            let mut files = fs::read_dir(path)
+                .map_err(|e| AuditError::Read(path.to_path_buf(), e))?
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect::<Vec<_>>();
+            files.sort();
+            for file in files {
+                entries.extend(Self::read_file(&file)?);
+            }
+        } else {
+            entries = Self::read_file(path)?;
+        }
+
+        debug!(
+            "loaded {} vulnerabilities from {}",
+            entries.len(),
+            path.display()
+        );
+        Ok(VulnerabilityDatabase { entries })
+    }
+
+    fn read_file(path: &Path) -> Result<Vec<OsvEntry>, AuditError> {
+        let contents = fs::read(path).map_err(|e| AuditError::Read(path.to_path_buf(), e))?;
+        Self::parse(&contents).map_err(|e| AuditError::Parse(path.to_path_buf(), e))
+    }
+
+    fn parse(contents: &[u8]) -> Result<Vec<OsvEntry>, serde_json::Error> {
+        let value: Value = serde_json::from_slice(contents)?;
+        match value {
+            Value::Array(_) => serde_json::from_value(value),
+            Value::Object(ref object) if object.contains_key("vulns") => {
+                serde_json::from_value(value["vulns"].clone())
+            },
+            _ => Ok(vec![serde_json::from_value(value)?]),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Only match packages of the given OSV ecosystem, e.g. `Debian` or `PyPI`
+    ///
+    /// Entries that don't affect any package of the ecosystem are dropped.
+    pub fn retain_ecosystem(&mut self, ecosystem: &str) {
+        for entry in &mut self.entries {
+            entry.affected.retain(|affected| {
+                affected
+                    .package
+                    .as_ref()
+                    .is_some_and(|package| package.in_ecosystem(ecosystem))
+            });
+        }
+        self.entries.retain(|entry| !entry.affected.is_empty());
+    }
+
+    /// Find the vulnerabilities affecting version `version` of package `name`
+    pub fn matches(&self, name: &str, version: &str) -> Vec<Vulnerability> {
+        let mut vulnerabilities = Vec::new();
+
+        for entry in &self.entries {
+            let affected = entry.affected.iter().filter(|affected| {
+                affected
+                    .package
+                    .as_ref()
+                    .is_some_and(|package| package.name.eq_ignore_ascii_case(name))
+            });
+
+            let mut matched = None;
+            for affected in affected {
+                if affected.versions.iter().any(|v| v == version) {
+                    matched = Some(None);
+                    break;
+                }
+                if let Some(fixed) = affected
+                    .ranges
+                    .iter()
+                    .find_map(|range| range.affects(version))
+                {
+                    matched = Some(fixed);
+                    break;
+                }
+            }
+
+            let Some(fixed) = matched else {
+                continue;
+            };
+
+            let severity = entry
+                .database_specific
+                .as_ref()
+                .and_then(|specific| specific.get("severity"))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .or_else(|| {
+                    entry
+                        .severity
+                        .first()
+                        .map(|severity| severity.score.clone())
+                });
+
+            vulnerabilities.push(Vulnerability {
+                id: entry.id.clone(),
+                summary: entry.summary.clone(),
+                aliases: entry.aliases.clone(),
+                severity,
+                fixed: fixed.map(str::to_string),
+            });
+        }
+        vulnerabilities
+    }
+
+    /// Attach the vulnerabilities of each package to an [Sbom]
+    ///
+    /// Packages without a version can't be matched.
+    pub fn audit(&self, sbom: &mut Sbom) {
+        for package in &mut sbom.packages {
+            if let Some(version) = &package.version {
+                package.vulnerabilities = self.matches(&package.pname, version);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_osv_ranges_and_versions() {
+        let entries = serde_json::json!([
+            {
+                "id": "OSV-1",
+                "summary": "fixed in 1.2.3",
+                "affected": [{
+                    "package": { "ecosystem": "Debian", "name": "openssl" },
+                    "ranges": [{
+                        "type": "ECOSYSTEM",
+                        "events": [{ "introduced": "0" }, { "fixed": "1.2.3" }]
+                    }]
+                }],
+                "database_specific": { "severity": "HIGH" }
+            },
+            {
+                "id": "OSV-2",
+                "affected": [{
+                    "package": { "name": "OpenSSL" },
+                    "versions": ["1.2.3"]
+                }]
+            },
+            {
+                "id": "OSV-3",
+                "affected": [{
+                    "package": { "name": "openssl" },
+                    "ranges": [{
+                        "type": "SEMVER",
+                        "events": [{ "introduced": "2.0.0" }, { "last_affected": "2.1.0" }]
+                    }]
+                }]
+            }
+        ]);
+        let database = VulnerabilityDatabase {
+            entries: VulnerabilityDatabase::parse(entries.to_string().as_bytes()).unwrap(),
+        };
+
+        let ids = |version| {
+            database
+                .matches("openssl", version)
+                .into_iter()
+                .map(|v| v.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids("1.1.0"), vec!["OSV-1"]);
+        assert_eq!(ids("1.2.3"), vec!["OSV-2"]);
+        assert_eq!(ids("2.1.0"), vec!["OSV-3"]);
+        assert!(ids("2.1.1").is_empty());
+
+        let vulnerability = &database.matches("openssl", "1.0")[0];
+        assert_eq!(vulnerability.severity.as_deref(), Some("HIGH"));
+        assert_eq!(vulnerability.fixed.as_deref(), Some("1.2.3"));
+    }
+
+    #[test]
+    fn retain_ecosystem_skips_other_ecosystems() {
+        let entries = serde_json::json!([
+            {
+                "id": "DEBIAN-1",
+                "affected": [{
+                    "package": { "ecosystem": "Debian:12", "name": "requests" },
+                    "versions": ["2.31.0"]
+                }]
+            },
+            {
+                "id": "PYSEC-1",
+                "affected": [{
+                    "package": { "ecosystem": "PyPI", "name": "requests" },
+                    "versions": ["2.31.0"]
+                }]
+            }
+        ]);
+        let mut database = VulnerabilityDatabase {
+            entries: VulnerabilityDatabase::parse(entries.to_string().as_bytes()).unwrap(),
+        };
+        assert_eq!(database.matches("requests", "2.31.0").len(), 2);
+
+        database.retain_ecosystem("debian");
+        assert_eq!(database.len(), 1);
+        let ids = database
+            .matches("requests", "2.31.0")
+            .into_iter()
+            .map(|v| v.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["DEBIAN-1"]);
+
+        database.retain_ecosystem("npm");
+        assert!(database.is_empty());
+    }
+}
@@ -0,0 +1,617 @@
+//! Changes between two versions of a lockfile
+//!
+//! Used to report what `flox update` and `flox upgrade` changed,
+//! and to describe generations of managed environments.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::DateTime;
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+use super::lockfile::{
+    FlakeRef,
+    InstalledPackage,
+    LockedManifest,
+    LockedManifestError,
+    TypedLockedManifest,
+};
+use super::versions::compare_versions;
+use crate::data::System;
+
+/// How a package changed between two lockfiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PackageChangeKind {
+    Added,
+    Upgraded,
+    Downgraded,
+    /// The version changed but the versions can't be ordered
+    Changed,
+    /// The package is broken in the new lockfile but was not before,
+    /// and its version did not change
+    Broken,
+    Removed,
+}
+
+impl Display for PackageChangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            PackageChangeKind::Added => "added",
+            PackageChangeKind::Upgraded => "upgraded",
+            PackageChangeKind::Downgraded => "downgraded",
+            PackageChangeKind::Changed => "changed",
+            PackageChangeKind::Broken => "newly broken",
+            PackageChangeKind::Removed => "removed",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// A change of a package, for all systems it applies to
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PackageChange {
+    /// The install ID of the package
+    pub id: String,
+    pub kind: PackageChangeKind,
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+    /// The package is broken in the new lockfile but was not before,
+    /// also set if its version changed as well
+    pub newly_broken: bool,
+    pub systems: Vec<System>,
+}
+
+impl PackageChange {
+    /// The risk of applying the change
+    ///
+    /// Only version changes of packages that are neither added, removed
+    /// nor newly broken can be less than [UpgradeRisk::Major].
+    pub fn risk(&self) -> UpgradeRisk {
+        if self.newly_broken {
+            return UpgradeRisk::Major;
+        }
+        match (self.kind, &self.old_version, &self.new_version) {
+            (
+                PackageChangeKind::Upgraded
+                | PackageChangeKind::Downgraded
+                | PackageChangeKind::Changed,
+                Some(old),
+                Some(new),
+            ) => UpgradeRisk::between(old, new),
+            _ => UpgradeRisk::Major,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("unknown upgrade risk '{0}', expected 'patch', 'minor' or 'major'")]
+pub struct ParseUpgradeRiskError(String);
+
+/// The size of a version change according to semantic versioning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpgradeRisk {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl UpgradeRisk {
+    /// Compare the numeric components of two versions
+    ///
+    /// As with semver, a change of the minor version of a `0.x` release
+    /// is considered a major change.
+    /// Versions that don't start with a number are always a major change.
+    pub fn between(old: &str, new: &str) -> Self {
+        let components = |version: &str| {
+            version
+                .split(['.', '-', '+'])
+                .map(|component| {
+                    let digits = component
+                        .chars()
+                        .take_while(char::is_ascii_digit)
+                        .collect::<String>();
+                    digits.parse::<u64>().ok()
+                })
+                .collect::<Vec<_>>()
+        };
+        let old = components(old);
+        let new = components(new);
+
+        let (Some(Some(old_major)), Some(Some(new_major))) = (old.first(), new.first()) else {
+            return UpgradeRisk::Major;
+        };
+        if old_major != new_major {
+            return UpgradeRisk::Major;
+        }
+        if old.get(1) != new.get(1) {
+            return if *old_major == 0 {
+                UpgradeRisk::Major
+            } else {
+                UpgradeRisk::Minor
+            };
+        }
+        UpgradeRisk::Patch
+    }
+}
+
+impl FromStr for UpgradeRisk {
+    type Err = ParseUpgradeRiskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "patch" => Ok(UpgradeRisk::Patch),
+            "minor" => Ok(UpgradeRisk::Minor),
+            "major" => Ok(UpgradeRisk::Major),
+            _ => Err(ParseUpgradeRiskError(s.to_string())),
+        }
+    }
+}
+
+impl Display for UpgradeRisk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpgradeRisk::Patch => write!(f, "patch"),
+            UpgradeRisk::Minor => write!(f, "minor"),
+            UpgradeRisk::Major => write!(f, "major"),
+        }
+    }
+}
+
+/// A locked revision of an input
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InputRevision {
+    pub rev: Option<String>,
+    /// The date of the last commit of the revision (`YYYY-MM-DD`)
+    pub date: Option<String>,
+}
+
+impl InputRevision {
+    fn new(from: &FlakeRef) -> Self {
+        InputRevision {
+            rev: from.get("rev").and_then(Value::as_str).map(str::to_string),
+            date: from
+                .get("lastModified")
+                .and_then(Value::as_i64)
+                .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+                .map(|date| date.format("%Y-%m-%d").to_string()),
+        }
+    }
+}
+
+impl Display for InputRevision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rev = self.rev.as_deref().unwrap_or("unknown");
+        write!(f, "{}", &rev[..rev.len().min(7)])?;
+        if let Some(date) = &self.date {
+            write!(f, " ({date})")?;
+        }
+        Ok(())
+    }
+}
+
+/// A change of a registry input
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InputChange {
+    pub name: String,
+    /// `None` if the input was added
+    pub old: Option<InputRevision>,
+    /// `None` if the input was removed
+    pub new: Option<InputRevision>,
+}
+
+/// Package and input changes between two lockfiles
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Changelog {
+    pub packages: Vec<PackageChange>,
+    pub inputs: Vec<InputChange>,
+}
+
+impl Changelog {
+    /// Compare two lockfiles, `old` is `None` if the environment was not locked before
+    pub fn new(old: Option<&TypedLockedManifest>, new: &TypedLockedManifest) -> Self {
+        Changelog {
+            packages: Self::package_changes(old, new),
+            inputs: Self::input_changes(old, new),
+        }
+    }
+
+    /// Like [Changelog::new] for untyped lockfiles
+    pub fn from_lockfiles(
+        old: Option<&LockedManifest>,
+        new: &LockedManifest,
+    ) -> Result<Self, LockedManifestError> {
+        let old = old
+            .cloned()
+            .map(TypedLockedManifest::try_from)
+            .transpose()?;
+        let new = TypedLockedManifest::try_from(new.clone())?;
+        Ok(Self::new(old.as_ref(), &new))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty() && self.inputs.is_empty()
+    }
+
+    fn package_changes(
+        old: Option<&TypedLockedManifest>,
+        new: &TypedLockedManifest,
+    ) -> Vec<PackageChange> {
+        let packages_by_id = |lockfile: &TypedLockedManifest, system: &System| {
+            lockfile
+                .list_packages(system)
+                .into_iter()
+                .map(|package| (package.name.clone(), package))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let mut systems = new.systems().cloned().collect::<Vec<_>>();
+        if let Some(old) = old {
+            systems.extend(old.systems().cloned());
+        }
+        systems.sort();
+        systems.dedup();
+
+        // changes that apply to several systems are listed once
+        type ChangeKey = (
+            String,
+            PackageChangeKind,
+            Option<String>,
+            Option<String>,
+            bool,
+        );
+        let mut changes: BTreeMap<ChangeKey, Vec<System>> = BTreeMap::new();
+
+        for system in systems {
+            let old_packages = old
+                .map(|old| packages_by_id(old, &system))
+                .unwrap_or_default();
+            let new_packages = packages_by_id(new, &system);
+
+            let ids = old_packages.keys().chain(new_packages.keys());
+            for id in ids.collect::<std::collections::BTreeSet<_>>() {
+                let old_package = old_packages.get(id);
+                let new_package = new_packages.get(id);
+                let Some((kind, newly_broken)) = Self::change_kind(old_package, new_package) else {
+                    continue;
+                };
+                let version = |package: Option<&InstalledPackage>| {
+                    package.and_then(|package| package.info.version.clone())
+                };
+                changes
+                    .entry((
+                        id.clone(),
+                        kind,
+                        version(old_package),
+                        version(new_package),
+                        newly_broken,
+                    ))
+                    .or_default()
+                    .push(system.clone());
+            }
+        }
+
+        changes
+            .into_iter()
+            .map(
+                |((id, kind, old_version, new_version, newly_broken), systems)| PackageChange {
+                    id,
+                    kind,
+                    old_version,
+                    new_version,
+                    newly_broken,
+                    systems,
+                },
+            )
+            .collect()
+    }
+
+    /// How a package changed and whether it is newly broken
+    fn change_kind(
+        old: Option<&InstalledPackage>,
+        new: Option<&InstalledPackage>,
+    ) -> Option<(PackageChangeKind, bool)> {
+        let (old, new) = match (old, new) {
+            (None, None) => return None,
+            (None, Some(_)) => return Some((PackageChangeKind::Added, false)),
+            (Some(_), None) => return Some((PackageChangeKind::Removed, false)),
+            (Some(old), Some(new)) => (old, new),
+        };
+
+        let newly_broken = new.info.broken && !old.info.broken;
+
+        let kind = match (&old.info.version, &new.info.version) {
+            (old, new) if old == new => None,
+            (Some(old), Some(new)) => match compare_versions(new, old) {
+                Ordering::Greater => Some(PackageChangeKind::Upgraded),
+                Ordering::Less => Some(PackageChangeKind::Downgraded),
+                Ordering::Equal => Some(PackageChangeKind::Changed),
+            },
+            _ => Some(PackageChangeKind::Changed),
+        };
+
+        match kind {
+            Some(kind) => Some((kind, newly_broken)),
+            None if newly_broken => Some((PackageChangeKind::Broken, true)),
+            None => None,
+        }
+    }
+
+    fn input_changes(
+        old: Option<&TypedLockedManifest>,
+        new: &TypedLockedManifest,
+    ) -> Vec<InputChange> {
+        let old_inputs = old.map(|old| &old.registry().inputs);
+        let new_inputs = &new.registry().inputs;
+
+        let mut changes = Vec::new();
+        for (name, input) in new_inputs {
+            let old_input = old_inputs.and_then(|inputs| inputs.get(name));
+            if old_input.is_some_and(|old_input| old_input.rev() == input.rev()) {
+                continue;
+            }
+            changes.push(InputChange {
+                name: name.clone(),
+                old: old_input.map(|old_input| InputRevision::new(&old_input.from)),
+                new: Some(InputRevision::new(&input.from)),
+            });
+        }
+        for (name, input) in old_inputs.into_iter().flatten() {
+            if !new_inputs.contains_key(name) {
+                changes.push(InputChange {
+                    name: name.clone(),
+                    old: Some(InputRevision::new(&input.from)),
+                    new: None,
+                });
+            }
+        }
+        changes
+    }
+}
+
+/// Render the changes as tables of packages and inputs
+///
+/// ```text
+/// PACKAGE  OLD     NEW     CHANGE    SYSTEMS
+/// hello    2.12.1  2.12.2  upgraded  aarch64-darwin, x86_64-linux
+///
+/// INPUT    OLD                     NEW
+/// nixpkgs  ab5fd15 (2024-01-03)    9a9dae8 (2024-02-10)
+/// ```
+impl Display for Changelog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let version = |version: &Option<String>| version.clone().unwrap_or("-".to_string());
+
+        if !self.packages.is_empty() {
+            let rows = self.packages.iter().map(|change| {
+                vec![
+                    change.id.clone(),
+                    version(&change.old_version),
+                    version(&change.new_version),
+                    if change.newly_broken && change.kind != PackageChangeKind::Broken {
+                        format!("{}, newly broken", change.kind)
+                    } else {
+                        change.kind.to_string()
+                    },
+                    change.systems.join(", "),
+                ]
+            });
+            write_table(f, &["PACKAGE", "OLD", "NEW", "CHANGE", "SYSTEMS"], rows)?;
+        }
+
+        if !self.packages.is_empty() && !self.inputs.is_empty() {
+            writeln!(f)?;
+        }
+
+        if !self.inputs.is_empty() {
+            let revision = |revision: &Option<InputRevision>| {
+                revision
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or("-".to_string())
+            };
+            let rows = self.inputs.iter().map(|change| {
+                vec![
+                    change.name.clone(),
+                    revision(&change.old),
+                    revision(&change.new),
+                ]
+            });
+            write_table(f, &["INPUT", "OLD", "NEW"], rows)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_table(
+    f: &mut std::fmt::Formatter<'_>,
+    header: &[&str],
+    rows: impl Iterator<Item = Vec<String>>,
+) -> std::fmt::Result {
+    let rows = std::iter::once(header.iter().map(|column| column.to_string()).collect())
+        .chain(rows)
+        .collect::<Vec<Vec<String>>>();
+
+    let mut widths = vec![0; header.len()];
+    for row in &rows {
+        for (width, column) in widths.iter_mut().zip(row) {
+            *width = (*width).max(column.chars().count());
+        }
+    }
+
+    for (index, row) in rows.iter().enumerate() {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(column, width)| format!("{column:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        if index > 0 {
+            writeln!(f)?;
+        }
+        write!(f, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn lockfile(packages: Value, rev: &str, last_modified: i64) -> TypedLockedManifest {
+        serde_json::from_value(json!({
+            "lockfile-version": 0,
+            "packages": packages,
+            "registry": {
+                "inputs": {
+                    "nixpkgs": {
+                        "from": {
+                            "lastModified": last_modified,
+                            "owner": "NixOS",
+                            "repo": "nixpkgs",
+                            "rev": rev,
+                            "type": "github"
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    fn package(pname: &str, version: &str, broken: bool) -> Value {
+        json!({
+            "info": {
+                "description": null,
+                "broken": broken,
+                "license": null,
+                "pname": pname,
+                "unfree": false,
+                "version": version
+            },
+            "attr-path": ["legacyPackages", "x86_64-linux", pname],
+            "priority": 5
+        })
+    }
+
+    #[test]
+    fn changelog_lists_package_and_input_changes() {
+        let old = lockfile(
+            json!({
+                "aarch64-darwin": { "hello": package("hello", "2.12.1", false) },
+                "x86_64-linux": {
+                    "hello": package("hello", "2.12.1", false),
+                    "curl": package("curl", "8.4.0", false),
+                    "jq": package("jq", "1.7", false)
+                }
+            }),
+            "ab5fd150146dcfe41fda501134e6503932cc8dfd",
+            1704300003,
+        );
+        let new = lockfile(
+            json!({
+                "aarch64-darwin": { "hello": package("hello", "2.12.2", false) },
+                "x86_64-linux": {
+                    "hello": package("hello", "2.12.2", false),
+                    "curl": package("curl", "8.4.0", true)
+                }
+            }),
+            "9a9dae8f6319600fa9aebde37f340975cab4b8c0",
+            1707523200,
+        );
+
+        let changelog = Changelog::new(Some(&old), &new);
+        assert_eq!(changelog.packages, vec![
+            PackageChange {
+                id: "curl".to_string(),
+                kind: PackageChangeKind::Broken,
+                old_version: Some("8.4.0".to_string()),
+                new_version: Some("8.4.0".to_string()),
+                newly_broken: true,
+                systems: vec!["x86_64-linux".to_string()],
+            },
+            PackageChange {
+                id: "hello".to_string(),
+                kind: PackageChangeKind::Upgraded,
+                old_version: Some("2.12.1".to_string()),
+                new_version: Some("2.12.2".to_string()),
+                newly_broken: false,
+                systems: vec!["aarch64-darwin".to_string(), "x86_64-linux".to_string()],
+            },
+            PackageChange {
+                id: "jq".to_string(),
+                kind: PackageChangeKind::Removed,
+                old_version: Some("1.7".to_string()),
+                new_version: None,
+                newly_broken: false,
+                systems: vec!["x86_64-linux".to_string()],
+            },
+        ]);
+
+        assert_eq!(changelog.inputs.len(), 1);
+        assert_eq!(
+            changelog.inputs[0].new.as_ref().unwrap().to_string(),
+            "9a9dae8 (2024-02-10)"
+        );
+
+        let table = changelog.to_string();
+        assert!(
+            table.contains("hello    2.12.1  2.12.2  upgraded      aarch64-darwin, x86_64-linux")
+        );
+        assert!(table.contains("nixpkgs  ab5fd15 (2024-01-03)  9a9dae8 (2024-02-10)"));
+    }
+
+    #[test]
+    fn broken_upgrade_keeps_versions() {
+        let old = lockfile(
+            json!({ "x86_64-linux": { "curl": package("curl", "8.4.0", false) } }),
+            "ab5fd150146dcfe41fda501134e6503932cc8dfd",
+            0,
+        );
+        let new = lockfile(
+            json!({ "x86_64-linux": { "curl": package("curl", "8.4.1", true) } }),
+            "ab5fd150146dcfe41fda501134e6503932cc8dfd",
+            0,
+        );
+
+        let changelog = Changelog::new(Some(&old), &new);
+        let change = &changelog.packages[0];
+        assert_eq!(change.kind, PackageChangeKind::Upgraded);
+        assert_eq!(change.old_version.as_deref(), Some("8.4.0"));
+        assert_eq!(change.new_version.as_deref(), Some("8.4.1"));
+        assert!(change.newly_broken);
+        // a patch release that breaks the package is not a safe upgrade
+        assert_eq!(change.risk(), UpgradeRisk::Major);
+        assert!(changelog
+            .to_string()
+            .contains("curl     8.4.0  8.4.1  upgraded, newly broken"));
+    }
+
+    #[test]
+    fn upgrade_risk_follows_semver() {
+        assert_eq!(UpgradeRisk::between("2.12.1", "2.12.2"), UpgradeRisk::Patch);
+        assert_eq!(UpgradeRisk::between("2.12", "2.12-r1"), UpgradeRisk::Patch);
+        assert_eq!(UpgradeRisk::between("2.12.1", "2.13.0"), UpgradeRisk::Minor);
+        assert_eq!(UpgradeRisk::between("0.4.1", "0.5.0"), UpgradeRisk::Major);
+        assert_eq!(UpgradeRisk::between("1.9", "2.0"), UpgradeRisk::Major);
+        assert_eq!(
+            UpgradeRisk::between("unstable-2024-01-01", "unstable-2024-02-01"),
+            UpgradeRisk::Major
+        );
+        assert!(UpgradeRisk::Patch < UpgradeRisk::Minor);
+    }
+
+    #[test]
+    fn unchanged_lockfiles_have_empty_changelog() {
+        let packages = json!({ "x86_64-linux": { "hello": package("hello", "2.12.1", false) } });
+        let lockfile = lockfile(packages, "ab5fd150146dcfe41fda501134e6503932cc8dfd", 0);
+        assert!(Changelog::new(Some(&lockfile), &lockfile).is_empty());
+    }
+}
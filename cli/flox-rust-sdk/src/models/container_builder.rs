@@ -1,7 +1,10 @@
-use std::io::{self, Write};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::process::{ChildStdout, Command, Stdio};
 
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 use thiserror::Error;
 
 /// Type representing a container builder script,
@@ -27,18 +30,204 @@ impl ContainerBuilder {
     /// Run the container builder script
     /// and write the container tarball to the given sink
     pub fn stream_container(&self, mut sink: impl Write) -> Result<(), ContainerBuilderError> {
+        let mut stdout = self.spawn()?;
+
+        io::copy(&mut stdout, &mut sink).map_err(ContainerBuilderError::StreamContainer)?;
+
+        Ok(())
+    }
+
+    /// Run the container builder script
+    /// and write the container tarball with `file` added to the image
+    /// to the given sink
+    ///
+    /// See [embed_file] for how the image is modified.
+    pub fn stream_container_with_file(
+        &self,
+        sink: impl Write,
+        file: &EmbeddedFile,
+    ) -> Result<(), ContainerBuilderError> {
+        let stdout = self.spawn()?;
+        embed_file(stdout, sink, file)
+    }
+
+    fn spawn(&self) -> Result<ChildStdout, ContainerBuilderError> {
         let mut container_builder_command = Command::new(&self.path);
         container_builder_command.stdout(Stdio::piped());
 
         let handle = container_builder_command
             .spawn()
             .map_err(ContainerBuilderError::CallContainerBuilder)?;
-        let mut stdout = handle.stdout.expect("stdout set to piped");
+        Ok(handle.stdout.expect("stdout set to piped"))
+    }
+}
 
-        io::copy(&mut stdout, &mut sink).map_err(ContainerBuilderError::StreamContainer)?;
+/// A file that is added to a container image in a layer of its own
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddedFile {
+    /// The absolute path of the file in the image
+    pub path: String,
+    pub contents: Vec<u8>,
+    /// Labels to add to the image configuration
+    pub labels: BTreeMap<String, String>,
+}
 
-        Ok(())
+/// Copy a container tarball in `docker save` format from `source` to `sink`,
+/// adding a layer that contains `file` and the labels of `file`.
+///
+/// Layers are streamed as they are read,
+/// only the image configuration and `manifest.json` are held in memory
+/// and written to the end of the archive with the new layer
+/// once the archive has been read.
+pub fn embed_file(
+    source: impl Read,
+    sink: impl Write,
+    file: &EmbeddedFile,
+) -> Result<(), ContainerBuilderError> {
+    let mut archive = tar::Archive::new(source);
+    let mut builder = tar::Builder::new(sink);
+    let mut metadata: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+
+    for entry in archive
+        .entries()
+        .map_err(ContainerBuilderError::StreamContainer)?
+    {
+        let mut entry = entry.map_err(ContainerBuilderError::StreamContainer)?;
+        let path = entry
+            .path()
+            .map_err(ContainerBuilderError::StreamContainer)?
+            .to_string_lossy()
+            .to_string();
+        let is_metadata =
+            path == "manifest.json" || (!path.contains('/') && path.ends_with(".json"));
+
+        if is_metadata {
+            let mut data = Vec::new();
+            entry
+                .read_to_end(&mut data)
+                .map_err(ContainerBuilderError::StreamContainer)?;
+            metadata.insert(path, data);
+            continue;
+        }
+
+        let mut header = entry.header().clone();
+        let link_name = entry
+            .link_name()
+            .map_err(ContainerBuilderError::StreamContainer)?
+            .map(|link_name| link_name.into_owned());
+        match link_name {
+            Some(link_name) => builder.append_link(&mut header, &path, link_name),
+            None => builder.append_data(&mut header, &path, &mut entry),
+        }
+        .map_err(ContainerBuilderError::StreamContainer)?;
+    }
+    // drain the trailing padding of the archive
+    io::copy(&mut archive.into_inner(), &mut io::sink())
+        .map_err(ContainerBuilderError::StreamContainer)?;
+
+    let manifest = metadata
+        .remove("manifest.json")
+        .ok_or_else(|| ContainerBuilderError::InvalidImage("missing manifest.json".to_string()))?;
+    let mut manifest: Value =
+        serde_json::from_slice(&manifest).map_err(ContainerBuilderError::ParseImageMetadata)?;
+    let config_path = manifest[0]["Config"]
+        .as_str()
+        .ok_or_else(|| ContainerBuilderError::InvalidImage("missing image config".to_string()))?
+        .to_string();
+    let config = metadata.remove(&config_path).ok_or_else(|| {
+        ContainerBuilderError::InvalidImage(format!("missing image config '{config_path}'"))
+    })?;
+    let mut config: Value =
+        serde_json::from_slice(&config).map_err(ContainerBuilderError::ParseImageMetadata)?;
+
+    let layer = layer_tar(file)?;
+    let layer_digest = format!("{:x}", Sha256::digest(&layer));
+    let layer_path = format!("{layer_digest}/layer.tar");
+
+    let Some(diff_ids) = config["rootfs"]["diff_ids"].as_array_mut() else {
+        return Err(ContainerBuilderError::InvalidImage(
+            "missing layers in image config".to_string(),
+        ));
+    };
+    diff_ids.push(json!(format!("sha256:{layer_digest}")));
+    if let Some(history) = config["history"].as_array_mut() {
+        history.push(json!({ "created_by": "flox containerize", "comment": file.path }));
+    }
+    if !config["config"].is_object() {
+        config["config"] = json!({});
+    }
+    if !config["config"]["Labels"].is_object() {
+        config["config"]["Labels"] = json!({});
     }
+    for (key, value) in &file.labels {
+        config["config"]["Labels"][key] = json!(value);
+    }
+
+    let config = serde_json::to_vec(&config).map_err(ContainerBuilderError::ParseImageMetadata)?;
+    let config_path = format!("{:x}.json", Sha256::digest(&config));
+    manifest[0]["Config"] = json!(config_path);
+    match manifest[0]["Layers"].as_array_mut() {
+        Some(layers) => layers.push(json!(layer_path)),
+        None => manifest[0]["Layers"] = json!([layer_path]),
+    }
+    let manifest =
+        serde_json::to_vec(&manifest).map_err(ContainerBuilderError::ParseImageMetadata)?;
+
+    append_directory(&mut builder, &layer_digest)
+        .and_then(|_| append_file(&mut builder, &layer_path, &layer))
+        .and_then(|_| {
+            metadata
+                .iter()
+                .try_for_each(|(path, data)| append_file(&mut builder, path, data))
+        })
+        .and_then(|_| append_file(&mut builder, &config_path, &config))
+        .and_then(|_| append_file(&mut builder, "manifest.json", &manifest))
+        .and_then(|_| builder.into_inner())
+        .and_then(|mut sink| sink.flush())
+        .map_err(ContainerBuilderError::StreamContainer)
+}
+
+/// A layer tarball containing `file` and its parent directories
+fn layer_tar(file: &EmbeddedFile) -> Result<Vec<u8>, ContainerBuilderError> {
+    let path = file.path.trim_start_matches('/');
+    if path.is_empty() {
+        return Err(ContainerBuilderError::InvalidImage(format!(
+            "can not add file '{}' to the image",
+            file.path
+        )));
+    }
+
+    let mut builder = tar::Builder::new(Vec::new());
+    let components = path.split('/').collect::<Vec<_>>();
+    for depth in 1..components.len() {
+        append_directory(&mut builder, &components[..depth].join("/"))
+            .map_err(ContainerBuilderError::StreamContainer)?;
+    }
+    append_file(&mut builder, path, &file.contents)
+        .map_err(ContainerBuilderError::StreamContainer)?;
+    builder
+        .into_inner()
+        .map_err(ContainerBuilderError::StreamContainer)
+}
+
+/// Append a directory owned by root to `builder`
+fn append_directory<W: Write>(builder: &mut tar::Builder<W>, path: &str) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Directory);
+    header.set_mode(0o755);
+    header.set_size(0);
+    builder.append_data(&mut header, path, io::empty())
+}
+
+/// Append a regular file owned by root to `builder`
+///
+/// Paths that don't fit into a tar header are written as GNU long names.
+fn append_file<W: Write>(builder: &mut tar::Builder<W>, path: &str, data: &[u8]) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_mode(0o644);
+    header.set_size(data.len() as u64);
+    builder.append_data(&mut header, path, data)
 }
 
 #[derive(Debug, Error)]
@@ -47,6 +236,10 @@ pub enum ContainerBuilderError {
     CallContainerBuilder(#[source] std::io::Error),
     #[error("failed to stream container to sink")]
     StreamContainer(#[source] std::io::Error),
+    #[error("container builder produced an invalid image: {0}")]
+    InvalidImage(String),
+    #[error("failed to parse image metadata")]
+    ParseImageMetadata(#[source] serde_json::Error),
 }
 
 #[cfg(test)]
@@ -140,4 +333,92 @@ mod tests {
         let output = fs::read_to_string(&output_path).unwrap();
         assert_eq!(output, "hello world\n");
     }
+
+    /// Read the entries of a tar archive into a map of paths to contents
+    fn read_tar(archive: &[u8]) -> BTreeMap<String, Vec<u8>> {
+        let mut archive = tar::Archive::new(archive);
+        archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let path = entry.path().unwrap().to_string_lossy().to_string();
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data).unwrap();
+                (path, data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn embed_file_adds_layer_and_labels() {
+        let config = json!({
+            "rootfs": { "type": "layers", "diff_ids": ["sha256:0000"] },
+            "history": [{ "comment": "store paths" }],
+            "config": { "Env": ["PATH=/bin"] },
+        });
+        let manifest = json!([{
+            "Config": "0000.json",
+            "RepoTags": ["project:latest"],
+            "Layers": ["0000/layer.tar"],
+        }]);
+        let long_path = format!("0000/{}/store-path", "nix".repeat(40));
+        let mut image = tar::Builder::new(Vec::new());
+        append_file(&mut image, "0000/layer.tar", b"store layer").unwrap();
+        append_file(&mut image, &long_path, b"long name").unwrap();
+        append_file(&mut image, "0000.json", config.to_string().as_bytes()).unwrap();
+        append_file(&mut image, "manifest.json", manifest.to_string().as_bytes()).unwrap();
+        let mut image = image.into_inner().unwrap();
+        // `docker save` pads archives beyond the two terminating blocks
+        image.extend_from_slice(&[0; 20 * 512]);
+
+        let file = EmbeddedFile {
+            path: "/flox/sbom.spdx.json".to_string(),
+            contents: b"{}".to_vec(),
+            labels: BTreeMap::from([(
+                "dev.flox.sbom".to_string(),
+                "/flox/sbom.spdx.json".to_string(),
+            )]),
+        };
+        let mut output = Vec::new();
+        embed_file(image.as_slice(), &mut output, &file).unwrap();
+
+        let entries = read_tar(&output);
+        assert_eq!(entries["0000/layer.tar"], b"store layer");
+        assert_eq!(entries[&long_path], b"long name");
+
+        let manifest: Value = serde_json::from_slice(&entries["manifest.json"]).unwrap();
+        let layers = manifest[0]["Layers"].as_array().unwrap();
+        assert_eq!(layers.len(), 2);
+
+        let config: Value =
+            serde_json::from_slice(&entries[manifest[0]["Config"].as_str().unwrap()]).unwrap();
+        assert_eq!(
+            config["config"]["Labels"]["dev.flox.sbom"],
+            "/flox/sbom.spdx.json"
+        );
+        assert_eq!(config["config"]["Env"], json!(["PATH=/bin"]));
+        assert_eq!(config["history"].as_array().unwrap().len(), 2);
+
+        let layer = &entries[layers[1].as_str().unwrap()];
+        assert_eq!(
+            config["rootfs"]["diff_ids"][1],
+            format!("sha256:{:x}", Sha256::digest(layer))
+        );
+        let layer_entries = read_tar(layer);
+        assert_eq!(layer_entries["flox/sbom.spdx.json"], b"{}");
+        assert!(layer_entries.contains_key("flox"));
+    }
+
+    #[test]
+    fn layer_tar_supports_long_paths() {
+        let path = format!("/{}/sbom.spdx.json", "nested".repeat(20));
+        let file = EmbeddedFile {
+            path: path.clone(),
+            contents: b"{}".to_vec(),
+            labels: BTreeMap::new(),
+        };
+        let entries = read_tar(&layer_tar(&file).unwrap());
+        assert_eq!(entries[path.trim_start_matches('/')], b"{}");
+    }
 }
@@ -77,6 +77,16 @@ impl EnvRegistry {
         Ok(entry.path.clone())
     }
 
+    /// Returns the entries whose latest environment matches `name`,
+    /// see [EnvironmentPointer::matches_name].
+    pub fn entries_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a RegistryEntry> {
+        self.entries.iter().filter(move |entry| {
+            entry
+                .latest_env()
+                .is_some_and(|env| env.pointer.matches_name(name))
+        })
+    }
+
     /// Registers the environment, creating a new [RegistryEntry] if necessary and returning the
     /// [RegisteredEnv] that was created. If the environment was already it returns `Ok(None)`.
     fn register_env(
@@ -478,5 +488,22 @@ mod test {
             // Empty entries should be removed
             prop_assert!(reg.entry_for_hash(&hash).is_none());
         }
+
+        #[test]
+        fn finds_entries_by_name(reg: EnvRegistry) {
+            for entry in reg.entries.iter() {
+                let Some(latest) = entry.latest_env() else {
+                    continue;
+                };
+                let name = latest.pointer.name().to_string();
+                prop_assert!(reg.entries_named(&name).any(|e| e == entry));
+                if let Some(owner) = latest.pointer.owner() {
+                    let qualified = format!("{owner}/{name}");
+                    prop_assert!(reg.entries_named(&qualified).any(|e| e == entry));
+                    let other_owner = format!("not-{owner}/{name}");
+                    prop_assert!(reg.entries_named(&other_owner).all(|e| e != entry));
+                }
+            }
+        }
     }
 }
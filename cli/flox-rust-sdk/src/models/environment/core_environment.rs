@@ -18,7 +18,7 @@ use super::{
 use crate::flox::Flox;
 use crate::models::container_builder::ContainerBuilder;
 use crate::models::environment::{call_pkgdb, global_manifest_path, CanonicalPath};
-use crate::models::lockfile::{LockedManifest, LockedManifestError};
+use crate::models::lockfile::{LockedManifest, LockedManifestError, LockfileManifest};
 use crate::models::manifest::{
     insert_packages,
     remove_packages,
@@ -88,6 +88,7 @@ impl<State> CoreEnvironment<State> {
     /// and because it doesn't modify the manifest.
     ///
     /// todo: should we always write the lockfile to disk?
+    #[tracing::instrument(name = "lock", level = "debug", skip_all)]
     pub fn lock(&mut self, flox: &Flox) -> Result<LockedManifest, CoreEnvironmentError> {
         let manifest_path = self.manifest_path();
         let environment_lockfile_path = self.lockfile_path();
@@ -141,6 +142,7 @@ impl<State> CoreEnvironment<State> {
     ///
     /// todo: should we always write the lockfile to disk?
     #[must_use = "don't discard the store path of built environments"]
+    #[tracing::instrument(name = "build", level = "debug", skip_all)]
     pub fn build(&mut self, flox: &Flox) -> Result<PathBuf, CoreEnvironmentError> {
         let lockfile = self.lock(flox)?;
 
@@ -212,6 +214,7 @@ impl<State> CoreEnvironment<State> {
     ///
     /// Builds the environment if necessary.
     /// TODO: should we always build implicitly?
+    #[tracing::instrument(name = "link", level = "debug", skip_all)]
     pub fn link(
         &mut self,
         flox: &Flox,
@@ -400,17 +403,72 @@ impl CoreEnvironment<ReadOnly> {
         &mut self,
         flox: &Flox,
         groups_or_iids: &[String],
+    ) -> Result<UpgradeResult, CoreEnvironmentError> {
+        let result = self.upgrade_dry_run(flox, groups_or_iids)?;
+        self.apply_upgrade(flox, result)
+    }
+
+    /// Apply an upgrade computed by [Self::upgrade_dry_run]
+    /// without locking the packages again
+    pub fn apply_upgrade(
+        &mut self,
+        flox: &Flox,
+        mut result: UpgradeResult,
+    ) -> Result<UpgradeResult, CoreEnvironmentError> {
+        let store_path = self.transact_with_lockfile_contents(
+            serde_json::to_string(&result.new_lockfile).unwrap(),
+            flox,
+        )?;
+        result.store_path = Some(store_path);
+
+        Ok(result)
+    }
+
+    /// Lock the upgraded packages without modifying the environment
+    ///
+    /// Held packages and their pkg-groups are not upgraded,
+    /// see [LockfileManifest::exclude_held].
+    /// The returned [UpgradeResult] has no store path.
+    pub fn upgrade_dry_run(
+        &self,
+        flox: &Flox,
+        groups_or_iids: &[String],
     ) -> Result<UpgradeResult, CoreEnvironmentError> {
         // TODO double check canonicalization
         let manifest_path = self.manifest_path();
         let lockfile_path = self.lockfile_path();
         let maybe_lockfile = if lockfile_path.exists() {
             debug!("found existing lockfile: {}", lockfile_path.display());
-            Some(lockfile_path)
+            Some(CanonicalPath::new(lockfile_path).map_err(CoreEnvironmentError::BadLockfilePath)?)
         } else {
             debug!("no existing lockfile found");
             None
         };
+        let old_lockfile = maybe_lockfile
+            .as_ref()
+            .map(LockedManifest::read_from_file)
+            .transpose()
+            .map_err(CoreEnvironmentError::LockedManifest)?;
+
+        let manifest = LockfileManifest::from_toml(&self.manifest_content()?)
+            .map_err(CoreEnvironmentError::LockedManifest)?;
+        let (groups_or_iids, held) = manifest.exclude_held(groups_or_iids);
+
+        // pkgdb upgrades everything if no groups are given,
+        // so stop here if everything selected is held
+        if groups_or_iids.is_empty() && !held.is_empty() {
+            if let Some(old_lockfile) = old_lockfile {
+                debug!("all selected packages are held, not upgrading");
+                return Ok(UpgradeResult {
+                    packages: Vec::new(),
+                    held,
+                    new_lockfile: old_lockfile.clone(),
+                    old_lockfile: Some(old_lockfile),
+                    store_path: None,
+                });
+            }
+        }
+
         let mut pkgdb_cmd = Command::new(Path::new(&*PKGDB_BIN));
         pkgdb_cmd
             .args(["manifest", "upgrade"])
@@ -419,12 +477,10 @@ impl CoreEnvironment<ReadOnly> {
             .arg(global_manifest_path(flox))
             .arg("--manifest")
             .arg(manifest_path);
-        if let Some(lf_path) = maybe_lockfile {
-            let canonical_lockfile_path =
-                CanonicalPath::new(lf_path).map_err(CoreEnvironmentError::BadLockfilePath)?;
+        if let Some(canonical_lockfile_path) = maybe_lockfile {
             pkgdb_cmd.arg("--lockfile").arg(canonical_lockfile_path);
         }
-        pkgdb_cmd.args(groups_or_iids);
+        pkgdb_cmd.args(&groups_or_iids);
 
         debug!(
             "upgrading environment with command: {}",
@@ -435,11 +491,16 @@ impl CoreEnvironment<ReadOnly> {
         )
         .map_err(CoreEnvironmentError::ParseUpgradeOutput)?;
 
-        let store_path = self.transact_with_lockfile_contents(json.lockfile.to_string(), flox)?;
+        let new_lockfile = serde_json::from_value(json.lockfile).map_err(|e| {
+            CoreEnvironmentError::LockedManifest(LockedManifestError::ParseLockfile(e))
+        })?;
 
         Ok(UpgradeResult {
             packages: json.result.0,
-            store_path: Some(store_path),
+            held,
+            new_lockfile,
+            old_lockfile,
+            store_path: None,
         })
     }
 
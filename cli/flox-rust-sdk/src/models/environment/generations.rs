@@ -31,10 +31,11 @@ use super::{copy_dir_recursive, ENV_DIR_NAME};
 use crate::data::Version;
 use crate::flox::EnvironmentName;
 use crate::models::environment::MANIFEST_FILENAME;
+use crate::models::floxmeta::{PLACEHOLDER_COMMIT_EMAIL, PLACEHOLDER_COMMIT_NAME};
 use crate::providers::git::{
+    AnyGitProvider,
     GitCommandError,
     GitCommandOptions,
-    GitCommandProvider,
     GitProvider,
     GitRemoteCommandError,
 };
@@ -75,7 +76,7 @@ pub struct Generations<State = ReadOnly> {
     ///   If not bare, updating it using `git push` may fail to update checked out branches.
     /// - When [ReadWrite], this is required to not be bare.
     ///   This is enforced when created using [Self::writable].
-    repo: GitCommandProvider,
+    repo: AnyGitProvider,
 
     /// The name of the branch containing the generations
     /// Used to pick the correct branch when showing files, cloning, and pushing.
@@ -121,14 +122,14 @@ impl<S> Generations<S> {
         self.manifest(*current_gen)
     }
 
-    pub(super) fn git(&self) -> &GitCommandProvider {
+    pub(super) fn git(&self) -> &AnyGitProvider {
         &self.repo
     }
 }
 
 impl Generations<ReadOnly> {
     /// Create a new generations instance
-    pub fn new(repo: GitCommandProvider, branch: String) -> Self {
+    pub fn new(repo: AnyGitProvider, branch: String) -> Self {
         Self {
             repo,
             branch,
@@ -147,7 +148,7 @@ impl Generations<ReadOnly> {
         branch: String,
         name: &EnvironmentName,
     ) -> Result<Self, GenerationsError> {
-        let repo = GitCommandProvider::init_with(options.clone(), &checkedout_tempdir, false)
+        let repo = AnyGitProvider::init_with(options.clone(), &checkedout_tempdir, false)
             .map_err(GenerationsError::InitRepo)?;
         repo.checkout(&branch, true)
             .map_err(GenerationsError::CreateBranch)?;
@@ -163,7 +164,7 @@ impl Generations<ReadOnly> {
         ))
         .map_err(GenerationsError::CommitChanges)?;
 
-        let bare = GitCommandProvider::clone_branch_with(
+        let bare = AnyGitProvider::clone_branch_with(
             options,
             checkedout_tempdir.as_ref(),
             bare_tempdir,
@@ -250,7 +251,10 @@ impl Generations<ReadWrite> {
         description: String,
         set_current: bool,
     ) -> Result<(), GenerationsError> {
-        let mut generation_metadata = SingleGenerationMetadata::new(description.clone());
+        let mut generation_metadata = SingleGenerationMetadata::new(
+            description.clone(),
+            commit_author(self.repo.get_options()),
+        );
 
         let mut metadata = self.metadata()?;
 
@@ -404,21 +408,20 @@ pub enum GenerationsError {
 
 /// Realize the generations branch into a temporary directory
 fn checkout_to_tempdir(
-    repo: &GitCommandProvider,
+    repo: &AnyGitProvider,
     branch: &str,
     tempdir: PathBuf,
-) -> Result<GitCommandProvider, GenerationsError> {
+) -> Result<AnyGitProvider, GenerationsError> {
     let git_options = repo.get_options().clone();
-    let repo =
-        GitCommandProvider::clone_branch_with(git_options, repo.path(), tempdir, branch, false)
-            .map_err(GenerationsError::CloneToFS)?;
+    let repo = AnyGitProvider::clone_branch_with(git_options, repo.path(), tempdir, branch, false)
+        .map_err(GenerationsError::CloneToFS)?;
 
     Ok(repo)
 }
 
 /// Reads the generations metadata file directly from the repository
 fn read_metadata(
-    repo: &GitCommandProvider,
+    repo: &AnyGitProvider,
     ref_name: &str,
 ) -> Result<AllGenerationsMetadata, GenerationsError> {
     let metadata = {
@@ -481,19 +484,42 @@ pub struct SingleGenerationMetadata {
 
     /// log message(s) describing the change from the previous generation
     pub description: String,
+
+    /// name and email (`Name <email>`) of the user who created this generation
+    /// `None` for generations created before authors were recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
     // todo: do we still need to track this?
     //       do we now?
     // /// store path of the built generation
     // path: PathBuf,
 }
 
+/// The author of new generations as configured for commits to the repository
+///
+/// The placeholder identity git commits with if the user didn't configure one
+/// is not recorded.
+fn commit_author(options: &GitCommandOptions) -> Option<String> {
+    let name = options
+        .get_config_flag("user.name")
+        .filter(|name| *name != PLACEHOLDER_COMMIT_NAME)?;
+    match options
+        .get_config_flag("user.email")
+        .filter(|email| *email != PLACEHOLDER_COMMIT_EMAIL)
+    {
+        Some(email) => Some(format!("{name} <{email}>")),
+        None => Some(name.to_string()),
+    }
+}
+
 impl SingleGenerationMetadata {
     /// Create a new generation metadata instance
-    pub fn new(description: String) -> Self {
+    pub fn new(description: String, author: Option<String>) -> Self {
         Self {
             created: Utc::now(),
             last_active: None,
             description,
+            author,
         }
     }
 }
@@ -520,4 +546,25 @@ pub struct GenerationId(usize);
 mod tests {
     // todo: tests for this will be easier with the `init` method implemented
     // in https://github.com/flox/flox/pull/563
+
+    use super::*;
+
+    #[test]
+    fn placeholder_identity_is_not_recorded_as_author() {
+        let mut options = GitCommandOptions::default();
+        assert_eq!(commit_author(&options), None);
+
+        options.add_config_flag("user.name", PLACEHOLDER_COMMIT_NAME);
+        options.add_config_flag("user.email", PLACEHOLDER_COMMIT_EMAIL);
+        assert_eq!(commit_author(&options), None);
+
+        options.add_config_flag("user.name", "alice");
+        assert_eq!(commit_author(&options), Some("alice".to_string()));
+
+        options.add_config_flag("user.email", "alice@example.com");
+        assert_eq!(
+            commit_author(&options),
+            Some("alice <alice@example.com>".to_string())
+        );
+    }
 }
@@ -4,6 +4,7 @@ use std::{fs, io};
 use log::debug;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use url::Url;
 
 use super::core_environment::CoreEnvironment;
 use super::generations::{Generations, GenerationsError};
@@ -28,6 +29,7 @@ use super::{
 };
 use crate::data::Version;
 use crate::flox::{EnvironmentRef, Flox};
+use crate::models::changelog::Changelog;
 use crate::models::container_builder::ContainerBuilder;
 use crate::models::env_registry::{
     deregister,
@@ -38,9 +40,15 @@ use crate::models::env_registry::{
     EnvRegistryError,
 };
 use crate::models::environment_ref::{EnvironmentName, EnvironmentOwner};
-use crate::models::floxmeta::{floxmeta_git_options, FloxMeta, FloxMetaError};
+use crate::models::floxmeta::{floxmeta_remote, FloxMeta, FloxMetaError};
 use crate::models::lockfile::LockedManifest;
-use crate::models::manifest::PackageToInstall;
+use crate::models::manifest::{
+    merge_manifests,
+    ConflictResolution,
+    ManifestMergeError,
+    MergeConflict,
+    PackageToInstall,
+};
 use crate::models::pkgdb::UpgradeResult;
 use crate::providers::git::{
     GitCommandBranchHashError,
@@ -151,6 +159,9 @@ pub enum ManagedEnvironmentError {
 
     #[error("failed to locate project in environment registry")]
     Registry(#[from] EnvRegistryError),
+
+    #[error("could not merge upstream changes")]
+    Merge(#[source] ManifestMergeError),
 }
 
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
@@ -314,8 +325,11 @@ impl Environment for ManagedEnvironment {
 
         let result = temporary.update(flox, inputs)?;
 
-        // TODO: better message
-        let metadata = "updated environment".to_string();
+        let metadata = describe_changes(
+            "updated environment".to_string(),
+            result.old_lockfile.as_ref(),
+            &result.new_lockfile,
+        );
 
         generations
             .add_generation(&mut temporary, metadata)
@@ -327,10 +341,10 @@ impl Environment for ManagedEnvironment {
     }
 
     /// Atomically upgrade packages in this environment
-    fn upgrade(
+    fn apply_upgrade(
         &mut self,
         flox: &Flox,
-        groups_or_iids: &[String],
+        upgrade: UpgradeResult,
     ) -> Result<UpgradeResult, EnvironmentError> {
         let mut generations = self
             .generations()
@@ -341,9 +355,13 @@ impl Environment for ManagedEnvironment {
             .get_current_generation()
             .map_err(ManagedEnvironmentError::CreateGenerationFiles)?;
 
-        let result = temporary.upgrade(flox, groups_or_iids)?;
+        let result = temporary.apply_upgrade(flox, upgrade)?;
 
-        let metadata = format!("upgraded packages: {}", result.packages.join(", "));
+        let metadata = describe_changes(
+            format!("upgraded packages: {}", result.packages.join(", ")),
+            result.old_lockfile.as_ref(),
+            &result.new_lockfile,
+        );
 
         generations
             .add_generation(&mut temporary, metadata)
@@ -358,6 +376,15 @@ impl Environment for ManagedEnvironment {
         Ok(result)
     }
 
+    fn upgrade_dry_run(
+        &mut self,
+        flox: &Flox,
+        groups_or_iids: &[String],
+    ) -> Result<UpgradeResult, EnvironmentError> {
+        let current = self.get_current_generation(flox)?;
+        Ok(current.upgrade_dry_run(flox, groups_or_iids)?)
+    }
+
     /// Extract the current content of the manifest
     fn manifest_content(&self, _flox: &Flox) -> Result<String, EnvironmentError> {
         let manifest = self
@@ -443,7 +470,11 @@ impl Environment for ManagedEnvironment {
                 .map_err(|e| ManagedEnvironmentError::DeleteEnvironmentLink(out_link_path, e))?;
         }
 
-        deregister(flox, &self.path, &EnvironmentPointer::Managed(self.pointer))?;
+        deregister(
+            flox,
+            &self.path,
+            &EnvironmentPointer::Managed(Box::new(self.pointer)),
+        )?;
 
         Ok(())
     }
@@ -527,10 +558,14 @@ impl ManagedEnvironment {
             },
             Err(FloxMetaError::CloneBranch(GitRemoteCommandError::RefNotFound(_)))
             | Err(FloxMetaError::FetchBranch(GitRemoteCommandError::RefNotFound(_))) => {
+                let host = match &pointer.remote_url {
+                    Some(remote_url) => remote_url.to_string(),
+                    None => flox.floxhub.base_url().to_string(),
+                };
                 return Err(ManagedEnvironmentError::UpstreamNotFound(
                     pointer.into(),
-                    flox.floxhub.base_url().to_string(),
-                ))
+                    host,
+                ));
             },
             Err(e) => Err(ManagedEnvironmentError::OpenFloxmeta(e))?,
         };
@@ -564,7 +599,7 @@ impl ManagedEnvironment {
         ensure_registered(
             flox,
             &dot_flox_path,
-            &EnvironmentPointer::Managed(pointer.clone()),
+            &EnvironmentPointer::Managed(Box::new(pointer.clone())),
         )?;
 
         Ok(ManagedEnvironment {
@@ -924,6 +959,25 @@ pub fn remote_branch_name(pointer: &ManagedPointer) -> String {
     format!("{}", pointer.name)
 }
 
+/// Append a [Changelog] of the lockfile to the description of a generation
+///
+/// Lockfiles that can't be compared don't prevent creating the generation,
+/// the summary is used as is in that case.
+fn describe_changes(
+    summary: String,
+    old_lockfile: Option<&LockedManifest>,
+    new_lockfile: &LockedManifest,
+) -> String {
+    match Changelog::from_lockfiles(old_lockfile, new_lockfile) {
+        Ok(changelog) if !changelog.is_empty() => format!("{summary}\n\n{changelog}"),
+        Ok(_) => summary,
+        Err(e) => {
+            debug!("could not create changelog: {e}");
+            summary
+        },
+    }
+}
+
 pub enum PullResult {
     /// The environment was already up to date
     UpToDate,
@@ -931,14 +985,27 @@ pub enum PullResult {
     Updated,
 }
 
+pub enum MergeResult {
+    /// The environment already contained all upstream changes
+    UpToDate,
+    /// The environment had no local changes and was fast-forwarded to upstream
+    FastForward,
+    /// Local changes were merged on top of the latest upstream version
+    Merged,
+}
+
 impl ManagedEnvironment {
     /// If access to a remote repository requires authentication,
     /// the FloxHub token must be set in the flox instance.
     /// The caller is responsible for ensuring that the token is present and valid.
+    ///
+    /// If `remote_url` is set, the environment is pushed to that git repository
+    /// instead of FloxHub, using the user's git configuration for authentication.
     pub fn push_new(
         flox: &Flox,
         path_environment: PathEnvironment,
         owner: EnvironmentOwner,
+        remote_url: Option<Url>,
         force: bool,
     ) -> Result<Self, ManagedEnvironmentError> {
         // path of the original .flox directory
@@ -952,7 +1019,15 @@ impl ManagedEnvironment {
             .build(flox)
             .map_err(ManagedEnvironmentError::Build)?;
 
-        Self::push_new_without_building(flox, owner, name, force, dot_flox_path, core_environment)
+        Self::push_new_without_building(
+            flox,
+            owner,
+            name,
+            remote_url,
+            force,
+            dot_flox_path,
+            core_environment,
+        )
     }
 
     /// Push an environment and open the resulting [ManagedEnvironment],
@@ -964,21 +1039,20 @@ impl ManagedEnvironment {
         flox: &Flox,
         owner: EnvironmentOwner,
         name: EnvironmentName,
+        remote_url: Option<Url>,
         force: bool,
         dot_flox_path: CanonicalPath,
         mut core_environment: CoreEnvironment,
     ) -> Result<Self, ManagedEnvironmentError> {
-        let pointer = ManagedPointer::new(owner, name.clone(), &flox.floxhub);
+        let pointer =
+            ManagedPointer::new(owner, name.clone(), &flox.floxhub).with_remote_url(remote_url);
 
         let checkedout_floxmeta_path = tempfile::tempdir_in(&flox.temp_dir).unwrap().into_path();
         let temp_floxmeta_path = tempfile::tempdir_in(&flox.temp_dir).unwrap().into_path();
 
         // Caller decides whether to set token
-        let token = flox.floxhub_token.as_ref();
-
-        let git_url = flox.floxhub.git_url();
-
-        let options = floxmeta_git_options(git_url, &pointer.owner, token);
+        let (options, upstream_url) =
+            floxmeta_remote(flox, &pointer).map_err(ManagedEnvironmentError::OpenFloxmeta)?;
 
         let generations = Generations::init(
             options,
@@ -1000,10 +1074,7 @@ impl ManagedEnvironment {
             .map_err(ManagedEnvironmentError::CommitGeneration)?;
 
         temp_floxmeta_git
-            .add_remote(
-                "upstream",
-                &format!("{}/{}/floxmeta", &git_url, &pointer.owner),
-            )
+            .add_remote("upstream", &upstream_url)
             .unwrap();
 
         match temp_floxmeta_git.push_ref("upstream", "HEAD", force) {
@@ -1138,6 +1209,134 @@ impl ManagedEnvironment {
     }
 }
 
+impl ManagedEnvironment {
+    /// Merge upstream changes into an environment that diverged from upstream
+    ///
+    /// The manifests of the local and upstream generations are merged
+    /// with [merge_manifests], using the latest generation both share as the base.
+    /// Conflicting changes are passed to `resolve`.
+    ///
+    /// The local branch is then reset to upstream and the merged manifest
+    /// is added as a new generation on top of it,
+    /// so that a subsequent [Self::push] is a fast-forward.
+    /// If the merged environment fails to build, the local branch is restored.
+    pub fn merge_upstream(
+        &mut self,
+        flox: &Flox,
+        resolve: impl FnMut(&MergeConflict) -> Option<ConflictResolution>,
+    ) -> Result<MergeResult, EnvironmentError> {
+        let sync_branch = remote_branch_name(&self.pointer);
+        let project_branch = branch_name(&self.pointer, &self.path);
+
+        self.floxmeta
+            .git
+            .fetch_ref("dynamicorigin", &format!("+{sync_branch}:{sync_branch}"))
+            .map_err(ManagedEnvironmentError::FetchUpdates)?;
+
+        let git = &self.floxmeta.git;
+        if git
+            .branch_contains_commit(&sync_branch, &project_branch)
+            .map_err(ManagedEnvironmentError::Git)?
+        {
+            return Ok(MergeResult::UpToDate);
+        }
+        if git
+            .branch_contains_commit(&project_branch, &sync_branch)
+            .map_err(ManagedEnvironmentError::Git)?
+        {
+            self.pull(false)?;
+            return Ok(MergeResult::FastForward);
+        }
+
+        let local = self.generations();
+        let upstream = Generations::new(git.clone(), sync_branch.clone());
+        let local_metadata = local
+            .metadata()
+            .map_err(ManagedEnvironmentError::ReadManifest)?;
+        let upstream_metadata = upstream
+            .metadata()
+            .map_err(ManagedEnvironmentError::ReadManifest)?;
+
+        // Generations are append only,
+        // so the latest generation with the same metadata on both sides
+        // is the state both sides started from.
+        let base_generation = local_metadata
+            .generations
+            .iter()
+            .filter(|(id, local)| {
+                upstream_metadata
+                    .generations
+                    .get(id)
+                    .is_some_and(|upstream| {
+                        upstream.created == local.created
+                            && upstream.description == local.description
+                    })
+            })
+            .map(|(id, _)| **id)
+            .max();
+        let base = match base_generation {
+            Some(generation) => local
+                .manifest(generation)
+                .map_err(ManagedEnvironmentError::ReadManifest)?,
+            None => String::new(),
+        };
+        let ours = local
+            .current_gen_manifest()
+            .map_err(ManagedEnvironmentError::ReadManifest)?;
+        let theirs = upstream
+            .current_gen_manifest()
+            .map_err(ManagedEnvironmentError::ReadManifest)?;
+
+        let merged = merge_manifests(&base, &ours, &theirs, resolve)
+            .map_err(ManagedEnvironmentError::Merge)?;
+
+        let local_rev = git
+            .branch_hash(&project_branch)
+            .map_err(ManagedEnvironmentError::GitBranchHash)?;
+        git.reset_branch(&project_branch, &sync_branch)
+            .map_err(ManagedEnvironmentError::Git)?;
+
+        if let Err(err) = self.add_merged_generation(flox, merged) {
+            debug!("merged environment could not be created, restoring local branch");
+            self.floxmeta
+                .git
+                .reset_branch(&project_branch, &local_rev)
+                .map_err(ManagedEnvironmentError::Git)?;
+            return Err(err);
+        }
+        self.lock_pointer()?;
+
+        Ok(MergeResult::Merged)
+    }
+
+    /// Add a generation with the merged manifest, ensuring that it builds
+    fn add_merged_generation(
+        &mut self,
+        flox: &Flox,
+        merged: String,
+    ) -> Result<(), EnvironmentError> {
+        let mut generations = self
+            .generations()
+            .writable(flox.temp_dir.clone())
+            .map_err(ManagedEnvironmentError::CreateFloxmetaDir)?;
+        let mut temporary = generations
+            .get_current_generation()
+            .map_err(ManagedEnvironmentError::CreateGenerationFiles)?;
+
+        let result = temporary.edit(flox, merged)?;
+        if result == EditResult::Unchanged {
+            return Ok(());
+        }
+
+        generations
+            .add_generation(&mut temporary, "merged local changes".to_string())
+            .map_err(ManagedEnvironmentError::CommitGeneration)?;
+        temporary.link(flox, &self.out_link, &result.store_path())?;
+
+        Ok(())
+    }
+}
+
 pub mod test_helpers {
 
     use tempfile::tempdir_in;
@@ -1183,6 +1382,7 @@ pub mod test_helpers {
             flox,
             owner,
             "name".parse().unwrap(),
+            None,
             false,
             CanonicalPath::new(tempdir_in(&flox.temp_dir).unwrap().into_path()).unwrap(),
             new_core_environment(flox, contents),
@@ -1197,7 +1397,6 @@ mod test {
     use std::time::Duration;
 
     use fslock::LockFile;
-    use url::Url;
 
     use super::*;
     use crate::flox::test_helpers::flox_instance;
@@ -1209,8 +1408,9 @@ mod test {
         RegisteredEnv,
         RegistryEntry,
     };
+    use crate::models::environment::core_environment::test_helpers::new_core_environment;
     use crate::models::environment::DOT_FLOX;
-    use crate::models::floxmeta::floxmeta_dir;
+    use crate::models::floxmeta::{floxmeta_dir, CommitIdentity, REMOTE_FLOXMETA_DIR_NAME};
     use crate::providers::git::tests::commit_file;
     use crate::providers::git::{GitCommandProvider, GitProvider};
 
@@ -1222,6 +1422,7 @@ mod test {
             floxhub_git_url_override: Some(
                 Url::from_directory_path(mock_floxhub_git_path).unwrap(),
             ),
+            remote_url: None,
             version: Version::<1> {},
         }
     }
@@ -1827,6 +2028,7 @@ mod test {
             name: EnvironmentName::from_str("name").unwrap(),
             floxhub_url: Url::from_str("https://hub.flox.dev").unwrap(),
             floxhub_git_url_override: None,
+            remote_url: None,
             version: Version::<1>,
         };
         let reg = EnvRegistry {
@@ -1836,7 +2038,7 @@ mod test {
                 path: path.to_path_buf(),
                 envs: vec![RegisteredEnv {
                     created_at: 0,
-                    pointer: EnvironmentPointer::Managed(pointer.clone()),
+                    pointer: EnvironmentPointer::Managed(Box::new(pointer.clone())),
                 }],
             }],
         };
@@ -1927,4 +2129,68 @@ mod test {
         let reg = read_environment_registry(&reg_path).unwrap().unwrap();
         assert!(reg.entries.is_empty());
     }
+
+    /// Environments can be pushed to a plain git repository instead of FloxHub
+    /// and are cloned apart from FloxHub environments
+    #[test]
+    fn push_new_to_remote_url() {
+        let (flox, _temp_dir_handle) = flox_instance();
+
+        let remote_path = flox.temp_dir.join("remote.git");
+        fs::create_dir_all(&remote_path).unwrap();
+        let remote = GitCommandProvider::init(&remote_path, true).unwrap();
+        let remote_url = Url::from_directory_path(&remote_path).unwrap();
+
+        let env = ManagedEnvironment::push_new_without_building(
+            &flox,
+            "owner".parse().unwrap(),
+            "name".parse().unwrap(),
+            Some(remote_url.clone()),
+            false,
+            CanonicalPath::new(tempfile::tempdir_in(&flox.temp_dir).unwrap().into_path()).unwrap(),
+            new_core_environment(&flox, ""),
+        )
+        .unwrap();
+
+        assert_eq!(env.pointer().remote_url, Some(remote_url));
+        assert!(remote.has_branch("name").unwrap());
+        assert!(env
+            .floxmeta
+            .git
+            .path()
+            .starts_with(flox.data_dir.join(REMOTE_FLOXMETA_DIR_NAME)));
+    }
+
+    /// Generations record the configured commit identity as their author
+    #[test]
+    fn generations_record_author() {
+        let (mut flox, _temp_dir_handle) = flox_instance();
+        flox.commit_identity = CommitIdentity {
+            name: Some("Jane Doe".to_string()),
+            email: Some("jane@example.com".to_string()),
+            signing: None,
+        };
+
+        let remote_path = flox.temp_dir.join("remote.git");
+        fs::create_dir_all(&remote_path).unwrap();
+        GitCommandProvider::init(&remote_path, true).unwrap();
+
+        let env = ManagedEnvironment::push_new_without_building(
+            &flox,
+            "owner".parse().unwrap(),
+            "name".parse().unwrap(),
+            Some(Url::from_directory_path(&remote_path).unwrap()),
+            false,
+            CanonicalPath::new(tempfile::tempdir_in(&flox.temp_dir).unwrap().into_path()).unwrap(),
+            new_core_environment(&flox, ""),
+        )
+        .unwrap();
+
+        let metadata = env.generations().metadata().unwrap();
+        let generation = metadata.generations.values().next().unwrap();
+        assert_eq!(
+            generation.author.as_deref(),
+            Some("Jane Doe <jane@example.com>")
+        );
+    }
 }
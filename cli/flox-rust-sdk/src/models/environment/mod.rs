@@ -21,8 +21,8 @@ use crate::data::Version;
 use crate::flox::{Flox, Floxhub};
 use crate::models::pkgdb::call_pkgdb;
 use crate::providers::git::{
+    AnyGitProvider,
     GitCommandDiscoverError,
-    GitCommandProvider,
     GitDiscoverError,
     GitProvider,
 };
@@ -172,6 +172,27 @@ pub trait Environment: Send {
         &mut self,
         flox: &Flox,
         groups_or_iids: &[String],
+    ) -> Result<UpgradeResult, EnvironmentError> {
+        let upgrade = self.upgrade_dry_run(flox, groups_or_iids)?;
+        self.apply_upgrade(flox, upgrade)
+    }
+
+    /// Atomically apply upgrades computed by [Environment::upgrade_dry_run]
+    ///
+    /// The packages are not locked again,
+    /// so exactly the changes that were computed are applied.
+    fn apply_upgrade(
+        &mut self,
+        flox: &Flox,
+        upgrade: UpgradeResult,
+    ) -> Result<UpgradeResult, EnvironmentError>;
+
+    /// Compute the upgrades [Environment::upgrade] would apply,
+    /// without modifying the environment
+    fn upgrade_dry_run(
+        &mut self,
+        flox: &Flox,
+        groups_or_iids: &[String],
     ) -> Result<UpgradeResult, EnvironmentError>;
 
     /// Extract the current content of the manifest
@@ -242,7 +263,7 @@ pub trait Environment: Send {
 #[cfg_attr(test, derive(proptest_derive::Arbitrary))]
 pub enum EnvironmentPointer {
     /// Identifies an environment whose source of truth lies outside of the project itself
-    Managed(ManagedPointer),
+    Managed(Box<ManagedPointer>),
     /// Identifies an environment whose source of truth lies inside the project
     Path(PathPointer),
 }
@@ -281,6 +302,13 @@ pub struct ManagedPointer {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[cfg_attr(test, proptest(value = "None"))]
     pub floxhub_git_url_override: Option<Url>,
+    /// URL of a plain git repository hosting the environment instead of FloxHub
+    ///
+    /// If set, the environment is pushed to and pulled from this repository
+    /// rather than `{floxhub_git_url}/{owner}/floxmeta`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, proptest(value = "None"))]
+    pub remote_url: Option<Url>,
     version: Version<1>,
 }
 
@@ -292,9 +320,23 @@ impl ManagedPointer {
             owner,
             floxhub_url: floxhub.base_url().clone(),
             floxhub_git_url_override: floxhub.git_url_override().cloned(),
+            remote_url: None,
             version: Version::<1>,
         }
     }
+
+    /// Host the environment in the git repository at `remote_url`
+    /// instead of FloxHub
+    pub fn with_remote_url(mut self, remote_url: Option<Url>) -> Self {
+        self.remote_url = remote_url;
+        self
+    }
+}
+
+impl From<ManagedPointer> for EnvironmentPointer {
+    fn from(pointer: ManagedPointer) -> Self {
+        EnvironmentPointer::Managed(Box::new(pointer))
+    }
 }
 
 impl EnvironmentPointer {
@@ -346,6 +388,17 @@ impl EnvironmentPointer {
             EnvironmentPointer::Path(_) => None,
         }
     }
+
+    /// Whether the environment is called `name`,
+    /// or `<owner>/<name>` for managed environments
+    pub fn matches_name(&self, name: &str) -> bool {
+        match name.split_once('/') {
+            Some((owner, name)) => {
+                self.owner().is_some_and(|o| o.as_str() == owner) && self.name().as_ref() == name
+            },
+            None => self.name().as_ref() == name,
+        }
+    }
 }
 
 /// Represents a `.flox` directory that contains an `env.json`.
@@ -578,7 +631,7 @@ pub fn find_dot_flox(initial_dir: &Path) -> Result<Option<DotFlox>, EnvironmentE
     }
 
     // Check if we're in a git repo.
-    let toplevel = match GitCommandProvider::discover(&path) {
+    let toplevel = match AnyGitProvider::discover(&path) {
         Ok(repo) if repo.workdir().is_some() => repo.workdir().unwrap().to_owned(),
         Ok(_) => return Ok(None),
         // Assume we're not in a git repo.
@@ -632,7 +685,7 @@ mod test {
 
     use super::*;
     use crate::flox::DEFAULT_FLOXHUB_URL;
-    use crate::providers::git::GitProvider;
+    use crate::providers::git::{GitCommandProvider, GitProvider};
 
     const MANAGED_ENV_JSON: &'_ str = r#"{
         "name": "name",
@@ -647,24 +700,26 @@ mod test {
     }"#;
 
     static MANAGED_ENV_POINTER: Lazy<EnvironmentPointer> = Lazy::new(|| {
-        EnvironmentPointer::Managed(ManagedPointer {
+        EnvironmentPointer::Managed(Box::new(ManagedPointer {
             name: EnvironmentName::from_str("name").unwrap(),
             owner: EnvironmentOwner::from_str("owner").unwrap(),
             floxhub_url: DEFAULT_FLOXHUB_URL.clone(),
             floxhub_git_url_override: None,
+            remote_url: None,
             version: Version::<1> {},
-        })
+        }))
     });
 
     #[test]
     fn serializes_managed_environment_pointer() {
-        let managed_pointer = EnvironmentPointer::Managed(ManagedPointer {
+        let managed_pointer = EnvironmentPointer::Managed(Box::new(ManagedPointer {
             name: EnvironmentName::from_str("name").unwrap(),
             owner: EnvironmentOwner::from_str("owner").unwrap(),
             floxhub_url: DEFAULT_FLOXHUB_URL.clone(),
             floxhub_git_url_override: None,
+            remote_url: None,
             version: Version::<1> {},
-        });
+        }));
 
         let json = serde_json::to_string(&managed_pointer).unwrap();
         // Convert both to `serde_json::Value` to test equality without worrying about whitespace
@@ -264,18 +264,27 @@ impl Environment for PathEnvironment {
     }
 
     /// Atomically upgrade packages in this environment
-    fn upgrade(
+    fn apply_upgrade(
         &mut self,
         flox: &Flox,
-        groups_or_iids: &[String],
+        upgrade: UpgradeResult,
     ) -> Result<UpgradeResult, EnvironmentError> {
         let mut env_view = CoreEnvironment::new(self.path.join(ENV_DIR_NAME));
-        let result = env_view.upgrade(flox, groups_or_iids)?;
+        let result = env_view.apply_upgrade(flox, upgrade)?;
         env_view.link(flox, self.out_link(&flox.system)?, &result.store_path)?;
 
         Ok(result)
     }
 
+    fn upgrade_dry_run(
+        &mut self,
+        flox: &Flox,
+        groups_or_iids: &[String],
+    ) -> Result<UpgradeResult, EnvironmentError> {
+        let env_view = CoreEnvironment::new(self.path.join(ENV_DIR_NAME));
+        Ok(env_view.upgrade_dry_run(flox, groups_or_iids)?)
+    }
+
     /// Read the environment definition file as a string
     fn manifest_content(&self, flox: &Flox) -> Result<String, EnvironmentError> {
         fs::read_to_string(self.manifest_path(flox)?).map_err(EnvironmentError::ReadManifest)
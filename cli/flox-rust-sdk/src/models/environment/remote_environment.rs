@@ -245,12 +245,12 @@ impl Environment for RemoteEnvironment {
     }
 
     /// Atomically upgrade packages in this environment
-    fn upgrade(
+    fn apply_upgrade(
         &mut self,
         flox: &Flox,
-        groups_or_iids: &[String],
+        upgrade: UpgradeResult,
     ) -> Result<UpgradeResult, EnvironmentError> {
-        let result = self.inner.upgrade(flox, groups_or_iids)?;
+        let result = self.inner.apply_upgrade(flox, upgrade)?;
         self.inner
             .push(flox, false)
             .map_err(|e| RemoteEnvironmentError::UpdateUpstream(e).into())
@@ -259,6 +259,14 @@ impl Environment for RemoteEnvironment {
         Ok(result)
     }
 
+    fn upgrade_dry_run(
+        &mut self,
+        flox: &Flox,
+        groups_or_iids: &[String],
+    ) -> Result<UpgradeResult, EnvironmentError> {
+        self.inner.upgrade_dry_run(flox, groups_or_iids)
+    }
+
     /// Extract the current content of the manifest
     fn manifest_content(&self, flox: &Flox) -> Result<String, EnvironmentError> {
         self.inner.manifest_content(flox)
@@ -1,6 +1,7 @@
 use std::path::{Path, PathBuf};
 
 use log::debug;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use url::Url;
 
@@ -9,19 +10,22 @@ use super::environment::ManagedPointer;
 use super::environment_ref::EnvironmentOwner;
 use crate::flox::{Flox, Floxhub, FloxhubError, FloxhubToken};
 use crate::providers::git::{
+    AnyGitProvider,
     GitCommandBranchHashError,
     GitCommandOpenError,
     GitCommandOptions,
-    GitCommandProvider,
     GitProvider,
     GitRemoteCommandError,
 };
 
 pub const FLOXMETA_DIR_NAME: &str = "meta";
+/// Directory for clones of environments hosted outside of FloxHub,
+/// see [ManagedPointer::remote_url]
+pub const REMOTE_FLOXMETA_DIR_NAME: &str = "meta-remotes";
 
 #[derive(Debug)]
 pub struct FloxMeta {
-    pub(super) git: GitCommandProvider,
+    pub(super) git: AnyGitProvider,
 }
 
 #[derive(Error, Debug)]
@@ -56,27 +60,11 @@ impl FloxMeta {
         flox: &Flox,
         pointer: &ManagedPointer,
     ) -> Result<Self, FloxMetaError> {
-        let token = flox.floxhub_token.as_ref();
-
-        let floxhub = Floxhub::new(
-            pointer.floxhub_url.to_owned(),
-            pointer.floxhub_git_url_override.clone(),
-        )
-        .map_err(FloxMetaError::FloxhubError)?;
-
-        let git_url = floxhub.git_url();
-
-        let git_options = floxmeta_git_options(git_url, &pointer.owner, token);
+        let (git_options, remote_url) = floxmeta_remote(flox, pointer)?;
         let branch = remote_branch_name(pointer);
 
-        let git = GitCommandProvider::clone_branch_with(
-            git_options,
-            format!("{}/{}/floxmeta", git_url, pointer.owner),
-            path,
-            branch,
-            true,
-        )
-        .map_err(FloxMetaError::CloneBranch)?;
+        let git = AnyGitProvider::clone_branch_with(git_options, remote_url, path, branch, true)
+            .map_err(FloxMetaError::CloneBranch)?;
 
         Ok(FloxMeta { git })
     }
@@ -89,7 +77,7 @@ impl FloxMeta {
     ///
     /// Like [`FloxmetaV2::clone_to`], but uses the system path for floxmeta repositories in XDG_DATA_HOME
     pub fn clone(flox: &Flox, pointer: &ManagedPointer) -> Result<Self, FloxMetaError> {
-        Self::clone_to(floxmeta_dir(flox, &pointer.owner), flox, pointer)
+        Self::clone_to(pointer_floxmeta_dir(flox, pointer), flox, pointer)
     }
 
     /// Open a floxmeta repository at a given path
@@ -110,23 +98,13 @@ impl FloxMeta {
         flox: &Flox,
         pointer: &ManagedPointer,
     ) -> Result<Self, FloxMetaError> {
-        let token = flox.floxhub_token.as_ref();
-
-        let floxhub = Floxhub::new(
-            pointer.floxhub_url.to_owned(),
-            pointer.floxhub_git_url_override.clone(),
-        )
-        .map_err(FloxMetaError::FloxhubError)?;
-
-        let git_url = floxhub.git_url();
-
-        let git_options = floxmeta_git_options(git_url, &pointer.owner, token);
+        let (git_options, _) = floxmeta_remote(flox, pointer)?;
 
         if !user_floxmeta_dir.as_ref().exists() {
             Err(FloxMetaError::NotFound(pointer.owner.to_string()))?
         }
 
-        let git = GitCommandProvider::open_with(git_options, user_floxmeta_dir)
+        let git = AnyGitProvider::open_with(git_options, user_floxmeta_dir)
             .map_err(FloxMetaError::Open)?;
         let branch: String = remote_branch_name(pointer);
         if !git
@@ -144,7 +122,7 @@ impl FloxMeta {
     ///
     /// Like [`FloxmetaV2::open_at`], but uses the system path for floxmeta repositories in XDG_DATA_HOME.
     pub fn open(flox: &Flox, pointer: &ManagedPointer) -> Result<Self, FloxMetaError> {
-        let user_floxmeta_dir = floxmeta_dir(flox, &pointer.owner);
+        let user_floxmeta_dir = pointer_floxmeta_dir(flox, pointer);
         Self::open_at(user_floxmeta_dir, flox, pointer)
     }
 
@@ -153,8 +131,29 @@ impl FloxMeta {
         flox: &Flox,
         pointer: &ManagedPointer,
     ) -> Result<Self, FloxMetaError> {
-        let token = flox.floxhub_token.as_ref();
+        let (git_options, _) = floxmeta_remote(flox, pointer)?;
+
+        let git = AnyGitProvider::init_with(git_options, user_floxmeta_dir, false).unwrap();
+        git.rename_branch(&remote_branch_name(pointer)).unwrap();
+
+        Ok(FloxMeta { git })
+    }
+}
 
+/// Returns the git options and the url of the repository
+/// hosting the environment described by `pointer`
+///
+/// Environments are hosted on FloxHub unless the pointer sets a [ManagedPointer::remote_url].
+/// Commits are authored (and optionally signed) according to [Flox::commit_identity].
+pub(crate) fn floxmeta_remote(
+    flox: &Flox,
+    pointer: &ManagedPointer,
+) -> Result<(GitCommandOptions, String), FloxMetaError> {
+    let floxhub_token = flox.floxhub_token.as_ref();
+
+    let (mut options, url) = if let Some(remote_url) = &pointer.remote_url {
+        (remote_git_options(remote_url), remote_url.to_string())
+    } else {
         let floxhub = Floxhub::new(
             pointer.floxhub_url.to_owned(),
             pointer.floxhub_git_url_override.clone(),
@@ -163,12 +162,92 @@ impl FloxMeta {
 
         let git_url = floxhub.git_url();
 
-        let git_options = floxmeta_git_options(git_url, &pointer.owner, token);
+        (
+            floxmeta_git_options(git_url, &pointer.owner, floxhub_token),
+            format!("{}/{}/floxmeta", git_url, pointer.owner),
+        )
+    };
 
-        let git = GitCommandProvider::init_with(git_options, user_floxmeta_dir, false).unwrap();
-        git.rename_branch(&remote_branch_name(pointer)).unwrap();
+    set_commit_identity(&mut options, &flox.commit_identity, floxhub_token);
 
-        Ok(FloxMeta { git })
+    Ok((options, url))
+}
+
+/// Name git commits with if no [CommitIdentity] is configured
+///
+/// git requires an identity to commit,
+/// but this is not recorded as the author of generations.
+pub(crate) const PLACEHOLDER_COMMIT_NAME: &str = "Flox User";
+/// Email git commits with if no [CommitIdentity] is configured
+pub(crate) const PLACEHOLDER_COMMIT_EMAIL: &str = "floxuser@example.invalid";
+
+/// Identity used to author commits to floxmeta repositories,
+/// i.e. generations of managed environments
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitIdentity {
+    /// Author name, defaults to the FloxHub handle of the current user
+    pub name: Option<String>,
+    /// Author email
+    pub email: Option<String>,
+    /// Sign commits with the given key
+    pub signing: Option<CommitSigning>,
+}
+
+/// Key used to sign generation commits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitSigning {
+    /// A GPG key id, or the path to an SSH private key
+    pub key: String,
+    pub format: SigningFormat,
+}
+
+/// Signature formats supported for signing commits,
+/// mirrors git's `gpg.format` option
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningFormat {
+    #[default]
+    Openpgp,
+    Ssh,
+}
+
+impl SigningFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SigningFormat::Openpgp => "openpgp",
+            SigningFormat::Ssh => "ssh",
+        }
+    }
+}
+
+/// Set the commit author and signing options on top of the default floxmeta options
+///
+/// The author name is taken from the [CommitIdentity] if set,
+/// or the handle of the FloxHub user otherwise.
+/// Without either, the [PLACEHOLDER_COMMIT_NAME] set by [floxmeta_git_options] remains.
+fn set_commit_identity(
+    options: &mut GitCommandOptions,
+    identity: &CommitIdentity,
+    floxhub_token: Option<&FloxhubToken>,
+) {
+    let name = identity
+        .name
+        .as_deref()
+        .or(floxhub_token.map(|token| token.handle()));
+    if let Some(name) = name {
+        options.add_config_flag("user.name", name);
+    }
+    if let Some(email) = &identity.email {
+        options.add_config_flag("user.email", email);
+    }
+
+    match &identity.signing {
+        Some(signing) => {
+            options.add_config_flag("commit.gpgsign", "true");
+            options.add_config_flag("user.signingkey", &signing.key);
+            options.add_config_flag("gpg.format", signing.format.as_str());
+        },
+        None => options.add_config_flag("commit.gpgsign", "false"),
     }
 }
 
@@ -189,10 +268,10 @@ pub fn floxmeta_git_options(
 ) -> GitCommandOptions {
     let mut options = GitCommandOptions::default();
 
-    // set the user config
-    // todo: eventually use the user's name and email once integrated with FloxHub
-    options.add_config_flag("user.name", "Flox User");
-    options.add_config_flag("user.email", "floxuser@example.invalid");
+    // set the default user config,
+    // usually overridden by the user's identity, see [set_commit_identity]
+    options.add_config_flag("user.name", PLACEHOLDER_COMMIT_NAME);
+    options.add_config_flag("user.email", PLACEHOLDER_COMMIT_EMAIL);
 
     // unset the global and system config
     options.add_env_var("GIT_CONFIG_GLOBAL", "/dev/null");
@@ -233,12 +312,47 @@ pub fn floxmeta_git_options(
     options
 }
 
+/// Returns the git options for environments hosted in a plain git repository
+///
+/// * Keep the user's global and system config,
+///   so that credential helpers, ssh settings and url rewrites
+///   configured for the remote apply to flox operations as well
+/// * Set required user config (name and email)
+/// * Point the dynamic origin at the remote repository
+pub fn remote_git_options(remote_url: &Url) -> GitCommandOptions {
+    let mut options = GitCommandOptions::default();
+
+    options.add_config_flag("user.name", PLACEHOLDER_COMMIT_NAME);
+    options.add_config_flag("user.email", PLACEHOLDER_COMMIT_EMAIL);
+
+    options.add_config_flag("remote.dynamicorigin.url", remote_url.as_str());
+
+    options
+}
+
 pub(super) fn floxmeta_dir(flox: &Flox, owner: &EnvironmentOwner) -> PathBuf {
     flox.data_dir
         .join(FLOXMETA_DIR_NAME)
         .join(owner.to_string())
 }
 
+/// Returns the local floxmeta directory for the environment described by `pointer`
+///
+/// Clones of plain git remotes are kept apart from FloxHub clones
+/// (and from each other) to avoid mixing up branches of equally named environments.
+pub(super) fn pointer_floxmeta_dir(flox: &Flox, pointer: &ManagedPointer) -> PathBuf {
+    let Some(remote_url) = &pointer.remote_url else {
+        return floxmeta_dir(flox, &pointer.owner);
+    };
+
+    let remote_hash = blake3::hash(remote_url.as_str().as_bytes()).to_hex();
+
+    flox.data_dir
+        .join(REMOTE_FLOXMETA_DIR_NAME)
+        .join(&remote_hash[..16])
+        .join(pointer.owner.to_string())
+}
+
 pub mod test_helpers {
     use super::*;
     use crate::providers::git::test_helpers::mock_provider;
@@ -258,7 +372,7 @@ mod tests {
     use super::*;
     use crate::flox::test_helpers::flox_instance;
     use crate::flox::DEFAULT_FLOXHUB_URL;
-    use crate::providers::git::GitProvider;
+    use crate::providers::git::{GitCommandProvider, GitProvider};
 
     /// Create an upstream floxmeta repository with an environment under a given base path
     fn create_fake_floxmeta(
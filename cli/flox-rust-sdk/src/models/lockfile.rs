@@ -3,7 +3,7 @@ use serde_json::Value;
 
 pub type FlakeRef = Value;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
@@ -31,6 +31,13 @@ pub struct Input {
     _json: Value,
 }
 
+impl Input {
+    /// The locked revision of the input, if any
+    pub fn rev(&self) -> Option<&str> {
+        self.from.get("rev").and_then(Value::as_str)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Registry {
     pub inputs: BTreeMap<String, Input>,
@@ -219,20 +226,29 @@ impl LockedManifest {
         serde_json::from_slice(&contents).map_err(LockedManifestError::ParseLockfile)
     }
 
+    /// Check the packages of a lockfile against the `allow` options
+    /// of the manifest it was locked from.
+    ///
+    /// Only returns warnings about packages that are allowed,
+    /// e.g. unfree packages that should be reviewed.
+    /// Use [TypedLockedManifest::check] to find all problems.
     pub fn check_lockfile(
         path: &CanonicalPath,
     ) -> Result<Vec<LockfileCheckWarning>, LockedManifestError> {
-        let mut pkgdb_cmd = Command::new(Path::new(&*PKGDB_BIN));
-        pkgdb_cmd
-            .args(["manifest", "check"])
-            .arg("--lockfile")
-            .arg(path.as_os_str());
-
-        debug!("checking lockfile with command: {}", pkgdb_cmd.display());
-
-        let value = call_pkgdb(pkgdb_cmd).map_err(LockedManifestError::CheckLockfile)?;
-        let warnings: Vec<LockfileCheckWarning> =
-            serde_json::from_value(value).map_err(LockedManifestError::ParseCheckWarnings)?;
+        debug!("checking lockfile: {}", path.display());
+        let lockfile = TypedLockedManifest::try_from(Self::read_from_file(path)?)?;
+
+        let warnings = lockfile
+            .check_packages(None)
+            .into_iter()
+            .filter(|problem| problem.severity == ProblemSeverity::Warning)
+            .filter_map(|problem| {
+                Some(LockfileCheckWarning {
+                    package: problem.package?,
+                    message: problem.message,
+                })
+            })
+            .collect();
 
         Ok(warnings)
     }
@@ -274,6 +290,181 @@ pub struct TypedLockedManifest {
     lockfile_version: Version<0>,
     packages: BTreeMap<System, BTreeMap<String, Option<LockedPackage>>>,
     registry: Registry,
+    #[serde(default)]
+    manifest: LockfileManifest,
+}
+
+/// The parts of a manifest that are recorded in a lockfile
+/// and that are relevant to [TypedLockedManifest::check].
+///
+/// Can be read from the `manifest` field of a lockfile,
+/// or from a `manifest.toml` with [LockfileManifest::from_toml].
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+pub struct LockfileManifest {
+    #[serde(default)]
+    install: BTreeMap<String, Option<LockfileDescriptor>>,
+    #[serde(default)]
+    options: LockfileOptions,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+struct LockfileDescriptor {
+    pkg_path: Option<String>,
+    #[serde(alias = "package-group")]
+    pkg_group: Option<String>,
+    version: Option<String>,
+    systems: Option<Vec<System>>,
+    optional: Option<bool>,
+    priority: Option<usize>,
+    held: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+struct LockfileOptions {
+    systems: Option<Vec<System>>,
+    #[serde(alias = "allows")]
+    allow: Option<LockfileAllows>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+struct LockfileAllows {
+    unfree: Option<bool>,
+    broken: Option<bool>,
+    licenses: Option<Vec<String>>,
+}
+
+impl LockfileManifest {
+    pub fn from_toml(contents: &str) -> Result<Self, LockedManifestError> {
+        toml::from_str(contents).map_err(LockedManifestError::ParseManifest)
+    }
+
+    /// The descriptor of a package, `pkg = {}` is the same as no descriptor
+    fn descriptor(&self, id: &str) -> Option<LockfileDescriptor> {
+        self.install
+            .get(id)
+            .map(|descriptor| descriptor.clone().unwrap_or_default())
+    }
+
+    /// The pkg-group of a package, `toplevel` if none is set
+    pub fn package_group(&self, id: &str) -> String {
+        self.descriptor(id)
+            .and_then(|descriptor| descriptor.pkg_group)
+            .unwrap_or_else(|| "toplevel".to_string())
+    }
+
+    pub fn is_installed(&self, id: &str) -> bool {
+        self.install.contains_key(id)
+    }
+
+    /// Whether a package is excluded from upgrades with `held = true`
+    pub fn is_held(&self, id: &str) -> bool {
+        self.descriptor(id)
+            .and_then(|descriptor| descriptor.held)
+            .unwrap_or(false)
+    }
+
+    /// Remove held packages from a selection of pkg-groups or install IDs
+    /// to upgrade, where an empty selection means all pkg-groups.
+    ///
+    /// Packages in a pkg-group are upgraded together,
+    /// so a held package holds back its whole pkg-group.
+    /// Returns the remaining selection and the held back packages.
+    pub fn exclude_held(&self, groups_or_iids: &[String]) -> (Vec<String>, Vec<String>) {
+        let held_groups = self
+            .install
+            .keys()
+            .filter(|id| self.is_held(id))
+            .map(|id| self.package_group(id))
+            .collect::<BTreeSet<_>>();
+        if held_groups.is_empty() {
+            return (groups_or_iids.to_vec(), Vec::new());
+        }
+
+        let group_of = |group_or_iid: &String| {
+            if self.install.contains_key(group_or_iid) {
+                self.package_group(group_or_iid)
+            } else {
+                group_or_iid.clone()
+            }
+        };
+
+        let selection = if groups_or_iids.is_empty() {
+            self.install
+                .keys()
+                .map(|id| self.package_group(id))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect()
+        } else {
+            groups_or_iids.to_vec()
+        };
+        let remaining = selection
+            .into_iter()
+            .filter(|group_or_iid| !held_groups.contains(&group_of(group_or_iid)))
+            .collect();
+
+        let held = self
+            .install
+            .keys()
+            .filter(|id| {
+                let group = self.package_group(id);
+                held_groups.contains(&group)
+                    && (groups_or_iids.is_empty()
+                        || groups_or_iids
+                            .iter()
+                            .any(|selected| selected == *id || *selected == group))
+            })
+            .cloned()
+            .collect();
+
+        (remaining, held)
+    }
+
+    fn allows(&self) -> LockfileAllows {
+        self.options.allow.clone().unwrap_or_default()
+    }
+}
+
+/// How serious a problem found by [TypedLockedManifest::check] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProblemSeverity {
+    /// The package is allowed but should be reviewed
+    Warning,
+    /// The lockfile is unusable or violates the manifest's policies
+    Error,
+}
+
+/// A problem found by [TypedLockedManifest::check]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LockfileProblem {
+    pub severity: ProblemSeverity,
+    /// The install ID of the affected package, if the problem concerns a package
+    pub package: Option<String>,
+    /// The affected system, if the problem is specific to a system
+    pub system: Option<System>,
+    pub message: String,
+}
+
+impl LockfileProblem {
+    fn error(package: Option<&str>, system: Option<&System>, message: String) -> Self {
+        LockfileProblem {
+            severity: ProblemSeverity::Error,
+            package: package.map(str::to_string),
+            system: system.cloned(),
+            message,
+        }
+    }
+
+    fn warning(package: &str, message: String) -> Self {
+        LockfileProblem {
+            severity: ProblemSeverity::Warning,
+            package: Some(package.to_string()),
+            system: None,
+            message,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -282,6 +473,24 @@ struct LockedPackage {
     #[serde(rename = "attr-path")]
     abs_path: Vec<String>,
     priority: usize,
+    #[serde(default)]
+    input: Option<LockedInput>,
+}
+
+/// The flake a package was locked from
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct LockedInput {
+    pub url: String,
+    /// The attributes of the locked flake reference,
+    /// e.g. `type`, `owner`, `repo` and `rev`
+    #[serde(default)]
+    pub attrs: FlakeRef,
+}
+
+impl LockedInput {
+    pub fn rev(&self) -> Option<&str> {
+        self.attrs.get("rev").and_then(Value::as_str)
+    }
 }
 
 impl LockedPackage {
@@ -318,6 +527,175 @@ impl TypedLockedManifest {
         &self.registry
     }
 
+    /// The manifest the lockfile was locked from
+    pub fn manifest(&self) -> &LockfileManifest {
+        &self.manifest
+    }
+
+    /// Check the lockfile for problems
+    ///
+    /// - the lockfile is out of sync with `manifest`, i.e. the manifest
+    ///   was changed after it was locked
+    /// - packages are missing for systems in `options.systems`
+    /// - packages are unfree or broken although not allowed
+    /// - packages have licenses that are not listed in `allow.licenses`
+    ///
+    /// If `system` is set, only packages for that system are checked.
+    pub fn check(
+        &self,
+        manifest: &LockfileManifest,
+        system: Option<&System>,
+    ) -> Vec<LockfileProblem> {
+        let mut problems = self.check_sync(manifest);
+        problems.extend(self.check_systems(system));
+        problems.extend(self.check_packages(system));
+        problems
+    }
+
+    /// Compare the manifest recorded in the lockfile with `manifest`
+    fn check_sync(&self, manifest: &LockfileManifest) -> Vec<LockfileProblem> {
+        let mut problems = Vec::new();
+        let out_of_sync = |what: String| {
+            LockfileProblem::error(
+                None,
+                None,
+                format!("The lockfile is out of sync with the manifest: {what}. Run 'flox install' or 'flox upgrade' to lock the environment again."),
+            )
+        };
+
+        for id in manifest.install.keys() {
+            match self.manifest.descriptor(id) {
+                None => problems.push(out_of_sync(format!("'{id}' is not locked"))),
+                Some(ref locked) if manifest.descriptor(id).as_ref() != Some(locked) => {
+                    problems.push(out_of_sync(format!("'{id}' was changed")))
+                },
+                Some(_) => {},
+            }
+        }
+        for id in self.manifest.install.keys() {
+            if !manifest.install.contains_key(id) {
+                problems.push(out_of_sync(format!("'{id}' was removed")));
+            }
+        }
+        if self.manifest.options.systems != manifest.options.systems {
+            problems.push(out_of_sync("'options.systems' was changed".to_string()));
+        }
+        if self.manifest.allows() != manifest.allows() {
+            problems.push(out_of_sync("'options.allow' was changed".to_string()));
+        }
+        problems
+    }
+
+    /// Check that every package is locked for all systems of the manifest
+    fn check_systems(&self, system: Option<&System>) -> Vec<LockfileProblem> {
+        let mut problems = Vec::new();
+        let systems = self.manifest.options.systems.clone().unwrap_or_default();
+
+        for manifest_system in systems
+            .iter()
+            .filter(|s| system.is_none_or(|system| system == *s))
+        {
+            let locked = self.packages.get(manifest_system);
+            for id in self.manifest.install.keys() {
+                let descriptor = self.manifest.descriptor(id).unwrap_or_default();
+                if descriptor.optional.unwrap_or(false) {
+                    continue;
+                }
+                if let Some(systems) = &descriptor.systems {
+                    if !systems.contains(manifest_system) {
+                        continue;
+                    }
+                }
+
+                if !matches!(locked.and_then(|packages| packages.get(id)), Some(Some(_))) {
+                    problems.push(LockfileProblem::error(
+                        Some(id),
+                        Some(manifest_system),
+                        format!("The package '{id}' is not locked for '{manifest_system}'."),
+                    ));
+                }
+            }
+        }
+        problems
+    }
+
+    /// Check packages against the `allow` options of the manifest
+    ///
+    /// Messages of packages that are allowed match those of `pkgdb manifest check`.
+    fn check_packages(&self, system: Option<&System>) -> Vec<LockfileProblem> {
+        let allows = self.manifest.allows();
+        let mut problems = Vec::new();
+
+        // packages are usually locked for multiple systems
+        // but problems should only be reported once per package
+        let packages = self
+            .packages
+            .iter()
+            .filter(|(s, _)| system.is_none_or(|system| system == *s))
+            .flat_map(|(_, packages)| packages.iter())
+            .filter_map(|(id, package)| Some((id, package.as_ref()?)))
+            .collect::<BTreeMap<_, _>>();
+
+        for (id, package) in packages {
+            let info = &package.info;
+
+            if info.unfree {
+                if allows.unfree == Some(false) {
+                    problems.push(LockfileProblem::error(
+                        Some(id),
+                        None,
+                        format!("The package '{id}' has an unfree license, but 'options.allow.unfree' is false."),
+                    ));
+                } else {
+                    problems.push(LockfileProblem::warning(
+                        id,
+                        format!("The package '{id}' has an unfree license, please verify the licensing terms of use"),
+                    ));
+                }
+            }
+
+            if info.broken {
+                if allows.broken == Some(true) {
+                    problems.push(LockfileProblem::warning(
+                        id,
+                        format!("The package '{id}' is marked as broken, it may not behave as expected during runtime."),
+                    ));
+                } else {
+                    problems.push(LockfileProblem::error(
+                        Some(id),
+                        None,
+                        format!("The package '{id}' is marked as broken, but 'options.allow.broken' is not true."),
+                    ));
+                }
+            }
+
+            match (&allows.licenses, &info.license) {
+                (Some(licenses), Some(license))
+                    if !licenses.is_empty() && !licenses.contains(license) =>
+                {
+                    problems.push(LockfileProblem::error(
+                        Some(id),
+                        None,
+                        format!("The package '{id}' has the license '{license}', which is not listed in 'options.allow.licenses'."),
+                    ));
+                },
+                (Some(licenses), None) if !licenses.is_empty() => {
+                    problems.push(LockfileProblem::warning(
+                        id,
+                        format!("The package '{id}' has no license information, please verify the licensing terms of use"),
+                    ));
+                },
+                _ => {},
+            }
+        }
+        problems
+    }
+
+    /// The systems packages are locked for
+    pub fn systems(&self) -> impl Iterator<Item = &System> {
+        self.packages.keys()
+    }
+
     /// List all packages in the locked manifest for a given system
     pub fn list_packages(&self, system: &System) -> Vec<InstalledPackage> {
         let mut packages = vec![];
@@ -329,6 +707,7 @@ impl TypedLockedManifest {
                         rel_path: locked_package.rel_path(),
                         info: locked_package.info.clone(),
                         priority: locked_package.priority,
+                        input: locked_package.input.clone(),
                     });
                 };
             }
@@ -342,18 +721,15 @@ pub struct InstalledPackage {
     pub rel_path: String,
     pub info: PackageInfo,
     pub priority: usize,
+    pub input: Option<LockedInput>,
 }
 
 #[derive(Debug, Error)]
 pub enum LockedManifestError {
     #[error("failed to lock manifest")]
     LockManifest(#[source] CallPkgDbError),
-    #[error("failed to check lockfile")]
-    CheckLockfile(#[source] CallPkgDbError),
     #[error("failed to build environment")]
     BuildEnv(#[source] CallPkgDbError),
-    #[error("failed to parse check warnings")]
-    ParseCheckWarnings(#[source] serde_json::Error),
     #[error("package is unsupported for this sytem")]
     UnsupportedPackageWithDocLink(#[source] CallPkgDbError),
     #[error("failed to build container builder")]
@@ -376,13 +752,15 @@ pub enum LockedManifestError {
     /// when parsing a [LockedManifest] into a [TypedLockedManifest]
     #[error("failed to parse contents of locked manifest")]
     ParseLockedManifest(#[source] serde_json::Error),
+    #[error("could not parse manifest")]
+    ParseManifest(#[source] toml::de::Error),
     #[error("could not serialize global lockfile")]
     SerializeGlobalLockfile(#[source] serde_json::Error),
     #[error("could not write global lockfile")]
     WriteGlobalLockfile(#[source] std::io::Error),
 }
 
-/// A warning produced by `pkgdb manifest check`
+/// A warning about a package that is allowed but should be reviewed
 #[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct LockfileCheckWarning {
     pub package: String,
@@ -481,4 +859,144 @@ mod tests {
             None
         );
     }
+
+    fn locked_package(pname: &str, license: &str, unfree: bool, broken: bool) -> Value {
+        serde_json::json!({
+            "info": {
+                "description": null,
+                "broken": broken,
+                "license": license,
+                "pname": pname,
+                "unfree": unfree,
+                "version": "1.0.0"
+            },
+            "attr-path": ["legacyPackages", "x86_64-linux", pname],
+            "priority": 5
+        })
+    }
+
+    fn checked_lockfile() -> TypedLockedManifest {
+        serde_json::from_value(serde_json::json!({
+            "lockfile-version": 0,
+            "manifest": {
+                "install": {
+                    "hello": null,
+                    "vscode": { "pkg-path": "vscode" },
+                    "gcc": { "systems": ["x86_64-linux"] }
+                },
+                "options": {
+                    "systems": ["x86_64-linux", "aarch64-darwin"],
+                    "allow": { "licenses": ["GPL-3.0-or-later"] }
+                }
+            },
+            "packages": {
+                "x86_64-linux": {
+                    "hello": locked_package("hello", "GPL-3.0-or-later", false, false),
+                    "vscode": locked_package("vscode", "unfree", true, false),
+                    "gcc": locked_package("gcc", "GPL-3.0-or-later", false, true)
+                },
+                "aarch64-darwin": {
+                    "hello": locked_package("hello", "GPL-3.0-or-later", false, false)
+                }
+            },
+            "registry": { "inputs": {} }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn check_finds_policy_and_system_problems() {
+        let lockfile = checked_lockfile();
+        let manifest = LockfileManifest::from_toml(indoc::indoc! {r#"
+            [install]
+            hello = {}
+            vscode.pkg-path = "vscode"
+            gcc.systems = ["x86_64-linux"]
+
+            [options]
+            systems = ["x86_64-linux", "aarch64-darwin"]
+            allow.licenses = ["GPL-3.0-or-later"]
+        "#})
+        .unwrap();
+
+        let problems = lockfile
+            .check(&manifest, None)
+            .into_iter()
+            .map(|problem| (problem.severity, problem.package, problem.system))
+            .collect::<Vec<_>>();
+
+        assert_eq!(problems, vec![
+            // vscode is not optional but missing on darwin
+            (
+                ProblemSeverity::Error,
+                Some("vscode".to_string()),
+                Some("aarch64-darwin".to_string())
+            ),
+            // broken packages are not allowed by default
+            (ProblemSeverity::Error, Some("gcc".to_string()), None),
+            // unfree packages are allowed by default
+            (ProblemSeverity::Warning, Some("vscode".to_string()), None),
+            (ProblemSeverity::Error, Some("vscode".to_string()), None),
+        ]);
+    }
+
+    #[test]
+    fn check_detects_manifest_changes() {
+        let lockfile = checked_lockfile();
+        let manifest = LockfileManifest::from_toml(indoc::indoc! {r#"
+            [install]
+            hello.version = "2.12"
+            vscode.pkg-path = "vscode"
+
+            [options]
+            systems = ["x86_64-linux", "aarch64-darwin"]
+            allow.licenses = ["GPL-3.0-or-later"]
+        "#})
+        .unwrap();
+
+        let messages = lockfile
+            .check_sync(&manifest)
+            .into_iter()
+            .map(|problem| problem.message)
+            .collect::<Vec<_>>();
+
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].contains("'hello' was changed"));
+        assert!(messages[1].contains("'gcc' was removed"));
+    }
+
+    #[test]
+    fn exclude_held_holds_back_pkg_groups() {
+        let manifest = LockfileManifest::from_toml(indoc::indoc! {r#"
+            [install]
+            hello = {}
+            gcc = { held = true, pkg-group = "toolchain" }
+            gnumake.pkg-group = "toolchain"
+            vim.held = false
+        "#})
+        .unwrap();
+
+        assert!(manifest.is_held("gcc"));
+        assert!(!manifest.is_held("vim"));
+
+        // all pkg-groups but the held one
+        assert_eq!(
+            manifest.exclude_held(&[]),
+            (vec!["toplevel".to_string()], vec![
+                "gcc".to_string(),
+                "gnumake".to_string()
+            ])
+        );
+
+        // a package sharing a pkg-group with a held package is held back too
+        assert_eq!(
+            manifest.exclude_held(&["gnumake".to_string(), "hello".to_string()]),
+            (vec!["hello".to_string()], vec!["gnumake".to_string()])
+        );
+
+        assert_eq!(
+            manifest.exclude_held(&["toolchain".to_string()]),
+            (vec![], vec!["gcc".to_string(), "gnumake".to_string()])
+        );
+    }
 }
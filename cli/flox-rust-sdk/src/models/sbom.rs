@@ -0,0 +1,673 @@
+//! Software bills of materials for environments
+//!
+//! Describes the packages of a lockfile as
+//! [SPDX 2.3](https://spdx.github.io/spdx-spec/v2.3/) or
+//! [CycloneDX 1.5](https://cyclonedx.org/docs/1.5/json/) JSON documents.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::audit::Vulnerability;
+use super::lockfile::{FlakeRef, TypedLockedManifest};
+use crate::data::System;
+use crate::flox::FLOX_VERSION;
+
+#[derive(Debug, Error)]
+pub enum SbomError {
+    #[error("unknown SBOM format '{0}', expected 'spdx' or 'cyclonedx'")]
+    UnknownFormat(String),
+}
+
+/// The document formats an [Sbom] can be rendered as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SbomFormat {
+    Spdx,
+    CycloneDx,
+}
+
+impl SbomFormat {
+    pub fn render(&self, sbom: &Sbom) -> Value {
+        match self {
+            SbomFormat::Spdx => sbom.to_spdx(),
+            SbomFormat::CycloneDx => sbom.to_cyclonedx(),
+        }
+    }
+
+    /// The conventional file name of a document in this format
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            SbomFormat::Spdx => "sbom.spdx.json",
+            SbomFormat::CycloneDx => "sbom.cdx.json",
+        }
+    }
+
+    /// The name and version of the specification
+    pub fn specification(&self) -> &'static str {
+        match self {
+            SbomFormat::Spdx => "SPDX-2.3",
+            SbomFormat::CycloneDx => "CycloneDX-1.5",
+        }
+    }
+}
+
+impl FromStr for SbomFormat {
+    type Err = SbomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "spdx" => Ok(SbomFormat::Spdx),
+            "cyclonedx" | "cdx" => Ok(SbomFormat::CycloneDx),
+            _ => Err(SbomError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+impl Display for SbomFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SbomFormat::Spdx => write!(f, "spdx"),
+            SbomFormat::CycloneDx => write!(f, "cyclonedx"),
+        }
+    }
+}
+
+/// A flake packages are locked from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SbomInput {
+    /// The name of the input in the registry,
+    /// unknown for the inputs of individual packages
+    pub name: Option<String>,
+    pub url: Option<String>,
+    pub rev: Option<String>,
+    pub nar_hash: Option<String>,
+    /// The location to fetch the input from,
+    /// in the format of SPDX `downloadLocation`
+    pub download_location: Option<String>,
+}
+
+impl SbomInput {
+    fn new(name: Option<String>, url: Option<String>, attrs: &FlakeRef) -> Self {
+        let attr = |key: &str| attrs.get(key).and_then(Value::as_str).map(str::to_string);
+        let rev = attr("rev");
+
+        let download_location = match (attr("type").as_deref(), attr("owner"), attr("repo")) {
+            (Some(forge @ ("github" | "gitlab")), Some(owner), Some(repo)) => {
+                // self-hosted instances are given by `host`,
+                // otherwise nix uses the public instance of the forge
+                let host = attr("host").unwrap_or_else(|| format!("{forge}.com"));
+                let mut location = format!("git+https://{host}/{owner}/{repo}");
+                if let Some(rev) = &rev {
+                    location.push_str(&format!("@{rev}"));
+                }
+                Some(location)
+            },
+            (Some("git"), _, _) => attr("url").map(|url| match &rev {
+                Some(rev) => format!("git+{url}@{rev}"),
+                None => format!("git+{url}"),
+            }),
+            (Some("tarball" | "file"), _, _) => attr("url"),
+            _ => None,
+        };
+
+        SbomInput {
+            name,
+            url: url.or_else(|| attrs.as_str().map(str::to_string)),
+            rev,
+            nar_hash: attr("narHash"),
+            download_location,
+        }
+    }
+}
+
+/// A package of an environment as listed in an SBOM
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SbomPackage {
+    /// The install ID of the package
+    pub id: String,
+    pub pname: String,
+    pub pkg_path: String,
+    pub version: Option<String>,
+    pub license: Option<String>,
+    pub unfree: bool,
+    pub broken: bool,
+    /// The systems the package is locked for with this version
+    pub systems: Vec<System>,
+    /// The flake the package is locked from
+    pub input: Option<SbomInput>,
+    /// Known vulnerabilities, see [super::audit]
+    pub vulnerabilities: Vec<Vulnerability>,
+}
+
+impl SbomPackage {
+    /// A package URL identifying the package
+    ///
+    /// There is no registered purl type for nix (yet),
+    /// `pkg:nix` is used by other nix tools as well.
+    pub fn purl(&self) -> String {
+        match &self.version {
+            Some(version) => format!("pkg:nix/{}@{version}", self.pname),
+            None => format!("pkg:nix/{}", self.pname),
+        }
+    }
+}
+
+/// The packages of an environment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sbom {
+    /// The name of the environment
+    pub name: String,
+    pub packages: Vec<SbomPackage>,
+    /// The inputs of the registry the environment is locked against
+    pub inputs: Vec<SbomInput>,
+}
+
+impl Sbom {
+    /// Collect the packages of all systems in a lockfile
+    ///
+    /// A package that is locked to the same path and version
+    /// for several systems is listed once.
+    pub fn from_lockfile(name: impl Into<String>, lockfile: &TypedLockedManifest) -> Self {
+        let mut packages: BTreeMap<(String, String, Option<String>), SbomPackage> = BTreeMap::new();
+
+        for system in lockfile.systems() {
+            for installed in lockfile.list_packages(system) {
+                let key = (
+                    installed.name.clone(),
+                    installed.rel_path.clone(),
+                    installed.info.version.clone(),
+                );
+                packages
+                    .entry(key)
+                    .or_insert_with(|| SbomPackage {
+                        id: installed.name,
+                        pname: installed.info.pname,
+                        pkg_path: installed.rel_path,
+                        version: installed.info.version,
+                        license: installed.info.license,
+                        unfree: installed.info.unfree,
+                        broken: installed.info.broken,
+                        systems: Vec::new(),
+                        input: installed
+                            .input
+                            .map(|input| SbomInput::new(None, Some(input.url), &input.attrs)),
+                        vulnerabilities: Vec::new(),
+                    })
+                    .systems
+                    .push(system.clone());
+            }
+        }
+
+        let inputs = lockfile
+            .registry()
+            .inputs
+            .iter()
+            .map(|(name, input)| SbomInput::new(Some(name.clone()), None, &input.from))
+            .collect();
+
+        Sbom {
+            name: name.into(),
+            packages: packages.into_values().collect(),
+            inputs,
+        }
+    }
+
+    /// Only keep the packages locked for `system`
+    pub fn retain_system(&mut self, system: &System) {
+        self.packages
+            .retain(|package| package.systems.contains(system));
+        for package in &mut self.packages {
+            package.systems = vec![system.clone()];
+        }
+    }
+
+    /// Render an SPDX 2.3 JSON document
+    ///
+    /// Vulnerabilities are listed as `SECURITY` references of the affected package.
+    pub fn to_spdx(&self) -> Value {
+        let packages = self
+            .packages
+            .iter()
+            .enumerate()
+            .map(|(index, package)| {
+                let mut external_refs = vec![json!({
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": package.purl(),
+                })];
+                external_refs.extend(package.vulnerabilities.iter().map(|vulnerability| {
+                    json!({
+                        "referenceCategory": "SECURITY",
+                        "referenceType": "advisory",
+                        "referenceLocator": vulnerability.url(),
+                    })
+                }));
+
+                let input = package.input.as_ref();
+                let mut spdx_package = json!({
+                    "SPDXID": spdx_package_id(index, package),
+                    "name": package.pname,
+                    "versionInfo": package.version.as_deref().unwrap_or("NOASSERTION"),
+                    "downloadLocation": input
+                        .and_then(|input| input.download_location.as_deref())
+                        .unwrap_or("NOASSERTION"),
+                    "filesAnalyzed": false,
+                    "licenseConcluded": "NOASSERTION",
+                    "licenseDeclared": package.license.as_deref().unwrap_or("NOASSERTION"),
+                    "externalRefs": external_refs,
+                    "comment": format!(
+                        "flox install ID '{}', attribute path '{}', systems: {}",
+                        package.id,
+                        package.pkg_path,
+                        package.systems.join(", ")
+                    ),
+                });
+                if let Some(url) = input.and_then(|input| input.url.as_ref()) {
+                    spdx_package["sourceInfo"] = json!(format!("locked from {url}"));
+                }
+                spdx_package
+            })
+            .collect::<Vec<_>>();
+
+        // registry inputs are listed as packages that are not described by the document
+        let input_packages = self.inputs.iter().map(|input| {
+            json!({
+                "SPDXID": spdx_input_id(input),
+                "name": input.name,
+                "versionInfo": input.rev.as_deref().unwrap_or("NOASSERTION"),
+                "downloadLocation": input.download_location.as_deref().unwrap_or("NOASSERTION"),
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": "NOASSERTION",
+                "primaryPackagePurpose": "SOURCE",
+            })
+        });
+        let packages = packages
+            .into_iter()
+            .chain(input_packages)
+            .collect::<Vec<_>>();
+
+        let relationships = self
+            .packages
+            .iter()
+            .enumerate()
+            .map(|(index, package)| {
+                json!({
+                    "spdxElementId": "SPDXRef-DOCUMENT",
+                    "relationshipType": "DESCRIBES",
+                    "relatedSpdxElement": spdx_package_id(index, package),
+                })
+            })
+            .chain(
+                self.packages
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(index, package)| {
+                        // link packages to the registry input with the same revision
+                        self.inputs
+                            .iter()
+                            .filter(|input| {
+                                input.rev.is_some()
+                                    && input.rev
+                                        == package.input.as_ref().and_then(|i| i.rev.clone())
+                            })
+                            .map(move |input| {
+                                json!({
+                                    "spdxElementId": spdx_package_id(index, package),
+                                    "relationshipType": "GENERATED_FROM",
+                                    "relatedSpdxElement": spdx_input_id(input),
+                                })
+                            })
+                    }),
+            )
+            .collect::<Vec<_>>();
+
+        json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": self.name,
+            "documentNamespace": format!(
+                "https://flox.dev/spdxdocs/{}-{}",
+                self.name,
+                Uuid::new_v4()
+            ),
+            "creationInfo": {
+                "created": timestamp(),
+                "creators": [format!("Tool: flox-{}", &*FLOX_VERSION)],
+            },
+            "packages": packages,
+            "relationships": relationships,
+        })
+    }
+
+    /// Render a CycloneDX 1.5 JSON document
+    pub fn to_cyclonedx(&self) -> Value {
+        let components = self
+            .packages
+            .iter()
+            .enumerate()
+            .map(|(index, package)| {
+                let mut component = json!({
+                    "type": "library",
+                    "bom-ref": cyclonedx_ref(index, package),
+                    "name": package.pname,
+                    "purl": package.purl(),
+                    "properties": [
+                        { "name": "flox:install-id", "value": package.id },
+                        { "name": "flox:pkg-path", "value": package.pkg_path },
+                        { "name": "flox:systems", "value": package.systems.join(",") },
+                    ],
+                });
+                if let Some(version) = &package.version {
+                    component["version"] = json!(version);
+                }
+                if let Some(license) = &package.license {
+                    component["licenses"] = json!([{ "license": { "name": license } }]);
+                }
+                if let Some(input) = &package.input {
+                    cyclonedx_input_properties(&mut component, input);
+                }
+                component
+            })
+            .collect::<Vec<_>>();
+
+        // CycloneDX lists each vulnerability once with all affected components
+        let mut vulnerabilities: BTreeMap<&str, (&Vulnerability, Vec<String>)> = BTreeMap::new();
+        for (index, package) in self.packages.iter().enumerate() {
+            for vulnerability in &package.vulnerabilities {
+                vulnerabilities
+                    .entry(&vulnerability.id)
+                    .or_insert_with(|| (vulnerability, Vec::new()))
+                    .1
+                    .push(cyclonedx_ref(index, package));
+            }
+        }
+        let vulnerabilities = vulnerabilities
+            .into_values()
+            .map(|(vulnerability, affected)| {
+                let mut entry = json!({
+                    "id": vulnerability.id,
+                    "source": { "name": "OSV", "url": vulnerability.url() },
+                    "affects": affected
+                        .into_iter()
+                        .map(|bom_ref| json!({ "ref": bom_ref }))
+                        .collect::<Vec<_>>(),
+                });
+                if let Some(summary) = &vulnerability.summary {
+                    entry["description"] = json!(summary);
+                }
+                if let Some(fixed) = &vulnerability.fixed {
+                    entry["recommendation"] = json!(format!("Upgrade to version {fixed}"));
+                }
+                entry
+            })
+            .collect::<Vec<_>>();
+
+        let mut bom = json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "serialNumber": format!("urn:uuid:{}", Uuid::new_v4()),
+            "version": 1,
+            "metadata": {
+                "timestamp": timestamp(),
+                "tools": {
+                    "components": [{
+                        "type": "application",
+                        "name": "flox",
+                        "version": &*FLOX_VERSION,
+                    }],
+                },
+                "component": {
+                    "type": "application",
+                    "bom-ref": "environment",
+                    "name": self.name,
+                },
+            },
+            "components": components,
+        });
+        let input_properties = self
+            .inputs
+            .iter()
+            .filter_map(|input| {
+                let value = input.download_location.as_ref().or(input.rev.as_ref())?;
+                Some(json!({
+                    "name": format!("flox:input:{}", input.name.as_deref().unwrap_or_default()),
+                    "value": value,
+                }))
+            })
+            .collect::<Vec<_>>();
+        if !input_properties.is_empty() {
+            bom["metadata"]["properties"] = json!(input_properties);
+        }
+        if !vulnerabilities.is_empty() {
+            bom["vulnerabilities"] = json!(vulnerabilities);
+        }
+        bom
+    }
+}
+
+fn timestamp() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// SPDX identifiers may only contain letters, numbers, `.` and `-`
+fn spdx_id_safe(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn spdx_package_id(index: usize, package: &SbomPackage) -> String {
+    format!("SPDXRef-Package-{index}-{}", spdx_id_safe(&package.id))
+}
+
+fn spdx_input_id(input: &SbomInput) -> String {
+    format!(
+        "SPDXRef-Input-{}",
+        spdx_id_safe(input.name.as_deref().unwrap_or_default())
+    )
+}
+
+fn cyclonedx_input_properties(component: &mut Value, input: &SbomInput) {
+    if let Some(location) = &input.download_location {
+        component["externalReferences"] = json!([{ "type": "vcs", "url": location }]);
+    }
+    if let (Some(properties), Some(rev)) = (component["properties"].as_array_mut(), &input.rev) {
+        properties.push(json!({ "name": "flox:input-rev", "value": rev }));
+    }
+}
+
+fn cyclonedx_ref(index: usize, package: &SbomPackage) -> String {
+    format!("{}-{index}", package.purl())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sbom() -> Sbom {
+        let lockfile: TypedLockedManifest = serde_json::from_value(json!({
+            "lockfile-version": 0,
+            "packages": {
+                "aarch64-darwin": {
+                    "hello": {
+                        "info": {
+                            "description": null,
+                            "broken": false,
+                            "license": "GPL-3.0-or-later",
+                            "pname": "hello",
+                            "unfree": false,
+                            "version": "2.12.1"
+                        },
+                        "attr-path": ["legacyPackages", "aarch64-darwin", "hello"],
+                        "input": {
+                            "attrs": {
+                                "owner": "NixOS",
+                                "repo": "nixpkgs",
+                                "rev": "ab5fd150146dcfe41fda501134e6503932cc8dfd",
+                                "type": "github"
+                            },
+                            "url": "github:NixOS/nixpkgs/ab5fd150146dcfe41fda501134e6503932cc8dfd"
+                        },
+                        "priority": 5
+                    }
+                },
+                "x86_64-linux": {
+                    "hello": {
+                        "info": {
+                            "description": null,
+                            "broken": false,
+                            "license": "GPL-3.0-or-later",
+                            "pname": "hello",
+                            "unfree": false,
+                            "version": "2.12.1"
+                        },
+                        "attr-path": ["legacyPackages", "x86_64-linux", "hello"],
+                        "input": {
+                            "attrs": {
+                                "owner": "NixOS",
+                                "repo": "nixpkgs",
+                                "rev": "ab5fd150146dcfe41fda501134e6503932cc8dfd",
+                                "type": "github"
+                            },
+                            "url": "github:NixOS/nixpkgs/ab5fd150146dcfe41fda501134e6503932cc8dfd"
+                        },
+                        "priority": 5
+                    },
+                    "gcc": null
+                }
+            },
+            "registry": {
+                "inputs": {
+                    "nixpkgs": {
+                        "from": {
+                            "owner": "NixOS",
+                            "repo": "nixpkgs",
+                            "rev": "ab5fd150146dcfe41fda501134e6503932cc8dfd",
+                            "type": "github"
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        Sbom::from_lockfile("project", &lockfile)
+    }
+
+    #[test]
+    fn packages_are_merged_across_systems() {
+        let sbom = sbom();
+        assert_eq!(sbom.packages.len(), 1);
+        assert_eq!(sbom.packages[0].systems, vec![
+            "aarch64-darwin".to_string(),
+            "x86_64-linux".to_string()
+        ]);
+        assert_eq!(sbom.packages[0].purl(), "pkg:nix/hello@2.12.1");
+    }
+
+    #[test]
+    fn spdx_document_describes_packages() {
+        let document = sbom().to_spdx();
+        assert_eq!(document["spdxVersion"], "SPDX-2.3");
+        assert_eq!(document["packages"][0]["SPDXID"], "SPDXRef-Package-0-hello");
+        assert_eq!(
+            document["packages"][0]["licenseDeclared"],
+            "GPL-3.0-or-later"
+        );
+        assert_eq!(
+            document["relationships"][0]["relatedSpdxElement"],
+            "SPDXRef-Package-0-hello"
+        );
+    }
+
+    #[test]
+    fn spdx_document_links_packages_to_inputs() {
+        let document = sbom().to_spdx();
+        let location =
+            "git+https://github.com/NixOS/nixpkgs@ab5fd150146dcfe41fda501134e6503932cc8dfd";
+        assert_eq!(document["packages"][0]["downloadLocation"], location);
+        assert_eq!(document["packages"][1]["SPDXID"], "SPDXRef-Input-nixpkgs");
+        assert_eq!(document["packages"][1]["downloadLocation"], location);
+        assert_eq!(
+            document["relationships"][1],
+            json!({
+                "spdxElementId": "SPDXRef-Package-0-hello",
+                "relationshipType": "GENERATED_FROM",
+                "relatedSpdxElement": "SPDXRef-Input-nixpkgs",
+            })
+        );
+    }
+
+    #[test]
+    fn download_location_uses_forge_host() {
+        let input = SbomInput::new(
+            None,
+            None,
+            &json!({
+                "type": "gitlab",
+                "host": "gitlab.gnome.org",
+                "owner": "GNOME",
+                "repo": "glib",
+                "rev": "abc"
+            }),
+        );
+        assert_eq!(
+            input.download_location.as_deref(),
+            Some("git+https://gitlab.gnome.org/GNOME/glib@abc")
+        );
+
+        let input = SbomInput::new(
+            None,
+            None,
+            &json!({ "type": "gitlab", "owner": "owner", "repo": "repo" }),
+        );
+        assert_eq!(
+            input.download_location.as_deref(),
+            Some("git+https://gitlab.com/owner/repo")
+        );
+    }
+
+    #[test]
+    fn retain_system_drops_other_systems() {
+        let mut sbom = sbom();
+        sbom.retain_system(&"x86_64-linux".to_string());
+        assert_eq!(sbom.packages[0].systems, vec!["x86_64-linux".to_string()]);
+
+        sbom.retain_system(&"aarch64-linux".to_string());
+        assert!(sbom.packages.is_empty());
+    }
+
+    #[test]
+    fn cyclonedx_document_lists_vulnerabilities() {
+        let mut sbom = sbom();
+        sbom.packages[0].vulnerabilities.push(Vulnerability {
+            id: "OSV-2024-1".to_string(),
+            summary: Some("hello says goodbye".to_string()),
+            aliases: vec![],
+            severity: None,
+            fixed: Some("2.12.2".to_string()),
+        });
+
+        let document = sbom.to_cyclonedx();
+        assert_eq!(document["specVersion"], "1.5");
+        assert_eq!(document["components"][0]["version"], "2.12.1");
+        assert_eq!(document["vulnerabilities"][0]["id"], "OSV-2024-1");
+        assert_eq!(
+            document["vulnerabilities"][0]["affects"][0]["ref"],
+            document["components"][0]["bom-ref"]
+        );
+    }
+}
@@ -0,0 +1,521 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, Utc};
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use super::search::{do_search, PathOrJson, Query, SearchError, SearchParams, SearchResult};
+use crate::data::Version;
+use crate::flox::Flox;
+use crate::models::search::SearchResults;
+
+pub const SEARCH_INDEX_FILENAME: &str = "search-index.json";
+
+/// Errors encountered while building, reading or writing the search index.
+#[derive(Debug, thiserror::Error)]
+pub enum SearchIndexError {
+    #[error("couldn't collect packages for the search index")]
+    Search(#[source] SearchError),
+    #[error("couldn't open search index file")]
+    OpenIndex(#[source] std::io::Error),
+    #[error("couldn't parse search index")]
+    ParseIndex(#[source] serde_json::Error),
+    #[error("failed to open temporary file for search index")]
+    OpenTmpIndex(#[source] std::io::Error),
+    #[error("failed to write temporary search index file")]
+    WriteTmpIndex(#[source] serde_json::Error),
+    #[error("failed to rename temporary search index file")]
+    RenameIndex(#[source] tempfile::PersistError),
+    #[error("search index stored in an invalid location: {0}")]
+    InvalidIndexLocation(PathBuf),
+    #[error("version range '{0}' can't be evaluated with the search index")]
+    UnsupportedSemver(String),
+}
+
+/// A local copy of the packages available for search.
+///
+/// `pkgdb search` needs to evaluate or scrape its inputs,
+/// which is slow and may require network access.
+/// The index allows searching without either, at the cost of results
+/// that are only as recent as the last time the index was built.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchIndex {
+    /// The schema version of the search index file.
+    pub version: Version<1>,
+    /// When the index was built
+    pub created_at: DateTime<Utc>,
+    /// One result per package, i.e. deduplicated across versions and systems
+    pub packages: Vec<SearchResult>,
+}
+
+impl SearchIndex {
+    /// Build a new index of all packages available in the inputs of `global_manifest`
+    /// as locked by `lockfile`.
+    pub fn build(
+        global_manifest: PathOrJson,
+        lockfile: PathOrJson,
+    ) -> Result<Self, SearchIndexError> {
+        let query = Query {
+            deduplicate: true,
+            ..Default::default()
+        };
+        let params = SearchParams {
+            manifest: None,
+            global_manifest,
+            lockfile,
+            query,
+        };
+
+        let (results, _) = do_search(&params).map_err(SearchIndexError::Search)?;
+        debug!("built search index of {} packages", results.results.len());
+
+        Ok(Self {
+            version: Version,
+            created_at: Utc::now(),
+            packages: results.results,
+        })
+    }
+
+    /// How long ago the index was built
+    pub fn age(&self) -> Duration {
+        Utc::now() - self.created_at
+    }
+
+    /// Search the index for packages matching `query`.
+    ///
+    /// This supports the subset of [Query] used by `flox search`:
+    /// the `match`, `match-name` and `match-name-or-rel-path` terms are
+    /// matched case-insensitively as substrings, and `semver` is matched
+    /// against the version of the indexed package like pkgdb would,
+    /// see [SemverRange].
+    /// Ranges the index can't evaluate are rejected with [SearchIndexError::UnsupportedSemver].
+    ///
+    /// Results are ranked by how closely the package name matches the term,
+    /// exact matches first, then prefixes, then other matches.
+    pub fn search(&self, query: &Query) -> Result<SearchResults, SearchIndexError> {
+        let range = query
+            .semver
+            .as_deref()
+            .map(SemverRange::from_str)
+            .transpose()?;
+
+        let mut matches = self
+            .packages
+            .iter()
+            .filter(|package| {
+                range
+                    .as_ref()
+                    .is_none_or(|range| range.matches(package.version.as_deref()))
+            })
+            .filter_map(|package| Some((match_rank(package, query)?, package)))
+            .collect::<Vec<_>>();
+
+        matches.sort_by(|(rank_a, a), (rank_b, b)| {
+            rank_a
+                .cmp(rank_b)
+                .then_with(|| a.rel_path.len().cmp(&b.rel_path.len()))
+                .then_with(|| a.rel_path.cmp(&b.rel_path))
+        });
+
+        let count = matches.len() as u64;
+        let limit = query.limit.map(usize::from).unwrap_or(usize::MAX);
+
+        Ok(SearchResults {
+            results: matches
+                .into_iter()
+                .take(limit)
+                .map(|(_, package)| package.clone())
+                .collect(),
+            count: Some(count),
+        })
+    }
+}
+
+/// Rank how well `package` matches the search term of `query`,
+/// lower is better, [None] if it doesn't match at all.
+fn match_rank(package: &SearchResult, query: &Query) -> Option<u8> {
+    let (term, include_rel_path, include_description) = if let Some(term) = &query.r#match {
+        (term, true, true)
+    } else if let Some(term) = &query.match_name {
+        (term, false, false)
+    } else if let Some(term) = &query.match_name_or_rel_path {
+        (term, true, false)
+    } else {
+        return Some(0);
+    };
+    let term = term.to_lowercase();
+
+    let pname = package.pname.as_deref().unwrap_or_default().to_lowercase();
+    let attr_name = package
+        .rel_path
+        .last()
+        .map(|name| name.to_lowercase())
+        .unwrap_or_default();
+    let rel_path = package.rel_path.join(".").to_lowercase();
+
+    if pname == term || attr_name == term {
+        return Some(0);
+    }
+    if pname.starts_with(&term) || attr_name.starts_with(&term) {
+        return Some(1);
+    }
+    if pname.contains(&term) || attr_name.contains(&term) {
+        return Some(2);
+    }
+    if include_rel_path && rel_path.contains(&term) {
+        return Some(3);
+    }
+    if include_description
+        && package
+            .description
+            .as_deref()
+            .is_some_and(|description| description.to_lowercase().contains(&term))
+    {
+        return Some(4);
+    }
+    None
+}
+
+/// A semver range with the node-semver syntax that `pkgdb search` accepts,
+/// translated into [semver::VersionReq]s.
+///
+/// A range consists of alternatives separated by `||`,
+/// each either a hyphen range `A - B` or space separated comparators.
+/// Unlike Cargo requirements, a bare version like `1.2.3` matches exactly,
+/// and `x` may be used as a wildcard, e.g. `1.2.x`.
+///
+/// <https://github.com/npm/node-semver#ranges>
+#[derive(Debug)]
+struct SemverRange(Vec<semver::VersionReq>);
+
+impl FromStr for SemverRange {
+    type Err = SearchIndexError;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let unsupported = || SearchIndexError::UnsupportedSemver(range.to_string());
+
+        range
+            .split("||")
+            .map(|alternative| {
+                let comparators = node_comparators(alternative).ok_or_else(unsupported)?;
+                if comparators.is_empty() {
+                    return Ok(semver::VersionReq::STAR);
+                }
+                comparators.join(", ").parse().map_err(|_| unsupported())
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl SemverRange {
+    /// Check whether `version` is in the range.
+    ///
+    /// Versions with fewer than three components are padded with zeroes,
+    /// versions that can't be parsed never match.
+    fn matches(&self, version: Option<&str>) -> bool {
+        let Some(version) = version else {
+            return false;
+        };
+
+        let padded = match version.split('.').count() {
+            1 => format!("{version}.0.0"),
+            2 => format!("{version}.0"),
+            _ => version.to_string(),
+        };
+
+        semver::Version::parse(&padded)
+            .is_ok_and(|version| self.0.iter().any(|req| req.matches(&version)))
+    }
+}
+
+/// Translate one alternative of a node-semver range into Cargo comparators.
+///
+/// Returns [None] if the syntax is not understood.
+fn node_comparators(alternative: &str) -> Option<Vec<String>> {
+    let tokens = alternative.split_whitespace().collect::<Vec<_>>();
+
+    // `<=` with a partial version excludes the whole release series in both syntaxes,
+    // e.g. `1.2 - 1.4` is `>=1.2.0, <1.5.0`
+    if let [from, "-", to] = tokens[..] {
+        return Some(vec![
+            format!(">={}", node_version(from)?),
+            format!("<={}", node_version(to)?),
+        ]);
+    }
+
+    let mut comparators = vec![];
+    // an operator separated from its version by whitespace, e.g. `>= 1.2`
+    let mut operator = None;
+    for token in tokens {
+        let split = token
+            .find(|c: char| !"<>=~^".contains(c))
+            .unwrap_or(token.len());
+        let (token_operator, version) = token.split_at(split);
+        if version.is_empty() {
+            if operator.replace(token_operator).is_some() {
+                return None;
+            }
+            continue;
+        }
+        let token_operator = match operator.take() {
+            Some(_) if !token_operator.is_empty() => return None,
+            Some(operator) => operator,
+            None => token_operator,
+        };
+
+        let version = node_version(version)?;
+        if version == "*" {
+            // any version, the other comparators decide
+            continue;
+        }
+        match token_operator {
+            // bare versions match exactly, partial versions and wildcards match a series
+            "" | "=" if version.contains('*') => comparators.push(version),
+            "" | "=" => comparators.push(format!("={version}")),
+            "<" | "<=" | ">" | ">=" | "~" | "^" => {
+                comparators.push(format!("{token_operator}{version}"))
+            },
+            _ => return None,
+        }
+    }
+
+    if operator.is_some() {
+        return None;
+    }
+    Some(comparators)
+}
+
+/// Normalize a version of a node-semver range for Cargo,
+/// dropping a leading `v` and replacing `x` wildcards with `*`.
+fn node_version(version: &str) -> Option<String> {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    if version.is_empty() {
+        return None;
+    }
+
+    let mut components = version.split('.').map(|component| match component {
+        "x" | "X" => "*",
+        component => component,
+    });
+    // drop trailing wildcards after the first, `1.x.x` is `1.*`
+    let mut normalized = vec![];
+    for component in components.by_ref() {
+        normalized.push(component);
+        if component == "*" {
+            break;
+        }
+    }
+    Some(normalized.join("."))
+}
+
+/// Returns the path to the search index in the cache directory.
+pub fn search_index_path(flox: &Flox) -> PathBuf {
+    flox.cache_dir.join(SEARCH_INDEX_FILENAME)
+}
+
+/// Reads the search index.
+///
+/// Returns [None] if the index hasn't been built yet.
+pub fn read_search_index(
+    index_path: impl AsRef<Path>,
+) -> Result<Option<SearchIndex>, SearchIndexError> {
+    let index_path = index_path.as_ref();
+    if !index_path.exists() {
+        return Ok(None);
+    }
+    let f = File::open(index_path).map_err(SearchIndexError::OpenIndex)?;
+    let reader = BufReader::new(f);
+    let index = serde_json::from_reader(reader).map_err(SearchIndexError::ParseIndex)?;
+    Ok(Some(index))
+}
+
+/// Writes the search index to disk, replacing any existing index atomically.
+pub fn write_search_index(
+    index: &SearchIndex,
+    index_path: impl AsRef<Path>,
+) -> Result<(), SearchIndexError> {
+    let index_path = index_path.as_ref();
+    let parent = index_path
+        .parent()
+        .ok_or(SearchIndexError::InvalidIndexLocation(
+            index_path.to_path_buf(),
+        ))?;
+    let temp_file =
+        tempfile::NamedTempFile::new_in(parent).map_err(SearchIndexError::OpenTmpIndex)?;
+
+    let writer = BufWriter::new(&temp_file);
+    serde_json::to_writer(writer, index).map_err(SearchIndexError::WriteTmpIndex)?;
+    temp_file
+        .persist(index_path)
+        .map_err(SearchIndexError::RenameIndex)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::models::search::{SearchStrategy, Subtree};
+
+    fn package(rel_path: &str, pname: &str, version: &str, description: &str) -> SearchResult {
+        SearchResult {
+            input: "nixpkgs".to_string(),
+            abs_path: vec![],
+            subtree: Subtree::LegacyPackages,
+            system: "x86_64-linux".to_string(),
+            rel_path: rel_path.split('.').map(String::from).collect(),
+            pname: Some(pname.to_string()),
+            version: Some(version.to_string()),
+            description: Some(description.to_string()),
+            broken: None,
+            unfree: None,
+            license: None,
+            id: 0,
+        }
+    }
+
+    fn index() -> SearchIndex {
+        SearchIndex {
+            version: Version,
+            created_at: Utc::now(),
+            packages: vec![
+                package(
+                    "python311Packages.pip",
+                    "pip",
+                    "23.2.1",
+                    "PyPA package installer",
+                ),
+                package(
+                    "hello",
+                    "hello",
+                    "2.12.1",
+                    "A program that produces a greeting",
+                ),
+                package(
+                    "hello-wayland",
+                    "hello-wayland",
+                    "0.1",
+                    "Hello world for Wayland",
+                ),
+                package("gnused", "gnused", "4.9", "GNU sed, a stream editor"),
+                package("python3", "python3", "3.11.6", "A high-level language"),
+            ],
+        }
+    }
+
+    fn rel_paths(results: &SearchResults) -> Vec<String> {
+        results
+            .results
+            .iter()
+            .map(|r| r.rel_path.join("."))
+            .collect()
+    }
+
+    #[test]
+    fn ranks_exact_matches_first() {
+        let query = Query::new("hello", SearchStrategy::MatchNameOrRelPath, None, true).unwrap();
+        let results = index().search(&query).unwrap();
+        assert_eq!(rel_paths(&results), vec!["hello", "hello-wayland"]);
+        assert_eq!(results.count, Some(2));
+    }
+
+    #[test]
+    fn matches_descriptions_only_with_match_strategy() {
+        let query = Query::new("stream editor", SearchStrategy::Match, None, true).unwrap();
+        assert_eq!(rel_paths(&index().search(&query).unwrap()), vec!["gnused"]);
+
+        let query = Query::new(
+            "stream editor",
+            SearchStrategy::MatchNameOrRelPath,
+            None,
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            rel_paths(&index().search(&query).unwrap()),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn matches_rel_path_and_semver() {
+        let query = Query::new(
+            "python311Packages",
+            SearchStrategy::MatchNameOrRelPath,
+            None,
+            true,
+        )
+        .unwrap();
+        assert_eq!(rel_paths(&index().search(&query).unwrap()), vec![
+            "python311Packages.pip"
+        ]);
+
+        let query =
+            Query::new("hello@>=1", SearchStrategy::MatchNameOrRelPath, None, true).unwrap();
+        assert_eq!(rel_paths(&index().search(&query).unwrap()), vec!["hello"]);
+    }
+
+    #[test]
+    fn semver_ranges_use_node_semver_syntax() {
+        let matches = |range: &str, version: &str| {
+            SemverRange::from_str(range).unwrap().matches(Some(version))
+        };
+
+        // bare versions match exactly
+        assert!(matches("1.2.3", "1.2.3"));
+        assert!(!matches("1.2.3", "1.2.4"));
+        // partial versions and x-ranges match a series
+        assert!(matches("1.2", "1.2.9"));
+        assert!(matches("1.2.x", "1.2.9"));
+        assert!(!matches("1.2.x", "1.3.0"));
+        assert!(matches("1.x.x", "1.9.0"));
+        assert!(matches("*", "0.1"));
+        // hyphen ranges include the whole upper release series
+        assert!(matches("1.2 - 1.4", "1.4.7"));
+        assert!(!matches("1.2 - 1.4", "1.5.0"));
+        assert!(!matches("1.2 - 1.4", "1.1.9"));
+        // alternatives and space separated comparators
+        assert!(matches("<1 || >=2", "2.1"));
+        assert!(!matches("<1 || >=2", "1.5"));
+        assert!(matches(">=1.2 <1.4", "1.3.0"));
+        assert!(!matches(">= 1.2 < 1.4", "1.4.0"));
+        assert!(matches("^1.2", "1.9"));
+        assert!(matches("~1.2", "1.2.5"));
+        assert!(!matches("~1.2", "1.3"));
+
+        assert!(matches!(
+            SemverRange::from_str(">= <1"),
+            Err(SearchIndexError::UnsupportedSemver(_))
+        ));
+        assert!(matches!(
+            SemverRange::from_str("!1.2"),
+            Err(SearchIndexError::UnsupportedSemver(_))
+        ));
+    }
+
+    #[test]
+    fn limits_results_but_counts_all() {
+        let query = Query::new("p", SearchStrategy::MatchNameOrRelPath, Some(1), true).unwrap();
+        let results = index().search(&query).unwrap();
+        assert_eq!(results.results.len(), 1);
+        assert_eq!(results.count, Some(2));
+    }
+
+    #[test]
+    fn index_roundtrips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SEARCH_INDEX_FILENAME);
+        assert!(read_search_index(&path).unwrap().is_none());
+
+        let index = index();
+        write_search_index(&index, &path).unwrap();
+        let read = read_search_index(&path).unwrap().unwrap();
+        assert_eq!(read.created_at, index.created_at);
+        assert_eq!(read.packages, index.packages);
+    }
+}
@@ -0,0 +1,79 @@
+//! Comparing versions of nix packages
+
+use std::cmp::Ordering;
+
+/// Compare versions the way nix does (`builtins.compareVersions`)
+///
+/// Versions are split into numeric and alphabetic components,
+/// numbers are compared numerically, `pre` sorts before anything else,
+/// and numbers sort after letters, i.e. `2.3a` < `2.3.1`.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let mut a = version_components(a).into_iter();
+    let mut b = version_components(b).into_iter();
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (c1, c2) => {
+                let c1 = c1.unwrap_or_default();
+                let c2 = c2.unwrap_or_default();
+                if components_lt(&c1, &c2) {
+                    return Ordering::Less;
+                }
+                if components_lt(&c2, &c1) {
+                    return Ordering::Greater;
+                }
+            },
+        }
+    }
+}
+
+fn version_components(version: &str) -> Vec<String> {
+    let mut components = Vec::new();
+    let mut chars = version.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c == '.' || c == '-' {
+            chars.next();
+            continue;
+        }
+        let numeric = c.is_ascii_digit();
+        let mut component = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == '.' || c == '-' || c.is_ascii_digit() != numeric {
+                break;
+            }
+            component.push(c);
+            chars.next();
+        }
+        components.push(component);
+    }
+    components
+}
+
+fn components_lt(c1: &str, c2: &str) -> bool {
+    let n1 = c1.parse::<u64>().ok();
+    let n2 = c2.parse::<u64>().ok();
+    match (n1, n2) {
+        (Some(n1), Some(n2)) => n1 < n2,
+        _ if c1.is_empty() && n2.is_some() => true,
+        _ if c1 == "pre" && c2 != "pre" => true,
+        _ if c2 == "pre" => false,
+        (_, Some(_)) => true,
+        (Some(_), _) => false,
+        _ => c1 < c2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nix_version_ordering() {
+        assert_eq!(compare_versions("1.0", "1.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2", "1.10"), Ordering::Less);
+        assert_eq!(compare_versions("2.3a", "2.3.1"), Ordering::Less);
+        assert_eq!(compare_versions("2.3pre1", "2.3"), Ordering::Less);
+        assert_eq!(compare_versions("2.3", "2.3.0"), Ordering::Less);
+        assert_eq!(compare_versions("3.0", "2.99"), Ordering::Greater);
+    }
+}
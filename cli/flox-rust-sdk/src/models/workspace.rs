@@ -0,0 +1,227 @@
+//! Workspaces group the environments of a monorepo
+//!
+//! A workspace is a directory containing a `flox.workspace.toml`
+//! that lists the directories of its member environments
+//! relative to the workspace directory:
+//!
+//! ```toml
+//! members = ["services/api", "services/web", "tools/*"]
+//! ```
+//!
+//! A member ending in `/*` includes every subdirectory that contains a `.flox`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::environment::{CanonicalPath, CanonicalizeError, DOT_FLOX};
+use crate::providers::git::{
+    AnyGitProvider,
+    GitCommandDiscoverError,
+    GitDiscoverError,
+    GitProvider,
+};
+
+pub const WORKSPACE_FILENAME: &str = "flox.workspace.toml";
+
+#[derive(Debug, Error)]
+pub enum WorkspaceError {
+    #[error(transparent)]
+    StartDiscoveryDir(CanonicalizeError),
+    #[error("error checking if in a git repo")]
+    DiscoverGitDirectory(#[source] GitCommandDiscoverError),
+    #[error("could not read workspace file '{0}'")]
+    Read(PathBuf, #[source] std::io::Error),
+    #[error("could not parse workspace file '{0}'")]
+    Parse(PathBuf, #[source] toml::de::Error),
+    #[error("could not read workspace members in '{0}'")]
+    ReadMembers(PathBuf, #[source] std::io::Error),
+    #[error("workspace member '{0}' does not contain a .flox directory")]
+    MemberNotFound(String),
+    #[error("workspace member '{0}' is outside of the workspace")]
+    MemberOutsideWorkspace(String),
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkspaceFile {
+    #[serde(default)]
+    members: Vec<String>,
+}
+
+/// A workspace and the directories of its member environments
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workspace {
+    /// The directory containing the `flox.workspace.toml`
+    pub root: PathBuf,
+    /// Directories containing a `.flox`, in the order they are listed
+    pub members: Vec<PathBuf>,
+}
+
+impl Workspace {
+    /// Search for a `flox.workspace.toml` like [super::environment::find_dot_flox]
+    /// searches for a `.flox`:
+    /// in the current directory, and if it's contained by a git repo,
+    /// in its parent directories up to the repo toplevel.
+    pub fn find(initial_dir: &Path) -> Result<Option<Self>, WorkspaceError> {
+        let path = CanonicalPath::new(initial_dir).map_err(WorkspaceError::StartDiscoveryDir)?;
+
+        if path.join(WORKSPACE_FILENAME).exists() {
+            return Self::open(&path).map(Some);
+        }
+
+        let toplevel = match AnyGitProvider::discover(&path) {
+            Ok(repo) if repo.workdir().is_some() => repo.workdir().unwrap().to_owned(),
+            Ok(_) => return Ok(None),
+            Err(e) if e.not_found() => return Ok(None),
+            Err(e) => Err(WorkspaceError::DiscoverGitDirectory(e))?,
+        };
+
+        for ancestor in path.ancestors().skip(1) {
+            if !ancestor.starts_with(&toplevel) {
+                debug!("git boundary reached: path={}", ancestor.display());
+                return Ok(None);
+            }
+            if ancestor.join(WORKSPACE_FILENAME).exists() {
+                return Self::open(ancestor).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Open the workspace in `root`, which must contain a `flox.workspace.toml`
+    pub fn open(root: &Path) -> Result<Self, WorkspaceError> {
+        let root = CanonicalPath::new(root)
+            .map_err(WorkspaceError::StartDiscoveryDir)?
+            .into_path_buf();
+        let path = root.join(WORKSPACE_FILENAME);
+        debug!("opening workspace: path={}", path.display());
+        let contents =
+            fs::read_to_string(&path).map_err(|e| WorkspaceError::Read(path.clone(), e))?;
+        let file: WorkspaceFile =
+            toml::from_str(&contents).map_err(|e| WorkspaceError::Parse(path.clone(), e))?;
+
+        let mut members = Vec::new();
+        for member in &file.members {
+            for member_path in Self::resolve_member(&root, member)? {
+                if !members.contains(&member_path) {
+                    members.push(member_path);
+                }
+            }
+        }
+
+        Ok(Workspace { root, members })
+    }
+
+    /// The directories of a single entry of `members`
+    ///
+    /// Entries and the directories they match, e.g. via symlinks,
+    /// must be within the workspace.
+    fn resolve_member(root: &Path, member: &str) -> Result<Vec<PathBuf>, WorkspaceError> {
+        if let Some(parent) = member.strip_suffix("/*").or((member == "*").then_some("")) {
+            let parent = Self::canonical_member(root, &root.join(parent), member)?;
+            let mut children = fs::read_dir(&parent)
+                .map_err(|e| WorkspaceError::ReadMembers(parent.clone(), e))?
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.join(DOT_FLOX).is_dir())
+                .map(|path| Self::canonical_member(root, &path, member))
+                .collect::<Result<Vec<_>, _>>()?;
+            children.sort();
+            return Ok(children);
+        }
+
+        let path = root.join(member);
+        if !path.join(DOT_FLOX).is_dir() {
+            return Err(WorkspaceError::MemberNotFound(member.to_string()));
+        }
+        Ok(vec![Self::canonical_member(root, &path, member)?])
+    }
+
+    /// Canonicalize a directory matched by the `members` entry `member`,
+    /// and ensure it is within the workspace
+    fn canonical_member(root: &Path, path: &Path, member: &str) -> Result<PathBuf, WorkspaceError> {
+        let path = CanonicalPath::new(path)
+            .map_err(|_| WorkspaceError::MemberNotFound(member.to_string()))?
+            .into_path_buf();
+        if !path.starts_with(root) {
+            return Err(WorkspaceError::MemberOutsideWorkspace(member.to_string()));
+        }
+        Ok(path)
+    }
+
+    /// The path of a member relative to the workspace, e.g. `services/api`
+    pub fn member_name(&self, member: &Path) -> String {
+        member
+            .strip_prefix(&self.root)
+            .unwrap_or(member)
+            .display()
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_listed_and_globbed_members() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = CanonicalPath::new(tempdir.path()).unwrap().into_path_buf();
+        for member in ["api", "tools/lint", "tools/fmt", "tools/docs"] {
+            fs::create_dir_all(root.join(member)).unwrap();
+        }
+        for member in ["api", "tools/lint", "tools/fmt"] {
+            fs::create_dir_all(root.join(member).join(DOT_FLOX)).unwrap();
+        }
+        fs::write(
+            root.join(WORKSPACE_FILENAME),
+            r#"members = ["api", "tools/*", "tools/lint"]"#,
+        )
+        .unwrap();
+
+        let workspace = Workspace::find(&root.join("api")).unwrap();
+        // not in a git repo, so only the current directory is searched
+        assert_eq!(workspace, None);
+
+        let workspace = Workspace::find(&root).unwrap().unwrap();
+        let names = workspace
+            .members
+            .iter()
+            .map(|member| workspace.member_name(member))
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["api", "tools/fmt", "tools/lint"]);
+
+        fs::write(root.join(WORKSPACE_FILENAME), r#"members = ["tools/docs"]"#).unwrap();
+        assert!(matches!(
+            Workspace::open(&root),
+            Err(WorkspaceError::MemberNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn globbed_members_must_be_in_workspace() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let outer = CanonicalPath::new(tempdir.path()).unwrap().into_path_buf();
+        let root = outer.join("workspace");
+        fs::create_dir_all(outer.join("other").join(DOT_FLOX)).unwrap();
+        fs::create_dir_all(root.join("api").join(DOT_FLOX)).unwrap();
+
+        fs::write(root.join(WORKSPACE_FILENAME), r#"members = ["../*"]"#).unwrap();
+        assert!(matches!(
+            Workspace::open(&root),
+            Err(WorkspaceError::MemberOutsideWorkspace(_))
+        ));
+
+        // a symlink matched by a glob can't point outside either
+        std::os::unix::fs::symlink(outer.join("other"), root.join("linked")).unwrap();
+        fs::write(root.join(WORKSPACE_FILENAME), r#"members = ["*"]"#).unwrap();
+        assert!(matches!(
+            Workspace::open(&root),
+            Err(WorkspaceError::MemberOutsideWorkspace(_))
+        ));
+    }
+}
@@ -5,12 +5,13 @@ use enum_dispatch::enum_dispatch;
 use thiserror::Error;
 
 use crate::data::System;
+use crate::flox::FloxhubToken;
 
 pub const DEFAULT_CATALOG_URL: &str = "https://flox-catalog.flox.dev";
 
 /// Either a client for the actual catalog service,
 /// or a mock client for testing.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[enum_dispatch(ClientTrait)]
 pub enum Client {
     Catalog(CatalogClient),
@@ -20,7 +21,7 @@ pub enum Client {
 /// A client for the catalog service.
 ///
 /// This is a wrapper around the auto-generated APIClient.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CatalogClient {
     client: APIClient,
 }
@@ -30,9 +31,35 @@ impl CatalogClient {
             client: APIClient::new(DEFAULT_CATALOG_URL),
         }
     }
+
+    /// Create a client that authenticates requests with a FloxHub token
+    pub fn with_floxhub_token(token: &FloxhubToken) -> Self {
+        let mut headers = reqwest::header::HeaderMap::new();
+        match reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token.secret())) {
+            Ok(mut value) => {
+                value.set_sensitive(true);
+                headers.insert(reqwest::header::AUTHORIZATION, value);
+            },
+            // a token that passed validation is always a valid header value,
+            // fall back to an unauthenticated client just in case
+            Err(_) => return Self::new(),
+        }
+
+        let timeout = std::time::Duration::from_secs(15);
+        let client = reqwest::ClientBuilder::new()
+            .connect_timeout(timeout)
+            .timeout(timeout)
+            .default_headers(headers)
+            .build()
+            .expect("client configuration is valid");
+
+        Self {
+            client: APIClient::new_with_client(DEFAULT_CATALOG_URL, client),
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MockClient;
 
 impl Default for CatalogClient {
@@ -54,6 +81,7 @@ pub trait ClientTrait {
 
 #[async_trait]
 impl ClientTrait for CatalogClient {
+    #[tracing::instrument(name = "catalog.resolve", skip_all, fields(groups = package_groups.len()))]
     async fn resolve(
         &self,
         package_groups: Vec<PackageGroup>,
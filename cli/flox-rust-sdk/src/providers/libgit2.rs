@@ -0,0 +1,1442 @@
+use std::cell::{Cell, RefCell};
+use std::ffi::{OsStr, OsString};
+use std::io::Write;
+use std::os::unix::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use git2::build::{CheckoutBuilder, RepoBuilder};
+use git2::{
+    AutotagOption,
+    BranchType,
+    Cred,
+    CredentialType,
+    ErrorClass,
+    ErrorCode,
+    FetchOptions,
+    IndexAddOption,
+    ObjectType,
+    Oid,
+    PushOptions,
+    Remote,
+    RemoteCallbacks,
+    Repository,
+    RepositoryOpenFlags,
+    Signature,
+};
+use log::{debug, warn};
+
+use super::git::{
+    BranchInfo,
+    GitCommandBranchHashError,
+    GitCommandDiscoverError,
+    GitCommandError,
+    GitCommandGetOriginError,
+    GitCommandOpenError,
+    GitCommandOptions,
+    GitProvider,
+    GitRemoteCommandError,
+    OriginInfo,
+};
+
+/// A representation of a git repository using libgit2
+///
+/// This implements the same operations as
+/// [GitCommandProvider](super::git::GitCommandProvider),
+/// but doesn't require a `git` binary.
+/// Errors returned by libgit2 carry an error code,
+/// which is used to produce typed errors such as [GitRemoteCommandError::AccessDenied]
+/// rather than matching on the output of git.
+///
+/// [GitCommandOptions] are interpreted as follows:
+///
+/// * `user.name` and `user.email` set the identity used for commits
+/// * `remote.<name>.url` defines remotes that are not stored in the repository
+/// * `credential.helper` and `credential.<url>.helper` are run to get credentials
+///   with the configured environment variables
+///
+/// The git binary configured in the options is not used.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Libgit2Provider {
+    options: GitCommandOptions,
+    workdir: Option<PathBuf>,
+    path: PathBuf,
+}
+
+impl From<git2::Error> for GitRemoteCommandError {
+    fn from(err: git2::Error) -> Self {
+        match err.code() {
+            ErrorCode::Auth => {
+                debug!("Access denied: {err}");
+                GitRemoteCommandError::AccessDenied
+            },
+            ErrorCode::NotFastForward => {
+                debug!("Branches diverged: {err}");
+                GitRemoteCommandError::Diverged
+            },
+            _ => GitRemoteCommandError::Command(GitCommandError::Library(err)),
+        }
+    }
+}
+
+impl Libgit2Provider {
+    fn repo(&self) -> Result<Repository, git2::Error> {
+        Repository::open_ext(
+            &self.path,
+            RepositoryOpenFlags::NO_SEARCH,
+            std::iter::empty::<&OsStr>(),
+        )
+    }
+
+    fn from_repo(options: GitCommandOptions, repo: &Repository, path: &Path) -> Self {
+        Libgit2Provider {
+            options,
+            workdir: repo.workdir().map(|_| path.to_path_buf()),
+            path: path.to_path_buf(),
+        }
+    }
+
+    pub fn init_with<P: AsRef<Path>>(
+        options: GitCommandOptions,
+        path: P,
+        bare: bool,
+    ) -> Result<Libgit2Provider, GitCommandError> {
+        let repo = if bare {
+            Repository::init_bare(&path)?
+        } else {
+            Repository::init(&path)?
+        };
+
+        Ok(Self::from_repo(options, &repo, path.as_ref()))
+    }
+
+    /// Open a repo, erroring if `path` is not a repo or is a subdirectory of a repo
+    pub fn open_with<P: AsRef<Path>>(
+        options: GitCommandOptions,
+        path: P,
+    ) -> Result<Self, GitCommandOpenError> {
+        debug!("attempting to open repo: path={}", path.as_ref().display());
+        let repo = match Repository::open_ext(
+            path.as_ref(),
+            RepositoryOpenFlags::NO_SEARCH,
+            std::iter::empty::<&OsStr>(),
+        ) {
+            Ok(repo) => repo,
+            Err(err) if err.code() == ErrorCode::NotFound => {
+                if Repository::discover(path.as_ref()).is_ok() {
+                    return Err(GitCommandOpenError::Subdirectory);
+                }
+                Err(GitCommandDiscoverError::Command(err.into()))?
+            },
+            Err(err) => Err(GitCommandDiscoverError::Command(err.into()))?,
+        };
+
+        let bare = repo.is_bare();
+        let toplevel_or_git_dir = match repo.workdir() {
+            Some(workdir) if !bare => workdir,
+            _ => repo.path(),
+        };
+        let resolved_path = toplevel_or_git_dir
+            .canonicalize()
+            .map_err(GitCommandOpenError::Canonicalize)?;
+
+        let path = path
+            .as_ref()
+            .canonicalize()
+            .map_err(GitCommandOpenError::Canonicalize)?;
+
+        if resolved_path != path {
+            return Err(GitCommandOpenError::Subdirectory);
+        }
+        debug!("canonicalized path: path={}", path.display());
+
+        Ok(Libgit2Provider {
+            options,
+            workdir: (!bare).then(|| path.clone()),
+            path,
+        })
+    }
+
+    /// Open a repo with default options,
+    /// erroring if `path` is not a repo or is a subdirectory of a repo
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, GitCommandOpenError> {
+        Self::open_with(GitCommandOptions::default(), path)
+    }
+
+    /// Discover a git repository at or above `path`
+    pub fn discover_with<P: AsRef<Path>>(
+        options: GitCommandOptions,
+        path: P,
+    ) -> Result<Self, GitCommandDiscoverError> {
+        let repo = Repository::discover(path.as_ref()).map_err(GitCommandError::from)?;
+
+        let Some(workdir) = repo.workdir().filter(|_| !repo.is_bare()) else {
+            return Ok(Libgit2Provider {
+                options,
+                workdir: None,
+                path: path.as_ref().to_path_buf(),
+            });
+        };
+
+        let workdir = workdir.canonicalize().map_err(GitCommandError::from)?;
+
+        Ok(Libgit2Provider {
+            options,
+            workdir: Some(workdir.clone()),
+            path: workdir,
+        })
+    }
+
+    /// Checks if the specified revision identifies a commit in the repo
+    pub fn contains_commit(&self, rev: &str) -> Result<bool, GitCommandError> {
+        let repo = self.repo()?;
+        let result = repo
+            .revparse_single(&format!("{rev}^{{commit}}"))
+            .map(|_| ());
+        match result {
+            Ok(()) => Ok(true),
+            Err(err)
+                if matches!(
+                    err.code(),
+                    ErrorCode::NotFound | ErrorCode::InvalidSpec | ErrorCode::Ambiguous
+                ) =>
+            {
+                Ok(false)
+            },
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Check if commit exists and is part of the branch's history
+    pub fn branch_contains_commit(
+        &self,
+        commit: &str,
+        branch: &str,
+    ) -> Result<bool, GitCommandError> {
+        if !self.contains_commit(commit)? {
+            return Ok(false);
+        }
+
+        let repo = self.repo()?;
+        let commit = repo.revparse_single(&format!("{commit}^{{commit}}"))?.id();
+        let branch = repo.revparse_single(&format!("{branch}^{{commit}}"))?.id();
+
+        Ok(commit == branch || repo.graph_descendant_of(branch, commit)?)
+    }
+
+    /// Create branch at a specified revision
+    pub fn create_branch(&self, name: &str, rev: &str) -> Result<(), GitCommandError> {
+        let repo = self.repo()?;
+        let commit = repo.revparse_single(rev)?.peel_to_commit()?;
+        repo.branch(name, &commit, false)?;
+        Ok(())
+    }
+
+    /// Reset branch to rev or create it if it does not exist
+    pub fn reset_branch(&self, name: &str, rev: &str) -> Result<(), GitCommandError> {
+        let repo = self.repo()?;
+        let commit = repo.revparse_single(rev)?.peel_to_commit()?;
+        repo.branch(name, &commit, true)?;
+        Ok(())
+    }
+
+    /// Return the hash of a branch or error if it does not exist
+    pub fn branch_hash(&self, name: &str) -> Result<String, GitCommandBranchHashError> {
+        let repo = self.repo().map_err(GitCommandError::from)?;
+        match repo.refname_to_id(&format!("refs/heads/{name}")) {
+            Ok(oid) => Ok(oid.to_string()),
+            Err(err) if err.code() == ErrorCode::NotFound => {
+                Err(GitCommandBranchHashError::DoesNotExist)
+            },
+            Err(err) => Err(GitCommandError::from(err).into()),
+        }
+    }
+
+    pub fn has_branch(&self, name: &str) -> Result<bool, GitCommandBranchHashError> {
+        match self.branch_hash(name) {
+            Ok(_) => Ok(true),
+            Err(GitCommandBranchHashError::DoesNotExist) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Clone a branch from a remote repository
+    pub fn clone_branch_with(
+        options: GitCommandOptions,
+        origin: impl AsRef<OsStr>,
+        path: impl AsRef<Path>,
+        branch: impl AsRef<OsStr>,
+        bare: bool,
+    ) -> Result<Libgit2Provider, GitRemoteCommandError> {
+        let origin = origin.as_ref().to_string_lossy();
+        let branch = branch.as_ref().to_string_lossy().to_string();
+
+        let refspec = if bare {
+            format!("+refs/heads/{branch}:refs/heads/{branch}")
+        } else {
+            format!("+refs/heads/{branch}:refs/remotes/origin/{branch}")
+        };
+
+        let repo = if bare {
+            Repository::init_bare(&path)?
+        } else {
+            Repository::init(&path)?
+        };
+
+        // Like `git clone --single-branch`, only fetch the requested branch
+        {
+            let mut remote = repo.remote_with_fetch("origin", &origin, &refspec)?;
+            match local_path(&origin) {
+                Some(source) => {
+                    if fetch_local(&repo, &source, &refspec)?.is_none() {
+                        debug!("Ref not found: {branch}");
+                        return Err(GitRemoteCommandError::RefNotFound(branch));
+                    }
+                },
+                None => {
+                    let credentials = Credentials::new(&options);
+                    let mut fetch_options = FetchOptions::new();
+                    fetch_options
+                        .remote_callbacks(credentials.callbacks())
+                        .download_tags(AutotagOption::None);
+
+                    remote
+                        .fetch(&[&refspec], Some(&mut fetch_options), None)
+                        .map_err(|err| remote_error(err, Some(&branch)))?;
+                },
+            }
+        }
+
+        let fetched = if bare {
+            format!("refs/heads/{branch}")
+        } else {
+            format!("refs/remotes/origin/{branch}")
+        };
+        let commit = match repo.find_reference(&fetched) {
+            Ok(reference) => reference.peel_to_commit()?,
+            Err(err) if err.code() == ErrorCode::NotFound => {
+                debug!("Ref not found: {branch}");
+                return Err(GitRemoteCommandError::RefNotFound(branch));
+            },
+            Err(err) => Err(err)?,
+        };
+
+        if !bare {
+            repo.branch(&branch, &commit, true)?;
+            let mut config = repo.config()?;
+            config.set_str(&format!("branch.{branch}.remote"), "origin")?;
+            config.set_str(
+                &format!("branch.{branch}.merge"),
+                &format!("refs/heads/{branch}"),
+            )?;
+        }
+        repo.set_head(&format!("refs/heads/{branch}"))?;
+        if !bare {
+            repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+        }
+
+        Ok(Self::from_repo(options, &repo, path.as_ref()))
+    }
+
+    /// Clone a branch from a remote repository using default options
+    pub fn clone_branch(
+        origin: impl AsRef<OsStr>,
+        path: impl AsRef<Path>,
+        branch: &str,
+        bare: bool,
+    ) -> Result<Libgit2Provider, GitRemoteCommandError> {
+        Self::clone_branch_with(GitCommandOptions::default(), origin, path, branch, bare)
+    }
+
+    /// Fetch branch and update the corresponding local ref
+    pub fn fetch_branch(
+        &self,
+        repository: &str,
+        branch: &str,
+    ) -> Result<(), GitRemoteCommandError> {
+        self.fetch_ref(
+            repository,
+            &format!("refs/heads/{branch}:refs/heads/{branch}"),
+        )
+    }
+
+    pub fn fetch_ref(&self, repository: &str, r#ref: &str) -> Result<(), GitRemoteCommandError> {
+        let repo = self.repo()?;
+        let mut remote = self.remote(&repo, repository)?;
+        let src = r#ref
+            .trim_start_matches('+')
+            .split_once(':')
+            .map_or(r#ref.trim_start_matches('+'), |(src, _)| src);
+
+        if let Some(source) = remote.url().and_then(local_path) {
+            return match fetch_local(&repo, &source, r#ref)? {
+                Some(_) => Ok(()),
+                None => {
+                    debug!("Ref not found: {src}");
+                    Err(GitRemoteCommandError::RefNotFound(src.to_string()))
+                },
+            };
+        }
+
+        let credentials = Credentials::new(&self.options);
+        let mut fetch_options = FetchOptions::new();
+        fetch_options
+            .remote_callbacks(credentials.callbacks())
+            .download_tags(AutotagOption::None);
+
+        let result = remote.fetch(&[r#ref], Some(&mut fetch_options), None);
+
+        // libgit2 skips refspecs that don't match a remote ref,
+        // so check explicitly that the requested ref was advertised.
+        let missing = Oid::from_str(src).is_err()
+            && remote.list().is_ok_and(|heads| {
+                !heads.iter().any(|head| {
+                    [
+                        src.to_string(),
+                        format!("refs/heads/{src}"),
+                        format!("refs/tags/{src}"),
+                    ]
+                    .contains(&head.name().to_string())
+                })
+            });
+        if missing {
+            debug!("Ref not found: {src}");
+            return Err(GitRemoteCommandError::RefNotFound(src.to_string()));
+        }
+
+        result.map_err(|err| remote_error(err, Some(src)))
+    }
+
+    /// Like [GitProvider::push] but allows to specify the refspec explicitly
+    pub fn push_ref(
+        &self,
+        repository: impl AsRef<str>,
+        push_spec: impl AsRef<str>,
+        force: bool,
+    ) -> Result<(), GitRemoteCommandError> {
+        let repo = self.repo()?;
+        let mut remote = self.remote(&repo, repository.as_ref())?;
+        let refspec = expand_push_spec(&repo, push_spec.as_ref(), force)?;
+        self.push_refspec(&mut remote, &refspec)
+    }
+
+    fn push_refspec(
+        &self,
+        remote: &mut Remote,
+        refspec: &str,
+    ) -> Result<(), GitRemoteCommandError> {
+        let rejected = RefCell::new(None);
+        {
+            let credentials = Credentials::new(&self.options);
+            let mut callbacks = credentials.callbacks();
+            callbacks.push_update_reference(|reference, status| {
+                if let Some(status) = status {
+                    *rejected.borrow_mut() = Some((reference.to_string(), status.to_string()));
+                }
+                Ok(())
+            });
+            let mut push_options = PushOptions::new();
+            push_options.remote_callbacks(callbacks);
+
+            remote.push(&[refspec], Some(&mut push_options))?;
+        }
+
+        match rejected.into_inner() {
+            Some((reference, status)) => Err(push_rejected(&reference, &status)),
+            None => Ok(()),
+        }
+    }
+
+    /// Deletes the specified branch
+    pub fn delete_branch(&self, branch: &str, force: bool) -> Result<(), GitCommandError> {
+        let repo = self.repo()?;
+        let mut local = repo.find_branch(branch, BranchType::Local)?;
+
+        if !force {
+            let branch_oid = local.get().peel_to_commit()?.id();
+            if let Ok(head) = repo.head().and_then(|head| head.peel_to_commit()) {
+                if head.id() != branch_oid && !repo.graph_descendant_of(head.id(), branch_oid)? {
+                    return Err(git2::Error::new(
+                        ErrorCode::GenericError,
+                        ErrorClass::Reference,
+                        format!("the branch '{branch}' is not fully merged"),
+                    )
+                    .into());
+                }
+            }
+        }
+
+        local.delete()?;
+        Ok(())
+    }
+
+    /// Update the options used by this provider.
+    pub fn set_options(&mut self, options: GitCommandOptions) {
+        self.options = options;
+    }
+
+    /// Get the options used by this provider
+    pub fn get_options(&self) -> &GitCommandOptions {
+        &self.options
+    }
+
+    /// Get a mutable reference to the options used by this provider
+    pub fn get_options_mut(&mut self) -> &mut GitCommandOptions {
+        &mut self.options
+    }
+
+    /// Look up a remote by name,
+    /// preferring remotes configured in the options over those stored in the repo.
+    /// If there is no remote with that name, `name` is used as a url.
+    fn remote<'r>(&self, repo: &'r Repository, name: &str) -> Result<Remote<'r>, git2::Error> {
+        if let Some(url) = self.options.get_config_flag(&format!("remote.{name}.url")) {
+            return repo.remote_anonymous(url);
+        }
+        match repo.find_remote(name) {
+            Err(err) if err.code() == ErrorCode::NotFound => repo.remote_anonymous(name),
+            result => result,
+        }
+    }
+
+    /// The identity used for commits
+    ///
+    /// Like git, this prefers `user.name` and `user.email` from the options,
+    /// then the `GIT_AUTHOR_*` environment variables, then the repository config.
+    fn signature(&self, repo: &Repository) -> Result<Signature<'static>, git2::Error> {
+        let config = repo.config()?;
+        let lookup = |key: &str, var: &str| {
+            self.options
+                .get_config_flag(key)
+                .map(String::from)
+                .or_else(|| self.options.get_env_var(var).map(String::from))
+                .or_else(|| std::env::var(var).ok())
+                .or_else(|| config.get_string(key).ok())
+        };
+
+        match (
+            lookup("user.name", "GIT_AUTHOR_NAME"),
+            lookup("user.email", "GIT_AUTHOR_EMAIL"),
+        ) {
+            (Some(name), Some(email)) => Signature::now(&name, &email),
+            _ => Err(git2::Error::new(
+                ErrorCode::NotFound,
+                ErrorClass::Config,
+                "no identity configured for commits, set user.name and user.email",
+            )),
+        }
+    }
+
+    /// Sign a commit buffer like `git commit -S` would,
+    /// using the key and format configured in the options
+    ///
+    /// * `openpgp`: `gpg --status-fd=2 -bsau <key>`
+    /// * `ssh`: `ssh-keygen -Y sign -n git -f <key>`
+    fn sign(&self, content: &str) -> Result<String, git2::Error> {
+        let error = |message: String| {
+            git2::Error::new(ErrorCode::GenericError, ErrorClass::Object, message)
+        };
+
+        let key = self
+            .options
+            .get_config_flag("user.signingkey")
+            .ok_or_else(|| error("no signing key configured (user.signingkey)".to_string()))?;
+
+        let mut command = match self.options.get_config_flag("gpg.format") {
+            None | Some("openpgp") => {
+                let mut command = Command::new("gpg");
+                command.args(["--status-fd=2", "-bsau", key]);
+                command
+            },
+            Some("ssh") => {
+                let mut command = Command::new("ssh-keygen");
+                command.args(["-Y", "sign", "-n", "git", "-f", key]);
+                command
+            },
+            Some(format) => Err(error(format!("unsupported signing format '{format}'")))?,
+        };
+
+        debug!("signing commit: {command:?}");
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| error(format!("could not run signing program: {e}")))?;
+
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(content.as_bytes())
+            .map_err(|e| error(format!("could not pass commit to signing program: {e}")))?;
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| error(format!("signing program failed: {e}")))?;
+        if !output.status.success() {
+            Err(error(format!(
+                "failed to sign commit: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )))?;
+        }
+
+        String::from_utf8(output.stdout)
+            .map_err(|_| error("signature is not valid utf-8".to_string()))
+    }
+
+    /// Resolve `path` relative to the working directory,
+    /// which is how libgit2 addresses paths in the index
+    fn relative_path(&self, path: &Path) -> PathBuf {
+        if path.is_relative() {
+            return path.to_path_buf();
+        }
+        let workdir = self.workdir.as_deref().unwrap_or(&self.path);
+        if let Ok(relative) = path.strip_prefix(workdir) {
+            return relative.to_path_buf();
+        }
+
+        // The path may refer to the workdir through a symlink,
+        // or may not exist anymore, so canonicalize its parent.
+        let canonical = |path: &Path| {
+            let parent = path.parent()?.canonicalize().ok()?;
+            Some(parent.join(path.file_name()?))
+        };
+        match (workdir.canonicalize(), canonical(path)) {
+            (Ok(workdir), Some(path)) => path
+                .strip_prefix(workdir)
+                .map(Path::to_path_buf)
+                .unwrap_or(path),
+            _ => path.to_path_buf(),
+        }
+    }
+
+    /// Name of the branch HEAD points to, even if it has no commits yet
+    fn head_branch(repo: &Repository) -> Result<String, git2::Error> {
+        let head = repo.find_reference("HEAD")?;
+        let target = head.symbolic_target().ok_or_else(|| {
+            git2::Error::new(
+                ErrorCode::GenericError,
+                ErrorClass::Reference,
+                "HEAD does not point to a branch",
+            )
+        })?;
+        Ok(target.trim_start_matches("refs/heads/").to_string())
+    }
+}
+
+/// Map errors from remote operations,
+/// reporting a missing ref if libgit2 failed to find `reference`.
+fn remote_error(err: git2::Error, reference: Option<&str>) -> GitRemoteCommandError {
+    match reference {
+        Some(reference)
+            if err.code() == ErrorCode::NotFound && err.class() == ErrorClass::Reference =>
+        {
+            debug!("Ref not found: {reference}");
+            GitRemoteCommandError::RefNotFound(reference.to_string())
+        },
+        _ => err.into(),
+    }
+}
+
+/// Convert the status of a reference the remote rejected to update into an error
+///
+/// Like [GitCommandProvider](super::git::GitCommandProvider),
+/// rejections because the remote has commits the local branch doesn't have
+/// are reported as [GitRemoteCommandError::Diverged].
+fn push_rejected(reference: &str, status: &str) -> GitRemoteCommandError {
+    if status.contains("non-fast-forward") || status.contains("fetch first") {
+        debug!("Branches diverged: {reference}: {status}");
+        return GitRemoteCommandError::Diverged;
+    }
+    GitCommandError::Library(git2::Error::new(
+        ErrorCode::GenericError,
+        ErrorClass::Reference,
+        format!("remote rejected {reference}: {status}"),
+    ))
+    .into()
+}
+
+/// The path of the repository `url` refers to if it is on the local filesystem
+fn local_path(url: &str) -> Option<PathBuf> {
+    if let Some(path) = url.strip_prefix("file://") {
+        return Some(PathBuf::from(path));
+    }
+    if url.contains("://") {
+        return None;
+    }
+    let path = Path::new(url);
+    path.exists().then(|| path.to_path_buf())
+}
+
+/// Fetch `refspec` from the repository at `source` on the local filesystem
+///
+/// libgit2's local transport sends every object reachable from any ref of the
+/// source repository,
+/// so instead only the objects reachable from the requested ref are copied,
+/// as `git fetch` does.
+///
+/// Returns the fetched commit, or [None] if the source ref doesn't exist.
+fn fetch_local(
+    repo: &Repository,
+    source: &Path,
+    refspec: &str,
+) -> Result<Option<Oid>, git2::Error> {
+    let (force, spec) = match refspec.strip_prefix('+') {
+        Some(spec) => (true, spec),
+        None => (false, refspec),
+    };
+    let (src, dst) = match spec.split_once(':') {
+        Some((src, dst)) => (src, Some(dst)),
+        None => (spec, None),
+    };
+
+    let source = Repository::open(source)?;
+    let oid = match Oid::from_str(src) {
+        Ok(oid) if src.len() == 40 => source.odb()?.exists(oid).then_some(oid),
+        _ => [
+            src.to_string(),
+            format!("refs/heads/{src}"),
+            format!("refs/tags/{src}"),
+        ]
+        .iter()
+        .find_map(|name| source.refname_to_id(name).ok()),
+    };
+    let Some(oid) = oid else {
+        return Ok(None);
+    };
+
+    if !repo.odb()?.exists(oid) {
+        let mut walk = source.revwalk()?;
+        walk.push(oid)?;
+        // Don't copy history the destination already has
+        for reference in repo.references()? {
+            if let Some(target) = reference?.target() {
+                if source.odb()?.exists(target) {
+                    // Refs to objects other than commits can't be hidden
+                    let _ = walk.hide(target);
+                }
+            }
+        }
+
+        let mut builder = source.packbuilder()?;
+        builder.insert_walk(&mut walk)?;
+        let odb = repo.odb()?;
+        let mut writer = odb.packwriter()?;
+        builder.foreach(|chunk| writer.write_all(chunk).is_ok())?;
+        writer.commit()?;
+    }
+
+    if let Some(dst) = dst {
+        if !force {
+            if let Ok(current) = repo.refname_to_id(dst) {
+                if current != oid && !repo.graph_descendant_of(oid, current)? {
+                    return Err(git2::Error::new(
+                        ErrorCode::NotFastForward,
+                        ErrorClass::Reference,
+                        format!("cannot fetch '{src}' into '{dst}': not a fast-forward"),
+                    ));
+                }
+            }
+        }
+        repo.reference(dst, oid, true, &format!("fetch: {spec}"))?;
+    }
+
+    Ok(Some(oid))
+}
+
+/// Expand a push spec as accepted by `git push` into a refspec libgit2 understands
+///
+/// `HEAD` is resolved to the current branch,
+/// short branch names are expanded to `refs/heads/<name>`,
+/// and a missing destination defaults to the source.
+fn expand_push_spec(repo: &Repository, spec: &str, force: bool) -> Result<String, git2::Error> {
+    let (src, dst) = spec.split_once(':').unwrap_or((spec, spec));
+
+    let expand = |name: &str| -> Result<String, git2::Error> {
+        if name == "HEAD" {
+            Ok(format!(
+                "refs/heads/{}",
+                Libgit2Provider::head_branch(repo)?
+            ))
+        } else if name.starts_with("refs/") {
+            Ok(name.to_string())
+        } else {
+            Ok(format!("refs/heads/{name}"))
+        }
+    };
+
+    let force = if force { "+" } else { "" };
+    Ok(format!("{force}{}:{}", expand(src)?, expand(dst)?))
+}
+
+/// Provides credentials to libgit2 using the credential helpers
+/// configured in [GitCommandOptions],
+/// or the ssh agent for ssh remotes.
+///
+/// libgit2 asks for credentials again if they are rejected,
+/// so only a single attempt is made before failing with [ErrorCode::Auth].
+struct Credentials<'a> {
+    options: &'a GitCommandOptions,
+    attempted: Cell<bool>,
+}
+
+impl<'a> Credentials<'a> {
+    fn new(options: &'a GitCommandOptions) -> Self {
+        Self {
+            options,
+            attempted: Cell::new(false),
+        }
+    }
+
+    fn callbacks(&self) -> RemoteCallbacks<'_> {
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.credentials(|url, username, allowed| self.get(url, username, allowed));
+        callbacks
+    }
+
+    fn get(
+        &self,
+        url: &str,
+        username: Option<&str>,
+        allowed: CredentialType,
+    ) -> Result<Cred, git2::Error> {
+        // ssh remotes without a user in the url ask for one first,
+        // this is not an authentication attempt
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(username.unwrap_or("git"));
+        }
+
+        if self.attempted.replace(true) {
+            return Err(git2::Error::new(
+                ErrorCode::Auth,
+                ErrorClass::Http,
+                format!("authentication failed for '{url}'"),
+            ));
+        }
+
+        if allowed.contains(CredentialType::SSH_KEY) {
+            return Cred::ssh_key_from_agent(username.unwrap_or("git"));
+        }
+
+        if !allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            return Cred::default();
+        }
+
+        match self.helper(url) {
+            Some(helper) => {
+                let (username, password) = self.run_helper(helper, url)?;
+                Cred::userpass_plaintext(&username, &password)
+            },
+            None => {
+                let config = git2::Config::open_default()?;
+                Cred::credential_helper(&config, url, username)
+            },
+        }
+    }
+
+    /// The most specific credential helper configured for `url`
+    fn helper(&self, url: &str) -> Option<&str> {
+        self.options
+            .config_flags()
+            .filter_map(|(key, value)| {
+                let scope = key.strip_prefix("credential")?.strip_suffix(".helper")?;
+                match scope.strip_prefix('.') {
+                    None if scope.is_empty() => Some((0, value)),
+                    Some(prefix) if url.starts_with(prefix.trim_end_matches('/')) => {
+                        Some((prefix.len(), value))
+                    },
+                    _ => None,
+                }
+            })
+            .max_by_key(|(specificity, _)| *specificity)
+            .map(|(_, helper)| helper)
+    }
+
+    /// Run a credential helper like git would
+    /// and return the username and password it provided
+    fn run_helper(&self, helper: &str, url: &str) -> Result<(String, String), git2::Error> {
+        let helper_error =
+            |message: String| git2::Error::new(ErrorCode::Auth, ErrorClass::Callback, message);
+
+        let script = match helper.strip_prefix('!') {
+            Some(script) => format!("{script} get"),
+            None if Path::new(helper).is_absolute() => format!("{helper} get"),
+            None => format!("git-credential-{helper} get"),
+        };
+
+        let url = url::Url::parse(url)
+            .map_err(|err| helper_error(format!("invalid remote url '{url}': {err}")))?;
+
+        let mut command = Command::new("sh");
+        command
+            .args(["-c", &script])
+            .envs(self.options.env_vars())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit());
+
+        let mut child = command
+            .spawn()
+            .map_err(|err| helper_error(format!("could not run credential helper: {err}")))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            let input = format!(
+                "protocol={}\nhost={}\npath={}\n\n",
+                url.scheme(),
+                url.host_str().unwrap_or_default(),
+                url.path().trim_start_matches('/'),
+            );
+            // helpers are free to not read their input
+            let _ = stdin.write_all(input.as_bytes());
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|err| helper_error(format!("credential helper failed: {err}")))?;
+        if !output.status.success() {
+            warn!("credential helper exited with {}", output.status);
+        }
+
+        let output = String::from_utf8_lossy(&output.stdout);
+        let mut username = String::new();
+        let mut password = String::new();
+        for line in output.lines() {
+            match line.split_once('=') {
+                Some(("username", value)) => username = value.to_string(),
+                Some(("password", value)) => password = value.to_string(),
+                _ => {},
+            }
+        }
+        Ok((username, password))
+    }
+}
+
+impl GitProvider for Libgit2Provider {
+    type AddError = GitCommandError;
+    type AddRemoteError = GitCommandError;
+    type CheckoutError = GitCommandError;
+    type CloneError = GitRemoteCommandError;
+    type CommitError = GitCommandError;
+    type DiscoverError = GitCommandDiscoverError;
+    type FetchError = GitRemoteCommandError;
+    type GetOriginError = GitCommandGetOriginError;
+    type InitError = GitCommandError;
+    type ListBranchesError = GitCommandError;
+    type MvError = GitCommandError;
+    type PushError = GitRemoteCommandError;
+    type RenameError = GitCommandError;
+    type RmError = GitCommandError;
+    type SetOriginError = GitCommandError;
+    type ShowError = GitCommandError;
+
+    fn discover<P: AsRef<Path>>(path: P) -> Result<Self, Self::DiscoverError> {
+        Self::discover_with(GitCommandOptions::default(), path)
+    }
+
+    fn init<P: AsRef<Path>>(path: P, bare: bool) -> Result<Self, Self::InitError> {
+        Self::init_with(GitCommandOptions::default(), path, bare)
+    }
+
+    fn clone<O: AsRef<OsStr>, P: AsRef<Path>>(
+        origin: O,
+        path: P,
+        bare: bool,
+    ) -> Result<Self, Self::CloneError> {
+        let options = GitCommandOptions::default();
+        let repo = {
+            let credentials = Credentials::new(&options);
+            let mut fetch_options = FetchOptions::new();
+            fetch_options.remote_callbacks(credentials.callbacks());
+
+            let repo = RepoBuilder::new()
+                .bare(bare)
+                .fetch_options(fetch_options)
+                .clone(&origin.as_ref().to_string_lossy(), path.as_ref())?;
+            repo
+        };
+
+        Ok(Self::from_repo(options, &repo, path.as_ref()))
+    }
+
+    fn checkout(&self, name: &str, orphan: bool) -> Result<(), Self::CheckoutError> {
+        let repo = self.repo()?;
+        let refname = format!("refs/heads/{name}");
+
+        if orphan {
+            // Like `git checkout --orphan`, keep the index and working tree
+            // and make HEAD point to a branch without commits.
+            if repo.find_reference(&refname).is_ok() {
+                return Err(git2::Error::new(
+                    ErrorCode::Exists,
+                    ErrorClass::Reference,
+                    format!("a branch named '{name}' already exists"),
+                )
+                .into());
+            }
+            repo.set_head(&refname)?;
+            return Ok(());
+        }
+
+        let mut checkout = CheckoutBuilder::new();
+        checkout.safe();
+        if let Ok(reference) = repo.find_reference(&refname) {
+            repo.checkout_tree(&reference.peel(ObjectType::Commit)?, Some(&mut checkout))?;
+            repo.set_head(&refname)?;
+        } else {
+            let commit = repo.revparse_single(name)?.peel_to_commit()?;
+            repo.checkout_tree(commit.as_object(), Some(&mut checkout))?;
+            repo.set_head_detached(commit.id())?;
+        }
+        Ok(())
+    }
+
+    fn list_branches(&self) -> Result<Vec<BranchInfo>, Self::ListBranchesError> {
+        let repo = self.repo()?;
+
+        let mut info = Vec::new();
+        for branch in repo.branches(None)? {
+            let (branch, branch_type) = branch?;
+            let reference = branch.get();
+            if reference.symbolic_target().is_some() {
+                continue;
+            }
+            let Some(full_name) = branch.name()? else {
+                continue;
+            };
+
+            let (remote, name) = match branch_type {
+                BranchType::Remote => match full_name.split_once('/') {
+                    Some((remote, name)) => (Some(remote.to_string()), name.to_string()),
+                    None => (None, full_name.to_string()),
+                },
+                BranchType::Local => (None, full_name.to_string()),
+            };
+
+            let commit = reference.peel_to_commit()?;
+            info.push(BranchInfo {
+                name,
+                remote,
+                rev: commit
+                    .as_object()
+                    .short_id()?
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                description: commit.summary().unwrap_or_default().to_string(),
+            });
+        }
+
+        Ok(info)
+    }
+
+    fn rename_branch(&self, new_name: &str) -> Result<(), Self::RenameError> {
+        let repo = self.repo()?;
+        let current = Self::head_branch(&repo)?;
+
+        match repo.find_branch(&current, BranchType::Local) {
+            Ok(mut branch) => {
+                branch.rename(new_name, false)?;
+            },
+            // HEAD points to a branch without commits
+            Err(err) if err.code() == ErrorCode::NotFound => {
+                repo.set_head(&format!("refs/heads/{new_name}"))?;
+            },
+            Err(err) => Err(err)?,
+        }
+        Ok(())
+    }
+
+    fn add_remote(&self, origin_name: &str, url: &str) -> Result<(), Self::AddRemoteError> {
+        self.repo()?.remote(origin_name, url)?;
+        Ok(())
+    }
+
+    fn mv(&self, from: &Path, to: &Path) -> Result<(), Self::MvError> {
+        let repo = self.repo()?;
+        let workdir = self.workdir.as_deref().unwrap_or(&self.path);
+        let (from, to) = (self.relative_path(from), self.relative_path(to));
+
+        std::fs::rename(workdir.join(&from), workdir.join(&to))?;
+
+        let mut index = repo.index()?;
+        if workdir.join(&to).is_dir() {
+            index.remove_dir(&from, 0)?;
+        } else {
+            index.remove_path(&from)?;
+        }
+        index.add_all([to.as_path()], IndexAddOption::DEFAULT, None)?;
+        index.write()?;
+        Ok(())
+    }
+
+    fn rm(
+        &self,
+        paths: &[&Path],
+        recursive: bool,
+        _force: bool,
+        cached: bool,
+    ) -> Result<(), Self::RmError> {
+        let repo = self.repo()?;
+        let workdir = self.workdir.as_deref().unwrap_or(&self.path);
+        let mut index = repo.index()?;
+
+        for path in paths {
+            let path = self.relative_path(path);
+            let absolute = workdir.join(&path);
+
+            if recursive && absolute.is_dir() {
+                index.remove_dir(&path, 0)?;
+                if !cached {
+                    std::fs::remove_dir_all(&absolute)?;
+                }
+            } else {
+                index.remove_path(&path)?;
+                if !cached && absolute.exists() {
+                    std::fs::remove_file(&absolute)?;
+                }
+            }
+        }
+
+        index.write()?;
+        Ok(())
+    }
+
+    fn add(&self, paths: &[&Path]) -> Result<(), Self::AddError> {
+        let repo = self.repo()?;
+        let paths = paths
+            .iter()
+            .map(|path| self.relative_path(path))
+            .collect::<Vec<_>>();
+
+        let mut index = repo.index()?;
+        index.add_all(
+            paths.iter().map(PathBuf::as_path),
+            IndexAddOption::DEFAULT,
+            None,
+        )?;
+        // like `git add`, also stage removed files
+        index.update_all(paths.iter().map(PathBuf::as_path), None)?;
+        index.write()?;
+        Ok(())
+    }
+
+    fn commit(&self, message: &str) -> Result<(), Self::CommitError> {
+        let repo = self.repo()?;
+        let signature = self.signature(&repo)?;
+
+        let mut index = repo.index()?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+
+        let parent = match repo.head() {
+            Ok(head) => Some(head.peel_to_commit()?),
+            Err(err) if err.code() == ErrorCode::UnbornBranch => None,
+            Err(err) => Err(err)?,
+        };
+
+        let unchanged = match &parent {
+            Some(parent) => parent.tree_id() == tree.id(),
+            None => tree.is_empty(),
+        };
+        if unchanged {
+            return Err(git2::Error::new(
+                ErrorCode::GenericError,
+                ErrorClass::Index,
+                "nothing to commit",
+            )
+            .into());
+        }
+
+        let parents = parent.iter().collect::<Vec<_>>();
+
+        if self.options.get_config_flag("commit.gpgsign") != Some("true") {
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )?;
+            return Ok(());
+        }
+
+        // libgit2 does not sign commits itself,
+        // so create the commit object from a signed buffer and advance HEAD manually
+        let buffer = repo.commit_create_buffer(&signature, &signature, message, &tree, &parents)?;
+        let content = buffer.as_str().ok_or_else(|| {
+            git2::Error::new(
+                ErrorCode::GenericError,
+                ErrorClass::Object,
+                "commit is not valid utf-8",
+            )
+        })?;
+        let commit_signature = self.sign(content)?;
+        let oid = repo.commit_signed(content, &commit_signature, None)?;
+
+        match repo.find_reference("HEAD")?.symbolic_target() {
+            Some(branch) => {
+                repo.reference(branch, oid, true, "commit")?;
+            },
+            None => repo.set_head_detached(oid)?,
+        }
+        Ok(())
+    }
+
+    /// Show the contents of a file (`<rev>:<path>`),
+    /// the entries of a tree, or the header and message of a commit
+    fn show(&self, object: &str) -> Result<OsString, Self::ShowError> {
+        let repo = self.repo()?;
+        let object = repo.revparse_single(object)?;
+
+        let shown = match object.kind() {
+            Some(ObjectType::Blob) => object.peel_to_blob()?.content().to_vec(),
+            Some(ObjectType::Tree) => {
+                let tree = object.peel_to_tree()?;
+                let mut shown = String::new();
+                for entry in tree.iter() {
+                    shown.push_str(entry.name().unwrap_or_default());
+                    if entry.kind() == Some(ObjectType::Tree) {
+                        shown.push('/');
+                    }
+                    shown.push('\n');
+                }
+                shown.into_bytes()
+            },
+            _ => {
+                let commit = object.peel_to_commit()?;
+                let author = commit.author();
+                format!(
+                    "commit {}\nAuthor: {} <{}>\n\n{}\n",
+                    commit.id(),
+                    author.name().unwrap_or_default(),
+                    author.email().unwrap_or_default(),
+                    commit.message().unwrap_or_default(),
+                )
+                .into_bytes()
+            },
+        };
+
+        Ok(OsString::from_vec(shown))
+    }
+
+    fn fetch(&self) -> Result<(), Self::FetchError> {
+        let repo = self.repo()?;
+        for name in repo.remotes()?.iter().flatten() {
+            let mut remote = repo.find_remote(name)?;
+            let credentials = Credentials::new(&self.options);
+            let mut fetch_options = FetchOptions::new();
+            fetch_options.remote_callbacks(credentials.callbacks());
+            remote.fetch::<&str>(&[], Some(&mut fetch_options), None)?;
+        }
+        Ok(())
+    }
+
+    fn push(&self, remote: &str, force: bool) -> Result<(), Self::PushError> {
+        let repo = self.repo()?;
+        let branch = Self::head_branch(&repo)?;
+        let refspec = expand_push_spec(&repo, "HEAD", force)?;
+
+        let mut git_remote = self.remote(&repo, remote)?;
+        self.push_refspec(&mut git_remote, &refspec)?;
+
+        // like `git push -u`
+        let mut config = repo.config()?;
+        config.set_str(&format!("branch.{branch}.remote"), remote)?;
+        config.set_str(
+            &format!("branch.{branch}.merge"),
+            &format!("refs/heads/{branch}"),
+        )?;
+        Ok(())
+    }
+
+    fn set_origin(&self, branch: &str, origin_name: &str) -> Result<(), Self::SetOriginError> {
+        let repo = self.repo()?;
+        let current = Self::head_branch(&repo)?;
+
+        let mut config = repo.config()?;
+        config.set_str(&format!("branch.{current}.remote"), origin_name)?;
+        config.set_str(
+            &format!("branch.{current}.merge"),
+            &format!("refs/heads/{branch}"),
+        )?;
+        Ok(())
+    }
+
+    fn get_origin(&self) -> Result<OriginInfo, Self::GetOriginError> {
+        let repo = self.repo().map_err(GitCommandError::from)?;
+        let config = repo.config().map_err(GitCommandError::from)?;
+
+        let (remote_name, remote_branch) = {
+            let branch =
+                Self::head_branch(&repo).map_err(|_| GitCommandGetOriginError::NoUpstream)?;
+            let remote_name = config
+                .get_string(&format!("branch.{branch}.remote"))
+                .map_err(|_| GitCommandGetOriginError::NoUpstream)?;
+            let merge = config
+                .get_string(&format!("branch.{branch}.merge"))
+                .map_err(|_| GitCommandGetOriginError::NoUpstream)?;
+            let remote_branch = merge.trim_start_matches("refs/heads/").to_string();
+            (remote_name, remote_branch)
+        };
+
+        let mut remote = self
+            .remote(&repo, &remote_name)
+            .map_err(GitCommandError::from)?;
+        let url = remote.url().unwrap_or_default().to_string();
+
+        let remote_revision = {
+            let credentials = Credentials::new(&self.options);
+            let connection = remote
+                .connect_auth(git2::Direction::Fetch, Some(credentials.callbacks()), None)
+                .map_err(GitCommandError::from)?;
+            let heads = connection.list().map_err(GitCommandError::from)?;
+            let refname = format!("refs/heads/{remote_branch}");
+            let remote_revision = heads
+                .iter()
+                .find(|head| head.name() == refname)
+                .map(|head| head.oid().to_string());
+            if remote_revision.is_none() {
+                warn!("No commit found found upstream for ref {remote_branch}");
+            }
+            remote_revision
+        };
+
+        Ok(OriginInfo {
+            name: remote_name,
+            url,
+            reference: remote_branch,
+            revision: remote_revision,
+        })
+    }
+
+    fn workdir(&self) -> Option<&Path> {
+        self.workdir.as_deref()
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn auth_errors_are_access_denied() {
+        let err = git2::Error::new(ErrorCode::Auth, ErrorClass::Http, "denied");
+        assert!(matches!(
+            GitRemoteCommandError::from(err),
+            GitRemoteCommandError::AccessDenied
+        ));
+
+        let err = git2::Error::new(ErrorCode::NotFastForward, ErrorClass::Reference, "");
+        assert!(matches!(
+            GitRemoteCommandError::from(err),
+            GitRemoteCommandError::Diverged
+        ));
+    }
+
+    #[test]
+    fn rejected_pushes_of_diverged_branches_are_diverged() {
+        for status in ["non-fast-forward", "[rejected] (fetch first)"] {
+            assert!(matches!(
+                push_rejected("refs/heads/main", status),
+                GitRemoteCommandError::Diverged
+            ));
+        }
+        assert!(matches!(
+            push_rejected("refs/heads/main", "pre-receive hook declined"),
+            GitRemoteCommandError::Command(GitCommandError::Library(_))
+        ));
+    }
+
+    #[test]
+    fn local_paths_are_detected() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().to_str().unwrap();
+        assert_eq!(
+            local_path(&format!("file://{path}")),
+            Some(tempdir.path().to_path_buf())
+        );
+        assert_eq!(local_path(path), Some(tempdir.path().to_path_buf()));
+        assert_eq!(local_path("https://example.com/owner/floxmeta"), None);
+        assert_eq!(local_path("git@github.com:owner/floxmeta.git"), None);
+    }
+
+    #[test]
+    fn most_specific_credential_helper_is_used() {
+        let mut options = GitCommandOptions::default();
+        options.add_config_flag("credential.helper", "store");
+        options.add_config_flag("credential.https://example.com/.helper", "!echo example");
+
+        let credentials = Credentials::new(&options);
+        assert_eq!(
+            credentials.helper("https://example.com/owner/floxmeta"),
+            Some("!echo example")
+        );
+        assert_eq!(
+            credentials.helper("https://other.com/floxmeta"),
+            Some("store")
+        );
+    }
+
+    #[test]
+    fn credential_helper_gets_configured_env() {
+        let mut options = GitCommandOptions::default();
+        options.add_env_var("TEST_TOKEN", "secret");
+        let credentials = Credentials::new(&options);
+
+        let (username, password) = credentials
+            .run_helper(
+                r#"!f(){ echo "username=oauth"; echo "password=$TEST_TOKEN"; }; f"#,
+                "https://example.com/owner/floxmeta",
+            )
+            .unwrap();
+        assert_eq!(username, "oauth");
+        assert_eq!(password, "secret");
+    }
+
+    #[test]
+    fn ssh_remotes_use_the_ssh_agent() {
+        let options = GitCommandOptions::default();
+        let credentials = Credentials::new(&options);
+        let url = "ssh://example.com/owner/floxmeta";
+
+        let cred = credentials
+            .get(url, None, CredentialType::USERNAME)
+            .unwrap();
+        assert_eq!(cred.credtype(), CredentialType::USERNAME.bits());
+
+        let cred = credentials
+            .get(
+                url,
+                Some("git"),
+                CredentialType::SSH_KEY | CredentialType::SSH_MEMORY,
+            )
+            .unwrap();
+        assert_eq!(cred.credtype(), CredentialType::SSH_KEY.bits());
+        assert!(cred.has_username());
+
+        // a rejected key is not retried
+        assert!(credentials
+            .get(url, Some("git"), CredentialType::SSH_KEY)
+            .is_err());
+    }
+
+    #[test]
+    fn push_specs_are_expanded() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(tempdir.path()).unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+
+        assert_eq!(
+            expand_push_spec(&repo, "HEAD", false).unwrap(),
+            "refs/heads/main:refs/heads/main"
+        );
+        assert_eq!(
+            expand_push_spec(&repo, "a:refs/heads/b", true).unwrap(),
+            "+refs/heads/a:refs/heads/b"
+        );
+    }
+}
@@ -1,2 +1,3 @@
 pub mod catalog;
 pub mod git;
+pub mod libgit2;
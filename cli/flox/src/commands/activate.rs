@@ -32,6 +32,7 @@ use indexmap::IndexSet;
 use indoc::formatdoc;
 use itertools::Itertools;
 use log::{debug, warn};
+use tracing::{instrument, Instrument};
 
 use super::{
     activated_environments,
@@ -42,7 +43,10 @@ use super::{
 use crate::commands::{ensure_environment_trust, ConcreteEnvironment, EnvironmentSelectError};
 use crate::config::{Config, EnvironmentPromptConfig};
 use crate::utils::dialog::{Dialog, Spinner};
+use crate::utils::errors::display_chain;
 use crate::utils::openers::Shell;
+use crate::utils::otlp::flush_traces;
+use crate::utils::profile::{self, enable_profiling, ProfileOutput};
 use crate::utils::{default_nix_env_vars, message};
 use crate::{subcommand_metric, utils};
 
@@ -59,6 +63,14 @@ pub struct Activate {
     #[bpaf(long, short)]
     trust: bool,
 
+    /// Print how long each phase of the activation takes
+    #[bpaf(long)]
+    profile: bool,
+
+    /// Write the profile as a Chrome trace JSON file instead of printing it
+    #[bpaf(long("profile-trace"), argument("file"))]
+    profile_trace: Option<PathBuf>,
+
     /// Print an activation script to stdout instead of spawning a subshell
     #[bpaf(long("print-script"), short, hide)]
     print_script: bool,
@@ -69,9 +81,26 @@ pub struct Activate {
 }
 
 impl Activate {
+    #[instrument(
+        name = "activate",
+        fields(environment_type, system = %flox.system),
+        skip_all
+    )]
     pub async fn handle(self, mut config: Config, flox: Flox) -> Result<()> {
         subcommand_metric!("activate");
-        let mut concrete_environment = match self.environment.to_concrete_environment(&flox) {
+
+        let profile_output = match (&self.profile_trace, self.profile) {
+            (Some(path), _) => Some(ProfileOutput::ChromeTrace(path.clone())),
+            (None, true) => Some(ProfileOutput::Summary),
+            (None, false) => ProfileOutput::from_env(),
+        };
+        if profile_output.is_some() {
+            enable_profiling();
+        }
+
+        let detected = tracing::debug_span!("detect")
+            .in_scope(|| self.environment.to_concrete_environment(&flox));
+        let mut concrete_environment = match detected {
             Ok(concrete_environment) => concrete_environment,
             Err(e @ EnvironmentSelectError::EnvNotFoundInCurrentDirectory) => {
                 bail!(formatdoc! {"
@@ -82,10 +111,13 @@ impl Activate {
             },
             Err(e) => Err(e)?,
         };
+        tracing::Span::current().record("environment_type", concrete_environment.kind());
 
         if let ConcreteEnvironment::Remote(ref env) = concrete_environment {
             if !self.trust {
-                ensure_environment_trust(&mut config, &flox, env).await?;
+                ensure_environment_trust(&mut config, &flox, env)
+                    .instrument(tracing::debug_span!("trust"))
+                    .await?;
             }
         }
 
@@ -95,9 +127,11 @@ impl Activate {
         let environment = concrete_environment.dyn_environment_ref_mut();
 
         let in_place = self.print_script || (!stdout().is_tty() && self.run_args.is_empty());
+        // Locking, building and linking the environment
+        let prepare_span = tracing::debug_span!("prepare");
         // Don't spin in bashrcs and similar contexts
         let activation_path_result = if in_place {
-            environment.activation_path(&flox)
+            prepare_span.in_scope(|| environment.activation_path(&flox))
         } else {
             Dialog {
                 message: &format!(
@@ -105,7 +139,9 @@ impl Activate {
                     now_active.message_description()?
                 ),
                 help_message: None,
-                typed: Spinner::new(|| environment.activation_path(&flox)),
+                typed: Spinner::new(|| {
+                    prepare_span.in_scope(|| environment.activation_path(&flox))
+                }),
             }
             .spin()
         };
@@ -260,6 +296,10 @@ impl Activate {
         // e.g. in a .bashrc or .zshrc file:
         //
         //    eval "$(flox activate)"
+        if let Some(output) = &profile_output {
+            Self::report_profile(output);
+        }
+
         if in_place {
             let shell = Self::detect_shell_for_in_place()?;
             Self::activate_in_place(&shell, &exports, &activation_path);
@@ -268,6 +308,8 @@ impl Activate {
         }
 
         let shell = Self::detect_shell_for_subshell()?;
+        // exec replaces this process, export the trace collected so far
+        flush_traces();
         // These functions will only return if exec fails
         if !self.run_args.is_empty() {
             Self::activate_command(self.run_args, shell, exports, activation_path)
@@ -276,6 +318,13 @@ impl Activate {
         }
     }
 
+    /// Print or write the profile collected for this activation
+    fn report_profile(output: &ProfileOutput) {
+        if let Err(e) = profile::report(output) {
+            message::warning(format!("Failed to report profile: {}", display_chain(&*e)));
+        }
+    }
+
     /// Used for `flox activate -- run_args`
     fn activate_command(
         run_args: Vec<String>,
@@ -534,7 +583,7 @@ impl Activate {
     /// Detect the shell to use for in-place activation
     ///
     /// Used to determine shell for `eval "$(flox activate)"` / `flox activate --print-script`
-    fn detect_shell_for_in_place() -> Result<Shell> {
+    pub(super) fn detect_shell_for_in_place() -> Result<Shell> {
         Self::detect_shell_for_in_place_with(Shell::detect_from_parent_process)
     }
 
@@ -554,7 +603,7 @@ impl Activate {
     /// Construct the envrionment list for the shell prompt
     ///
     /// [`None`] if the prompt is disabled, or filters removed all components.
-    fn make_environment_prompt(
+    pub(super) fn make_environment_prompt(
         prompt_display_config: EnvironmentPromptConfig,
         flox_active_environments: &super::ActiveEnvironments,
     ) -> Option<String> {
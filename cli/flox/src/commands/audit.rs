@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use bpaf::Bpaf;
+use flox_rust_sdk::flox::Flox;
+use flox_rust_sdk::models::audit::VulnerabilityDatabase;
+use flox_rust_sdk::models::sbom::{Sbom, SbomPackage};
+use tracing::instrument;
+
+use super::list::List;
+use super::{environment_select, EnvironmentSelect};
+use crate::subcommand_metric;
+use crate::utils::message;
+
+// Report licenses and known vulnerabilities of the packages in an environment
+#[derive(Bpaf, Clone)]
+pub struct Audit {
+    #[bpaf(external(environment_select), fallback(Default::default()))]
+    environment: EnvironmentSelect,
+
+    /// Match packages against the OSV entries in <path>,
+    /// a JSON file or a directory of JSON files
+    #[bpaf(long("vulnerabilities"), argument("path"))]
+    vulnerabilities: Option<PathBuf>,
+
+    /// Only use OSV entries of the ecosystem <name>, e.g. 'Debian'
+    #[bpaf(long("ecosystem"), argument("name"))]
+    ecosystem: Option<String>,
+
+    #[bpaf(external(audit_format), fallback(AuditFormat::Table))]
+    format: AuditFormat,
+}
+
+#[derive(Bpaf, Clone, Debug, PartialEq)]
+pub enum AuditFormat {
+    /// Print a table of packages (default)
+    #[bpaf(long)]
+    Table,
+
+    /// Print the report as JSON
+    #[bpaf(long)]
+    Json,
+
+    /// Print the report as an SPDX 2.3 SBOM
+    #[bpaf(long)]
+    Spdx,
+
+    /// Print the report as a CycloneDX 1.5 SBOM
+    #[bpaf(long)]
+    Cyclonedx,
+}
+
+impl Audit {
+    #[instrument(name = "audit", skip_all)]
+    pub async fn handle(self, flox: Flox) -> Result<()> {
+        subcommand_metric!("audit");
+
+        let mut env = self
+            .environment
+            .detect_concrete_environment(&flox, "Audit")?
+            .into_dyn_environment();
+
+        let lockfile = List::get_lockfile(&flox, &mut *env)?;
+        let mut sbom = Sbom::from_lockfile(env.name().to_string(), &lockfile);
+
+        if let Some(path) = &self.vulnerabilities {
+            let mut database = VulnerabilityDatabase::load(path)?;
+            if database.is_empty() {
+                message::warning(format!(
+                    "The vulnerability database '{}' is empty",
+                    path.display()
+                ));
+            } else if let Some(ecosystem) = &self.ecosystem {
+                database.retain_ecosystem(ecosystem);
+                if database.is_empty() {
+                    message::warning(format!(
+                        "The vulnerability database '{}' has no entries for the ecosystem '{ecosystem}'",
+                        path.display()
+                    ));
+                }
+            }
+            database.audit(&mut sbom);
+        }
+
+        match self.format {
+            AuditFormat::Table => Self::print_table(&sbom.packages),
+            AuditFormat::Json => println!("{}", serde_json::to_string_pretty(&sbom.packages)?),
+            AuditFormat::Spdx => println!("{}", serde_json::to_string_pretty(&sbom.to_spdx())?),
+            AuditFormat::Cyclonedx => {
+                println!("{}", serde_json::to_string_pretty(&sbom.to_cyclonedx())?)
+            },
+        }
+
+        let vulnerable = sbom
+            .packages
+            .iter()
+            .filter(|package| !package.vulnerabilities.is_empty())
+            .count();
+        if vulnerable > 0 {
+            bail!("Found known vulnerabilities in {vulnerable} package(s).");
+        }
+        Ok(())
+    }
+
+    /// print one line per package, followed by its vulnerabilities
+    ///
+    /// e.g. `hello  2.12.1  GPL-3.0-or-later  hello  aarch64-darwin, x86_64-linux`
+    fn print_table(packages: &[SbomPackage]) {
+        let rows = packages
+            .iter()
+            .map(|package| {
+                let mut license = package.license.clone().unwrap_or("N/A".to_string());
+                if package.unfree {
+                    license.push_str(" (unfree)");
+                }
+                if package.broken {
+                    license.push_str(" (broken)");
+                }
+                [
+                    package.id.clone(),
+                    package.version.clone().unwrap_or("N/A".to_string()),
+                    license,
+                    package.pkg_path.clone(),
+                    package.systems.join(", "),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let header = ["ID", "VERSION", "LICENSE", "PKG-PATH", "SYSTEMS"].map(String::from);
+        let mut widths = header.clone().map(|column| column.len());
+        for row in &rows {
+            for (width, column) in widths.iter_mut().zip(row) {
+                *width = (*width).max(column.len());
+            }
+        }
+        let format_row = |row: &[String; 5]| {
+            row.iter()
+                .zip(widths)
+                .map(|(column, width)| format!("{column:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        println!("{}", format_row(&header));
+        for (row, package) in rows.iter().zip(packages) {
+            println!("{}", format_row(row));
+            for vulnerability in &package.vulnerabilities {
+                let mut line = format!("  ! {}", vulnerability.id);
+                if let Some(severity) = &vulnerability.severity {
+                    line.push_str(&format!(" ({severity})"));
+                }
+                if let Some(summary) = &vulnerability.summary {
+                    line.push_str(&format!(": {summary}"));
+                }
+                if let Some(fixed) = &vulnerability.fixed {
+                    line.push_str(&format!(" [fixed in {fixed}]"));
+                }
+                println!("{line}");
+            }
+        }
+    }
+}
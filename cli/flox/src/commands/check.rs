@@ -0,0 +1,105 @@
+use anyhow::{bail, Result};
+use bpaf::Bpaf;
+use flox_rust_sdk::flox::Flox;
+use flox_rust_sdk::models::environment::CanonicalPath;
+use flox_rust_sdk::models::lockfile::{
+    LockedManifest,
+    LockfileManifest,
+    ProblemSeverity,
+    TypedLockedManifest,
+};
+use indoc::formatdoc;
+use tracing::instrument;
+
+use super::{environment_select, run_in_workspace, EnvironmentSelect, WorkspaceCommand};
+use crate::subcommand_metric;
+use crate::utils::message;
+
+// Check an environment's lockfile for problems
+#[derive(Bpaf, Clone)]
+pub struct Check {
+    #[bpaf(external(environment_select), fallback(Default::default()))]
+    environment: EnvironmentSelect,
+
+    /// Run in every environment of the workspace containing the current directory
+    #[bpaf(long)]
+    workspace: bool,
+
+    /// Only check packages for <system> (default: all systems of the manifest)
+    #[bpaf(long, argument("system"))]
+    system: Option<String>,
+
+    /// Print one JSON object per problem
+    #[bpaf(long)]
+    json: bool,
+}
+
+impl WorkspaceCommand for Check {
+    fn selected_environment(&self) -> Result<&EnvironmentSelect> {
+        Ok(&self.environment)
+    }
+
+    fn for_member(&self, environment: EnvironmentSelect) -> Self {
+        Self {
+            environment,
+            workspace: false,
+            ..self.clone()
+        }
+    }
+}
+
+impl Check {
+    #[instrument(name = "check", skip_all)]
+    pub async fn handle(self, flox: Flox) -> Result<()> {
+        if self.workspace {
+            return run_in_workspace(&flox, &self, |command, flox| Box::pin(command.handle(flox)))
+                .await;
+        }
+
+        subcommand_metric!("check");
+
+        let env = self
+            .environment
+            .detect_concrete_environment(&flox, "Check")?
+            .into_dyn_environment();
+
+        let manifest = LockfileManifest::from_toml(&env.manifest_content(&flox)?)?;
+
+        let lockfile_path = env.lockfile_path(&flox)?;
+        let Ok(lockfile_path) = CanonicalPath::new(&lockfile_path) else {
+            bail!(formatdoc! {"
+                The environment has not been locked yet.
+
+                Install a package or run 'flox upgrade' to lock it.
+            "});
+        };
+        let lockfile =
+            TypedLockedManifest::try_from(LockedManifest::read_from_file(&lockfile_path)?)?;
+
+        let problems = lockfile.check(&manifest, self.system.as_ref());
+
+        for problem in &problems {
+            if self.json {
+                println!("{}", serde_json::to_string(problem)?);
+                continue;
+            }
+            match problem.severity {
+                ProblemSeverity::Warning => message::warning(&problem.message),
+                ProblemSeverity::Error => message::error(&problem.message),
+            }
+        }
+
+        let errors = problems
+            .iter()
+            .filter(|problem| problem.severity == ProblemSeverity::Error)
+            .count();
+        if errors > 0 {
+            bail!("Found {errors} problem(s) in the lockfile.");
+        }
+
+        if !self.json {
+            message::plain("No problems found.");
+        }
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -5,9 +6,12 @@ use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 use bpaf::Bpaf;
 use flox_rust_sdk::flox::Flox;
+use flox_rust_sdk::models::container_builder::EmbeddedFile;
+use flox_rust_sdk::models::sbom::{Sbom, SbomFormat};
 use log::debug;
 use tracing::instrument;
 
+use super::list::List;
 use super::{environment_select, EnvironmentSelect};
 use crate::subcommand_metric;
 use crate::utils::dialog::{Dialog, Spinner};
@@ -22,6 +26,10 @@ pub struct Containerize {
     /// Path to write the container to (pass '-' to write to stdout)
     #[bpaf(short, long, argument("path"))]
     output: Option<PathBuf>,
+
+    /// Add an SBOM in <format> ('spdx' or 'cyclonedx') to the container
+    #[bpaf(long, argument("format"))]
+    sbom: Option<SbomFormat>,
 }
 impl Containerize {
     #[instrument(name = "containerize", skip_all)]
@@ -58,6 +66,16 @@ impl Containerize {
                 (Box::new(file), output_path.display().to_string())
             };
 
+        let sbom = match self.sbom {
+            Some(format) => {
+                let lockfile = List::get_lockfile(&flox, &mut *env)?;
+                let mut sbom = Sbom::from_lockfile(env.name().to_string(), &lockfile);
+                sbom.retain_system(&flox.system);
+                Some(Self::sbom_file(&sbom, format)?)
+            },
+            None => None,
+        };
+
         let builder = Dialog {
             message: &format!("Building container for environment {}...", env.name()),
             help_message: None,
@@ -68,11 +86,33 @@ impl Containerize {
         Dialog {
             message: &format!("Writing container to '{output_name}'"),
             help_message: None,
-            typed: Spinner::new(|| builder.stream_container(output)),
+            typed: Spinner::new(|| match &sbom {
+                Some(file) => builder.stream_container_with_file(output, file),
+                None => builder.stream_container(output),
+            }),
         }
         .spin()?;
 
         message::created(format!("Container written to '{output_name}'"));
         Ok(())
     }
+
+    /// The SBOM as a file in the image,
+    /// with labels pointing to its location and format
+    fn sbom_file(sbom: &Sbom, format: SbomFormat) -> Result<EmbeddedFile> {
+        let path = format!("/flox/{}", format.file_name());
+        let contents = serde_json::to_vec_pretty(&format.render(sbom))?;
+        let labels = BTreeMap::from([
+            ("dev.flox.sbom".to_string(), path.clone()),
+            (
+                "dev.flox.sbom.format".to_string(),
+                format.specification().to_string(),
+            ),
+        ]);
+        Ok(EmbeddedFile {
+            path,
+            contents,
+            labels,
+        })
+    }
 }
@@ -0,0 +1,533 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::stdout;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use anyhow::{bail, Context, Result};
+use bpaf::Bpaf;
+use crossterm::tty::IsTty;
+use flox_rust_sdk::flox::Flox;
+use flox_rust_sdk::models::environment::{
+    EnvironmentPointer,
+    FLOX_ACTIVE_ENVIRONMENTS_VAR,
+    FLOX_ENV_CACHE_VAR,
+    FLOX_ENV_DIRS_VAR,
+    FLOX_ENV_LIB_DIRS_VAR,
+    FLOX_ENV_PROJECT_VAR,
+    FLOX_ENV_VAR,
+    FLOX_PROMPT_ENVIRONMENTS_VAR,
+};
+use indoc::formatdoc;
+use itertools::Itertools;
+use log::debug;
+use serde::Serialize;
+use tracing::instrument;
+
+use super::activate::Activate;
+use super::{activated_environments, ActiveEnvironments, UninitializedEnvironment};
+use crate::config::{Config, EnvironmentPromptConfig};
+use crate::subcommand_metric;
+use crate::utils::message;
+
+/// Variables that activation prepends directories of the environment to,
+/// see `etc/profile.d/0100_common-paths.sh`
+const PATH_VARIABLES: &[&str] = &[
+    "PATH",
+    "MANPATH",
+    "FPATH",
+    "INFOPATH",
+    "CPATH",
+    "LIBRARY_PATH",
+    "PKG_CONFIG_PATH",
+    "ACLOCAL_PATH",
+    "XDG_DATA_DIRS",
+];
+
+// List the active environments, most recently activated first
+#[derive(Bpaf, Clone)]
+pub struct Active {
+    /// Print the active environments as JSON
+    #[bpaf(long)]
+    json: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ActiveEnvironment {
+    name: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    /// The directory containing the `.flox` of local environments
+    path: Option<PathBuf>,
+    /// The built environment, i.e. `$FLOX_ENV` of the activation
+    install_prefix: Option<PathBuf>,
+}
+
+impl Active {
+    #[instrument(name = "active", skip_all)]
+    pub async fn handle(self, _flox: Flox) -> Result<()> {
+        subcommand_metric!("active");
+
+        let prefixes = install_prefixes();
+        let active = activated_environments()
+            .iter()
+            .enumerate()
+            .map(|(index, env)| {
+                Ok(ActiveEnvironment {
+                    name: env.bare_description()?,
+                    kind: environment_kind(env),
+                    path: env.path().map(Path::to_path_buf),
+                    install_prefix: prefixes.get(index).cloned(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&active)?);
+            return Ok(());
+        }
+
+        if active.is_empty() {
+            message::plain("No environments are active");
+            return Ok(());
+        }
+
+        let rows = active
+            .iter()
+            .enumerate()
+            .map(|(index, env)| {
+                [
+                    (index + 1).to_string(),
+                    env.name.clone(),
+                    env.kind.to_string(),
+                    env.path
+                        .as_ref()
+                        .map(|path| path.display().to_string())
+                        .unwrap_or("-".to_string()),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let header = ["#", "NAME", "TYPE", "PATH"].map(String::from);
+        let mut widths = header.clone().map(|column| column.len());
+        for row in &rows {
+            for (width, column) in widths.iter_mut().zip(row) {
+                *width = (*width).max(column.len());
+            }
+        }
+        for row in [&header].into_iter().chain(&rows) {
+            let line = row
+                .iter()
+                .zip(widths)
+                .map(|(column, width)| format!("{column:<width$}"))
+                .join("  ");
+            println!("{}", line.trim_end());
+        }
+
+        Ok(())
+    }
+}
+
+// Deactivate an environment that was activated in the current shell
+#[derive(Bpaf, Clone)]
+pub struct Deactivate {
+    /// Name of the active environment to deactivate
+    /// (default: the most recently activated environment)
+    #[bpaf(positional("name"), optional)]
+    name: Option<String>,
+}
+
+impl Deactivate {
+    #[instrument(name = "deactivate", skip_all)]
+    pub async fn handle(self, config: Config, flox: Flox) -> Result<()> {
+        subcommand_metric!("deactivate");
+
+        let active = activated_environments();
+        let index = Self::find_active(&active, self.name.as_deref())?;
+
+        // Like `flox activate` in a shell rc file,
+        // the script has to be evaluated by the shell it modifies
+        if stdout().is_tty() {
+            let name = self.name.map(|name| format!(" {name}")).unwrap_or_default();
+            bail!(formatdoc! {"
+                'flox deactivate' prints a script that has to be evaluated by your shell:
+
+                    eval \"$(flox deactivate{name})\"
+
+                Environments activated in a subshell are deactivated with 'exit'."
+            });
+        }
+
+        let prefixes = install_prefixes();
+
+        // `FLOX_ENV_CACHE` and `FLOX_ENV_PROJECT` belong to the most recent activation,
+        // so they have to be read from the next environment if that one is deactivated
+        let next_paths = match active.iter().nth(1) {
+            Some(next) if index == 0 => Self::environment_paths(&flox, next.clone()),
+            _ => None,
+        };
+
+        let shell = Activate::detect_shell_for_in_place()?.to_string();
+        let script = Self::deactivation_script(
+            &active,
+            &prefixes,
+            index,
+            &shell,
+            config
+                .flox
+                .shell_prompt
+                .unwrap_or(EnvironmentPromptConfig::ShowAll),
+            next_paths,
+            |var| env::var(var).ok(),
+        )?;
+
+        println!("{script}");
+        Ok(())
+    }
+
+    /// The index of the environment to deactivate in the activation stack
+    fn find_active(active: &ActiveEnvironments, name: Option<&str>) -> Result<usize> {
+        if active.iter().next().is_none() {
+            bail!("No environment is active.");
+        }
+        let Some(name) = name else {
+            return Ok(0);
+        };
+        active
+            .iter()
+            .position(|env| env.pointer().matches_name(name))
+            .with_context(|| format!("Environment '{name}' is not active."))
+    }
+
+    /// The cache and project path of an active environment
+    fn environment_paths(flox: &Flox, env: UninitializedEnvironment) -> Option<(PathBuf, PathBuf)> {
+        let paths = env.into_concrete_environment(flox).map(|mut concrete| {
+            let environment = concrete.dyn_environment_ref_mut();
+            Some((
+                environment.cache_path().ok()?,
+                environment.project_path().ok()?,
+            ))
+        });
+        match paths {
+            Ok(paths) => paths,
+            Err(e) => {
+                debug!("could not open the next active environment: {e}");
+                None
+            },
+        }
+    }
+
+    /// Render a script that undoes the activation of the environment at `index`
+    ///
+    /// The directories of the environment are removed from `PATH` and similar variables,
+    /// which also reverts the reordering of `PATH` by [Activate::fixup_path]
+    /// as far as the environment is concerned.
+    /// Variables from the `[vars]` of the environment are unset,
+    /// or restored to the value of another active environment exporting them.
+    /// Values the variables had before the activation can't be restored.
+    fn deactivation_script(
+        active: &ActiveEnvironments,
+        prefixes: &[PathBuf],
+        index: usize,
+        shell: &str,
+        prompt_config: EnvironmentPromptConfig,
+        next_paths: Option<(PathBuf, PathBuf)>,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Result<String> {
+        let mut remaining = active.clone();
+        let deactivated = remaining
+            .remove(index)
+            .context("environment is not active")?;
+        let Some(prefix) = prefixes.get(index) else {
+            bail!(
+                "Could not determine where environment {} is installed.",
+                deactivated.message_description()?
+            );
+        };
+        let mut remaining_prefixes = prefixes.to_vec();
+        remaining_prefixes.remove(index);
+
+        let export = |name: &str, value: &str| {
+            format!(
+                "export {name}={}",
+                shell_escape::escape(Cow::Borrowed(value))
+            )
+        };
+        let mut lines = Vec::new();
+
+        // Another active environment may be built to the same store path,
+        // its directories have to stay in place
+        if !remaining_prefixes.contains(prefix) {
+            for name in PATH_VARIABLES {
+                let Some(value) = var(name) else {
+                    continue;
+                };
+                let new_value = value
+                    .split(':')
+                    .filter(|entry| !Path::new(entry).starts_with(prefix))
+                    .join(":");
+                if new_value == value {
+                    continue;
+                }
+                if new_value.is_empty() {
+                    lines.push(format!("unset {name}"));
+                } else {
+                    lines.push(export(name, &new_value));
+                }
+            }
+        }
+
+        // The value of a variable is set by the most recent activation exporting it
+        let scripts = prefixes
+            .iter()
+            .map(|prefix| ActivationScript::read(prefix, shell))
+            .collect::<Vec<_>>();
+        for name in scripts[index].exports.keys() {
+            if scripts[..index]
+                .iter()
+                .any(|script| script.exports.contains_key(name))
+            {
+                continue;
+            }
+            match scripts[index + 1..]
+                .iter()
+                .find_map(|script| script.exports.get(name))
+            {
+                Some(line) => lines.push(line.clone()),
+                None => lines.push(format!("unset {name}")),
+            }
+        }
+
+        if remaining_prefixes.is_empty() {
+            lines.push(
+                formatdoc! {"
+                unset {FLOX_ENV_VAR} {FLOX_ENV_CACHE_VAR} {FLOX_ENV_PROJECT_VAR}
+                unset {FLOX_ENV_DIRS_VAR} {FLOX_ENV_LIB_DIRS_VAR} {FLOX_ACTIVE_ENVIRONMENTS_VAR}
+                unset {FLOX_PROMPT_ENVIRONMENTS_VAR} FLOX_PROMPT_COLOR_1 FLOX_PROMPT_COLOR_2"
+                }
+                .to_string(),
+            );
+        } else {
+            let dirs = env::join_paths(&remaining_prefixes)?;
+            let lib_dirs = env::join_paths(remaining_prefixes.iter().map(|p| p.join("lib")))?;
+            lines.push(export(FLOX_ACTIVE_ENVIRONMENTS_VAR, &remaining.to_string()));
+            lines.push(export(FLOX_ENV_DIRS_VAR, &dirs.to_string_lossy()));
+            lines.push(export(FLOX_ENV_LIB_DIRS_VAR, &lib_dirs.to_string_lossy()));
+            if index == 0 {
+                lines.push(export(
+                    FLOX_ENV_VAR,
+                    &remaining_prefixes[0].to_string_lossy(),
+                ));
+                match next_paths {
+                    Some((cache, project)) => {
+                        lines.push(export(FLOX_ENV_CACHE_VAR, &cache.to_string_lossy()));
+                        lines.push(export(FLOX_ENV_PROJECT_VAR, &project.to_string_lossy()));
+                    },
+                    None => {
+                        lines.push(format!("unset {FLOX_ENV_CACHE_VAR} {FLOX_ENV_PROJECT_VAR}"))
+                    },
+                }
+            }
+        }
+
+        // Restore the prompt saved by the first activation,
+        // and add the remaining environments to it again
+        let prompt = Activate::make_environment_prompt(prompt_config, &remaining);
+        lines.push(
+            formatdoc! {r#"
+            if [ -n "${{FLOX_SAVE_PS1:-}}" ]; then
+              PS1="$FLOX_SAVE_PS1"
+              unset FLOX_SAVE_PS1
+            fi"#}
+            .to_string(),
+        );
+        if let Some(prompt) = prompt {
+            lines.push(export(FLOX_PROMPT_ENVIRONMENTS_VAR, &prompt));
+            let set_prompt = scripts
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != index)
+                .find_map(|(_, script)| script.set_prompt.as_ref());
+            if let Some(set_prompt) = set_prompt {
+                lines.push(set_prompt.clone());
+            }
+        } else if !remaining_prefixes.is_empty() {
+            lines.push(export(FLOX_PROMPT_ENVIRONMENTS_VAR, ""));
+        }
+
+        Ok(lines.join("\n"))
+    }
+}
+
+/// The kind of an active environment as shown by `flox active`
+fn environment_kind(env: &UninitializedEnvironment) -> &'static str {
+    match env {
+        UninitializedEnvironment::Remote(_) => "remote",
+        UninitializedEnvironment::DotFlox(dot_flox) => match dot_flox.pointer {
+            EnvironmentPointer::Managed(_) => "managed",
+            EnvironmentPointer::Path(_) => "path",
+        },
+    }
+}
+
+/// Install prefixes of the active environments, most recently activated first
+fn install_prefixes() -> Vec<PathBuf> {
+    env::split_paths(&env::var_os(FLOX_ENV_DIRS_VAR).unwrap_or_default())
+        .filter(|path| !path.as_os_str().is_empty())
+        .collect()
+}
+
+/// The parts of the activation script of a built environment
+/// (`<install prefix>/activate/<shell>`) that deactivation has to undo
+#[derive(Debug, Default)]
+struct ActivationScript {
+    /// `export NAME='value'` lines generated from `[vars]`, by variable name
+    exports: BTreeMap<String, String>,
+    /// The line sourcing the script that adds environments to the prompt
+    set_prompt: Option<String>,
+}
+
+impl ActivationScript {
+    fn read(prefix: &Path, shell: &str) -> Self {
+        let path = prefix.join("activate").join(shell);
+        match fs::read_to_string(&path) {
+            Ok(script) => Self::parse(&script),
+            Err(e) => {
+                debug!("could not read activation script {}: {e}", path.display());
+                Self::default()
+            },
+        }
+    }
+
+    fn parse(script: &str) -> Self {
+        let mut parsed = Self::default();
+        for line in script.lines() {
+            if let Some((name, _)) = line
+                .strip_prefix("export ")
+                .and_then(|export| export.split_once("='"))
+            {
+                let is_identifier = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                    && !name.starts_with(|c: char| c.is_ascii_digit());
+                if is_identifier && !name.is_empty() {
+                    parsed.exports.insert(name.to_string(), line.to_string());
+                }
+            } else if line.starts_with("source ") && line.contains("set-prompt") {
+                parsed.set_prompt = Some(line.to_string());
+            }
+        }
+        parsed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    use flox_rust_sdk::flox::EnvironmentName;
+    use flox_rust_sdk::models::environment::{DotFlox, PathPointer};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn path_environment(name: &str) -> UninitializedEnvironment {
+        UninitializedEnvironment::DotFlox(DotFlox {
+            path: PathBuf::from(format!("/projects/{name}")),
+            pointer: EnvironmentPointer::Path(PathPointer::new(
+                EnvironmentName::from_str(name).unwrap(),
+            )),
+        })
+    }
+
+    /// Deactivating the most recent environment removes its directories and variables,
+    /// and restores variables the outer environment exports
+    #[test]
+    fn deactivation_script_removes_environment() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let inner = tempdir.path().join("inner");
+        let outer = tempdir.path().join("outer");
+        for (prefix, vars) in [
+            (&inner, "export EDITOR='vim'\nexport INNER='1'\n"),
+            (&outer, "export EDITOR='nano'\n"),
+        ] {
+            fs::create_dir_all(prefix.join("activate")).unwrap();
+            fs::write(
+                prefix.join("activate").join("bash"),
+                format!("source ~/.bashrc\n{vars}source /nix/store/x-set-prompt.bash.sh\n"),
+            )
+            .unwrap();
+        }
+
+        let mut active = ActiveEnvironments::default();
+        active.set_last_active(path_environment("outer"));
+        active.set_last_active(path_environment("inner"));
+
+        let vars = HashMap::from([
+            (
+                "PATH",
+                format!(
+                    "{0}/bin:{0}/sbin:{1}/bin:{1}/sbin:/usr/bin",
+                    inner.display(),
+                    outer.display()
+                ),
+            ),
+            ("MANPATH", format!("{}/share/man:", inner.display())),
+        ]);
+
+        let script = Deactivate::deactivation_script(
+            &active,
+            &[inner.clone(), outer.clone()],
+            0,
+            "bash",
+            EnvironmentPromptConfig::ShowAll,
+            None,
+            |var| vars.get(var).cloned(),
+        )
+        .unwrap();
+        let lines = script.lines().collect::<Vec<_>>();
+
+        let outer_path = format!("{0}/bin:{0}/sbin:/usr/bin", outer.display());
+        let export_path = format!("export PATH={}", shell_escape::escape(outer_path.into()));
+        assert!(lines.contains(&export_path.as_str()));
+        assert!(lines.contains(&"unset MANPATH"));
+        assert!(lines.contains(&"export EDITOR='nano'"));
+        assert!(lines.contains(&"unset INNER"));
+        let export_flox_env = format!(
+            "export FLOX_ENV={}",
+            shell_escape::escape(outer.to_string_lossy())
+        );
+        assert!(lines.contains(&export_flox_env.as_str()));
+        assert!(lines.contains(&"export FLOX_PROMPT_ENVIRONMENTS=outer"));
+        assert_eq!(
+            lines.last(),
+            Some(&"source /nix/store/x-set-prompt.bash.sh")
+        );
+
+        // the outer environment is still overridden by the inner one
+        let script = Deactivate::deactivation_script(
+            &active,
+            &[inner.clone(), outer.clone()],
+            1,
+            "bash",
+            EnvironmentPromptConfig::ShowAll,
+            None,
+            |var| vars.get(var).cloned(),
+        )
+        .unwrap();
+        assert!(!script.contains("EDITOR"));
+        assert!(!script.contains("export FLOX_ENV="));
+        assert!(script.contains("export FLOX_PROMPT_ENVIRONMENTS=inner"));
+    }
+
+    #[test]
+    fn find_active_by_name() {
+        let mut active = ActiveEnvironments::default();
+        assert!(Deactivate::find_active(&active, None).is_err());
+
+        active.set_last_active(path_environment("outer"));
+        active.set_last_active(path_environment("inner"));
+        assert_eq!(Deactivate::find_active(&active, None).unwrap(), 0);
+        assert_eq!(Deactivate::find_active(&active, Some("outer")).unwrap(), 1);
+        assert!(Deactivate::find_active(&active, Some("missing")).is_err());
+    }
+}
@@ -27,7 +27,7 @@ use crate::commands::{ensure_floxhub_token, ConcreteEnvironment};
 use crate::subcommand_metric;
 use crate::utils::dialog::{Confirm, Dialog, Spinner};
 use crate::utils::errors::{apply_doc_link_for_unsupported_packages, format_locked_manifest_error};
-use crate::utils::message;
+use crate::utils::{didyoumean, message};
 
 // Edit declarative environment configuration
 #[derive(Bpaf, Clone)]
@@ -182,6 +182,9 @@ impl Edit {
             match result {
                 Err(EnvironmentError::Core(CoreEnvironmentError::LockedManifest(e))) => {
                     message::error(format_locked_manifest_error(&e));
+                    for suggestion in didyoumean::suggest_manifest_keys(&new_manifest) {
+                        message::plain(suggestion.to_string());
+                    }
 
                     if !Dialog::can_prompt() {
                         bail!("Can't prompt to continue editing in non-interactive context");
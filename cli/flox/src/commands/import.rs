@@ -0,0 +1,631 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use bpaf::Bpaf;
+use flox_rust_sdk::flox::Flox;
+use flox_rust_sdk::models::environment::EnvironmentError;
+use flox_rust_sdk::models::manifest::{ManifestPackageDescriptor, PackageToInstall};
+use indoc::formatdoc;
+use log::debug;
+use serde::Deserialize;
+use tracing::instrument;
+
+use super::init::{get_default_package_if_compatible, try_find_compatible_version};
+use super::{environment_select, EnvironmentSelect};
+use crate::commands::{
+    ensure_floxhub_token,
+    environment_description,
+    ConcreteEnvironment,
+    EnvironmentSelectError,
+};
+use crate::subcommand_metric;
+use crate::utils::dialog::{Dialog, Spinner};
+use crate::utils::message;
+
+/// Map tool names used by asdf, mise and devbox to the pname of the
+/// corresponding package in the catalog.
+///
+/// Tools not listed here are looked up by their own name.
+const TOOL_PNAMES: &[(&str, &str)] = &[
+    ("awscli", "awscli2"),
+    ("gcloud", "google-cloud-sdk"),
+    ("golang", "go"),
+    ("helm", "kubernetes-helm"),
+    ("java", "openjdk"),
+    ("node", "nodejs"),
+    ("postgres", "postgresql"),
+    ("python", "python3"),
+    ("rust", "rustc"),
+];
+
+// Import packages from an asdf, mise or devbox environment
+#[derive(Bpaf, Clone)]
+pub struct Import {
+    #[bpaf(external(environment_select), fallback(Default::default()))]
+    environment: EnvironmentSelect,
+
+    /// The file to import ('.tool-versions', '.mise.toml' or 'devbox.json')
+    #[bpaf(positional("file"))]
+    file: PathBuf,
+}
+
+impl Import {
+    #[instrument(name = "import", skip_all)]
+    pub async fn handle(self, mut flox: Flox) -> Result<()> {
+        subcommand_metric!("import");
+
+        let Some(source) = ImportSource::from_path(&self.file) else {
+            bail!(formatdoc! {"
+                Don't know how to import '{}'.
+
+                Supported files are {}.",
+                self.file.display(),
+                ImportSource::supported_filenames()
+            });
+        };
+
+        let concrete_environment = match self
+            .environment
+            .detect_concrete_environment(&flox, "Import to")
+        {
+            Ok(concrete_environment) => concrete_environment,
+            Err(EnvironmentSelectError::Environment(
+                ref e @ EnvironmentError::DotFloxNotFound(ref dir),
+            )) => {
+                bail!(formatdoc! {"
+                {e}
+
+                Create an environment with 'flox init --dir {}'", dir.to_string_lossy()
+                })
+            },
+            Err(e @ EnvironmentSelectError::EnvNotFoundInCurrentDirectory) => {
+                bail!(formatdoc! {"
+                {e}
+
+                Create an environment with 'flox init' or import to an environment found elsewhere with 'flox import {} --dir <PATH>'",
+                self.file.display()})
+            },
+            Err(e) => Err(e)?,
+        };
+        let description = environment_description(&concrete_environment)?;
+
+        // Ensure the user is logged in for the following remote operations
+        if let ConcreteEnvironment::Remote(_) = concrete_environment {
+            ensure_floxhub_token(&mut flox).await?;
+        };
+
+        let imported = Dialog {
+            message: &format!("Resolving tools from '{}'...", self.file.display()),
+            help_message: None,
+            typed: Spinner::new(|| ImportedPackages::from_file(&flox, source, &self.file)),
+        }
+        .spin()?;
+
+        for unresolved in imported.unresolved.iter() {
+            message::warning(format!("Could not import {unresolved}"));
+        }
+
+        let packages = imported.to_packages_to_install();
+        if packages.is_empty() {
+            bail!("No packages to import from '{}'", self.file.display());
+        }
+
+        let mut environment = concrete_environment.into_dyn_environment();
+        let installation = Dialog {
+            message: &format!("Installing packages to environment {description}..."),
+            help_message: None,
+            typed: Spinner::new(|| environment.install(&packages, &flox)),
+        }
+        .spin()?;
+
+        for pkg in packages.iter() {
+            if installation.new_manifest.is_some()
+                && installation.already_installed.get(&pkg.id) == Some(&false)
+            {
+                message::package_installed(pkg, &description);
+            } else {
+                message::warning(format!(
+                    "Package with id '{}' already installed to environment {description}",
+                    pkg.id
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A file describing the tools of a project managed by another tool
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ImportSource {
+    /// `.tool-versions` as used by asdf and mise
+    ToolVersions,
+    /// `.mise.toml` or `mise.toml`
+    MiseToml,
+    /// `devbox.json`
+    DevboxJson,
+}
+
+impl ImportSource {
+    /// Files checked by [Self::detect], in order of precedence
+    const FILENAMES: [(&'static str, Self); 4] = [
+        ("devbox.json", Self::DevboxJson),
+        (".mise.toml", Self::MiseToml),
+        ("mise.toml", Self::MiseToml),
+        (".tool-versions", Self::ToolVersions),
+    ];
+
+    fn supported_filenames() -> String {
+        Self::FILENAMES
+            .iter()
+            .map(|(name, _)| format!("'{name}'"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Determine the kind of file by its file name
+    pub(crate) fn from_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_str()?;
+        Self::FILENAMES
+            .iter()
+            .find(|(name, _)| *name == file_name)
+            .map(|(_, source)| *source)
+    }
+
+    /// Find the first importable file in `dir`
+    pub(crate) fn detect(dir: &Path) -> Option<(Self, PathBuf)> {
+        Self::FILENAMES.iter().find_map(|(name, source)| {
+            let path = dir.join(name);
+            path.is_file().then_some((*source, path))
+        })
+    }
+
+    /// Name of the tool that uses this kind of file
+    pub(crate) fn tool(&self) -> &'static str {
+        match self {
+            ImportSource::ToolVersions => "asdf",
+            ImportSource::MiseToml => "mise",
+            ImportSource::DevboxJson => "devbox",
+        }
+    }
+
+    /// Parse the requested tools from the contents of the file
+    pub(crate) fn parse(&self, content: &str) -> Result<Vec<RequestedTool>> {
+        match self {
+            ImportSource::ToolVersions => Ok(parse_tool_versions(content)),
+            ImportSource::MiseToml => parse_mise_toml(content),
+            ImportSource::DevboxJson => parse_devbox_json(content),
+        }
+    }
+}
+
+/// A tool and the version requested for it
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RequestedTool {
+    pub name: String,
+    /// The version as written in the file,
+    /// [None] if the file didn't specify one
+    pub version: Option<String>,
+}
+
+impl RequestedTool {
+    fn new(name: impl Into<String>, version: Option<impl Into<String>>) -> Self {
+        Self {
+            name: name.into(),
+            version: version.map(Into::into),
+        }
+    }
+}
+
+impl Display for RequestedTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "'{}@{version}'", self.name),
+            None => write!(f, "'{}'", self.name),
+        }
+    }
+}
+
+/// Why a [RequestedTool] could not be mapped to a package
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum UnresolvedReason {
+    /// The tool is installed from a source other than the catalog,
+    /// e.g. a mise backend like `npm:` or a flake reference in devbox.
+    UnsupportedSource,
+    /// The version can't be expressed as a version constraint
+    UnsupportedVersion,
+    /// No package was found for the tool
+    NotFound,
+    /// A package was found, but not in the requested version
+    VersionNotAvailable { available: Option<String> },
+}
+
+/// A [RequestedTool] that could not be mapped to a package
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct UnresolvedTool {
+    pub tool: RequestedTool,
+    pub reason: UnresolvedReason,
+}
+
+impl Display for UnresolvedTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tool = &self.tool;
+        match &self.reason {
+            UnresolvedReason::UnsupportedSource => {
+                write!(f, "{tool}: packages from this source are not supported")
+            },
+            UnresolvedReason::UnsupportedVersion => {
+                write!(
+                    f,
+                    "{tool}: the version can't be translated to a version constraint"
+                )
+            },
+            UnresolvedReason::NotFound => write!(f, "{tool}: no matching package found"),
+            UnresolvedReason::VersionNotAvailable {
+                available: Some(available),
+            } => write!(
+                f,
+                "{tool}: the requested version is not available (found {available})"
+            ),
+            UnresolvedReason::VersionNotAvailable { available: None } => {
+                write!(f, "{tool}: the requested version is not available")
+            },
+        }
+    }
+}
+
+/// The result of importing a file
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ImportedPackages {
+    pub source: ImportSource,
+    /// Packages keyed by install ID
+    pub packages: BTreeMap<String, ManifestPackageDescriptor>,
+    pub unresolved: Vec<UnresolvedTool>,
+}
+
+impl ImportedPackages {
+    /// Read and parse `path` and resolve all requested tools to packages
+    pub(crate) fn from_file(flox: &Flox, source: ImportSource, path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Could not read '{}'", path.display()))?;
+        let tools = source
+            .parse(&content)
+            .with_context(|| format!("Could not parse '{}'", path.display()))?;
+
+        let mut packages = BTreeMap::new();
+        let mut unresolved = vec![];
+        for tool in tools {
+            match resolve_tool(flox, &tool)? {
+                Ok((id, descriptor)) => {
+                    packages.insert(id, descriptor);
+                },
+                Err(reason) => unresolved.push(UnresolvedTool { tool, reason }),
+            }
+        }
+
+        Ok(Self {
+            source,
+            packages,
+            unresolved,
+        })
+    }
+
+    pub(crate) fn to_packages_to_install(&self) -> Vec<PackageToInstall> {
+        self.packages
+            .iter()
+            .map(|(id, descriptor)| descriptor.to_package_to_install(id))
+            .collect()
+    }
+}
+
+/// Parse `.tool-versions`
+///
+/// Each line is a tool name followed by one or more versions,
+/// of which the first is preferred.
+/// Comments start with `#`.
+fn parse_tool_versions(content: &str) -> Vec<RequestedTool> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+            let name = words.next()?;
+            Some(RequestedTool::new(name, words.next()))
+        })
+        .collect()
+}
+
+/// Parse the `[tools]` table of `.mise.toml`
+///
+/// Tool versions may be a string, a list of strings of which the first is
+/// preferred, or a table with a `version` key.
+fn parse_mise_toml(content: &str) -> Result<Vec<RequestedTool>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum MiseVersion {
+        Version(String),
+        Versions(Vec<String>),
+        Table { version: Option<String> },
+    }
+
+    #[derive(Deserialize)]
+    struct MiseToml {
+        #[serde(default)]
+        tools: BTreeMap<String, MiseVersion>,
+    }
+
+    let mise: MiseToml = toml_edit::de::from_str(content)?;
+    Ok(mise
+        .tools
+        .into_iter()
+        .map(|(name, version)| {
+            let version = match version {
+                MiseVersion::Version(version) => Some(version),
+                MiseVersion::Versions(versions) => versions.into_iter().next(),
+                MiseVersion::Table { version } => version,
+            };
+            RequestedTool::new(name, version)
+        })
+        .collect())
+}
+
+/// Parse the `packages` of `devbox.json`
+///
+/// Packages are either a list of `<name>[@<version>]` strings,
+/// or a map from name to a version string or a table with a `version` key.
+fn parse_devbox_json(content: &str) -> Result<Vec<RequestedTool>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum DevboxVersion {
+        Version(String),
+        Table { version: Option<String> },
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum DevboxPackages {
+        List(Vec<String>),
+        Map(BTreeMap<String, DevboxVersion>),
+    }
+
+    #[derive(Deserialize)]
+    struct DevboxJson {
+        packages: Option<DevboxPackages>,
+    }
+
+    let devbox: DevboxJson = serde_json::from_str(content)?;
+    let tools = match devbox.packages {
+        None => vec![],
+        Some(DevboxPackages::List(packages)) => packages
+            .into_iter()
+            .map(|package| match package.rsplit_once('@') {
+                // flake references may contain '@' only after the scheme
+                Some((name, version)) if !name.is_empty() && !name.contains(':') => {
+                    RequestedTool::new(name, Some(version))
+                },
+                _ => RequestedTool::new(package, None::<String>),
+            })
+            .collect(),
+        Some(DevboxPackages::Map(packages)) => packages
+            .into_iter()
+            .map(|(name, version)| {
+                let version = match version {
+                    DevboxVersion::Version(version) => Some(version),
+                    DevboxVersion::Table { version } => version,
+                };
+                RequestedTool::new(name, version)
+            })
+            .collect(),
+    };
+    Ok(tools)
+}
+
+/// Translate a tool version to a version constraint understood by the catalog.
+///
+/// Versions are treated as prefixes, so `20` matches any `20.x.y`
+/// and `3.11` any `3.11.x`, which mirrors how mise and devbox resolve versions.
+/// `latest` and similar aliases don't constrain the version.
+fn version_constraint(version: &str) -> Result<Option<String>, UnresolvedReason> {
+    let version = version.trim();
+    let version = version
+        .strip_prefix("latest:")
+        .or_else(|| version.strip_prefix("prefix:"))
+        .unwrap_or(version);
+
+    if matches!(version, "" | "latest" | "lts" | "stable" | "*") {
+        return Ok(None);
+    }
+
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let is_version = version.starts_with(|c: char| c.is_ascii_digit())
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'));
+
+    if !is_version {
+        return Err(UnresolvedReason::UnsupportedVersion);
+    }
+
+    Ok(Some(version.to_string()))
+}
+
+/// The catalog pname to search for a tool, or [None] if the tool isn't
+/// provided by the catalog
+fn tool_pname(name: &str) -> Option<String> {
+    let name = name.strip_prefix("core:").unwrap_or(name);
+    // Other backends and flake references
+    if name.contains(':') || name.contains('/') {
+        return None;
+    }
+    let name = name.to_lowercase();
+    let pname = TOOL_PNAMES
+        .iter()
+        .find(|(tool, _)| *tool == name)
+        .map(|(_, pname)| pname.to_string())
+        .unwrap_or(name);
+    Some(pname)
+}
+
+/// Search the catalog for a package providing `tool`.
+///
+/// Returns the install ID and descriptor of the package
+/// or the reason why no package could be found.
+fn resolve_tool(
+    flox: &Flox,
+    tool: &RequestedTool,
+) -> Result<Result<(String, ManifestPackageDescriptor), UnresolvedReason>> {
+    let Some(pname) = tool_pname(&tool.name) else {
+        return Ok(Err(UnresolvedReason::UnsupportedSource));
+    };
+
+    let version = match tool.version.as_deref().map(version_constraint) {
+        Some(Ok(version)) => version,
+        Some(Err(reason)) => return Ok(Err(reason)),
+        None => None,
+    };
+
+    debug!("resolving {tool} as pname '{pname}' with version {version:?}");
+
+    let found =
+        match try_find_compatible_version(flox, &pname, version.as_ref(), None::<Vec<String>>)? {
+            Some(found) => Some(found),
+            // devbox and unknown tools may name a package by its attribute path
+            None => get_default_package_if_compatible(flox, pname.split('.'), version.clone())?,
+        };
+
+    if let Some(found) = found {
+        let id = found.pname.clone().unwrap_or(pname);
+        let descriptor = ManifestPackageDescriptor::new(found.rel_path.join("."), version);
+        return Ok(Ok((id, descriptor)));
+    }
+
+    if version.is_none() {
+        return Ok(Err(UnresolvedReason::NotFound));
+    }
+
+    // Distinguish a missing package from a missing version
+    let available =
+        match try_find_compatible_version(flox, &pname, None::<String>, None::<Vec<String>>)? {
+            Some(found) => found,
+            None => match get_default_package_if_compatible(flox, pname.split('.'), None)? {
+                Some(found) => found,
+                None => return Ok(Err(UnresolvedReason::NotFound)),
+            },
+        };
+    Ok(Err(UnresolvedReason::VersionNotAvailable {
+        available: available.version,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_tool_versions() {
+        let content = indoc! {"
+            # runtimes
+            nodejs 20.11.1
+            python 3.11.4 3.10.13 # fallback
+            golang latest
+
+            terraform
+        "};
+        assert_eq!(parse_tool_versions(content), vec![
+            RequestedTool::new("nodejs", Some("20.11.1")),
+            RequestedTool::new("python", Some("3.11.4")),
+            RequestedTool::new("golang", Some("latest")),
+            RequestedTool::new("terraform", None::<String>),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_mise_toml() {
+        let content = indoc! {r#"
+            [env]
+            FOO = "bar"
+
+            [tools]
+            node = "20"
+            python = ["3.11", "3.10"]
+            terraform = { version = "1.5" }
+            "npm:prettier" = "latest"
+        "#};
+        assert_eq!(parse_mise_toml(content).unwrap(), vec![
+            RequestedTool::new("node", Some("20")),
+            RequestedTool::new("npm:prettier", Some("latest")),
+            RequestedTool::new("python", Some("3.11")),
+            RequestedTool::new("terraform", Some("1.5")),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_devbox_json() {
+        let list = r#"{"packages": ["python@3.10", "ripgrep", "github:numtide/flake-utils"]}"#;
+        assert_eq!(parse_devbox_json(list).unwrap(), vec![
+            RequestedTool::new("python", Some("3.10")),
+            RequestedTool::new("ripgrep", None::<String>),
+            RequestedTool::new("github:numtide/flake-utils", None::<String>),
+        ]);
+
+        let map = r#"{"packages": {"go": "1.21", "jq": {"version": "latest"}}}"#;
+        assert_eq!(parse_devbox_json(map).unwrap(), vec![
+            RequestedTool::new("go", Some("1.21")),
+            RequestedTool::new("jq", Some("latest")),
+        ]);
+
+        assert_eq!(parse_devbox_json("{}").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_version_constraint() {
+        assert_eq!(
+            version_constraint("20.11.1"),
+            Ok(Some("20.11.1".to_string()))
+        );
+        assert_eq!(version_constraint("v1.21"), Ok(Some("1.21".to_string())));
+        assert_eq!(version_constraint("latest:20"), Ok(Some("20".to_string())));
+        assert_eq!(version_constraint("latest"), Ok(None));
+        assert_eq!(version_constraint("lts"), Ok(None));
+        assert_eq!(
+            version_constraint("system"),
+            Err(UnresolvedReason::UnsupportedVersion)
+        );
+        assert_eq!(
+            version_constraint("ref:main"),
+            Err(UnresolvedReason::UnsupportedVersion)
+        );
+        assert_eq!(
+            version_constraint("temurin-17.0.2"),
+            Err(UnresolvedReason::UnsupportedVersion)
+        );
+    }
+
+    #[test]
+    fn test_tool_pname() {
+        assert_eq!(tool_pname("golang"), Some("go".to_string()));
+        assert_eq!(tool_pname("core:node"), Some("nodejs".to_string()));
+        assert_eq!(tool_pname("ripgrep"), Some("ripgrep".to_string()));
+        assert_eq!(tool_pname("npm:prettier"), None);
+        assert_eq!(tool_pname("github:numtide/flake-utils"), None);
+    }
+
+    #[test]
+    fn test_import_source_from_path() {
+        assert_eq!(
+            ImportSource::from_path(Path::new("project/.tool-versions")),
+            Some(ImportSource::ToolVersions)
+        );
+        assert_eq!(
+            ImportSource::from_path(Path::new("mise.toml")),
+            Some(ImportSource::MiseToml)
+        );
+        assert_eq!(ImportSource::from_path(Path::new("package.json")), None);
+    }
+}
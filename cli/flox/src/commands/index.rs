@@ -0,0 +1,140 @@
+use std::fs;
+
+use anyhow::{Context, Result};
+use bpaf::Bpaf;
+use chrono::Duration;
+use flox_rust_sdk::flox::Flox;
+use flox_rust_sdk::models::environment::global_manifest_path;
+use flox_rust_sdk::models::lockfile::LockedManifest;
+use flox_rust_sdk::models::search::PathOrJson;
+use flox_rust_sdk::models::search_index::{
+    read_search_index,
+    search_index_path,
+    write_search_index,
+    SearchIndex,
+};
+use tracing::instrument;
+
+use crate::subcommand_metric;
+use crate::utils::dialog::{Dialog, Spinner};
+use crate::utils::message;
+
+/// Age after which `flox search --offline` warns that the index may be outdated
+pub(crate) const STALE_INDEX_AGE: Duration = Duration::days(7);
+
+// Manage the local package index used by 'flox search --offline'
+#[derive(Clone, Debug, Bpaf)]
+pub enum Index {
+    /// Rebuild the local package index from the package sources
+    #[bpaf(command)]
+    Refresh,
+
+    /// Print the size and age of the local package index
+    #[bpaf(command)]
+    Status,
+}
+
+impl Index {
+    #[instrument(name = "index", skip_all)]
+    pub async fn handle(self, flox: Flox) -> Result<()> {
+        subcommand_metric!("index");
+
+        let index_path = search_index_path(&flox);
+
+        match self {
+            Index::Refresh => {
+                let span = tracing::info_span!("refresh");
+                let _guard = span.enter();
+
+                let previous = read_search_index(&index_path).unwrap_or_default();
+
+                let index = Dialog {
+                    message: "Building local package index...",
+                    help_message: Some("This may take a while."),
+                    typed: Spinner::new(|| {
+                        let lockfile = LockedManifest::ensure_global_lockfile(&flox)?;
+                        let global_manifest: PathOrJson = global_manifest_path(&flox).try_into()?;
+                        let index =
+                            SearchIndex::build(global_manifest, PathOrJson::Path(lockfile))?;
+                        Ok::<_, anyhow::Error>(index)
+                    }),
+                }
+                .spin()?;
+
+                fs::create_dir_all(&flox.cache_dir).context("Could not create cache directory")?;
+                write_search_index(&index, &index_path)?;
+
+                let replaced = match previous {
+                    Some(previous) => {
+                        format!(" (replaced index from {} ago)", format_age(previous.age()))
+                    },
+                    None => String::new(),
+                };
+                message::updated(format!(
+                    "Indexed {} packages{replaced}",
+                    index.packages.len()
+                ));
+
+                Ok(())
+            },
+            Index::Status => {
+                let span = tracing::info_span!("status");
+                let _guard = span.enter();
+
+                let Some(index) = read_search_index(&index_path)? else {
+                    message::warning(
+                        "No local package index found. Create one with 'flox index refresh'.",
+                    );
+                    return Ok(());
+                };
+
+                message::plain(format!(
+                    "The local package index contains {} packages and was last refreshed {} ago.",
+                    index.packages.len(),
+                    format_age(index.age())
+                ));
+
+                if index.age() > STALE_INDEX_AGE {
+                    message::warning("Refresh the index with 'flox index refresh'.");
+                }
+
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Format a duration as a coarse human readable age, e.g. "3 days"
+pub(crate) fn format_age(age: Duration) -> String {
+    fn plural(n: i64, unit: &str) -> String {
+        if n == 1 {
+            format!("{n} {unit}")
+        } else {
+            format!("{n} {unit}s")
+        }
+    }
+
+    if age.num_days() > 0 {
+        plural(age.num_days(), "day")
+    } else if age.num_hours() > 0 {
+        plural(age.num_hours(), "hour")
+    } else if age.num_minutes() > 0 {
+        plural(age.num_minutes(), "minute")
+    } else {
+        "less than a minute".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::seconds(20)), "less than a minute");
+        assert_eq!(format_age(Duration::minutes(1)), "1 minute");
+        assert_eq!(format_age(Duration::minutes(59)), "59 minutes");
+        assert_eq!(format_age(Duration::hours(5)), "5 hours");
+        assert_eq!(format_age(Duration::days(8)), "8 days");
+    }
+}
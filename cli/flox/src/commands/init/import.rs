@@ -0,0 +1,190 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use anyhow::Result;
+use flox_rust_sdk::flox::Flox;
+use flox_rust_sdk::models::environment::path_environment::InitCustomization;
+use indoc::formatdoc;
+
+use super::{format_customization, InitHook, AUTO_SETUP_HINT};
+use crate::commands::import::{ImportSource, ImportedPackages};
+use crate::utils::dialog::{Dialog, Select};
+use crate::utils::message;
+
+/// The import hook suggests the packages declared for another environment
+/// manager, i.e. asdf or mise (`.tool-versions`, `.mise.toml`) or devbox (`devbox.json`).
+///
+/// - [Self::new]: Detects an [ImportSource] in the current working directory
+///   and resolves the tools it declares to packages.
+/// - [Self::prompt_user]: Lists the packages and any tools that could not be resolved,
+///   and asks the user whether to install the packages.
+/// - [Self::get_init_customization]: Returns the resolved packages.
+pub(super) struct ImportedTools {
+    imported: ImportedPackages,
+}
+
+impl ImportedTools {
+    /// Creates and returns the [ImportedTools] hook if a supported file is found
+    /// and at least one of its tools could be resolved.
+    pub fn new(flox: &Flox, path: &Path) -> Result<Option<Self>> {
+        let Some((source, file)) = ImportSource::detect(path) else {
+            return Ok(None);
+        };
+
+        let imported = ImportedPackages::from_file(flox, source, &file)?;
+
+        if imported.packages.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self { imported }))
+    }
+
+    /// The name of the environment manager the tools are imported from
+    pub fn tool(&self) -> &'static str {
+        self.imported.source.tool()
+    }
+
+    /// Whether there are no packages left to suggest
+    pub fn is_empty(&self) -> bool {
+        self.imported.packages.is_empty()
+    }
+
+    /// Remove the packages with install IDs in `provided`,
+    /// which are suggested by another hook,
+    /// and return the install IDs of the removed packages.
+    pub fn skip_provided(&mut self, provided: &BTreeSet<String>) -> Vec<String> {
+        let skipped = self
+            .imported
+            .packages
+            .keys()
+            .filter(|id| provided.contains(*id))
+            .cloned()
+            .collect::<Vec<_>>();
+        for id in &skipped {
+            self.imported.packages.remove(id);
+        }
+        skipped
+    }
+}
+
+impl InitHook for ImportedTools {
+    fn prompt_user(&mut self, _flox: &Flox, _path: &Path) -> Result<bool> {
+        let source = self.imported.source;
+
+        let packages = self
+            .imported
+            .packages
+            .iter()
+            .map(|(id, descriptor)| match &descriptor.version {
+                Some(version) => format!("* {id} ({version})"),
+                None => format!("* {id}"),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        message::plain(formatdoc! {"
+            Flox detected {} configuration in the current directory.
+
+            The following tools can be installed from Flox:
+            {packages}
+        ", source.tool()});
+
+        if !self.imported.unresolved.is_empty() {
+            let unresolved = self
+                .imported
+                .unresolved
+                .iter()
+                .map(|unresolved| format!("* {unresolved}"))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            message::plain(formatdoc! {"
+                The following tools could not be imported:
+                {unresolved}
+            "});
+        }
+
+        let message = formatdoc! {"
+            Would you like Flox to install these tools?
+            You can always revisit the environment's declaration with 'flox edit'"};
+
+        let options = ["Yes", "No", "Show environment manifest"];
+
+        loop {
+            let dialog = Dialog {
+                message: &message,
+                help_message: Some(AUTO_SETUP_HINT),
+                typed: Select {
+                    options: options.to_vec(),
+                },
+            };
+
+            let (choice, _) = dialog.raw_prompt()?;
+
+            match choice {
+                0 => return Ok(true),
+                1 => return Ok(false),
+                2 => {
+                    message::plain(format_customization(&self.get_init_customization())?);
+                },
+                _ => unreachable!("Option selection is out of valid option bounds"),
+            }
+        }
+    }
+
+    fn get_init_customization(&self) -> InitCustomization {
+        InitCustomization {
+            profile: None,
+            packages: Some(self.imported.to_packages_to_install()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use flox_rust_sdk::models::manifest::ManifestPackageDescriptor;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::commands::import::ImportSource;
+
+    fn descriptor(pkg_path: &str) -> ManifestPackageDescriptor {
+        ManifestPackageDescriptor {
+            pkg_path: pkg_path.to_string(),
+            package_group: None,
+            priority: None,
+            version: None,
+            systems: None,
+            optional: false,
+            held: false,
+        }
+    }
+
+    /// Tools suggested by a language hook are removed from the import
+    #[test]
+    fn test_skip_provided() {
+        let mut imported = ImportedTools {
+            imported: ImportedPackages {
+                source: ImportSource::ToolVersions,
+                packages: BTreeMap::from([
+                    ("jq".to_string(), descriptor("jq")),
+                    ("nodejs".to_string(), descriptor("nodejs")),
+                ]),
+                unresolved: vec![],
+            },
+        };
+
+        let skipped = imported.skip_provided(&BTreeSet::from(["nodejs".to_string()]));
+        assert_eq!(skipped, vec!["nodejs".to_string()]);
+        assert_eq!(imported.imported.packages.keys().collect::<Vec<_>>(), vec![
+            "jq"
+        ]);
+        assert!(!imported.is_empty());
+
+        imported.skip_provided(&BTreeSet::from(["jq".to_string()]));
+        assert!(imported.is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Duration;
@@ -29,10 +29,12 @@ use crate::utils::dialog::{Dialog, Spinner};
 use crate::utils::message;
 
 mod go;
+mod import;
 mod node;
 mod python;
 
 use go::Go;
+use import::ImportedTools;
 use node::Node;
 use python::Python;
 
@@ -176,6 +178,33 @@ impl Init {
             hooks.push(Box::new(go));
         }
 
+        // A malformed file of another environment manager shouldn't prevent
+        // creating the environment
+        match ImportedTools::new(flox, path) {
+            Ok(Some(mut imported)) => {
+                // Language hooks take precedence over imported tools
+                let provided = hooks
+                    .iter()
+                    .flat_map(|hook| hook.install_ids())
+                    .collect::<BTreeSet<_>>();
+                let skipped = imported.skip_provided(&provided);
+                if !skipped.is_empty() {
+                    message::plain(format!(
+                        "Flox will suggest {} based on the detected language rather than the {} configuration",
+                        skipped.join(", "),
+                        imported.tool()
+                    ));
+                }
+                if !imported.is_empty() {
+                    hooks.push(Box::new(imported));
+                }
+            },
+            Ok(None) => {},
+            Err(e) => {
+                message::warning(format!("Skipping import of tools: {e:#}"));
+            },
+        }
+
         let mut customizations = vec![];
 
         for mut hook in hooks {
@@ -226,6 +255,16 @@ trait InitHook {
     fn prompt_user(&mut self, flox: &Flox, path: &Path) -> Result<bool>;
 
     fn get_init_customization(&self) -> InitCustomization;
+
+    /// Install IDs of the packages the hook may suggest
+    fn install_ids(&self) -> Vec<String> {
+        self.get_init_customization()
+            .packages
+            .unwrap_or_default()
+            .into_iter()
+            .map(|package| package.id)
+            .collect()
+    }
 }
 
 /// Create a temporary TOML document containing just the contents of the passed
@@ -343,7 +382,7 @@ impl From<ProvidedPackage> for PackageToInstall {
 }
 
 /// Get nixpkgs#rel_path optionally verifying that it satisfies a version constraint.
-fn get_default_package_if_compatible(
+pub(super) fn get_default_package_if_compatible(
     flox: &Flox,
     rel_path: impl IntoIterator<Item = impl ToString>,
     version: Option<String>,
@@ -376,7 +415,7 @@ fn get_default_package_if_compatible(
 }
 
 /// Searches for a given pname and version, optionally restricting rel_path
-fn try_find_compatible_version(
+pub(super) fn try_find_compatible_version(
     flox: &Flox,
     pname: impl Into<String>,
     version: Option<impl Into<String>>,
@@ -425,11 +464,30 @@ mod tests {
         }
     }
 
+    use flox_rust_sdk::flox::test_helpers::flox_instance;
     use indoc::indoc;
     use pretty_assertions::assert_eq;
 
     use super::*;
 
+    /// A malformed file of another environment manager doesn't abort init
+    #[test]
+    fn test_run_language_hooks_skips_malformed_import() {
+        let (flox, _temp_dir_handle) = flox_instance();
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".mise.toml"), "[tools\nnode = ").unwrap();
+
+        let init = Init {
+            dir: None,
+            env_name: None,
+            auto_setup: true,
+        };
+        assert_eq!(
+            init.run_language_hooks(&flox, dir.path()).unwrap(),
+            InitCustomization::default()
+        );
+    }
+
     /// combine_customizations() deduplicates a package and corretly concatenates profiles
     #[test]
     fn test_combine_customizations() {
@@ -23,11 +23,35 @@ const YARN_HOOK: &str = indoc! {"
                 # Install nodejs depedencies
                 yarn"};
 
+const PNPM_HOOK: &str = indoc! {"
+                # Install nodejs dependencies
+                pnpm install"};
+
+const BUN_HOOK: &str = indoc! {"
+                # Install nodejs dependencies
+                bun install"};
+
+/// Hook letting Corepack provide the package manager version requested by the
+/// `packageManager` field in package.json
+fn corepack_hook(manager: PackageManager) -> String {
+    formatdoc! {r#"
+        # Let Corepack provide the package manager requested in package.json
+        export COREPACK_HOME="$FLOX_ENV_CACHE/corepack"
+        mkdir -p "$COREPACK_HOME/bin"
+        corepack enable --install-directory "$COREPACK_HOME/bin"
+        export PATH="$COREPACK_HOME/bin:$PATH"
+
+        # Install nodejs dependencies
+        {manager} install"#,
+        manager = manager.name(),
+    }
+}
+
 /// The general flow of the node hook is:
 ///
-/// - [Self::new]: Calculate a [NodeAction]
-/// - [Self::prompt_user]: If the action is [NodeAction::InstallYarnOrNode],
-///   set it to one of [NodeAction::InstallNode] or [NodeAction::InstallYarn].
+/// - [Self::new]: Calculate a [NodeInstallAction]
+/// - [Self::prompt_user]: If the action is [NodeInstallAction::YarnOrNode],
+///   set it to one of [NodeInstallAction::Node] or [NodeInstallAction::Yarn].
 ///   Otherwise, just return true or false based on whether the user wants the
 ///   customization.
 /// - [Self::get_init_customization]: Return a customization based on [Self::action]
@@ -49,26 +73,77 @@ pub(super) struct Node {
     /// [Self::nodejs_message_and_version]
     /// TODO: should this be stored on [NodeAction::InstallNode]?
     ///
-    /// Node version as specified in .nvmrc if it exists
+    /// Node version as specified in the first [NodeVersionFile] that exists
+    /// and requests a version.
     /// If action is set to [NodeAction::InstallYarn], this is left
     /// uninitialized as [None].
-    nvmrc_version: Option<NVMRCVersion>,
+    version_file: Option<(NodeVersionFile, NVMRCVersion)>,
+}
+
+/// Files other than package.json that can request a version of nodejs,
+/// in order of precedence
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum NodeVersionFile {
+    /// `.nvmrc` as used by nvm
+    Nvmrc,
+    /// `.node-version` as used by nodenv, fnm and others
+    NodeVersion,
+    /// `.tool-versions` as used by asdf and mise
+    ToolVersions,
+}
+
+impl NodeVersionFile {
+    const ALL: [NodeVersionFile; 3] = [
+        NodeVersionFile::Nvmrc,
+        NodeVersionFile::NodeVersion,
+        NodeVersionFile::ToolVersions,
+    ];
+
+    fn filename(&self) -> &'static str {
+        match self {
+            NodeVersionFile::Nvmrc => ".nvmrc",
+            NodeVersionFile::NodeVersion => ".node-version",
+            NodeVersionFile::ToolVersions => ".tool-versions",
+        }
+    }
+
+    /// The filename with an indefinite article for use in messages
+    fn describe(&self) -> &'static str {
+        match self {
+            NodeVersionFile::Nvmrc => "an .nvmrc",
+            NodeVersionFile::NodeVersion => "a .node-version",
+            NodeVersionFile::ToolVersions => "a .tool-versions",
+        }
+    }
+
+    /// Translate the contents of the file into a [RequestedNVMRCVersion]
+    fn parse(&self, contents: &str) -> RequestedNVMRCVersion {
+        match self {
+            // .node-version files use the same format as .nvmrc files
+            NodeVersionFile::Nvmrc | NodeVersionFile::NodeVersion => {
+                Node::parse_nvmrc_version(contents)
+            },
+            NodeVersionFile::ToolVersions => Node::parse_tool_versions_node_version(contents),
+        }
+    }
 }
 
+/// Version of nodejs requested by a [NodeVersionFile]
 #[derive(Debug, PartialEq)]
 enum RequestedNVMRCVersion {
-    /// .nvmrc not present or empty
+    /// file not present, empty, or doesn't request nodejs
     None,
-    /// .nvmrc contains an alias or something we can't parse as a version.
+    /// file contains an alias or something we can't parse as a version.
     Unsure,
     Found(String),
 }
 
+/// Version of nodejs requested by a [NodeVersionFile] after searching the catalogs
 enum NVMRCVersion {
-    /// .nvmrc contains a version,
+    /// file contains a version,
     /// but flox doesn't provide it.
     Unavailable,
-    /// .nvmrc contains an alias or something we can't parse as a version.
+    /// file contains an alias or something we can't parse as a version.
     Unsure,
     Found(Box<SearchResult>),
 }
@@ -95,16 +170,107 @@ struct NodeInstall {
     npm_hook: bool,
 }
 
+/// Install pnpm or bun
+#[derive(Clone, Debug, PartialEq)]
+struct PackageManagerInstall {
+    manager: PackageManager,
+    /// The `packageManager` field in package.json if it requested [Self::manager]
+    requested: Option<String>,
+    /// The version constraint [Self::package] was found with, if any
+    version: Option<String>,
+    package: SearchResult,
+    /// The lockfile [Self::manager] was detected by, if any
+    lockfile: Option<&'static str>,
+    /// The nodejs the package manager runs with,
+    /// [None] for bun which doesn't require nodejs
+    node: Option<SearchResult>,
+    /// nodejs requested by a [NodeVersionFile],
+    /// installed alongside the package manager
+    version_file_node: Option<(NodeVersionFile, SearchResult)>,
+}
+
+/// Install nodejs and let Corepack provide the package manager requested by
+/// the `packageManager` field in package.json,
+/// because Flox can't provide the requested version
+#[derive(Clone, Debug, PartialEq)]
+struct CorepackInstall {
+    manager: PackageManager,
+    /// The `packageManager` field in package.json
+    requested: String,
+    node: SearchResult,
+    /// The [NodeVersionFile] [Self::node] was requested by, if any
+    version_file: Option<NodeVersionFile>,
+}
+
 #[derive(Clone)]
 enum NodeInstallAction {
     Yarn(Box<YarnInstall>),
     YarnOrNode(Box<YarnInstall>, Box<NodeInstall>),
     Node(Box<NodeInstall>),
+    PackageManager(Box<PackageManagerInstall>),
+    Corepack(Box<CorepackInstall>),
+}
+
+/// Package managers that may be requested by the `packageManager` field in
+/// package.json or detected by their lockfile
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PackageManager {
+    Npm,
+    Yarn,
+    Pnpm,
+    Bun,
+}
+
+impl PackageManager {
+    fn name(&self) -> &'static str {
+        match self {
+            PackageManager::Npm => "npm",
+            PackageManager::Yarn => "yarn",
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Bun => "bun",
+        }
+    }
+
+    fn lockfiles(&self) -> &'static [&'static str] {
+        match self {
+            PackageManager::Npm => &["package-lock.json"],
+            PackageManager::Yarn => &["yarn.lock"],
+            PackageManager::Pnpm => &["pnpm-lock.yaml"],
+            PackageManager::Bun => &["bun.lockb", "bun.lock"],
+        }
+    }
+
+    /// The first lockfile of this package manager that exists in `path`
+    fn find_lockfile(&self, path: &Path) -> Option<&'static str> {
+        self.lockfiles()
+            .iter()
+            .find(|lockfile| path.join(lockfile).exists())
+            .copied()
+    }
+
+    /// Whether Corepack can provide this package manager
+    fn supported_by_corepack(&self) -> bool {
+        matches!(self, PackageManager::Yarn | PackageManager::Pnpm)
+    }
+}
+
+/// The `packageManager` field in package.json as used by Corepack,
+/// e.g. `pnpm@8.15.4+sha256.abc...`
+///
+/// <https://nodejs.org/api/packages.html#packagemanager>
+#[derive(Clone, Debug, PartialEq)]
+struct RequestedPackageManager {
+    manager: PackageManager,
+    version: String,
+    /// The unmodified field
+    raw: String,
 }
 
 struct PackageJSONVersions {
     yarn: Option<String>,
     node: Option<String>,
+    pnpm: Option<String>,
+    package_manager: Option<RequestedPackageManager>,
 }
 
 impl Node {
@@ -125,8 +291,13 @@ impl Node {
     ///   - yarn.lock does not exist
     /// - Returns [NodeAction::InstallYarnOrNode] if
     ///   - Constraints for yarn and constraints for nodejs are met
+    /// - Returns [NodeInstallAction::PackageManager] or
+    ///   [NodeInstallAction::Corepack] if
+    ///   - package.json requests a package manager other than npm with the
+    ///     `packageManager` field, or pnpm-lock.yaml or bun.lockb exist
+    ///   - See [Self::try_find_package_manager_action]
     /// - Returns [None] if
-    ///   - There's no package.json or .nvmrc
+    ///   - There's no package.json or [NodeVersionFile]
     ///   - We can't satisfy constraints in package.json
     pub fn new(flox: &Flox, path: &Path) -> Result<Option<Self>> {
         // Check for a viable yarn
@@ -138,7 +309,39 @@ impl Node {
         // satisfies all constraints,
         // but that seems unlikely to be as commonly needed.
         let versions = Self::get_package_json_versions(path)?;
+
+        // package.json takes precedence over version files,
+        // so only check them if package.json doesn't request a version of nodejs
+        let checked_version_file = match versions {
+            Some(PackageJSONVersions { node: Some(_), .. }) => None,
+            _ => Some(Self::get_version_file_version(flox, path)?),
+        };
+
+        if let Some(ref versions) = versions {
+            let version_file_node = match &checked_version_file {
+                Some(Some((version_file, NVMRCVersion::Found(result)))) => {
+                    Some((*version_file, *result.clone()))
+                },
+                _ => None,
+            };
+            if let Some(action) =
+                Self::try_find_package_manager_action(flox, path, versions, version_file_node)?
+            {
+                return Ok(Some(Self {
+                    action,
+                    package_json_node_version: None,
+                    version_file: None,
+                }));
+            }
+        }
+
         let yarn_lock_exists = path.join("yarn.lock").exists();
+        // If lockfiles of other package managers exist,
+        // but we couldn't find a compatible package manager,
+        // don't offer an npm hook either
+        let other_lock_exists = [PackageManager::Pnpm, PackageManager::Bun]
+            .iter()
+            .any(|manager| manager.find_lockfile(path).is_some());
         let yarn_install = match versions {
             None => None,
             Some(ref versions) => {
@@ -161,7 +364,7 @@ impl Node {
                 return Ok(Some(Self {
                     action: NodeInstallAction::Yarn(Box::new(yarn_install.clone())),
                     package_json_node_version: None,
-                    nvmrc_version: None,
+                    version_file: None,
                 }));
             }
         }
@@ -180,21 +383,24 @@ impl Node {
             _ => None,
         };
 
-        // Get value for self.nvmrc_version
+        // Get value for self.version_file
         // We only do this if we didn't return early with action [NodeAction::YarnInstall]
-        let nvmrc_version = match package_json_node_version {
-            // package.json is higher priority than .nvmrc,
-            // so don't check .nvmrc if we know we'll use the version in
+        let version_file = match package_json_node_version {
+            // package.json is higher priority than version files,
+            // so don't check version files if we know we'll use the version in
             // package.json or we know we can't provide it
             Some(PackageJSONVersion::Found(_)) | Some(PackageJSONVersion::Unavailable) => None,
-            _ => Self::get_nvmrc_version(flox, path)?,
+            _ => match checked_version_file {
+                Some(version_file) => version_file,
+                None => Self::get_version_file_version(flox, path)?,
+            },
         };
 
         let action = match yarn_install {
             Some(yarn_install) => {
                 Self::get_node_install(
                     &package_json_node_version,
-                    &nvmrc_version,
+                    &version_file,
                     valid_package_json,
                 )
                 .map_or(
@@ -212,13 +418,13 @@ impl Node {
             None => {
                 Self::get_node_install(
                     &package_json_node_version,
-                    &nvmrc_version,
+                    &version_file,
                     valid_package_json,
                 )
                 .map(|mut node_install| {
                     // If yarn.lock exists but we couldn't find a compatible
                     // yarn, don't offer an npm hook
-                    if yarn_lock_exists {
+                    if yarn_lock_exists || other_lock_exists {
                         node_install.npm_hook = false;
                     }
                     NodeInstallAction::Node(Box::new(node_install))
@@ -228,11 +434,145 @@ impl Node {
 
         Ok(action.map(|action| Self {
             package_json_node_version,
-            nvmrc_version,
+            version_file,
             action,
         }))
     }
 
+    /// Determine an action for a package manager other than npm or yarn
+    /// detected by its lockfile,
+    /// or any package manager other than npm requested by the
+    /// `packageManager` field in package.json.
+    ///
+    /// The `packageManager` field takes precedence over lockfiles.
+    ///
+    /// `version_file_node` is nodejs requested by a [NodeVersionFile],
+    /// which is installed alongside pnpm or bun, or provides Corepack.
+    ///
+    /// - Returns [NodeInstallAction::Yarn] or [NodeInstallAction::PackageManager]
+    ///   if we can find the requested package manager
+    ///   and a nodejs compatible with package.json engines
+    /// - Returns [NodeInstallAction::Corepack] if we can't provide the version
+    ///   requested by the `packageManager` field, but Corepack can
+    /// - Returns [None] otherwise,
+    ///   in which case the yarn.lock and package-lock.json detection applies
+    fn try_find_package_manager_action(
+        flox: &Flox,
+        path: &Path,
+        versions: &PackageJSONVersions,
+        version_file_node: Option<(NodeVersionFile, SearchResult)>,
+    ) -> Result<Option<NodeInstallAction>> {
+        let (manager, version, requested) = match &versions.package_manager {
+            Some(requested) => (
+                requested.manager,
+                Some(requested.version.clone()),
+                Some(requested.raw.clone()),
+            ),
+            None if PackageManager::Pnpm.find_lockfile(path).is_some() => {
+                (PackageManager::Pnpm, versions.pnpm.clone(), None)
+            },
+            None if PackageManager::Bun.find_lockfile(path).is_some() => {
+                (PackageManager::Bun, None, None)
+            },
+            None => return Ok(None),
+        };
+
+        // Fall back to Corepack if the `packageManager` field requests
+        // a version we can't provide
+        let corepack = |flox: &Flox| -> Result<Option<NodeInstallAction>> {
+            let Some(requested) = requested.clone() else {
+                return Ok(None);
+            };
+            if !manager.supported_by_corepack() {
+                return Ok(None);
+            }
+            let (node, version_file) = match version_file_node.clone() {
+                Some((version_file, node)) => (node, Some(version_file)),
+                None => match Self::try_find_package_manager_node(flox, versions)? {
+                    Some(node) => (node, None),
+                    None => return Ok(None),
+                },
+            };
+            Ok(Some(NodeInstallAction::Corepack(Box::new(
+                CorepackInstall {
+                    manager,
+                    requested,
+                    node,
+                    version_file,
+                },
+            ))))
+        };
+
+        match manager {
+            PackageManager::Npm => Ok(None),
+            PackageManager::Yarn => {
+                let yarn_versions = PackageJSONVersions {
+                    yarn: version,
+                    node: versions.node.clone(),
+                    pnpm: None,
+                    package_manager: None,
+                };
+                match Self::try_find_compatible_yarn(flox, &yarn_versions)? {
+                    Some(yarn_install) => Ok(Some(NodeInstallAction::Yarn(Box::new(yarn_install)))),
+                    None => corepack(flox),
+                }
+            },
+            PackageManager::Pnpm | PackageManager::Bun => {
+                // bun is a nodejs replacement and doesn't need nodejs
+                let node = if manager == PackageManager::Bun {
+                    None
+                } else {
+                    match Self::try_find_package_manager_node(flox, versions)? {
+                        Some(node) => Some(node),
+                        None => return Ok(None),
+                    }
+                };
+
+                let package = match &version {
+                    Some(version) => {
+                        Self::try_find_compatible_version(flox, manager.name(), version, None)?
+                    },
+                    None => Some(Self::get_default_package(flox, manager.name())?),
+                };
+
+                match package {
+                    Some(package) => Ok(Some(NodeInstallAction::PackageManager(Box::new(
+                        PackageManagerInstall {
+                            manager,
+                            requested,
+                            version,
+                            package,
+                            lockfile: manager.find_lockfile(path),
+                            node,
+                            version_file_node,
+                        },
+                    )))),
+                    None => corepack(flox),
+                }
+            },
+        }
+    }
+
+    /// Find nodejs compatible with package.json engines to run a package
+    /// manager with.
+    ///
+    /// We assume that package managers are built with nixpkgs#nodejs,
+    /// which is currently true in nixpkgs.
+    fn try_find_package_manager_node(
+        flox: &Flox,
+        versions: &PackageJSONVersions,
+    ) -> Result<Option<SearchResult>> {
+        match &versions.node {
+            Some(node_version) => {
+                Self::get_default_node_if_compatible(flox, Some(node_version.clone()))
+            },
+            None => Ok(Some(
+                Self::get_default_node_if_compatible(flox, None)?
+                    .ok_or(anyhow!("Flox couldn't find nodejs in nixpkgs"))?,
+            )),
+        }
+    }
+
     /// Look for nodejs, npm, and yarn versions in a (possibly non-existent)
     /// `package.json` file
     fn get_package_json_versions(path: &Path) -> Result<Option<PackageJSONVersions>> {
@@ -251,7 +591,18 @@ impl Node {
                 let yarn = package_json_json["engines"]["yarn"]
                     .as_str()
                     .map(|s| s.to_string());
-                Ok(Some(PackageJSONVersions { node, yarn }))
+                let pnpm = package_json_json["engines"]["pnpm"]
+                    .as_str()
+                    .map(|s| s.to_string());
+                let package_manager = package_json_json["packageManager"]
+                    .as_str()
+                    .and_then(Self::parse_package_manager);
+                Ok(Some(PackageJSONVersions {
+                    node,
+                    yarn,
+                    pnpm,
+                    package_manager,
+                }))
             },
         }
     }
@@ -262,22 +613,13 @@ impl Node {
         flox: &Flox,
         versions: &PackageJSONVersions,
     ) -> Result<Option<YarnInstall>> {
-        let PackageJSONVersions { yarn, node, .. } = versions;
+        let PackageJSONVersions { yarn, .. } = versions;
 
-        let found_node = match node {
-            Some(node_version) => {
-                match Self::get_default_node_if_compatible(flox, Some(node_version.clone()))? {
-                    // If the corresponding node isn't compatible, don't install yarn
-                    None => return Ok(None),
-                    Some(found_node) => found_node,
-                }
-            },
-            None => Self::get_default_node_if_compatible(flox, None)?
-                .ok_or(anyhow!("Flox couldn't find nodejs in nixpkgs"))?,
+        let Some(found_node) = Self::try_find_package_manager_node(flox, versions)? else {
+            // If the corresponding node isn't compatible, don't install yarn
+            return Ok(None);
         };
 
-        // We assume that yarn is built with found_node, which is currently true
-        // in nixpkgs
         let found_yarn = match yarn {
             Some(yarn_version) => {
                 Self::try_find_compatible_version(flox, "yarn", yarn_version, None)?
@@ -291,29 +633,35 @@ impl Node {
         }))
     }
 
-    /// Determine appropriate [NVMRCVersion] variant for a (possibly
-    /// non-existent) `.nvmrc` file in `path`.
+    /// Determine appropriate [NVMRCVersion] variant for the first
+    /// [NodeVersionFile] in `path` that requests a version of nodejs.
     ///
     /// This will perform a search to determine if a requested version is
     /// available.
-    fn get_nvmrc_version(flox: &Flox, path: &Path) -> Result<Option<NVMRCVersion>> {
-        let nvmrc = path.join(".nvmrc");
-        if !nvmrc.exists() {
-            return Ok(None);
-        }
+    fn get_version_file_version(
+        flox: &Flox,
+        path: &Path,
+    ) -> Result<Option<(NodeVersionFile, NVMRCVersion)>> {
+        for version_file in NodeVersionFile::ALL {
+            let file_path = path.join(version_file.filename());
+            if !file_path.exists() {
+                continue;
+            }
 
-        let nvmrc_contents = fs::read_to_string(&nvmrc)?;
-        let nvmrc_version = match Self::parse_nvmrc_version(&nvmrc_contents) {
-            RequestedNVMRCVersion::None => None,
-            RequestedNVMRCVersion::Unsure => Some(NVMRCVersion::Unsure),
-            RequestedNVMRCVersion::Found(version) => {
-                match Self::try_find_compatible_version(flox, "nodejs", &version, None)? {
-                    None => Some(NVMRCVersion::Unavailable),
-                    Some(result) => Some(NVMRCVersion::Found(Box::new(result))),
-                }
-            },
-        };
-        Ok(nvmrc_version)
+            let contents = fs::read_to_string(&file_path)?;
+            let version = match version_file.parse(&contents) {
+                RequestedNVMRCVersion::None => continue,
+                RequestedNVMRCVersion::Unsure => NVMRCVersion::Unsure,
+                RequestedNVMRCVersion::Found(version) => {
+                    match Self::try_find_compatible_version(flox, "nodejs", &version, None)? {
+                        None => NVMRCVersion::Unavailable,
+                        Some(result) => NVMRCVersion::Found(Box::new(result)),
+                    }
+                },
+            };
+            return Ok(Some((version_file, version)));
+        }
+        Ok(None)
     }
 
     /// Translate the contents of a `.nvmrc` file into a [RequestedNVMRCVersion]
@@ -353,6 +701,70 @@ impl Node {
         }
     }
 
+    /// Translate the contents of a `.tool-versions` file into a
+    /// [RequestedNVMRCVersion]
+    ///
+    /// asdf uses the first version listed for a tool,
+    /// further versions are fallbacks.
+    /// <https://asdf-vm.com/manage/configuration.html#tool-versions>
+    fn parse_tool_versions_node_version(tool_versions_contents: &str) -> RequestedNVMRCVersion {
+        let version = tool_versions_contents
+            .lines()
+            // strip comments
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .find_map(|line| {
+                let mut fields = line.split_whitespace();
+                // the asdf plugin is called nodejs, mise also accepts node
+                match fields.next() {
+                    Some("nodejs" | "node") => Some(fields.next()),
+                    _ => None,
+                }
+            });
+
+        match version {
+            None | Some(None) => RequestedNVMRCVersion::None,
+            Some(Some(version)) => {
+                let version = version.strip_prefix('v').unwrap_or(version);
+                // "latest", "lts", "system", "ref:<git-ref>", "path:<path>"
+                // can't be provided as versions
+                if version.starts_with(|c: char| c.is_ascii_digit())
+                    && VersionReq::parse(version).is_ok()
+                {
+                    RequestedNVMRCVersion::Found(version.to_string())
+                } else {
+                    RequestedNVMRCVersion::Unsure
+                }
+            },
+        }
+    }
+
+    /// Parse the `packageManager` field of package.json,
+    /// e.g. `pnpm@8.15.4` or `yarn@3.2.3+sha224.953c8233f7a92884eee2de69a1b92d1f2ec1655e66d08071ba9a02fa`
+    ///
+    /// Returns [None] if the field can't be parsed or names an unknown package manager.
+    fn parse_package_manager(field: &str) -> Option<RequestedPackageManager> {
+        let (name, version) = field.split_once('@')?;
+        // strip the optional hash
+        let version = version.split('+').next()?;
+
+        let manager = match name {
+            "npm" => PackageManager::Npm,
+            "yarn" => PackageManager::Yarn,
+            "pnpm" => PackageManager::Pnpm,
+            "bun" => PackageManager::Bun,
+            _ => return None,
+        };
+
+        // Corepack requires an exact version
+        semver::Version::parse(version).ok()?;
+
+        Some(RequestedPackageManager {
+            manager,
+            version: version.to_string(),
+            raw: field.to_string(),
+        })
+    }
+
     /// Searches for a given pname and version, optionally restricting rel_path
     fn try_find_compatible_version(
         flox: &Flox,
@@ -437,9 +849,10 @@ impl Node {
     /// and whether Flox can provide versions they request.
     fn get_node_install(
         package_json_node_version: &Option<PackageJSONVersion>,
-        nvmrc_version: &Option<NVMRCVersion>,
+        version_file: &Option<(NodeVersionFile, NVMRCVersion)>,
         npm_hook: bool,
     ) -> Option<NodeInstall> {
+        let nvmrc_version = version_file.as_ref().map(|(_, version)| version);
         match (package_json_node_version, nvmrc_version) {
             // package.json takes precedence over .nvmrc
             (Some(PackageJSONVersion::Found(result)), _) => Some(NodeInstall {
@@ -476,10 +889,13 @@ impl Node {
     ///    printing that message twice)
     fn nodejs_message_and_version(&self, flox: &Flox) -> Result<(String, Option<String>, bool)> {
         let mut mentions_package_json = false;
-        let (message, version) = match (
-            &self.package_json_node_version,
-            self.nvmrc_version.as_ref(),
-        ) {
+        let version_file = self
+            .version_file
+            .as_ref()
+            .map(|(file, _)| file.describe())
+            .unwrap_or_default();
+        let nvmrc_version = self.version_file.as_ref().map(|(_, version)| version);
+        let (message, version) = match (&self.package_json_node_version, nvmrc_version) {
             // package.json takes precedence over .nvmrc
             (Some(PackageJSONVersion::Found(result)), _) => {
                 let message = format!(
@@ -494,20 +910,20 @@ impl Node {
             (Some(PackageJSONVersion::Unavailable), _) => unreachable!(),
             (_, Some(NVMRCVersion::Found(result))) => {
                 let message = format!(
-                    "Flox detected an .nvmrc{}",
+                    "Flox detected {version_file}{}",
                     Self::format_version_or_empty(result.version.as_ref())
                 );
                 (message, result.version.clone())
             },
             (_, Some(NVMRCVersion::Unsure)) => {
                 let result = Self::get_default_package(flox, "nodejs")?;
-                let message = format!("Flox detected an .nvmrc with a version specifier not understood by Flox, but Flox can provide {}",
+                let message = format!("Flox detected {version_file} with a version specifier not understood by Flox, but Flox can provide {}",
                        result.version.as_ref().map(|version|format!("version {version}")).unwrap_or("another version".to_string()));
                 (message, result.version)
             },
             (_, Some(NVMRCVersion::Unavailable)) => {
                 let result = Self::get_default_package(flox, "nodejs")?;
-                let message = format!("Flox detected an .nvmrc with a version of nodejs not provided by Flox, but Flox can provide {}",
+                let message = format!("Flox detected {version_file} with a version of nodejs not provided by Flox, but Flox can provide {}",
                 result.version.as_ref().map(|version|format!("version {version}")).unwrap_or("another version".to_string()));
                 (message, result.version.clone())
             },
@@ -594,6 +1010,75 @@ impl Node {
         self.single_option_prompt()
     }
 
+    /// Prompt whether to install pnpm or bun
+    fn prompt_with_package_manager(&self, install: &PackageManagerInstall) -> Result<bool> {
+        let manager = install.manager.name();
+        let manager_version = Self::format_version_or_empty(install.package.version.as_ref());
+
+        let mut detected = match (&install.requested, install.lockfile) {
+            (Some(requested), _) => format!("Flox detected a package.json requesting {requested}"),
+            (None, Some(lockfile)) => format!("Flox detected a package.json and a {lockfile}"),
+            (None, None) => "Flox detected a package.json".to_string(),
+        };
+
+        let mut packages = match (&install.node, &install.version_file_node) {
+            (Some(node), None) => format!(
+                "* {manager}{manager_version} with nodejs{} bundled",
+                Self::format_version_or_empty(node.version.as_ref())
+            ),
+            _ => format!("* {manager}{manager_version}"),
+        };
+
+        if let Some((version_file, node)) = &install.version_file_node {
+            let node_version = Self::format_version_or_empty(node.version.as_ref());
+            detected.push_str(&format!(
+                "\nFlox detected {}{node_version}",
+                version_file.describe()
+            ));
+            packages.push_str(&format!("\n* nodejs{node_version}"));
+        }
+
+        message::plain(formatdoc! {"
+            {detected}
+
+            Flox can add the following to your environment:
+            {packages}
+            * A {manager} installation hook
+            "});
+
+        self.single_option_prompt()
+    }
+
+    /// Prompt whether to install nodejs and let Corepack provide the
+    /// requested package manager
+    fn prompt_with_corepack(&self, install: &CorepackInstall) -> Result<bool> {
+        let node_version = Self::format_version_or_empty(install.node.version.as_ref());
+        let CorepackInstall {
+            manager,
+            requested,
+            version_file,
+            ..
+        } = install;
+        let manager = manager.name();
+        let version_file = version_file
+            .map(|version_file| {
+                format!("Flox detected {}{node_version}\n", version_file.describe())
+            })
+            .unwrap_or_default();
+
+        message::plain(formatdoc! {"
+            Flox detected a package.json requesting {requested}
+            {version_file}Flox can't provide {requested}, but Corepack can
+
+            Flox can add the following to your environment:
+            * nodejs{node_version} with Corepack bundled
+            * A hook letting Corepack provide {requested}
+            * A {manager} installation hook
+            "});
+
+        self.single_option_prompt()
+    }
+
     /// Prompt whether to install npm or yarn when either is viable
     fn prompt_for_package_manager(
         &mut self,
@@ -677,6 +1162,8 @@ impl InitHook for Node {
                 self.prompt_for_package_manager(flox, *yarn_install.clone(), *node_install.clone())
             },
             NodeInstallAction::Node(node_install) => self.prompt_with_node(flox, node_install),
+            NodeInstallAction::PackageManager(install) => self.prompt_with_package_manager(install),
+            NodeInstallAction::Corepack(install) => self.prompt_with_corepack(install),
         }
     }
 
@@ -720,6 +1207,36 @@ impl InitHook for Node {
                     None
                 }
             },
+            NodeInstallAction::PackageManager(install) => {
+                packages.push(PackageToInstall {
+                    id: install.manager.name().to_string(),
+                    pkg_path: install.package.rel_path.join("."),
+                    // Only constrain the version if one was requested
+                    version: install.version.clone(),
+                    input: None,
+                });
+                if let Some((_, node)) = &install.version_file_node {
+                    packages.push(PackageToInstall {
+                        id: "nodejs".to_string(),
+                        pkg_path: node.rel_path.join("."),
+                        version: node.version.clone(),
+                        input: None,
+                    });
+                }
+                match install.manager {
+                    PackageManager::Bun => Some(BUN_HOOK.to_string()),
+                    _ => Some(PNPM_HOOK.to_string()),
+                }
+            },
+            NodeInstallAction::Corepack(install) => {
+                packages.push(PackageToInstall {
+                    id: "nodejs".to_string(),
+                    pkg_path: install.node.rel_path.join("."),
+                    version: install.node.version.clone(),
+                    input: None,
+                });
+                Some(corepack_hook(install.manager))
+            },
         };
 
         InitCustomization {
@@ -727,6 +1244,24 @@ impl InitHook for Node {
             packages: Some(packages),
         }
     }
+
+    /// nodejs and the package manager the user may choose,
+    /// even if they come bundled rather than as separate packages
+    fn install_ids(&self) -> Vec<String> {
+        let manager = match &self.action {
+            NodeInstallAction::Yarn(_) | NodeInstallAction::YarnOrNode(_, _) => {
+                Some(PackageManager::Yarn)
+            },
+            NodeInstallAction::Node(_) => None,
+            NodeInstallAction::PackageManager(install) => Some(install.manager),
+            NodeInstallAction::Corepack(install) => Some(install.manager),
+        };
+        ["nodejs"]
+            .into_iter()
+            .chain(manager.map(|manager| manager.name()))
+            .map(String::from)
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -787,13 +1322,260 @@ mod tests {
         assert_eq!(Node::parse_nvmrc_version("\n"), RequestedNVMRCVersion::None);
     }
 
+    #[test]
+    fn test_parse_tool_versions_node_version() {
+        assert_eq!(
+            Node::parse_tool_versions_node_version("python 3.11.4\nnodejs 20.11.1 18.19.0\n"),
+            RequestedNVMRCVersion::Found("20.11.1".to_string())
+        );
+        assert_eq!(
+            Node::parse_tool_versions_node_version("node v20 # comment"),
+            RequestedNVMRCVersion::Found("20".to_string())
+        );
+        assert_eq!(
+            Node::parse_tool_versions_node_version("nodejs lts"),
+            RequestedNVMRCVersion::Unsure
+        );
+        assert_eq!(
+            Node::parse_tool_versions_node_version("nodejs ref:v20.11.1"),
+            RequestedNVMRCVersion::Unsure
+        );
+        assert_eq!(
+            Node::parse_tool_versions_node_version("python 3.11.4\n# nodejs 20"),
+            RequestedNVMRCVersion::None
+        );
+        assert_eq!(
+            Node::parse_tool_versions_node_version(""),
+            RequestedNVMRCVersion::None
+        );
+    }
+
+    /// .node-version files are parsed like .nvmrc files
+    #[test]
+    fn test_parse_node_version_file() {
+        assert_eq!(
+            NodeVersionFile::NodeVersion.parse("v20.11.1\n"),
+            RequestedNVMRCVersion::Found("20.11.1".to_string())
+        );
+        assert_eq!(
+            NodeVersionFile::NodeVersion.parse("lts/iron"),
+            RequestedNVMRCVersion::Unsure
+        );
+    }
+
+    #[test]
+    fn test_parse_package_manager() {
+        assert_eq!(
+            Node::parse_package_manager("pnpm@8.15.4"),
+            Some(RequestedPackageManager {
+                manager: PackageManager::Pnpm,
+                version: "8.15.4".to_string(),
+                raw: "pnpm@8.15.4".to_string(),
+            })
+        );
+        assert_eq!(
+            Node::parse_package_manager(
+                "yarn@3.2.3+sha224.953c8233f7a92884eee2de69a1b92d1f2ec1655e66d08071ba9a02fa"
+            ),
+            Some(RequestedPackageManager {
+                manager: PackageManager::Yarn,
+                version: "3.2.3".to_string(),
+                raw: "yarn@3.2.3+sha224.953c8233f7a92884eee2de69a1b92d1f2ec1655e66d08071ba9a02fa"
+                    .to_string(),
+            })
+        );
+        // Corepack requires an exact version
+        assert_eq!(Node::parse_package_manager("pnpm@8"), None);
+        assert_eq!(Node::parse_package_manager("pnpm"), None);
+        assert_eq!(Node::parse_package_manager("deno@1.40.0"), None);
+    }
+
+    /// Test get_init_customization() for action PackageManager with pnpm
+    #[test]
+    fn test_get_init_customization_pnpm() {
+        assert_eq!(
+            Node {
+                package_json_node_version: None,
+                version_file: None,
+                action: NodeInstallAction::PackageManager(Box::new(PackageManagerInstall {
+                    manager: PackageManager::Pnpm,
+                    requested: Some("pnpm@8.15.4".to_string()),
+                    version: Some("8.15.4".to_string()),
+                    package: SearchResult {
+                        rel_path: vec!["nodePackages".to_string(), "pnpm".to_string()],
+                        version: Some("8.15.4".to_string()),
+                        ..Default::default()
+                    },
+                    lockfile: None,
+                    node: Some(SearchResult::default()),
+                    version_file_node: None,
+                })),
+            }
+            .get_init_customization(),
+            InitCustomization {
+                packages: Some(vec![PackageToInstall {
+                    id: "pnpm".to_string(),
+                    pkg_path: "nodePackages.pnpm".to_string(),
+                    version: Some("8.15.4".to_string()),
+                    input: None,
+                }]),
+                profile: Some(PNPM_HOOK.to_string()),
+            }
+        );
+    }
+
+    /// Test get_init_customization() for action PackageManager with bun
+    /// detected by its lockfile
+    #[test]
+    fn test_get_init_customization_bun() {
+        assert_eq!(
+            Node {
+                package_json_node_version: None,
+                version_file: None,
+                action: NodeInstallAction::PackageManager(Box::new(PackageManagerInstall {
+                    manager: PackageManager::Bun,
+                    requested: None,
+                    version: None,
+                    package: SearchResult {
+                        rel_path: vec!["bun".to_string()],
+                        version: Some("1.0.30".to_string()),
+                        ..Default::default()
+                    },
+                    lockfile: Some("bun.lock"),
+                    node: None,
+                    version_file_node: None,
+                })),
+            }
+            .get_init_customization(),
+            InitCustomization {
+                packages: Some(vec![PackageToInstall {
+                    id: "bun".to_string(),
+                    pkg_path: "bun".to_string(),
+                    version: None,
+                    input: None,
+                }]),
+                profile: Some(BUN_HOOK.to_string()),
+            }
+        );
+    }
+
+    /// Test get_init_customization() for action PackageManager installs the
+    /// nodejs requested by a version file alongside the package manager
+    #[test]
+    fn test_get_init_customization_package_manager_version_file() {
+        assert_eq!(
+            Node {
+                package_json_node_version: None,
+                version_file: None,
+                action: NodeInstallAction::PackageManager(Box::new(PackageManagerInstall {
+                    manager: PackageManager::Bun,
+                    requested: None,
+                    version: None,
+                    package: SearchResult {
+                        rel_path: vec!["bun".to_string()],
+                        version: Some("1.0.30".to_string()),
+                        ..Default::default()
+                    },
+                    lockfile: Some("bun.lock"),
+                    node: None,
+                    version_file_node: Some((NodeVersionFile::Nvmrc, SearchResult {
+                        rel_path: vec!["nodejs_20".to_string()],
+                        version: Some("20.11.1".to_string()),
+                        ..Default::default()
+                    })),
+                })),
+            }
+            .get_init_customization(),
+            InitCustomization {
+                packages: Some(vec![
+                    PackageToInstall {
+                        id: "bun".to_string(),
+                        pkg_path: "bun".to_string(),
+                        version: None,
+                        input: None,
+                    },
+                    PackageToInstall {
+                        id: "nodejs".to_string(),
+                        pkg_path: "nodejs_20".to_string(),
+                        version: Some("20.11.1".to_string()),
+                        input: None,
+                    }
+                ]),
+                profile: Some(BUN_HOOK.to_string()),
+            }
+        );
+    }
+
+    /// find_lockfile() returns the lockfile that exists rather than the first
+    /// one the package manager may use
+    #[test]
+    fn test_find_lockfile() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert_eq!(PackageManager::Bun.find_lockfile(temp_dir.path()), None);
+
+        fs::write(temp_dir.path().join("bun.lock"), "").unwrap();
+        assert_eq!(
+            PackageManager::Bun.find_lockfile(temp_dir.path()),
+            Some("bun.lock")
+        );
+    }
+
+    /// Test get_init_customization() for action Corepack
+    #[test]
+    fn test_get_init_customization_corepack() {
+        assert_eq!(
+            Node {
+                package_json_node_version: None,
+                version_file: None,
+                action: NodeInstallAction::Corepack(Box::new(CorepackInstall {
+                    manager: PackageManager::Yarn,
+                    requested: "yarn@4.1.0".to_string(),
+                    node: SearchResult {
+                        rel_path: vec!["nodejs".to_string()],
+                        version: Some("18.19.0".to_string()),
+                        ..Default::default()
+                    },
+                    version_file: None,
+                })),
+            }
+            .get_init_customization(),
+            InitCustomization {
+                packages: Some(vec![PackageToInstall {
+                    id: "nodejs".to_string(),
+                    pkg_path: "nodejs".to_string(),
+                    version: Some("18.19.0".to_string()),
+                    input: None,
+                }]),
+                profile: Some(corepack_hook(PackageManager::Yarn)),
+            }
+        );
+        assert!(corepack_hook(PackageManager::Yarn).ends_with("yarn install"));
+    }
+
+    /// install_ids() includes nodejs bundled with yarn
+    #[test]
+    fn test_install_ids_yarn() {
+        let node = Node {
+            package_json_node_version: None,
+            version_file: None,
+            action: NodeInstallAction::Yarn(Box::new(YarnInstall {
+                yarn: SearchResult::default(),
+                node: SearchResult::default(),
+            })),
+        };
+        assert_eq!(node.install_ids(), vec![
+            "nodejs".to_string(),
+            "yarn".to_string()
+        ]);
+    }
+
     /// Test get_init_customization() for action InstallYarn
     #[test]
     fn test_get_init_customization_yarn() {
         assert_eq!(
             Node {
                 package_json_node_version: None,
-                nvmrc_version: None,
+                version_file: None,
                 action: NodeInstallAction::Yarn(Box::new(YarnInstall {
                     yarn: SearchResult {
                         rel_path: vec!["yarn".to_string(), "path".to_string()],
@@ -823,7 +1605,7 @@ mod tests {
         assert_eq!(
             Node {
                 package_json_node_version: None,
-                nvmrc_version: None,
+                version_file: None,
                 action: NodeInstallAction::YarnOrNode(
                     Box::new(YarnInstall {
                         yarn: SearchResult::default(),
@@ -857,7 +1639,7 @@ mod tests {
         assert_eq!(
             Node {
                 package_json_node_version: None,
-                nvmrc_version: None,
+                version_file: None,
                 action: NodeInstallAction::Node(Box::new(NodeInstall {
                     node: Some(SearchResult {
                         rel_path: vec!["nodejs".to_string(), "path".to_string()],
@@ -893,6 +1675,8 @@ mod tests {
         let yarn_install = Node::try_find_compatible_yarn(&flox, &PackageJSONVersions {
             yarn: None,
             node: None,
+            pnpm: None,
+            package_manager: None,
         })
         .unwrap()
         .unwrap();
@@ -909,6 +1693,8 @@ mod tests {
         let yarn_install = Node::try_find_compatible_yarn(&flox, &PackageJSONVersions {
             yarn: None,
             node: Some("18".to_string()),
+            pnpm: None,
+            package_manager: None,
         })
         .unwrap()
         .unwrap();
@@ -927,6 +1713,8 @@ mod tests {
         let yarn_install = Node::try_find_compatible_yarn(&flox, &PackageJSONVersions {
             yarn: None,
             node: Some("20".to_string()),
+            pnpm: None,
+            package_manager: None,
         })
         .unwrap();
 
@@ -941,6 +1729,8 @@ mod tests {
         let yarn_install = Node::try_find_compatible_yarn(&flox, &PackageJSONVersions {
             yarn: Some("1".to_string()),
             node: None,
+            pnpm: None,
+            package_manager: None,
         })
         .unwrap()
         .unwrap();
@@ -959,6 +1749,8 @@ mod tests {
         let yarn_install = Node::try_find_compatible_yarn(&flox, &PackageJSONVersions {
             yarn: Some("2".to_string()),
             node: None,
+            pnpm: None,
+            package_manager: None,
         })
         .unwrap();
 
@@ -974,6 +1766,8 @@ mod tests {
         let yarn_install = Node::try_find_compatible_yarn(&flox, &PackageJSONVersions {
             yarn: Some("1".to_string()),
             node: Some("18".to_string()),
+            pnpm: None,
+            package_manager: None,
         })
         .unwrap()
         .unwrap();
@@ -35,7 +35,10 @@ impl Python {
     pub fn new(flox: &Flox, path: &Path) -> Option<Self> {
         let providers = vec![
             PoetryPyProject::detect(flox, path).into(),
+            UvProject::detect(flox, path).into(),
+            Pipenv::detect(flox, path).into(),
             PyProject::detect(flox, path).into(),
+            CondaEnvironment::detect(flox, path).into(),
             Requirements::detect(flox, path).into(),
         ];
 
@@ -198,6 +201,158 @@ trait Provider: Debug {
     fn get_init_customization(&self) -> InitCustomization;
 }
 
+/// Resolve a python version from the catalogs for an optional requirement.
+///
+/// If no version was requested, the default python3 is considered compatible.
+/// If the requested version can not be provided, the default python3 is
+/// returned as a substitute.
+fn provide_python_version(
+    flox: &Flox,
+    required_python_version: Option<String>,
+) -> Result<ProvidedVersion> {
+    let search_default = || {
+        let default = get_default_package_if_compatible(flox, ["python3"], None)?
+            .context("No python3 in the catalogs")?
+            .try_into()?;
+        Ok::<_, Error>(default)
+    };
+
+    let Some(required_python_version) = required_python_version else {
+        return Ok(ProvidedVersion::Compatible {
+            compatible: search_default()?,
+            requested: None,
+        });
+    };
+
+    let compatible = try_find_compatible_version(
+        flox,
+        "python3",
+        Some(required_python_version.clone()),
+        None::<Vec<&str>>,
+    )?;
+
+    if let Some(found_version) = compatible {
+        return Ok(ProvidedVersion::Compatible {
+            compatible: found_version.try_into()?,
+            requested: Some(required_python_version),
+        });
+    }
+
+    log::debug!("python version {required_python_version} requested, but no compatible version found in the catalogs");
+
+    Ok(ProvidedVersion::Incompatible {
+        substitute: search_default()?,
+        requested: required_python_version,
+    })
+}
+
+/// Version constraint to lock python3 to, if a compatible version was found
+fn locked_python_version(provided_python_version: &ProvidedVersion) -> Option<String> {
+    match provided_python_version {
+        ProvidedVersion::Incompatible { .. } => None, /* do not lock if no compatible version was found */
+        ProvidedVersion::Compatible { requested, .. } => requested.clone(),
+    }
+}
+
+/// Note to append to a customization description
+/// if the requested python version could not be provided
+fn incompatible_version_note(provided_python_version: &ProvidedVersion) -> Option<String> {
+    match provided_python_version {
+        ProvidedVersion::Incompatible {
+            requested,
+            substitute,
+        } => Some(format!(
+            "Note: Flox could not provide requested version {requested}, but can provide {sub_version} instead.",
+            sub_version = substitute.display_version,
+        )),
+        ProvidedVersion::Compatible { .. } => None,
+    }
+}
+
+/// Convert a PEP 440 version specifier (as used by `requires-python`)
+/// into a semver requirement understood by pkgdb.
+///
+/// Only the subset of PEP 440 commonly used to constrain python versions
+/// is supported:
+///
+/// * `~=X.Y` (compatible release) is converted to `^X.Y`,
+///   `~=X.Y.Z` to `~X.Y.Z`
+/// * `==X.Y.*` is converted to `X.Y.*`
+/// * `==X.Y.Z` is converted to `=X.Y.Z`
+/// * `!=` exclusions can't be expressed and are dropped,
+///   so the result may allow slightly more versions than requested
+/// * all other comparison operators are passed on as is
+///
+/// <https://peps.python.org/pep-0440/#version-specifiers>
+fn pep440_to_semver(specifier: &str) -> Result<String> {
+    let converted = specifier
+        .split(',')
+        .map(str::trim)
+        .filter(|clause| {
+            let exclusion = clause.starts_with("!=");
+            if exclusion {
+                debug!("ignoring python version exclusion '{clause}'");
+            }
+            !exclusion
+        })
+        .map(|clause| {
+            if let Some(version) = clause.strip_prefix("~=") {
+                let version = version.trim();
+                match version.split('.').count() {
+                    0 | 1 => Err(anyhow!(
+                        "'~=' requires at least two version components, found '{clause}'"
+                    )),
+                    2 => Ok(format!("^{version}")),
+                    _ => Ok(format!("~{version}")),
+                }
+            } else if let Some(version) = clause.strip_prefix("==") {
+                let version = version.trim();
+                if version.ends_with(".*") {
+                    Ok(version.to_string())
+                } else {
+                    Ok(format!("={version}"))
+                }
+            } else {
+                Ok(clause.to_string())
+            }
+        })
+        .collect::<Result<Vec<_>>>()?
+        .join(", ");
+
+    if converted.is_empty() {
+        return Ok(semver::VersionReq::STAR.to_string());
+    }
+
+    Ok(converted.parse::<semver::VersionReq>()?.to_string())
+}
+
+/// Convert a python version pin like `3.11` or `3.11.4`,
+/// as found in `.python-version` or a Pipfile,
+/// into a semver requirement matching that release.
+///
+/// `3.11` matches any `3.11.x` release, `3.11.4` only matches exactly.
+/// An implementation prefix like in `pypy@3.10` or `cpython-3.11`
+/// is ignored, as flox provides the version of CPython.
+fn python_version_pin_to_semver(version: &str) -> Result<String> {
+    let version = version.trim();
+    let version = match version.find(|c: char| c.is_ascii_digit()) {
+        Some(0) | None => version,
+        Some(start) => {
+            debug!("ignoring python implementation in '{version}'");
+            let version = &version[start..];
+            // uv's full names may also include a platform, e.g. `cpython-3.11.4-linux-x86_64-gnu`
+            version
+                .split_once(|c: char| !c.is_ascii_digit() && c != '.')
+                .map_or(version, |(version, _)| version)
+        },
+    };
+    let requirement = match version.split('.').count() {
+        0..=2 => format!("~{version}"),
+        _ => format!("={version}"),
+    };
+    Ok(requirement.parse::<semver::VersionReq>()?.to_string())
+}
+
 /// Information gathered from a pyproject.toml file for poetry
 /// <https://packaging.python.org/en/latest/guides/distributing-packages-using-setuptools/#configuring-setup-py>
 #[derive(Debug, PartialEq)]
@@ -309,16 +464,9 @@ impl Provider for PoetryPyProject {
             Adds a hook to lock the poetry project and load the poetry environment
         ", self.provided_python_version.display_version(), self.poetry_version };
 
-        if let ProvidedVersion::Incompatible {
-            substitute,
-            requested,
-        } = &self.provided_python_version
-        {
+        if let Some(note) = incompatible_version_note(&self.provided_python_version) {
             message.push('\n');
-            message.push_str(&format!(
-                "Note: Flox could not provide requested version {requested}, but can provide {sub_version} instead.",
-                sub_version = substitute.display_version,
-            ));
+            message.push_str(&note);
             message.push('\n');
         }
 
@@ -326,10 +474,7 @@ impl Provider for PoetryPyProject {
     }
 
     fn get_init_customization(&self) -> InitCustomization {
-        let python_version = match &self.provided_python_version {
-            ProvidedVersion::Incompatible { .. } => None, /* do not lock if no compatible version was found */
-            ProvidedVersion::Compatible { requested, .. } => requested.clone(),
-        };
+        let python_version = locked_python_version(&self.provided_python_version);
 
         InitCustomization {
             profile: Some(
@@ -424,42 +569,7 @@ impl PyProject {
             .transpose()?
             .map(|req| req.to_string());
 
-        let provided_python_version = 'version: {
-            let search_default = || {
-                let default = get_default_package_if_compatible(flox, ["python3"], None)?
-                    .context("No python3 in the catalogs")?
-                    .try_into()?;
-                Ok::<_, Error>(default)
-            };
-
-            let Some(required_python_version) = required_python_version else {
-                break 'version ProvidedVersion::Compatible {
-                    compatible: search_default()?,
-                    requested: None,
-                };
-            };
-
-            let compatible = try_find_compatible_version(
-                flox,
-                "python3",
-                Some(required_python_version.clone()),
-                None::<Vec<&str>>,
-            )?;
-
-            if let Some(found_version) = compatible {
-                break 'version ProvidedVersion::Compatible {
-                    compatible: found_version.try_into()?,
-                    requested: Some(required_python_version),
-                };
-            }
-
-            log::debug!("pyproject.toml requires python version {required_python_version}, but no compatible version found in the catalogs");
-
-            ProvidedVersion::Incompatible {
-                substitute: search_default()?,
-                requested: required_python_version.clone(),
-            }
-        };
+        let provided_python_version = provide_python_version(flox, required_python_version)?;
 
         Ok(Some(PyProject {
             provided_python_version,
@@ -483,16 +593,9 @@ impl Provider for PyProject {
             Installs the dependencies from the pyproject.toml to the venv.
         ", self.provided_python_version.display_version() };
 
-        if let ProvidedVersion::Incompatible {
-            requested,
-            substitute,
-        } = &self.provided_python_version
-        {
+        if let Some(note) = incompatible_version_note(&self.provided_python_version) {
             message.push('\n');
-            message.push_str(&format!(
-                "Note: Flox could not provide requested version {requested}, but can provide {sub_version} instead.",
-                sub_version = substitute.display_version,
-            ));
+            message.push_str(&note);
             message.push('\n');
         }
 
@@ -500,10 +603,7 @@ impl Provider for PyProject {
     }
 
     fn get_init_customization(&self) -> InitCustomization {
-        let python_version = match &self.provided_python_version {
-            ProvidedVersion::Incompatible { .. } => None, /* do not lock if no compatible version was found */
-            ProvidedVersion::Compatible { requested, .. } => requested.clone(),
-        };
+        let python_version = locked_python_version(&self.provided_python_version);
 
         InitCustomization {
             profile: Some(
@@ -661,6 +761,577 @@ impl Provider for Requirements {
     }
 }
 
+/// Information gathered from a uv managed project
+///
+/// Detected by the presence of a `uv.lock` file.
+/// <https://docs.astral.sh/uv/concepts/projects/>
+#[derive(Debug, PartialEq)]
+struct UvProject {
+    /// Provided python version
+    ///
+    /// The requested version is taken from (in order of precedence)
+    /// a `.python-version` file, the `requires-python` field in the `uv.lock`,
+    /// or `project.requires-python` in the pyproject.toml.
+    ///
+    /// <https://docs.astral.sh/uv/concepts/python-versions/#python-version-files>
+    provided_python_version: ProvidedVersion,
+
+    /// Version of uv found in the catalog
+    uv_version: String,
+}
+
+impl UvProject {
+    fn detect(flox: &Flox, path: &Path) -> Result<Option<Self>> {
+        debug!("Detecting uv.lock at {:?}", path);
+
+        let uv_lock = path.join("uv.lock");
+
+        if !uv_lock.exists() {
+            debug!("No uv.lock found at {:?}", path);
+            return Ok(None);
+        }
+
+        let python_version_file = path.join(".python-version");
+        let python_version_content = python_version_file
+            .exists()
+            .then(|| std::fs::read_to_string(&python_version_file))
+            .transpose()?;
+
+        let uv_lock_content = std::fs::read_to_string(&uv_lock)?;
+
+        let pyproject_toml = path.join("pyproject.toml");
+        let pyproject_content = pyproject_toml
+            .exists()
+            .then(|| std::fs::read_to_string(&pyproject_toml))
+            .transpose()?;
+
+        let required_python_version = Self::required_python_version(
+            python_version_content.as_deref(),
+            &uv_lock_content,
+            pyproject_content.as_deref(),
+        )?;
+
+        let provided_python_version = provide_python_version(flox, required_python_version)?;
+
+        let uv_version = get_default_package_if_compatible(flox, ["uv"], None)?
+            .context("Did not find uv in the catalogs")?
+            .version
+            .unwrap_or_else(|| "N/A".to_string());
+
+        Ok(Some(UvProject {
+            provided_python_version,
+            uv_version,
+        }))
+    }
+
+    /// Determine the python version requested for a uv project
+    /// in the same order of precedence uv uses.
+    fn required_python_version(
+        python_version_content: Option<&str>,
+        uv_lock_content: &str,
+        pyproject_content: Option<&str>,
+    ) -> Result<Option<String>> {
+        // `.python-version` may contain multiple versions, uv uses the first one
+        if let Some(pin) = python_version_content
+            .and_then(|content| content.lines().map(str::trim).find(|line| !line.is_empty()))
+        {
+            return Ok(Some(python_version_pin_to_semver(pin)?));
+        }
+
+        let uv_lock = toml_edit::DocumentMut::from_str(uv_lock_content)?;
+        if let Some(requires_python) = uv_lock.get("requires-python") {
+            let requires_python = requires_python
+                .as_str()
+                .context("expected 'requires-python' to be a string")?;
+            return Ok(Some(pep440_to_semver(requires_python)?));
+        }
+
+        let Some(pyproject_content) = pyproject_content else {
+            return Ok(None);
+        };
+
+        let pyproject = toml_edit::DocumentMut::from_str(pyproject_content)?;
+        pyproject
+            .get("project")
+            .and_then(|project| project.get("requires-python"))
+            .map(|constraint| {
+                constraint
+                    .as_str()
+                    .context("expected a string")
+                    .and_then(pep440_to_semver)
+            })
+            .transpose()
+    }
+}
+
+impl Provider for UvProject {
+    fn describe_provider(&self) -> Cow<'static, str> {
+        "uv".into()
+    }
+
+    fn describe_reason(&self) -> Cow<'static, str> {
+        "uv.lock".into()
+    }
+
+    fn describe_customization(&self) -> Cow<'static, str> {
+        let mut message = formatdoc! {"
+            Installs python ({}) with uv ({})
+            Adds a hook to sync the uv project into a venv and activate it
+        ", self.provided_python_version.display_version(), self.uv_version };
+
+        if let Some(note) = incompatible_version_note(&self.provided_python_version) {
+            message.push('\n');
+            message.push_str(&note);
+            message.push('\n');
+        }
+
+        message.into()
+    }
+
+    fn get_init_customization(&self) -> InitCustomization {
+        let python_version = locked_python_version(&self.provided_python_version);
+
+        InitCustomization {
+            profile: Some(
+                // TODO: when we support fish, we'll need to source activate.fish
+                indoc! {r#"
+                # Setup a Python virtual environment
+
+                export UV_PROJECT_ENVIRONMENT="$FLOX_ENV_CACHE/uv/venv"
+                # Use the python provided by Flox rather than one managed by uv
+                export UV_PYTHON="$(command -v python3)"
+                export UV_PYTHON_DOWNLOADS="never"
+
+                if [ ! -d "$UV_PROJECT_ENVIRONMENT" ]; then
+                  echo "Creating uv virtual environment in $UV_PROJECT_ENVIRONMENT"
+                fi
+
+                uv sync --frozen --quiet
+
+                echo "Activating uv virtual environment"
+                source "$UV_PROJECT_ENVIRONMENT/bin/activate""#}
+                .to_string(),
+            ),
+            packages: Some(vec![
+                PackageToInstall {
+                    id: "python3".to_string(),
+                    pkg_path: "python3".to_string(),
+                    version: python_version,
+                    input: None,
+                },
+                PackageToInstall {
+                    id: "uv".to_string(),
+                    pkg_path: "uv".to_string(),
+                    version: None,
+                    input: None,
+                },
+            ]),
+        }
+    }
+}
+
+/// Information gathered from a Pipfile and/or Pipfile.lock
+/// <https://pipenv.pypa.io/en/latest/pipfile.html>
+#[derive(Debug, PartialEq)]
+struct Pipenv {
+    /// Provided python version
+    ///
+    /// The requested version is taken from `requires.python_full_version`
+    /// or `requires.python_version` in the Pipfile,
+    /// or `_meta.requires` in the Pipfile.lock if there is no Pipfile.
+    provided_python_version: ProvidedVersion,
+
+    /// Version of pipenv found in the catalog
+    pipenv_version: String,
+
+    /// Whether a Pipfile.lock exists,
+    /// in which case dependencies are installed exactly as locked
+    locked: bool,
+}
+
+impl Pipenv {
+    fn detect(flox: &Flox, path: &Path) -> Result<Option<Self>> {
+        debug!("Detecting Pipfile at {:?}", path);
+
+        let pipfile = path.join("Pipfile");
+        let pipfile_lock = path.join("Pipfile.lock");
+
+        let locked = pipfile_lock.exists();
+
+        let required_python_version = if pipfile.exists() {
+            let content = std::fs::read_to_string(&pipfile)?;
+            Self::required_python_version_from_pipfile(&content)?
+        } else if locked {
+            let content = std::fs::read_to_string(&pipfile_lock)?;
+            Self::required_python_version_from_pipfile_lock(&content)?
+        } else {
+            debug!("No Pipfile or Pipfile.lock found at {:?}", path);
+            return Ok(None);
+        };
+
+        let provided_python_version = provide_python_version(flox, required_python_version)?;
+
+        let pipenv_version = get_default_package_if_compatible(flox, ["pipenv"], None)?
+            .context("Did not find pipenv in the catalogs")?
+            .version
+            .unwrap_or_else(|| "N/A".to_string());
+
+        Ok(Some(Pipenv {
+            provided_python_version,
+            pipenv_version,
+            locked,
+        }))
+    }
+
+    /// Read the python version from the `[requires]` section of a Pipfile
+    fn required_python_version_from_pipfile(content: &str) -> Result<Option<String>> {
+        let toml = toml_edit::DocumentMut::from_str(content)?;
+
+        let Some(requires) = toml.get("requires") else {
+            return Ok(None);
+        };
+
+        // `python_full_version` is more specific and takes precedence
+        requires
+            .get("python_full_version")
+            .or_else(|| requires.get("python_version"))
+            .map(|version| {
+                version
+                    .as_str()
+                    .context("expected a string")
+                    .and_then(python_version_pin_to_semver)
+            })
+            .transpose()
+    }
+
+    /// Read the python version from the `_meta.requires` section of a Pipfile.lock
+    fn required_python_version_from_pipfile_lock(content: &str) -> Result<Option<String>> {
+        let lock: serde_json::Value = serde_json::from_str(content)?;
+        let requires = &lock["_meta"]["requires"];
+
+        requires["python_full_version"]
+            .as_str()
+            .or_else(|| requires["python_version"].as_str())
+            .map(python_version_pin_to_semver)
+            .transpose()
+    }
+}
+
+impl Provider for Pipenv {
+    fn describe_provider(&self) -> Cow<'static, str> {
+        "pipenv".into()
+    }
+
+    fn describe_reason(&self) -> Cow<'static, str> {
+        if self.locked {
+            "Pipfile.lock".into()
+        } else {
+            "Pipfile".into()
+        }
+    }
+
+    fn describe_customization(&self) -> Cow<'static, str> {
+        let mut message = formatdoc! {"
+            Installs python ({}) with pipenv ({})
+            Adds a hook to install the pipenv project into a venv and activate it
+        ", self.provided_python_version.display_version(), self.pipenv_version };
+
+        if let Some(note) = incompatible_version_note(&self.provided_python_version) {
+            message.push('\n');
+            message.push_str(&note);
+            message.push('\n');
+        }
+
+        message.into()
+    }
+
+    fn get_init_customization(&self) -> InitCustomization {
+        let python_version = locked_python_version(&self.provided_python_version);
+
+        // `pipenv sync` installs exactly what is locked,
+        // `pipenv install` would update the lock
+        let install_cmd = if self.locked {
+            "pipenv sync --quiet"
+        } else {
+            "pipenv install --quiet"
+        };
+
+        InitCustomization {
+            profile: Some(
+                // TODO: when we support fish, we'll need to source activate.fish
+                formatdoc! {r#"
+                # Setup a Python virtual environment
+
+                export WORKON_HOME="$FLOX_ENV_CACHE/pipenv/virtualenvs"
+
+                if [ -z "$(pipenv --venv 2>/dev/null)" ]; then
+                  echo "Creating pipenv virtual environment in $WORKON_HOME"
+                  pipenv --python "$(command -v python3)" --quiet
+                fi
+
+                {install_cmd}
+
+                echo "Activating pipenv virtual environment"
+                source "$(pipenv --venv)/bin/activate""#},
+            ),
+            packages: Some(vec![
+                PackageToInstall {
+                    id: "python3".to_string(),
+                    pkg_path: "python3".to_string(),
+                    version: python_version,
+                    input: None,
+                },
+                PackageToInstall {
+                    id: "pipenv".to_string(),
+                    pkg_path: "pipenv".to_string(),
+                    version: None,
+                    input: None,
+                },
+            ]),
+        }
+    }
+}
+
+/// Information gathered from a conda environment.yml
+///
+/// Flox does not provide conda itself.
+/// Instead python is provided by Flox and the `pip` dependencies
+/// of the environment file are installed into a venv.
+/// <https://docs.conda.io/projects/conda/en/latest/user-guide/tasks/manage-environments.html#creating-an-environment-file-manually>
+#[derive(Debug, PartialEq)]
+struct CondaEnvironment {
+    /// Name of the environment file, `environment.yml` or `environment.yaml`
+    filename: String,
+
+    /// Provided python version
+    ///
+    /// The requested version is taken from the `python` entry
+    /// in the `dependencies` of the environment file.
+    provided_python_version: ProvidedVersion,
+
+    /// Requirements listed in the `pip` section of the dependencies
+    pip_dependencies: Vec<String>,
+
+    /// Conda dependencies other than python and pip,
+    /// which are _not_ installed by this customization
+    conda_dependencies: Vec<String>,
+}
+
+/// Dependencies parsed from a conda environment file
+#[derive(Debug, Default, PartialEq)]
+struct CondaDependencies {
+    python: Option<String>,
+    pip: Vec<String>,
+    conda: Vec<String>,
+}
+
+impl CondaEnvironment {
+    const FILENAMES: [&'static str; 2] = ["environment.yml", "environment.yaml"];
+
+    fn detect(flox: &Flox, path: &Path) -> Result<Option<Self>> {
+        debug!("Detecting conda environment file at {:?}", path);
+
+        let Some(filename) = Self::FILENAMES
+            .iter()
+            .find(|filename| path.join(filename).is_file())
+        else {
+            debug!("No conda environment file found at {:?}", path);
+            return Ok(None);
+        };
+
+        let content = std::fs::read_to_string(path.join(filename))?;
+        let dependencies = Self::parse_dependencies(&content)?;
+
+        let provided_python_version = provide_python_version(flox, dependencies.python)?;
+
+        Ok(Some(CondaEnvironment {
+            filename: filename.to_string(),
+            provided_python_version,
+            pip_dependencies: dependencies.pip,
+            conda_dependencies: dependencies.conda,
+        }))
+    }
+
+    /// Parse the `dependencies` of a conda environment file
+    fn parse_dependencies(content: &str) -> Result<CondaDependencies> {
+        let environment: serde_yaml::Value = serde_yaml::from_str(content)?;
+
+        let mut dependencies = CondaDependencies::default();
+
+        let Some(entries) = environment.get("dependencies") else {
+            return Ok(dependencies);
+        };
+
+        let entries = entries
+            .as_sequence()
+            .context("expected 'dependencies' to be a list")?;
+
+        for entry in entries {
+            match entry {
+                serde_yaml::Value::String(spec) => {
+                    let (name, version) = Self::split_match_spec(spec);
+                    match name {
+                        "python" => {
+                            dependencies.python = version
+                                .map(Self::match_spec_version_to_semver)
+                                .transpose()?;
+                        },
+                        // pip is bundled with python
+                        "pip" => {},
+                        _ => dependencies.conda.push(spec.clone()),
+                    }
+                },
+                serde_yaml::Value::Mapping(mapping) => {
+                    let Some(pip) = mapping.get("pip") else {
+                        continue;
+                    };
+                    let pip = pip
+                        .as_sequence()
+                        .context("expected 'pip' dependencies to be a list")?;
+                    for requirement in pip {
+                        let requirement = requirement
+                            .as_str()
+                            .context("expected 'pip' dependency to be a string")?;
+                        dependencies.pip.push(requirement.to_string());
+                    }
+                },
+                _ => Err(anyhow!("unexpected entry in 'dependencies': {entry:?}"))?,
+            }
+        }
+
+        Ok(dependencies)
+    }
+
+    /// Split a conda match spec like `python=3.10`, `python>=3.9`
+    /// or `conda-forge::python 3.10` into name and version
+    ///
+    /// <https://docs.conda.io/projects/conda-build/en/latest/resources/package-spec.html#package-match-specifications>
+    fn split_match_spec(spec: &str) -> (&str, Option<&str>) {
+        // strip an optional channel prefix
+        let spec = spec.rsplit("::").next().unwrap_or(spec).trim();
+
+        let name_end = spec
+            .find(|c: char| c.is_whitespace() || "=<>!~".contains(c))
+            .unwrap_or(spec.len());
+
+        let (name, version) = spec.split_at(name_end);
+        let version = version.trim();
+
+        (name, (!version.is_empty()).then_some(version))
+    }
+
+    /// Convert the version part of a conda match spec into a semver requirement
+    ///
+    /// A single `=` (or no operator) is a fuzzy match, i.e. `=3.10` matches `3.10.x`,
+    /// `==` is an exact match, other operators are passed on as is.
+    fn match_spec_version_to_semver(version: &str) -> Result<String> {
+        // drop an optional build string, e.g. `=3.10.4=h12debd9_0` or `3.10.4 h12debd9_0`
+        let strip_build = |version: &'_ str| -> String {
+            version
+                .split(|c: char| c == '=' || c.is_whitespace())
+                .next()
+                .unwrap_or_default()
+                .trim_end_matches(".*")
+                .to_string()
+        };
+
+        let version = version.trim();
+        if let Some(exact) = version.strip_prefix("==") {
+            pep440_to_semver(&format!("=={}", strip_build(exact)))
+        } else if let Some(fuzzy) = version.strip_prefix('=') {
+            python_version_pin_to_semver(&strip_build(fuzzy))
+        } else if version.starts_with(|c: char| c.is_ascii_digit()) {
+            python_version_pin_to_semver(&strip_build(version))
+        } else {
+            pep440_to_semver(version)
+        }
+    }
+}
+
+impl Provider for CondaEnvironment {
+    fn describe_provider(&self) -> Cow<'static, str> {
+        "conda environment".into()
+    }
+
+    fn describe_reason(&self) -> Cow<'_, str> {
+        self.filename.as_str().into()
+    }
+
+    fn describe_customization(&self) -> Cow<'_, str> {
+        let mut message = formatdoc! {"
+            Installs python ({}) with pip bundled.
+            Adds a hook to setup a venv.
+        ", self.provided_python_version.display_version() };
+
+        if !self.pip_dependencies.is_empty() {
+            message.push_str(&format!(
+                "Installs the pip dependencies from {} to the venv.\n",
+                self.filename
+            ));
+        }
+
+        if !self.conda_dependencies.is_empty() {
+            message.push('\n');
+            message.push_str(&formatdoc! {"
+                Note: the following conda dependencies are not installed,
+                use 'flox install' to add equivalent packages: {}
+            ", self.conda_dependencies.join(", ")});
+        }
+
+        if let Some(note) = incompatible_version_note(&self.provided_python_version) {
+            message.push('\n');
+            message.push_str(&note);
+            message.push('\n');
+        }
+
+        message.into()
+    }
+
+    fn get_init_customization(&self) -> InitCustomization {
+        let python_version = locked_python_version(&self.provided_python_version);
+
+        let mut profile = indoc! {r#"
+            # Setup a Python virtual environment
+
+            PYTHON_DIR="$FLOX_ENV_CACHE/python"
+            if [ ! -d "$PYTHON_DIR" ]; then
+              echo "Creating python virtual environment in $PYTHON_DIR"
+              python -m venv "$PYTHON_DIR"
+            fi
+
+            echo "Activating python virtual environment"
+            source "$PYTHON_DIR/bin/activate""#}
+        .to_string();
+
+        if !self.pip_dependencies.is_empty() {
+            let requirements = self
+                .pip_dependencies
+                .iter()
+                .map(|requirement| shell_escape::escape(requirement.into()))
+                .join(" ");
+
+            profile.push_str(&formatdoc! {r#"
+
+
+                # install the pip dependencies from {filename}
+                pip install {requirements} --quiet"#,
+                filename = self.filename,
+            });
+        }
+
+        InitCustomization {
+            // TODO: when we support fish, we'll need to source activate.fish
+            profile: Some(profile),
+            packages: Some(vec![PackageToInstall {
+                id: "python3".to_string(),
+                pkg_path: "python3".to_string(),
+                version: python_version,
+                input: None,
+            }]),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs::File;
@@ -906,4 +1577,289 @@ mod tests {
             .any(|s| s == "requirements_versioned_dev.txt"));
         assert!(matches.iter().any(|s| s == "requirements_versioned.txt"));
     }
+
+    /// requires-python specifiers are converted to semver requirements
+    #[test]
+    fn test_pep440_to_semver() {
+        assert_eq!(pep440_to_semver(">= 3.8").unwrap(), ">=3.8");
+        assert_eq!(pep440_to_semver(">=3.8, <4").unwrap(), ">=3.8, <4");
+        assert_eq!(pep440_to_semver("~=3.8").unwrap(), "^3.8");
+        assert_eq!(pep440_to_semver("~=3.8.1").unwrap(), "~3.8.1");
+        assert_eq!(pep440_to_semver("==3.11.*").unwrap(), "3.11.*");
+        assert_eq!(pep440_to_semver("==3.11.4").unwrap(), "=3.11.4");
+        assert!(pep440_to_semver("~=3").is_err());
+        // exclusions are dropped, the rest is kept
+        assert_eq!(pep440_to_semver(">=3.8,!=3.9.*").unwrap(), ">=3.8");
+        assert_eq!(pep440_to_semver("!=3.9.*").unwrap(), "*");
+    }
+
+    /// python version pins match a release series or an exact version
+    #[test]
+    fn test_python_version_pin_to_semver() {
+        assert_eq!(python_version_pin_to_semver("3.11").unwrap(), "~3.11");
+        assert_eq!(python_version_pin_to_semver("3.11.4\n").unwrap(), "=3.11.4");
+        // the implementation is ignored
+        assert_eq!(python_version_pin_to_semver("pypy@3.10").unwrap(), "~3.10");
+        assert_eq!(
+            python_version_pin_to_semver("cpython-3.11").unwrap(),
+            "~3.11"
+        );
+        assert_eq!(
+            python_version_pin_to_semver("cpython-3.11.4-linux-x86_64-gnu").unwrap(),
+            "=3.11.4"
+        );
+        assert!(python_version_pin_to_semver("pypy").is_err());
+    }
+
+    /// .python-version takes precedence over uv.lock and pyproject.toml
+    #[test]
+    fn test_uv_required_python_version_precedence() {
+        let uv_lock = indoc! {r#"
+        version = 1
+        requires-python = ">=3.10"
+        "#};
+        let pyproject = indoc! {r#"
+        [project]
+        requires-python = ">=3.8"
+        "#};
+
+        assert_eq!(
+            UvProject::required_python_version(Some("3.12\n"), uv_lock, Some(pyproject)).unwrap(),
+            Some("~3.12".to_string())
+        );
+        assert_eq!(
+            UvProject::required_python_version(None, uv_lock, Some(pyproject)).unwrap(),
+            Some(">=3.10".to_string())
+        );
+        assert_eq!(
+            UvProject::required_python_version(None, "version = 1", Some(pyproject)).unwrap(),
+            Some(">=3.8".to_string())
+        );
+        assert_eq!(
+            UvProject::required_python_version(None, "version = 1", None).unwrap(),
+            None
+        );
+    }
+
+    /// A uv project is detected by its uv.lock,
+    /// using the python version requested in .python-version
+    #[test]
+    #[serial]
+    fn test_uv_detect() {
+        let (flox, _temp_dir_handle) = flox_instance_with_global_lock();
+        let path = flox.temp_dir.clone();
+
+        assert_eq!(UvProject::detect(&flox, &path).unwrap(), None);
+
+        std::fs::write(path.join("uv.lock"), "version = 1\n").unwrap();
+        std::fs::write(path.join(".python-version"), "3.11\n").unwrap();
+
+        let uv = UvProject::detect(&flox, &path).unwrap().unwrap();
+        assert_eq!(uv.provided_python_version, ProvidedVersion::Compatible {
+            requested: Some("~3.11".to_string()),
+            compatible: ProvidedPackage::new("python3", vec!["python3"], "3.11.6"),
+        });
+    }
+
+    /// A pipenv project is detected by its Pipfile or Pipfile.lock
+    #[test]
+    #[serial]
+    fn test_pipenv_detect() {
+        let (flox, _temp_dir_handle) = flox_instance_with_global_lock();
+        let path = flox.temp_dir.clone();
+
+        assert_eq!(Pipenv::detect(&flox, &path).unwrap(), None);
+
+        std::fs::write(
+            path.join("Pipfile.lock"),
+            r#"{"_meta": {"requires": {"python_version": "3.11"}}}"#,
+        )
+        .unwrap();
+        let pipenv = Pipenv::detect(&flox, &path).unwrap().unwrap();
+        assert!(pipenv.locked);
+        assert_eq!(
+            pipenv.provided_python_version,
+            ProvidedVersion::Compatible {
+                requested: Some("~3.11".to_string()),
+                compatible: ProvidedPackage::new("python3", vec!["python3"], "3.11.6"),
+            }
+        );
+
+        // the Pipfile takes precedence over the Pipfile.lock
+        std::fs::write(path.join("Pipfile"), "[packages]\n").unwrap();
+        let pipenv = Pipenv::detect(&flox, &path).unwrap().unwrap();
+        assert!(pipenv.locked);
+        assert_eq!(
+            pipenv.provided_python_version,
+            ProvidedVersion::Compatible {
+                requested: None,
+                compatible: ProvidedPackage::new("python3", vec!["python3"], "3.11.6"),
+            }
+        );
+    }
+
+    /// A conda environment is detected by its environment.yml or environment.yaml
+    #[test]
+    #[serial]
+    fn test_conda_detect() {
+        let (flox, _temp_dir_handle) = flox_instance_with_global_lock();
+        let path = flox.temp_dir.clone();
+
+        assert_eq!(CondaEnvironment::detect(&flox, &path).unwrap(), None);
+
+        std::fs::write(path.join("environment.yaml"), indoc! {"
+            dependencies:
+              - python=3.11
+              - numpy
+              - pip:
+                - requests
+            "})
+        .unwrap();
+
+        assert_eq!(
+            CondaEnvironment::detect(&flox, &path).unwrap(),
+            Some(CondaEnvironment {
+                filename: "environment.yaml".to_string(),
+                provided_python_version: ProvidedVersion::Compatible {
+                    requested: Some("~3.11".to_string()),
+                    compatible: ProvidedPackage::new("python3", vec!["python3"], "3.11.6"),
+                },
+                pip_dependencies: vec!["requests".to_string()],
+                conda_dependencies: vec!["numpy".to_string()],
+            })
+        );
+    }
+
+    /// python_full_version takes precedence over python_version in a Pipfile
+    #[test]
+    fn test_pipfile_required_python_version() {
+        let content = indoc! {r#"
+        [packages]
+        requests = "*"
+
+        [requires]
+        python_version = "3.11"
+        "#};
+        assert_eq!(
+            Pipenv::required_python_version_from_pipfile(content).unwrap(),
+            Some("~3.11".to_string())
+        );
+
+        let content = indoc! {r#"
+        [requires]
+        python_version = "3.11"
+        python_full_version = "3.11.4"
+        "#};
+        assert_eq!(
+            Pipenv::required_python_version_from_pipfile(content).unwrap(),
+            Some("=3.11.4".to_string())
+        );
+
+        assert_eq!(
+            Pipenv::required_python_version_from_pipfile("[packages]").unwrap(),
+            None
+        );
+    }
+
+    /// The python version is read from the _meta section of a Pipfile.lock
+    #[test]
+    fn test_pipfile_lock_required_python_version() {
+        let content = r#"{"_meta": {"requires": {"python_version": "3.10"}}, "default": {}}"#;
+        assert_eq!(
+            Pipenv::required_python_version_from_pipfile_lock(content).unwrap(),
+            Some("~3.10".to_string())
+        );
+        assert_eq!(
+            Pipenv::required_python_version_from_pipfile_lock(r#"{"_meta": {}}"#).unwrap(),
+            None
+        );
+    }
+
+    /// Conda dependencies are split into python, pip and other dependencies
+    #[test]
+    fn test_conda_parse_dependencies() {
+        let content = indoc! {r#"
+        name: example
+        channels:
+          - conda-forge
+        dependencies:
+          - python=3.10
+          - numpy>=1.26
+          - pip
+          - pip:
+            - requests==2.31.0
+            - "flask>=3"
+        "#};
+
+        assert_eq!(
+            CondaEnvironment::parse_dependencies(content).unwrap(),
+            CondaDependencies {
+                python: Some("~3.10".to_string()),
+                pip: vec!["requests==2.31.0".to_string(), "flask>=3".to_string()],
+                conda: vec!["numpy>=1.26".to_string()],
+            }
+        );
+
+        assert_eq!(
+            CondaEnvironment::parse_dependencies("name: example").unwrap(),
+            CondaDependencies::default()
+        );
+    }
+
+    /// Conda match specs for python are converted to semver requirements
+    #[test]
+    fn test_conda_python_match_spec() {
+        let python_version = |spec| {
+            let (name, version) = CondaEnvironment::split_match_spec(spec);
+            assert_eq!(name, "python");
+            version
+                .map(CondaEnvironment::match_spec_version_to_semver)
+                .transpose()
+                .unwrap()
+        };
+
+        assert_eq!(python_version("python"), None);
+        assert_eq!(python_version("python=3.10"), Some("~3.10".to_string()));
+        assert_eq!(python_version("python 3.10.*"), Some("~3.10".to_string()));
+        assert_eq!(
+            python_version("python==3.10.4"),
+            Some("=3.10.4".to_string())
+        );
+        assert_eq!(
+            python_version("python=3.10.4=h12debd9_0"),
+            Some("=3.10.4".to_string())
+        );
+        assert_eq!(python_version("python>=3.9"), Some(">=3.9".to_string()));
+        assert_eq!(
+            python_version("conda-forge::python>=3.9,<3.12"),
+            Some(">=3.9, <3.12".to_string())
+        );
+    }
+
+    /// The conda customization installs pip dependencies into a venv
+    #[test]
+    fn test_conda_customization_installs_pip_dependencies() {
+        let conda = CondaEnvironment {
+            filename: "environment.yml".to_string(),
+            provided_python_version: ProvidedVersion::Compatible {
+                requested: Some("~3.10".to_string()),
+                compatible: ProvidedPackage::new("python3", vec!["python310"], "3.10.13"),
+            },
+            pip_dependencies: vec!["requests==2.31.0".to_string(), "flask>=3".to_string()],
+            conda_dependencies: vec![],
+        };
+
+        let customization = conda.get_init_customization();
+
+        assert!(customization
+            .profile
+            .unwrap()
+            .ends_with("pip install requests==2.31.0 'flask>=3' --quiet"));
+        assert_eq!(customization.packages.unwrap(), vec![PackageToInstall {
+            id: "python3".to_string(),
+            pkg_path: "python3".to_string(),
+            version: Some("~3.10".to_string()),
+            input: None,
+        }]);
+    }
 }
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::str::FromStr;
 
 use anyhow::{anyhow, bail, Result};
@@ -17,13 +18,14 @@ use itertools::Itertools;
 use log::debug;
 use tracing::instrument;
 
-use super::{environment_select, EnvironmentSelect};
+use super::{environment_select, run_in_workspace, EnvironmentSelect, WorkspaceCommand};
 use crate::commands::{
     ensure_floxhub_token,
     environment_description,
     ConcreteEnvironment,
     EnvironmentSelectError,
 };
+use crate::config::Config;
 use crate::subcommand_metric;
 use crate::utils::dialog::{Dialog, Spinner};
 use crate::utils::didyoumean::{DidYouMean, InstallSuggestion};
@@ -36,6 +38,10 @@ pub struct Install {
     #[bpaf(external(environment_select), fallback(Default::default()))]
     environment: EnvironmentSelect,
 
+    /// Run in every environment of the workspace containing the current directory
+    #[bpaf(long)]
+    workspace: bool,
+
     /// Option to specify a package ID
     #[bpaf(external(pkg_with_id_option), many)]
     id: Vec<PkgWithIdOption>,
@@ -61,9 +67,44 @@ pub struct PkgWithIdOption {
     pub path: String,
 }
 
+impl WorkspaceCommand for Install {
+    fn selected_environment(&self) -> Result<&EnvironmentSelect> {
+        Ok(&self.environment)
+    }
+
+    fn for_member(&self, environment: EnvironmentSelect) -> Self {
+        Self {
+            environment,
+            workspace: false,
+            ..self.clone()
+        }
+    }
+}
+
 impl Install {
-    #[instrument(name = "install", fields(packages), skip_all)]
-    pub async fn handle(self, mut flox: Flox) -> Result<()> {
+    /// Install `packages` into the detected environment like `flox install <packages>`
+    pub(crate) fn packages(packages: Vec<String>) -> Self {
+        Install {
+            environment: EnvironmentSelect::Unspecified,
+            workspace: false,
+            id: Vec::new(),
+            packages,
+        }
+    }
+
+    #[instrument(
+        name = "install",
+        fields(packages, environment_type, system = %flox.system),
+        skip_all
+    )]
+    pub async fn handle(self, config: Config, mut flox: Flox) -> Result<()> {
+        if self.workspace {
+            return run_in_workspace(&flox, &self, |command, flox| {
+                Box::pin(command.handle(config.clone(), flox))
+            })
+            .await;
+        }
+
         subcommand_metric!("install");
 
         debug!(
@@ -94,6 +135,7 @@ impl Install {
             },
             Err(e) => Err(e)?,
         };
+        tracing::Span::current().record("environment_type", concrete_environment.kind());
         let description = environment_description(&concrete_environment)?;
 
         // Ensure the user is logged in for the following remote operations
@@ -127,7 +169,15 @@ impl Install {
             typed: Spinner::new(|| environment.install(&packages, &flox)),
         }
         .spin()
-        .map_err(|err| Self::handle_error(err, &flox, &*environment, &packages))?;
+        .map_err(|err| {
+            Self::handle_error(
+                err,
+                &flox,
+                &*environment,
+                &packages,
+                &config.flox.package_aliases,
+            )
+        })?;
 
         let lockfile_path = environment.lockfile_path(&flox)?;
         let lockfile_path = CanonicalPath::new(lockfile_path)?;
@@ -174,6 +224,7 @@ impl Install {
         flox: &Flox,
         environment: &dyn Environment,
         packages: &[PackageToInstall],
+        aliases: &BTreeMap<String, String>,
     ) -> anyhow::Error {
         debug!("install error: {:?}", err);
 
@@ -202,7 +253,8 @@ impl Install {
 
                 let head = format!("Could not find package {path}.");
 
-                let suggestion = DidYouMean::<InstallSuggestion>::new(flox, environment, &path);
+                let suggestion =
+                    DidYouMean::<InstallSuggestion>::new(flox, environment, &path, aliases);
                 if !suggestion.has_suggestions() {
                     break 'error anyhow!("{head} Try 'flox search' with a broader search term.");
                 }
@@ -13,7 +13,7 @@ use itertools::Itertools;
 use log::debug;
 use tracing::instrument;
 
-use super::{environment_select, EnvironmentSelect};
+use super::{environment_select, run_in_workspace, EnvironmentSelect, WorkspaceCommand};
 use crate::subcommand_metric;
 use crate::utils::dialog::{Dialog, Spinner};
 use crate::utils::message;
@@ -24,6 +24,10 @@ pub struct List {
     #[bpaf(external(environment_select), fallback(Default::default()))]
     environment: EnvironmentSelect,
 
+    /// Run in every environment of the workspace containing the current directory
+    #[bpaf(long)]
+    workspace: bool,
+
     #[bpaf(external(list_mode), fallback(ListMode::Extended))]
     list_mode: ListMode,
 }
@@ -39,7 +43,7 @@ pub enum ListMode {
     NameOnly,
 
     /// Show the name, pkg-path, and version of each package (default)
-    #[bpaf(long, short)]
+    #[bpaf(long)]
     Extended,
 
     /// Show all available package information including priority and license
@@ -47,9 +51,28 @@ pub enum ListMode {
     All,
 }
 
+impl WorkspaceCommand for List {
+    fn selected_environment(&self) -> Result<&EnvironmentSelect> {
+        Ok(&self.environment)
+    }
+
+    fn for_member(&self, environment: EnvironmentSelect) -> Self {
+        Self {
+            environment,
+            workspace: false,
+            ..self.clone()
+        }
+    }
+}
+
 impl List {
     #[instrument(name = "list", fields(mode), skip_all)]
     pub async fn handle(self, flox: Flox) -> Result<()> {
+        if self.workspace {
+            return run_in_workspace(&flox, &self, |command, flox| Box::pin(command.handle(flox)))
+                .await;
+        }
+
         subcommand_metric!("list");
 
         let mut env = self
@@ -133,6 +156,7 @@ impl List {
                     description,
                 },
             priority,
+            ..
         } in packages.iter().sorted_by_key(|p| p.priority)
         {
             let message = formatdoc! {"
@@ -158,7 +182,10 @@ impl List {
     ///
     /// Does not write the lockfile,
     /// as that would require writing to the environment in case of remote environments)
-    fn get_lockfile(flox: &Flox, env: &mut dyn Environment) -> Result<TypedLockedManifest> {
+    pub(super) fn get_lockfile(
+        flox: &Flox,
+        env: &mut dyn Environment,
+    ) -> Result<TypedLockedManifest> {
         let lockfile_path = env
             .lockfile_path(flox)
             .context("Could not get lockfile path")?;
@@ -0,0 +1,74 @@
+use anyhow::Result;
+use bpaf::Bpaf;
+use flox_rust_sdk::flox::Flox;
+use itertools::Itertools;
+use time::format_description::well_known::Rfc3339;
+use tracing::instrument;
+
+use crate::config::Config;
+use crate::subcommand_metric;
+use crate::utils::message;
+use crate::utils::metrics::{describe_metrics_endpoint, read_buffered_metrics};
+
+// Inspect the usage metrics collected by flox
+#[derive(Clone, Debug, Bpaf)]
+pub enum Metrics {
+    /// Print the metrics that have been collected but not yet sent
+    #[bpaf(command)]
+    Show {
+        /// Print one JSON object per entry
+        #[bpaf(long)]
+        json: bool,
+    },
+}
+
+impl Metrics {
+    #[instrument(name = "metrics", skip_all)]
+    pub async fn handle(self, config: Config, flox: Flox) -> Result<()> {
+        subcommand_metric!("metrics");
+
+        match self {
+            Metrics::Show { json } => {
+                let span = tracing::info_span!("show");
+                let _guard = span.enter();
+
+                if config.flox.disable_metrics {
+                    message::plain("Metrics collection is disabled.");
+                } else {
+                    message::plain(format!(
+                        "Metrics are sent to {}",
+                        describe_metrics_endpoint(&config)
+                    ));
+                }
+
+                let entries = read_buffered_metrics(&flox.cache_dir)?;
+                if entries.is_empty() {
+                    message::plain("No metrics are waiting to be sent.");
+                    return Ok(());
+                }
+
+                for entry in entries {
+                    if json {
+                        println!("{}", serde_json::to_string(&entry)?);
+                        continue;
+                    }
+
+                    let extras = entry
+                        .extras()
+                        .iter()
+                        .sorted()
+                        .map(|(key, value)| format!("{key}={value}"))
+                        .join(" ");
+                    let line = format!(
+                        "{}  {}  {extras}",
+                        entry.timestamp().format(&Rfc3339)?,
+                        entry.subcommand().unwrap_or("-"),
+                    );
+                    println!("{}", line.trim_end());
+                }
+
+                Ok(())
+            },
+        }
+    }
+}
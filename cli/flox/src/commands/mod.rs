@@ -1,15 +1,24 @@
 mod activate;
+mod audit;
 mod auth;
+mod check;
 mod containerize;
+mod deactivate;
 mod delete;
 mod edit;
 mod general;
+mod import;
+mod index;
 mod init;
 mod install;
 mod list;
+mod metrics;
+mod pin;
 mod pull;
 mod push;
+mod sbom;
 mod search;
+mod trust;
 mod uninstall;
 mod update;
 mod upgrade;
@@ -35,6 +44,7 @@ use flox_rust_sdk::flox::{
     FLOX_SENTRY_ENV,
     FLOX_VERSION,
 };
+use flox_rust_sdk::models::env_registry::{env_registry_path, read_environment_registry};
 use flox_rust_sdk::models::environment::managed_environment::ManagedEnvironment;
 use flox_rust_sdk::models::environment::path_environment::PathEnvironment;
 use flox_rust_sdk::models::environment::remote_environment::RemoteEnvironment;
@@ -49,6 +59,9 @@ use flox_rust_sdk::models::environment::{
     FLOX_ACTIVE_ENVIRONMENTS_VAR,
 };
 use flox_rust_sdk::models::environment_ref;
+use flox_rust_sdk::models::manifest::ActivationScripts;
+use flox_rust_sdk::models::workspace::{Workspace, WORKSPACE_FILENAME};
+use futures::future::LocalBoxFuture;
 use futures::Future;
 use indoc::{formatdoc, indoc};
 use log::{debug, info};
@@ -61,7 +74,8 @@ use time::{Duration, OffsetDateTime};
 use toml_edit::Key;
 use url::Url;
 
-use crate::commands::general::update_config;
+use crate::commands::trust::TrustDecision;
+use crate::config::credentials::{CredentialStore, Secret};
 use crate::config::{Config, EnvironmentTrust, FLOX_CONFIG_FILE};
 use crate::utils::dialog::{Dialog, Select};
 use crate::utils::errors::display_chain;
@@ -72,8 +86,9 @@ use crate::utils::init::{
     init_uuid,
     telemetry_opt_out_needs_migration,
 };
-use crate::utils::metrics::{AWSDatalakeConnection, Client, Hub, METRICS_UUID_FILE_NAME};
-use crate::utils::{message, TRAILING_NETWORK_CALL_TIMEOUT};
+use crate::utils::metrics::{connection_from_config, Client, Hub, METRICS_UUID_FILE_NAME};
+use crate::utils::{didyoumean, message, TRAILING_NETWORK_CALL_TIMEOUT};
+use crate::{report_error, FloxShellErrorCode};
 
 const UPDATE_NOTIFICATION_FILE_NAME: &str = "update-notification.json";
 const UPDATE_NOTIFICATION_EXPIRY: Duration = Duration::days(1);
@@ -99,7 +114,8 @@ static FLOX_WELCOME_MESSAGE: Lazy<String> = Lazy::new(|| {
 });
 
 const ADDITIONAL_COMMANDS: &str = indoc! {"
-    update, upgrade, config, auth
+    active, deactivate, update, upgrade, pin, unpin, check, audit, sbom, config, index,
+    trust, metrics, auth
 "};
 
 fn vec_len<T>(x: Vec<T>) -> usize {
@@ -148,6 +164,82 @@ impl Default for Verbosity {
 )]
 pub struct FloxCli(#[bpaf(external(flox_args))] pub FloxArgs);
 
+/// Names of the subcommands that are suggested for mistyped commands
+const SUBCOMMANDS: &[&str] = &[
+    "init",
+    "activate",
+    "search",
+    "show",
+    "install",
+    "import",
+    "uninstall",
+    "edit",
+    "list",
+    "delete",
+    "push",
+    "pull",
+    "containerize",
+    "active",
+    "deactivate",
+    "update",
+    "upgrade",
+    "pin",
+    "unpin",
+    "check",
+    "audit",
+    "sbom",
+    "config",
+    "index",
+    "trust",
+    "metrics",
+    "auth",
+];
+
+/// Improve the parse error for an unknown subcommand
+/// with a suggestion from [didyoumean::suggest_command].
+///
+/// bpaf only suggests commands that are a single edit away,
+/// and does not know about commands users reach for from other tools,
+/// e.g. `flox add` or `flox shell`.
+/// Other errors are returned unchanged.
+pub fn suggest_subcommand(args: impl IntoIterator<Item = String>, error: String) -> String {
+    let Some(command) = args.into_iter().find(|arg| !arg.starts_with('-')) else {
+        return error;
+    };
+
+    // Only handle errors about the command itself
+    if SUBCOMMANDS.contains(&command.as_str()) || !error.contains(&format!("`{command}`")) {
+        return error;
+    }
+
+    match didyoumean::suggest_command(&command, SUBCOMMANDS) {
+        Some(suggestion) => {
+            format!("no such command: `{command}`, did you mean `{suggestion}`?")
+        },
+        None => error,
+    }
+}
+
+/// Explain parse errors caused by flags that changed meaning
+///
+/// `flox list -e` used to select the extended output,
+/// which is now the default or `--extended`.
+/// `-e` selects an environment by name (`--env`) instead,
+/// so the old usage fails with a missing argument.
+/// Other errors are returned unchanged.
+pub fn explain_changed_flag(args: impl IntoIterator<Item = String>, error: String) -> String {
+    let command = args.into_iter().find(|arg| !arg.starts_with('-'));
+    if command.as_deref() != Some("list") || !error.contains("`-e` requires an argument") {
+        return error;
+    }
+
+    formatdoc! {"
+        {error}
+
+        '-e' now means '--env' and selects an environment by name.
+        Use 'flox list --extended' or just 'flox list' for the extended output."}
+}
+
 /// Main flox args parser
 ///
 /// This struct is used to parse the command line arguments
@@ -235,9 +327,13 @@ impl FloxArgs {
 
             init_telemetry_uuid(&config.flox.data_dir, &config.flox.cache_dir)?;
 
-            let connection = AWSDatalakeConnection::default();
-            let client = Client::new_with_config(&config, connection)?;
-            Hub::global().set_client(client);
+            match connection_from_config(&config) {
+                Ok(connection) => {
+                    let client = Client::new_with_config(&config, connection)?;
+                    Hub::global().set_client(client);
+                },
+                Err(e) => message::warning(format!("Not collecting metrics: {e}")),
+            }
         } else {
             debug!("Metrics collection disabled");
             env::set_var("FLOX_DISABLE_METRICS", "true");
@@ -260,6 +356,7 @@ impl FloxArgs {
                     Using {env_set_host} as FloxHub host
                     '$_FLOX_FLOXHUB_GIT_URL' is used for testing purposes only,
                     alternative FloxHub hosts are not yet supported!
+                    To host environments in a plain git repository use 'flox push --remote-url'.
                 "});
                 Some(Url::parse(&env_set_host)?)
             } else {
@@ -276,45 +373,74 @@ impl FloxArgs {
             git_url_override,
         )?;
 
-        let floxhub_token = config
-            .flox
-            .floxhub_token
+        let credential_store =
+            CredentialStore::new(&config.flox, floxhub.base_url(), &temp_dir_path);
+
+        let floxhub_token = match &credential_store {
+            Ok(store) => store
+                .get(&config.flox, Secret::FloxhubToken)
+                .unwrap_or_else(|e| {
+                    message::warning(format!(
+                        "Could not read your FloxHub token: {}",
+                        display_chain(&e)
+                    ));
+                    None
+                }),
+            Err(e) => {
+                message::warning(format!("Could not read your FloxHub token: {e}"));
+                None
+            },
+        };
+
+        let floxhub_token = floxhub_token
             .as_deref()
             .map(FloxhubToken::from_str)
             .transpose();
 
+        let erase_token = || {
+            if let Ok(store) = &credential_store {
+                if let Err(e) = store.erase(Secret::FloxhubToken) {
+                    log::debug!("Could not remove token from credential store: {e}");
+                }
+            }
+        };
+
         let floxhub_token = match floxhub_token {
+            Ok(Some(token)) if token.expires_within(auth::token_refresh_window()) => {
+                match &credential_store {
+                    Ok(store) => Some(
+                        auth::refresh_floxhub_token(&config, store)
+                            .await
+                            .unwrap_or(token),
+                    ),
+                    Err(_) => Some(token),
+                }
+            },
             Err(FloxhubTokenError::Expired) => {
-                message::warning("Your FloxHub token has expired. You may need to log in again.");
-                if let Err(e) = update_config(
-                    &config.flox.config_dir,
-                    &temp_dir_path,
-                    "floxhub_token",
-                    None::<String>,
-                ) {
-                    log::debug!("Could not remove token from user config: {e}");
+                let refreshed = match &credential_store {
+                    Ok(store) => auth::refresh_floxhub_token(&config, store).await,
+                    Err(_) => None,
+                };
+                if refreshed.is_none() {
+                    message::warning(
+                        "Your FloxHub token has expired. You may need to log in again.",
+                    );
+                    erase_token();
                 }
-                None
+                refreshed
             },
             Err(FloxhubTokenError::InvalidToken(token_error)) => {
                 message::error(formatdoc! {"
                     Your FloxHub token is invalid: {token_error}
                     You may need to log in again.
                 "});
-                if let Err(e) = update_config(
-                    &config.flox.config_dir,
-                    &temp_dir_path,
-                    "floxhub_token",
-                    None::<String>,
-                ) {
-                    log::debug!("Could not remove token from user config: {e}");
-                }
+                erase_token();
                 None
             },
             Ok(token) => token,
         };
 
-        let catalog_client = init_catalog_client(&config);
+        let catalog_client = init_catalog_client(&config, floxhub_token.as_ref());
 
         let flox = Flox {
             cache_dir: config.flox.cache_dir.clone(),
@@ -327,6 +453,7 @@ impl FloxArgs {
             uuid: init_uuid(&config.flox.data_dir).await?,
             floxhub_token,
             floxhub,
+            commit_identity: config.flox.commit_identity(),
             catalog_client,
         };
 
@@ -631,6 +758,9 @@ enum LocalDevelopmentCommands {
         footer("Run 'man flox-install' for more details.")
     )]
     Install(#[bpaf(external(install::install))] install::Install),
+    /// Import packages from an asdf, mise or devbox environment
+    #[bpaf(command, footer("Run 'man flox-import' for more details."))]
+    Import(#[bpaf(external(import::import))] import::Import),
     /// Uninstall installed packages from an environment
     #[bpaf(
         command,
@@ -660,7 +790,8 @@ impl LocalDevelopmentCommands {
             LocalDevelopmentCommands::Init(args) => args.handle(flox).await?,
             LocalDevelopmentCommands::Activate(args) => args.handle(config, flox).await?,
             LocalDevelopmentCommands::Edit(args) => args.handle(flox).await?,
-            LocalDevelopmentCommands::Install(args) => args.handle(flox).await?,
+            LocalDevelopmentCommands::Install(args) => args.handle(config, flox).await?,
+            LocalDevelopmentCommands::Import(args) => args.handle(flox).await?,
             LocalDevelopmentCommands::Uninstall(args) => args.handle(flox).await?,
             LocalDevelopmentCommands::List(args) => args.handle(flox).await?,
             LocalDevelopmentCommands::Search(args) => args.handle(config, flox).await?,
@@ -707,6 +838,12 @@ enum AdditionalCommands {
     Documentation(
         #[bpaf(external(AdditionalCommands::documentation))] AdditionalCommandsDocumentation,
     ),
+    /// List the active environments
+    #[bpaf(command, hide, footer("Run 'man flox-active' for more details."))]
+    Active(#[bpaf(external(deactivate::active))] deactivate::Active),
+    /// Deactivate an environment activated in the current shell
+    #[bpaf(command, hide, footer("Run 'man flox-deactivate' for more details."))]
+    Deactivate(#[bpaf(external(deactivate::deactivate))] deactivate::Deactivate),
     /// Update environment's base catalog or the global base catalog
     #[bpaf(command, hide, footer("Run 'man flox-update' for more details."))]
     Update(#[bpaf(external(update::update))] update::Update),
@@ -722,12 +859,39 @@ enum AdditionalCommands {
         Packages without a specified group in the manifest are placed in a group
         named 'toplevel'.
         The packages in that group can be upgraded without updating any other
-        groups by passing 'toplevel' as the group name.
+        groups by passing 'toplevel' as the group name.\n\n
+
+        Packages held with 'flox pin' are not upgraded,
+        and neither are other packages in their group.
     "}))]
     Upgrade(#[bpaf(external(upgrade::upgrade))] upgrade::Upgrade),
+    /// Hold packages at their locked versions during upgrades
+    #[bpaf(command, hide, footer("Run 'man flox-pin' for more details."))]
+    Pin(#[bpaf(external(pin::pin))] pin::Pin),
+    /// Allow upgrades of held packages again
+    #[bpaf(command, hide, footer("Run 'man flox-unpin' for more details."))]
+    Unpin(#[bpaf(external(pin::unpin))] pin::Unpin),
+    /// Check an environment's lockfile for problems
+    #[bpaf(command, hide, footer("Run 'man flox-check' for more details."))]
+    Check(#[bpaf(external(check::check))] check::Check),
+    /// Report licenses and known vulnerabilities of packages in an environment
+    #[bpaf(command, hide, footer("Run 'man flox-audit' for more details."))]
+    Audit(#[bpaf(external(audit::audit))] audit::Audit),
+    /// Generate a software bill of materials for an environment
+    #[bpaf(command, hide, footer("Run 'man flox-sbom' for more details."))]
+    Sbom(#[bpaf(external(sbom::sbom))] sbom::Sbom),
     /// View and set configuration options
     #[bpaf(command, hide, footer("Run 'man flox-config' for more details."))]
     Config(#[bpaf(external(general::config_args))] general::ConfigArgs),
+    /// Manage the local package index used by 'flox search --offline'
+    #[bpaf(command, hide, footer("Run 'man flox-index' for more details."))]
+    Index(#[bpaf(external(index::index))] index::Index),
+    /// Manage which remote environments are trusted for activation
+    #[bpaf(command, hide, footer("Run 'man flox-trust' for more details."))]
+    Trust(#[bpaf(external(trust::trust))] trust::Trust),
+    /// Inspect the usage metrics collected by flox
+    #[bpaf(command, hide, footer("Run 'man flox-metrics' for more details."))]
+    Metrics(#[bpaf(external(metrics::metrics))] metrics::Metrics),
 }
 
 impl AdditionalCommands {
@@ -740,9 +904,19 @@ impl AdditionalCommands {
     async fn handle(self, config: Config, flox: Flox) -> Result<()> {
         match self {
             AdditionalCommands::Documentation(args) => args.handle(),
+            AdditionalCommands::Active(args) => args.handle(flox).await?,
+            AdditionalCommands::Deactivate(args) => args.handle(config, flox).await?,
             AdditionalCommands::Update(args) => args.handle(flox).await?,
             AdditionalCommands::Upgrade(args) => args.handle(flox).await?,
+            AdditionalCommands::Pin(args) => args.handle(flox).await?,
+            AdditionalCommands::Unpin(args) => args.handle(flox).await?,
+            AdditionalCommands::Check(args) => args.handle(flox).await?,
+            AdditionalCommands::Audit(args) => args.handle(flox).await?,
+            AdditionalCommands::Sbom(args) => args.handle(flox).await?,
             AdditionalCommands::Config(args) => args.handle(config, flox).await?,
+            AdditionalCommands::Index(args) => args.handle(flox).await?,
+            AdditionalCommands::Trust(args) => args.handle(config, flox).await?,
+            AdditionalCommands::Metrics(args) => args.handle(config, flox).await?,
         }
         Ok(())
     }
@@ -834,6 +1008,12 @@ pub enum EnvironmentSelect {
         #[bpaf(long("remote"), short('r'), argument("owner>/<name"))]
         environment_ref::EnvironmentRef,
     ),
+    Name(
+        /// Name of an environment on this machine,
+        /// or <owner>/<name> of a managed environment
+        #[bpaf(long("env"), short('e'), argument("name"))]
+        String,
+    ),
     #[default]
     #[bpaf(hide)]
     Unspecified,
@@ -845,6 +1025,13 @@ pub enum EnvironmentSelectError {
     Environment(#[from] EnvironmentError),
     #[error("Did not find an environment in the current directory.")]
     EnvNotFoundInCurrentDirectory,
+    #[error("Did not find an environment named '{0}' on this machine.")]
+    EnvNameNotFound(String),
+    #[error(
+        "Found multiple environments named '{name}':\n{}\n\nUse '--dir <path>' to choose one of them.",
+        .paths.iter().map(|path| format!("  {path}")).collect::<Vec<_>>().join("\n")
+    )]
+    EnvNameAmbiguous { name: String, paths: Vec<String> },
     #[error(transparent)]
     Anyhow(#[from] anyhow::Error),
 }
@@ -863,6 +1050,9 @@ impl EnvironmentSelect {
     ) -> Result<ConcreteEnvironment, EnvironmentSelectError> {
         match self {
             EnvironmentSelect::Dir(path) => Ok(open_path(flox, path)?),
+            EnvironmentSelect::Name(name) => {
+                Ok(find_named_environment(flox, name)?.into_concrete_environment(flox)?)
+            },
             EnvironmentSelect::Unspecified => {
                 let current_dir = env::current_dir().context("could not get current directory")?;
                 let maybe_found_environment = find_dot_flox(&current_dir)?;
@@ -900,6 +1090,9 @@ impl EnvironmentSelect {
     ) -> Result<ConcreteEnvironment, EnvironmentSelectError> {
         match self {
             EnvironmentSelect::Dir(path) => Ok(open_path(flox, path)?),
+            EnvironmentSelect::Name(name) => {
+                Ok(find_named_environment(flox, name)?.into_concrete_environment(flox)?)
+            },
             // If the user doesn't specify an environment, check if there's an
             // already activated environment or an environment in the current
             // directory.
@@ -1005,6 +1198,63 @@ fn query_which_environment(
     }
 }
 
+/// Find an environment by name, see [EnvironmentPointer::matches_name]
+///
+/// The most recently activated environment with that name is preferred,
+/// otherwise the environments registered on this machine are searched,
+/// see [flox_rust_sdk::models::env_registry::EnvRegistry].
+/// If several of them share the name, the user is asked to choose one.
+fn find_named_environment(
+    flox: &Flox,
+    name: &str,
+) -> Result<UninitializedEnvironment, EnvironmentSelectError> {
+    if let Some(active) = activated_environments()
+        .into_iter()
+        .find(|env| env.pointer().matches_name(name))
+    {
+        debug!("found active environment named '{name}'");
+        return Ok(active);
+    }
+
+    let registry = read_environment_registry(env_registry_path(flox))
+        .context("could not read the environment registry")?
+        .unwrap_or_default();
+
+    // skip environments that were deleted or replaced since they were registered
+    let mut candidates = registry
+        .entries_named(name)
+        .filter_map(|entry| DotFlox::open(entry.path.parent()?).ok())
+        .filter(|dot_flox| dot_flox.pointer.matches_name(name))
+        .collect::<Vec<_>>();
+
+    match candidates.len() {
+        0 => Err(EnvironmentSelectError::EnvNameNotFound(name.to_string())),
+        1 => Ok(UninitializedEnvironment::DotFlox(candidates.remove(0))),
+        _ if Dialog::can_prompt() => {
+            let message = format!("Found multiple environments named '{name}', which one?");
+            let dialog = Dialog {
+                message: &message,
+                help_message: None,
+                typed: Select {
+                    options: candidates
+                        .iter()
+                        .map(|dot_flox| dot_flox.path.display().to_string())
+                        .collect(),
+                },
+            };
+            let (index, _) = dialog.raw_prompt().map_err(anyhow::Error::new)?;
+            Ok(UninitializedEnvironment::DotFlox(candidates.remove(index)))
+        },
+        _ => Err(EnvironmentSelectError::EnvNameAmbiguous {
+            name: name.to_string(),
+            paths: candidates
+                .iter()
+                .map(|dot_flox| dot_flox.path.display().to_string())
+                .collect(),
+        }),
+    }
+}
+
 /// Open an environment defined in `{path}/.flox`
 fn open_path(flox: &Flox, path: &PathBuf) -> Result<ConcreteEnvironment, EnvironmentError> {
     DotFlox::open(path)
@@ -1012,6 +1262,79 @@ fn open_path(flox: &Flox, path: &PathBuf) -> Result<ConcreteEnvironment, Environ
         .into_concrete_environment(flox)
 }
 
+/// A command that can run in every member of a workspace with `--workspace`
+pub(crate) trait WorkspaceCommand: Clone {
+    /// The environment selected on the command line
+    fn selected_environment(&self) -> Result<&EnvironmentSelect>;
+
+    /// A copy of the command that runs in a single member environment
+    fn for_member(&self, environment: EnvironmentSelect) -> Self;
+}
+
+/// Run a command in every member environment of the workspace
+/// containing the current directory, see [Workspace].
+///
+/// `run` is called with a copy of `command` for each member.
+/// A failing member doesn't stop the others,
+/// its error is printed and the failures are summarized at the end.
+pub(crate) async fn run_in_workspace<C, F>(flox: &Flox, command: &C, mut run: F) -> Result<()>
+where
+    C: WorkspaceCommand,
+    F: FnMut(C, Flox) -> LocalBoxFuture<'static, Result<()>>,
+{
+    if !matches!(
+        command.selected_environment()?,
+        EnvironmentSelect::Unspecified
+    ) {
+        bail!("'--workspace' can't be combined with '--dir', '--remote' or '--env'.");
+    }
+
+    let current_dir = env::current_dir().context("could not get current directory")?;
+    let Some(workspace) = Workspace::find(&current_dir)? else {
+        bail!(
+            "Did not find a '{WORKSPACE_FILENAME}' in the current directory or its parent directories."
+        );
+    };
+    if workspace.members.is_empty() {
+        bail!(
+            "The workspace in '{}' has no members.",
+            workspace.root.display()
+        );
+    }
+
+    let mut failed = Vec::new();
+    let mut exit_code = None;
+    for (i, member) in workspace.members.iter().enumerate() {
+        let name = workspace.member_name(member);
+        let separator = if i == 0 { "" } else { "\n" };
+        message::plain(format!("{separator}📂 {name}"));
+
+        let member_command = command.for_member(EnvironmentSelect::Dir(member.clone()));
+        match run(member_command, flox.clone()).await {
+            Ok(()) => {},
+            // e.g. 'flox upgrade --check' reports its result with the exit code
+            Err(e) if e.is::<FloxShellErrorCode>() => exit_code = Some(report_error(&e)),
+            Err(e) => {
+                report_error(&e);
+                failed.push(format!("'{name}'"));
+            },
+        }
+    }
+
+    if !failed.is_empty() {
+        bail!(
+            "Failed in {} of {} workspace members: {}",
+            failed.len(),
+            workspace.members.len(),
+            failed.join(", ")
+        );
+    }
+    if let Some(exit_code) = exit_code {
+        return Err(FloxShellErrorCode(exit_code).into());
+    }
+    Ok(())
+}
+
 /// The various ways in which an environment can be referred to
 pub enum ConcreteEnvironment {
     /// Container for [PathEnvironment]
@@ -1033,6 +1356,15 @@ impl ConcreteEnvironment {
         }
     }
 
+    /// The kind of environment, used to annotate trace spans
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ConcreteEnvironment::Path(_) => "path",
+            ConcreteEnvironment::Managed(_) => "managed",
+            ConcreteEnvironment::Remote(_) => "remote",
+        }
+    }
+
     pub fn dyn_environment_ref_mut(&mut self) -> &mut dyn Environment {
         match self {
             ConcreteEnvironment::Path(path_env) => path_env,
@@ -1059,7 +1391,7 @@ pub enum UninitializedEnvironment {
     /// Container for "local" environments pointed to by [DotFlox]
     DotFlox(DotFlox),
     /// Container for [RemoteEnvironment]
-    Remote(ManagedPointer),
+    Remote(Box<ManagedPointer>),
 }
 
 impl UninitializedEnvironment {
@@ -1081,7 +1413,7 @@ impl UninitializedEnvironment {
             },
             ConcreteEnvironment::Remote(remote_env) => {
                 let env_ref = remote_env.pointer().clone();
-                Ok(Self::Remote(env_ref))
+                Ok(Self::Remote(Box::new(env_ref)))
             },
         }
     }
@@ -1108,14 +1440,14 @@ impl UninitializedEnvironment {
                     },
                     EnvironmentPointer::Managed(managed_pointer) => {
                         debug!("detected concrete environment type: managed");
-                        let env = ManagedEnvironment::open(flox, managed_pointer, dot_flox_path)?;
+                        let env = ManagedEnvironment::open(flox, *managed_pointer, dot_flox_path)?;
                         ConcreteEnvironment::Managed(env)
                     },
                 };
                 Ok(env)
             },
             UninitializedEnvironment::Remote(pointer) => {
-                let env = RemoteEnvironment::new(flox, pointer)?;
+                let env = RemoteEnvironment::new(flox, *pointer)?;
                 Ok(ConcreteEnvironment::Remote(env))
             },
         }
@@ -1282,6 +1614,11 @@ impl ActiveEnvironments {
         self.0.push_front(env);
     }
 
+    /// Remove the environment at `index`, counting from the most recently activated
+    pub fn remove(&mut self, index: usize) -> Option<UninitializedEnvironment> {
+        self.0.remove(index)
+    }
+
     /// Check if the given environment is active
     pub fn is_active(&self, env: &UninitializedEnvironment) -> bool {
         self.0.contains(env)
@@ -1346,6 +1683,7 @@ fn activated_environments() -> ActiveEnvironments {
 /// Check whether the given [EnvironmentRef] is trusted.
 ///
 /// If not, prompt the user to trust or deny abort or ask again.
+/// See [trust::evaluate_trust] for the rules applied before prompting.
 ///
 /// This function returns [`Ok`] if the environment is trusted
 /// and a formatted error message if not.
@@ -1356,44 +1694,61 @@ pub(super) async fn ensure_environment_trust(
 ) -> Result<()> {
     let env_ref = EnvironmentRef::new_from_parts(environment.owner().clone(), environment.name());
 
-    let trust = config.flox.trusted_environments.get(&env_ref);
+    let scripts = ActivationScripts::from_manifest(&environment.manifest_content(flox)?)
+        .context("Could not read the hooks of the environment")?;
 
-    // Official Flox environments are trusted by default
-    // Only applies to the current flox owned FloxHub,
-    // so this rule might need to be revisited in the future.
-    if env_ref.owner().as_str() == "flox" {
-        debug!("Official Flox environment {env_ref} is trusted by default");
-        return Ok(());
-    }
+    let decision = trust::evaluate_trust(
+        &config.flox,
+        flox.floxhub_token.as_ref(),
+        &env_ref,
+        &scripts,
+    );
 
-    if let Some(ref token) = flox.floxhub_token {
-        if token.handle() == env_ref.owner().as_str() {
-            debug!("environment {env_ref} is trusted by token");
+    let scripts_changed = decision == TrustDecision::ScriptsChanged;
+    let message = match decision {
+        TrustDecision::Trusted(reason) => {
+            debug!("environment {env_ref} is trusted: {reason}");
             return Ok(());
-        }
-    }
-
-    if matches!(trust, Some(EnvironmentTrust::Trust)) {
-        debug!("environment {env_ref} is trusted by config");
-        return Ok(());
-    }
+        },
+        TrustDecision::Denied => {
+            debug!("environment {env_ref} is denied by config");
+            bail!(formatdoc! {"
+                Environment {env_ref} is not trusted.
 
-    if matches!(trust, Some(EnvironmentTrust::Deny)) {
-        debug!("environment {env_ref} is denied by config");
+                Run 'flox trust revoke {env_ref}' to remove the rule denying it."});
+        },
+        TrustDecision::OwnerDenied => {
+            debug!("owner of environment {env_ref} is denied by config");
+            bail!(formatdoc! {"
+                Environment {env_ref} is not trusted.
+
+                Run 'flox trust revoke --owner {owner}' to remove the rule denying its owner.",
+                owner = env_ref.owner(),
+            });
+        },
+        TrustDecision::ScriptsChanged => formatdoc! {"
+            The hooks, profile scripts or variables of {env_ref} changed since you trusted it.
 
-        let message = formatdoc! {"
+            flox environments do not run in a sandbox.
+            Activation hooks can run arbitrary code on your machine.
+            Thus, environments need to be trusted to be activated."},
+        TrustDecision::Unknown => formatdoc! {"
             Environment {env_ref} is not trusted.
 
-            Run 'flox config --set trusted_environments.{env_ref} trust' to trust it."};
-        bail!("{message}");
-    }
+            flox environments do not run in a sandbox.
+            Activation hooks can run arbitrary code on your machine.
+            Thus, environments need to be trusted to be activated."},
+    };
 
     #[derive(Debug, PartialEq)]
     enum Choices {
         Trust,
+        TrustScripts,
+        TrustOwner,
         Deny,
         TrustTemporarily,
         Abort,
+        ShowScripts,
         ShowConfig,
     }
 
@@ -1410,19 +1765,19 @@ pub(super) async fn ensure_environment_trust(
         }
     }
 
-    let message = formatdoc! {"
-        Environment {env_ref} is not trusted.
-
-        flox environments do not run in a sandbox.
-        Activation hooks can run arbitrary code on your machine.
-        Thus, environments need to be trusted to be activated."};
-
     if Dialog::can_prompt() {
         message::warning(message);
     } else {
         bail!("{message}")
     }
 
+    if scripts_changed {
+        eprintln!("{}", trust::format_scripts(flox, &env_ref, &scripts));
+    }
+
+    let owner = env_ref.owner().as_str();
+    let trust_owner = format!("Trust all environments of {owner}, save choice");
+
     loop {
         let message = format!("Do you trust {env_ref}?", env_ref = env_ref);
         let choice = Dialog {
@@ -1433,7 +1788,16 @@ pub(super) async fn ensure_environment_trust(
                     Choice("Do not trust, ask again next time", Choices::Abort),
                     Choice("Do not trust, save choice", Choices::Deny),
                     Choice("Trust, ask again next time", Choices::TrustTemporarily),
+                    Choice(
+                        "Trust, ask again when hooks, profile scripts or variables change",
+                        Choices::TrustScripts,
+                    ),
                     Choice("Trust, save choice", Choices::Trust),
+                    Choice(trust_owner.as_str(), Choices::TrustOwner),
+                    Choice(
+                        "Show hooks, profile scripts and variables",
+                        Choices::ShowScripts,
+                    ),
                     Choice("Show the manifest", Choices::ShowConfig),
                 ],
             },
@@ -1445,25 +1809,25 @@ pub(super) async fn ensure_environment_trust(
 
         match choice.as_ref() {
             Choices::Trust => {
-                update_config(
-                    &flox.config_dir,
-                    &flox.temp_dir,
-                    format!("trusted_environments.'{}'", env_ref),
-                    Some(EnvironmentTrust::Trust),
-                )
-                .context("Could not write token to config")?;
+                trust::set_environment_rule(flox, &env_ref, Some(EnvironmentTrust::Trust))?;
                 let _ = mem::replace(config, Config::parse()?);
                 info!("Trusted environment {env_ref} (saved choice)",);
                 return Ok(());
             },
+            Choices::TrustScripts => {
+                trust::trust_scripts(flox, &env_ref, &scripts)?;
+                let _ = mem::replace(config, Config::parse()?);
+                info!("Trusted environment {env_ref} until its hooks change (saved choice)");
+                return Ok(());
+            },
+            Choices::TrustOwner => {
+                trust::set_owner_rule(flox, owner, Some(EnvironmentTrust::Trust))?;
+                let _ = mem::replace(config, Config::parse()?);
+                info!("Trusted all environments of {owner} (saved choice)");
+                return Ok(());
+            },
             Choices::Deny => {
-                update_config(
-                    &flox.config_dir,
-                    &flox.temp_dir,
-                    format!("trusted_environments.'{}'", env_ref),
-                    Some(EnvironmentTrust::Deny),
-                )
-                .context("Could not write token to config")?;
+                trust::set_environment_rule(flox, &env_ref, Some(EnvironmentTrust::Deny))?;
                 let _ = mem::replace(config, Config::parse()?);
                 bail!("Denied {env_ref} (saved choice).");
             },
@@ -1472,6 +1836,9 @@ pub(super) async fn ensure_environment_trust(
                 return Ok(());
             },
             Choices::Abort => bail!("Denied {env_ref} (temporary)"),
+            Choices::ShowScripts => {
+                eprintln!("{}", trust::format_scripts(flox, &env_ref, &scripts))
+            },
             Choices::ShowConfig => eprintln!("{}", environment.manifest_content(flox)?),
         }
     }
@@ -1495,6 +1862,7 @@ pub(super) async fn ensure_floxhub_token(flox: &mut Flox) -> Result<()> {
 
                 To login you can either
                 * login to FloxHub with 'flox auth login',
+                * pipe a token to 'flox auth login --token-stdin',
                 * set the 'floxhub_token' field to '<your token>' in your config
                 * set the '$FLOX_FLOXHUB_TOKEN=<your_token>' environment variable."
             };
@@ -1518,8 +1886,14 @@ pub fn environment_description(environment: &ConcreteEnvironment) -> Result<Stri
 #[cfg(test)]
 mod tests {
 
+    use flox_rust_sdk::flox::test_helpers::flox_instance;
     use flox_rust_sdk::flox::EnvironmentName;
-    use flox_rust_sdk::models::environment::PathPointer;
+    use flox_rust_sdk::models::env_registry::ensure_registered;
+    use flox_rust_sdk::models::environment::{
+        CanonicalPath,
+        PathPointer,
+        ENVIRONMENT_POINTER_FILENAME,
+    };
     use sentry::test::with_captured_events;
     use tempfile::tempdir;
 
@@ -1602,6 +1976,62 @@ mod tests {
         assert_eq!(last_active.unwrap(), env2)
     }
 
+    /// Environments are found by name in the registry,
+    /// preferring active environments if the name is ambiguous
+    #[test]
+    fn test_find_named_environment() {
+        let (flox, _temp_dir_handle) = flox_instance();
+
+        let register = |dir: &str, name: &str| {
+            let path = flox.temp_dir.join(dir);
+            fs::create_dir_all(path.join(DOT_FLOX)).unwrap();
+            let pointer = EnvironmentPointer::Path(PathPointer::new(
+                EnvironmentName::from_str(name).unwrap(),
+            ));
+            fs::write(
+                path.join(DOT_FLOX).join(ENVIRONMENT_POINTER_FILENAME),
+                serde_json::to_string(&pointer).unwrap(),
+            )
+            .unwrap();
+            let dot_flox_path = CanonicalPath::new(path.join(DOT_FLOX)).unwrap();
+            ensure_registered(&flox, &dot_flox_path, &pointer).unwrap();
+            DotFlox::open(&path).unwrap()
+        };
+        let frontend = register("frontend", "frontend");
+        register("backend", "backend");
+        let other_backend = register("other/backend", "backend");
+
+        let find = |name: &str, active: &ActiveEnvironments| {
+            temp_env::with_var(
+                FLOX_ACTIVE_ENVIRONMENTS_VAR,
+                Some(active.to_string()),
+                || find_named_environment(&flox, name),
+            )
+        };
+        let none_active = ActiveEnvironments::default();
+
+        assert_eq!(
+            find("frontend", &none_active).unwrap(),
+            UninitializedEnvironment::DotFlox(frontend)
+        );
+        assert!(matches!(
+            find("missing", &none_active),
+            Err(EnvironmentSelectError::EnvNameNotFound(_))
+        ));
+        // tests don't run in a terminal, so there is no prompt
+        assert!(matches!(
+            find("backend", &none_active),
+            Err(EnvironmentSelectError::EnvNameAmbiguous { paths, .. }) if paths.len() == 2
+        ));
+
+        let mut active = ActiveEnvironments::default();
+        active.set_last_active(UninitializedEnvironment::DotFlox(other_backend.clone()));
+        assert_eq!(
+            find("backend", &active).unwrap(),
+            UninitializedEnvironment::DotFlox(other_backend)
+        );
+    }
+
     /// [UpdateNotification::print_new_version_available] should write notification_file
     #[test]
     fn test_print_new_version_available_writes_file() {
@@ -1757,4 +2187,57 @@ mod tests {
 
         assert!(result.unwrap().is_none());
     }
+
+    /// All suggested subcommands are actual subcommands
+    #[test]
+    fn subcommands_are_valid() {
+        for command in SUBCOMMANDS {
+            let args = [*command, "--help"];
+            let parsed = flox_cli().run_inner(&args[..]);
+            assert!(
+                matches!(parsed, Err(ParseFailure::Stdout(..))),
+                "'{command}' is not a subcommand"
+            );
+        }
+    }
+
+    #[test]
+    fn test_suggest_subcommand() {
+        let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            suggest_subcommand(
+                args(&["-v", "upgarde"]),
+                "`upgarde` is not expected in this context".to_string()
+            ),
+            "no such command: `upgarde`, did you mean `upgrade`?"
+        );
+        assert_eq!(
+            suggest_subcommand(
+                args(&["shell"]),
+                "no such command or positional: `shell`, did you mean `show`?".to_string()
+            ),
+            "no such command: `shell`, did you mean `activate`?"
+        );
+        // errors about other arguments are passed through
+        let error = "`--bogus` is not expected in this context".to_string();
+        assert_eq!(
+            suggest_subcommand(args(&["install", "--bogus"]), error.clone()),
+            error
+        );
+    }
+
+    #[test]
+    fn test_explain_changed_flag() {
+        let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let error = "`-e` requires an argument `<name>`".to_string();
+
+        assert!(explain_changed_flag(args(&["list", "-e"]), error.clone())
+            .contains("'-e' now means '--env'"));
+        // other commands never had `list -e`
+        assert_eq!(
+            explain_changed_flag(args(&["install", "-e"]), error.clone()),
+            error
+        );
+    }
 }
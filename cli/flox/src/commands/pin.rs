@@ -0,0 +1,109 @@
+use anyhow::Result;
+use bpaf::Bpaf;
+use flox_rust_sdk::flox::Flox;
+use flox_rust_sdk::models::lockfile::LockfileManifest;
+use flox_rust_sdk::models::manifest::set_held;
+use tracing::instrument;
+
+use super::{environment_select, EnvironmentSelect};
+use crate::commands::{ensure_floxhub_token, environment_description, ConcreteEnvironment};
+use crate::subcommand_metric;
+use crate::utils::dialog::{Dialog, Spinner};
+use crate::utils::message;
+
+// Hold packages at their locked versions during upgrades
+#[derive(Bpaf, Clone)]
+pub struct Pin {
+    #[bpaf(external(environment_select), fallback(Default::default()))]
+    environment: EnvironmentSelect,
+
+    /// The install IDs of the packages to hold
+    #[bpaf(positional("packages"), some("Must specify at least one package"))]
+    packages: Vec<String>,
+}
+
+// Allow upgrades of held packages again
+#[derive(Bpaf, Clone)]
+pub struct Unpin {
+    #[bpaf(external(environment_select), fallback(Default::default()))]
+    environment: EnvironmentSelect,
+
+    /// The install IDs of the packages to release
+    #[bpaf(positional("packages"), some("Must specify at least one package"))]
+    packages: Vec<String>,
+}
+
+impl Pin {
+    #[instrument(name = "pin", skip_all)]
+    pub async fn handle(self, flox: Flox) -> Result<()> {
+        subcommand_metric!("pin");
+        set_packages_held(flox, self.environment, self.packages, true).await
+    }
+}
+
+impl Unpin {
+    #[instrument(name = "unpin", skip_all)]
+    pub async fn handle(self, flox: Flox) -> Result<()> {
+        subcommand_metric!("unpin");
+        set_packages_held(flox, self.environment, self.packages, false).await
+    }
+}
+
+/// Set `held` for `packages` in the manifest and apply the edit,
+/// skipping packages that are already (un)held
+async fn set_packages_held(
+    mut flox: Flox,
+    environment: EnvironmentSelect,
+    packages: Vec<String>,
+    held: bool,
+) -> Result<()> {
+    let action = if held { "Pin" } else { "Unpin" };
+    let concrete_environment =
+        environment.detect_concrete_environment(&flox, &format!("{action} packages in"))?;
+
+    // Ensure the user is logged in for the following remote operations
+    if let ConcreteEnvironment::Remote(_) = concrete_environment {
+        ensure_floxhub_token(&mut flox).await?;
+    };
+
+    let description = environment_description(&concrete_environment)?;
+    let mut environment = concrete_environment.into_dyn_environment();
+
+    let contents = environment.manifest_content(&flox)?;
+    let manifest = LockfileManifest::from_toml(&contents)?;
+
+    let (unchanged, changed): (Vec<_>, Vec<_>) = packages
+        .into_iter()
+        .partition(|package| manifest.is_installed(package) && manifest.is_held(package) == held);
+
+    for package in &unchanged {
+        let state = if held { "already" } else { "not" };
+        message::warning(format!(
+            "'{package}' is {state} pinned in environment {description}"
+        ));
+    }
+    if changed.is_empty() {
+        return Ok(());
+    }
+
+    let new_contents = set_held(&contents, &changed, held)?.to_string();
+
+    let _ = Dialog {
+        message: &format!("{action}ning packages in environment {description}..."),
+        help_message: None,
+        typed: Spinner::new(|| environment.edit(&flox, new_contents)),
+    }
+    .spin()?;
+
+    for package in &changed {
+        if held {
+            message::updated(format!(
+                "'{package}' pinned in environment {description}, it will not be upgraded"
+            ));
+        } else {
+            message::updated(format!("'{package}' unpinned in environment {description}"));
+        }
+    }
+
+    Ok(())
+}
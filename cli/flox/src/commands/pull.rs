@@ -7,6 +7,7 @@ use flox_rust_sdk::flox::{EnvironmentRef, Flox};
 use flox_rust_sdk::models::environment::managed_environment::{
     ManagedEnvironment,
     ManagedEnvironmentError,
+    MergeResult,
     PullResult,
 };
 use flox_rust_sdk::models::environment::{
@@ -19,17 +20,18 @@ use flox_rust_sdk::models::environment::{
     ENVIRONMENT_POINTER_FILENAME,
 };
 use flox_rust_sdk::models::lockfile::LockedManifestError;
-use flox_rust_sdk::models::manifest;
+use flox_rust_sdk::models::manifest::{self, ConflictResolution, MergeConflict};
 use indoc::formatdoc;
 use log::debug;
 use toml_edit::DocumentMut;
 use tracing::instrument;
 use url::Url;
 
+use super::push::parse_remote_url;
 use super::{open_path, ConcreteEnvironment};
 use crate::subcommand_metric;
 use crate::utils::dialog::{Dialog, Select, Spinner};
-use crate::utils::errors::{display_chain, format_locked_manifest_error};
+use crate::utils::errors::{display_chain, format_locked_manifest_error, format_merge_conflict};
 use crate::utils::message;
 
 #[derive(Debug, Clone, Bpaf)]
@@ -67,6 +69,17 @@ pub struct Pull {
     #[bpaf(long, short)]
     force: bool,
 
+    /// Merge upstream changes into local changes
+    /// if the environment has diverged from upstream,
+    /// prompting to resolve conflicting changes
+    #[bpaf(long)]
+    merge: bool,
+
+    /// Pull a new environment from a plain git repository (URL or local path)
+    /// instead of FloxHub
+    #[bpaf(long, argument::<String>("url"), parse(parse_remote_url), optional)]
+    remote_url: Option<Url>,
+
     #[bpaf(external(pull_select), fallback(Default::default()))]
     pull_select: PullSelect,
 }
@@ -86,18 +99,36 @@ impl Pull {
 
         match self.pull_select {
             PullSelect::New { remote } | PullSelect::NewAbbreviated { remote } => {
-                let (start, complete) =
-                    Self::pull_new_messages(self.dir.as_deref(), &remote, flox.floxhub.base_url());
+                if self.merge {
+                    bail!("'--merge' can only be used when pulling an existing environment");
+                }
+
+                let host = self
+                    .remote_url
+                    .as_ref()
+                    .unwrap_or_else(|| flox.floxhub.base_url());
+                let (start, complete) = Self::pull_new_messages(self.dir.as_deref(), &remote, host);
 
                 let dir = self.dir.unwrap_or_else(|| std::env::current_dir().unwrap());
 
                 debug!("Resolved user intent: pull {remote:?} into {dir:?}");
 
-                Self::pull_new_environment(&flox, dir.join(DOT_FLOX), remote, self.force, &start)?;
+                Self::pull_new_environment(
+                    &flox,
+                    dir.join(DOT_FLOX),
+                    remote,
+                    self.remote_url,
+                    self.force,
+                    &start,
+                )?;
 
                 message::created(complete);
             },
             PullSelect::Existing {} => {
+                if self.remote_url.is_some() {
+                    bail!("'--remote-url' can only be used when pulling a new environment");
+                }
+
                 let dir = self.dir.unwrap_or_else(|| std::env::current_dir().unwrap());
 
                 debug!("Resolved user intent: pull changes for environment found in {dir:?}");
@@ -105,16 +136,27 @@ impl Pull {
                 let pointer = {
                     let p = EnvironmentPointer::open(&dir)?;
                     match p {
-                        EnvironmentPointer::Managed(managed_pointer) => managed_pointer,
+                        EnvironmentPointer::Managed(managed_pointer) => *managed_pointer,
                         EnvironmentPointer::Path(_) => bail!("Cannot pull into a path environment"),
                     }
                 };
 
+                if self.merge {
+                    if self.force {
+                        bail!("'--merge' and '--force' can not be used together");
+                    }
+                    return Self::pull_merge(&flox, &dir, pointer);
+                }
+
+                let floxhub_host = pointer
+                    .remote_url
+                    .clone()
+                    .unwrap_or_else(|| flox.floxhub.base_url().clone());
+
                 let start_message = format!(
                     "⬇️  Remote: pulling and building {owner}/{name} from {floxhub_host}",
                     owner = pointer.owner,
                     name = pointer.name,
-                    floxhub_host = flox.floxhub.base_url()
                 );
 
                 let result = Dialog {
@@ -139,7 +181,6 @@ impl Pull {
                             You can activate this environment with 'flox activate'
                             ",
                             owner = pointer.owner, name = pointer.name,
-                            floxhub_host = flox.floxhub.base_url(),
                             suffix = if self.force { " (forced)" } else { "" }
                         });
                     },
@@ -155,6 +196,40 @@ impl Pull {
         Ok(())
     }
 
+    /// Merge upstream changes into an existing environment
+    fn pull_merge(flox: &Flox, dir: &Path, pointer: ManagedPointer) -> Result<()> {
+        let mut env = ManagedEnvironment::open(flox, pointer.clone(), dir.join(DOT_FLOX))?;
+
+        match merge_upstream(flox, &mut env)? {
+            MergeResult::UpToDate => {
+                message::warning(formatdoc! {"
+                    {owner}/{name} is already up to date.
+                ", owner = pointer.owner, name = pointer.name});
+            },
+            MergeResult::FastForward => {
+                Dialog {
+                    message: "Building environment...",
+                    help_message: None,
+                    typed: Spinner::new(|| env.build(flox)),
+                }
+                .spin()?;
+                message::updated(formatdoc! {"
+                    Pulled {owner}/{name}
+
+                    You can activate this environment with 'flox activate'
+                ", owner = pointer.owner, name = pointer.name});
+            },
+            MergeResult::Merged => {
+                message::updated(formatdoc! {"
+                    Merged upstream changes of {owner}/{name} into your local changes
+
+                    Use 'flox push' to publish the merged environment.
+                ", owner = pointer.owner, name = pointer.name});
+            },
+        }
+        Ok(())
+    }
+
     /// Update an existing environment with the latest version from FloxHub
     ///
     /// Opens the environment and calls [ManagedEnvironment::pull] on it,
@@ -174,7 +249,7 @@ impl Pull {
         Ok(state)
     }
 
-    /// Pull a new environment from FloxHub into the given directory
+    /// Pull a new environment from FloxHub (or a plain git remote) into the given directory
     ///
     /// This will create a new environment in the given directory.
     /// Uses [ManagedEnvironment::open] which will try to clone the environment.
@@ -185,6 +260,7 @@ impl Pull {
         flox: &Flox,
         dot_flox_path: PathBuf,
         env_ref: EnvironmentRef,
+        remote_url: Option<Url>,
         force: bool,
         message: &str,
     ) -> Result<()> {
@@ -222,7 +298,8 @@ impl Pull {
             env_ref.owner().clone(),
             env_ref.name().clone(),
             &flox.floxhub,
-        );
+        )
+        .with_remote_url(remote_url);
         let pointer_content =
             serde_json::to_string_pretty(&pointer).context("Could not serialize pointer")?;
 
@@ -485,6 +562,46 @@ impl Pull {
     }
 }
 
+/// Merge upstream changes into a diverged environment
+///
+/// Conflicting changes are resolved by prompting the user, if possible.
+/// Otherwise, the merge fails listing the conflicts.
+pub(super) fn merge_upstream(flox: &Flox, env: &mut ManagedEnvironment) -> Result<MergeResult> {
+    let can_prompt = Dialog::can_prompt();
+    let result = env.merge_upstream(flox, |conflict| {
+        if !can_prompt {
+            return None;
+        }
+        query_conflict_resolution(conflict)
+            .map_err(|err| debug!("could not prompt for conflict resolution: {err}"))
+            .ok()
+    })?;
+    Ok(result)
+}
+
+/// Prompt the user which side of a conflicting change to keep
+fn query_conflict_resolution(conflict: &MergeConflict) -> Result<ConflictResolution> {
+    let message = formatdoc! {"
+        Both local and upstream changed {conflict}",
+        conflict = format_merge_conflict(conflict),
+    };
+
+    let dialog = Dialog {
+        message: &message,
+        help_message: None,
+        typed: Select {
+            options: ["Keep the local change", "Take the upstream change"].to_vec(),
+        },
+    };
+
+    let (choice, _) = dialog.raw_prompt()?;
+    if choice == 0 {
+        Ok(ConflictResolution::Ours)
+    } else {
+        Ok(ConflictResolution::Theirs)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use flox_rust_sdk::flox::test_helpers::{
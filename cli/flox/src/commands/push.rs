@@ -1,7 +1,7 @@
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use bpaf::Bpaf;
 use flox_rust_sdk::flox::{EnvironmentOwner, Flox};
 use flox_rust_sdk::models::environment::managed_environment::{
@@ -19,10 +19,12 @@ use flox_rust_sdk::models::environment::{
 use indoc::formatdoc;
 use log::debug;
 use tracing::instrument;
+use url::Url;
 
+use super::pull::merge_upstream;
 use crate::commands::ensure_floxhub_token;
 use crate::subcommand_metric;
-use crate::utils::dialog::{Dialog, Spinner};
+use crate::utils::dialog::{Confirm, Dialog, Spinner};
 use crate::utils::errors::format_core_error;
 use crate::utils::message;
 
@@ -34,9 +36,17 @@ pub struct Push {
     dir: Option<PathBuf>,
 
     /// FloxHub owner to push environment to (default: current FloxHub user)
+    ///
+    /// Required with '--remote-url'.
     #[bpaf(long, short, argument("owner"))]
     owner: Option<EnvironmentOwner>,
 
+    /// Push to a plain git repository (URL or local path) instead of FloxHub
+    ///
+    /// Only applies to environments that are pushed for the first time.
+    #[bpaf(long, argument::<String>("url"), parse(parse_remote_url), optional)]
+    remote_url: Option<Url>,
+
     /// Forceably overwrite the remote copy of the environment
     #[bpaf(long, short)]
     force: bool,
@@ -47,48 +57,109 @@ impl Push {
     pub async fn handle(self, mut flox: Flox) -> Result<()> {
         subcommand_metric!("push");
 
+        let dir = self.dir.unwrap_or_else(|| std::env::current_dir().unwrap());
+        let pointer = EnvironmentPointer::open(&dir)?;
+
+        // Environments hosted in plain git repositories
+        // authenticate using the user's git configuration
+        let pushes_to_floxhub = match &pointer {
+            EnvironmentPointer::Managed(managed_pointer) => managed_pointer.remote_url.is_none(),
+            EnvironmentPointer::Path(_) => self.remote_url.is_none(),
+        };
+
         // Ensure the user is logged in for the following remote operations
-        ensure_floxhub_token(&mut flox).await?;
+        if pushes_to_floxhub {
+            ensure_floxhub_token(&mut flox).await?;
+        }
 
         // Start a span that doesn't include authentication
         let span = tracing::info_span!("post-auth");
         let _guard = span.enter();
 
-        let dir = self.dir.unwrap_or_else(|| std::env::current_dir().unwrap());
-
-        match EnvironmentPointer::open(&dir)? {
+        match pointer {
             EnvironmentPointer::Managed(managed_pointer) => {
+                let managed_pointer = *managed_pointer;
+                if let Some(remote_url) = self.remote_url {
+                    bail!(formatdoc! {"
+                        {owner}/{name} is already hosted at {remote}.
+                        Can not push it to {remote_url}.
+                    ",
+                    owner = managed_pointer.owner,
+                    name = managed_pointer.name,
+                    remote = remote_name(&managed_pointer)});
+                }
+
                 let message = Self::push_existing_message(&managed_pointer, self.force);
 
-                Dialog {
-                    message: "Pushing updates to FloxHub...",
+                let push_message =
+                    format!("Pushing updates to {}...", remote_name(&managed_pointer));
+                let result = Dialog {
+                    message: &push_message,
                     help_message: None,
                     typed: Spinner::new(|| {
-                        Self::push_managed_env(&flox, managed_pointer, dir, self.force)
+                        Self::push_managed_env(&flox, managed_pointer.clone(), &dir, self.force)
                     }),
                 }
-                .spin()?;
+                .spin();
+
+                let merge = match &result {
+                    Err(err) if is_diverged(err) && Dialog::can_prompt() => {
+                        Self::query_merge().await?
+                    },
+                    _ => false,
+                };
+
+                if merge {
+                    let mut env = ManagedEnvironment::open(
+                        &flox,
+                        managed_pointer.clone(),
+                        dir.join(DOT_FLOX),
+                    )?;
+                    merge_upstream(&flox, &mut env)?;
+
+                    Dialog {
+                        message: &push_message,
+                        help_message: None,
+                        typed: Spinner::new(|| {
+                            Self::push_managed_env(&flox, managed_pointer, &dir, false)
+                        }),
+                    }
+                    .spin()?;
+                } else {
+                    result?;
+                }
 
                 message::updated(message);
             },
 
             EnvironmentPointer::Path(path_pointer) => {
-                let owner = if let Some(owner) = self.owner {
-                    owner
-                } else {
-                    EnvironmentOwner::from_str(
-                        flox.floxhub_token
-                            .as_ref()
-                            .context("Need to be loggedin")?
-                            .handle(),
-                    )?
+                let owner = match (self.owner, &flox.floxhub_token) {
+                    (Some(owner), _) => owner,
+                    // Plain git remotes don't know about FloxHub users
+                    (None, _) if self.remote_url.is_some() => {
+                        bail!("Use '--owner' to set the owner of an environment pushed with '--remote-url'")
+                    },
+                    (None, Some(token)) => EnvironmentOwner::from_str(token.handle())?,
+                    (None, None) => bail!("Need to be loggedin"),
+                };
+
+                let message = match &self.remote_url {
+                    Some(remote_url) => format!("Pushing environment to {remote_url}..."),
+                    None => "Pushing environment to FloxHub...".to_string(),
                 };
 
                 let env = Dialog {
-                    message: "Pushing environment to FloxHub...",
+                    message: &message,
                     help_message: None,
                     typed: Spinner::new(|| {
-                        Self::push_make_managed(&flox, path_pointer, &dir, owner, self.force)
+                        Self::push_make_managed(
+                            &flox,
+                            path_pointer,
+                            &dir,
+                            owner,
+                            self.remote_url,
+                            self.force,
+                        )
                     }),
                 }
                 .spin()?;
@@ -102,7 +173,7 @@ impl Push {
     fn push_managed_env(
         flox: &Flox,
         managed_pointer: ManagedPointer,
-        dir: PathBuf,
+        dir: &Path,
         force: bool,
     ) -> Result<()> {
         let mut env = ManagedEnvironment::open(flox, managed_pointer.clone(), dir.join(DOT_FLOX))?;
@@ -112,12 +183,26 @@ impl Push {
         Ok(())
     }
 
-    /// pushes a path environment in a directory to FloxHub and makes it a managed environment
+    /// Ask whether to merge upstream changes after a push was rejected
+    async fn query_merge() -> Result<bool> {
+        let dialog = Dialog {
+            message: "The environment has diverged from upstream. Merge upstream changes and push?",
+            help_message: Some("Use 'flox pull --merge' to merge without pushing"),
+            typed: Confirm {
+                default: Some(true),
+            },
+        };
+        Ok(dialog.prompt().await?)
+    }
+
+    /// pushes a path environment in a directory to FloxHub (or a plain git remote)
+    /// and makes it a managed environment
     fn push_make_managed(
         flox: &Flox,
         path_pointer: PathPointer,
         dir: &Path,
         owner: EnvironmentOwner,
+        remote_url: Option<Url>,
         force: bool,
     ) -> Result<ManagedEnvironment> {
         let dot_flox_path = dir.join(DOT_FLOX);
@@ -128,9 +213,10 @@ impl Push {
             &flox.temp_dir,
         )?;
 
-        let pointer = ManagedPointer::new(owner.clone(), path_environment.name(), &flox.floxhub);
+        let pointer = ManagedPointer::new(owner.clone(), path_environment.name(), &flox.floxhub)
+            .with_remote_url(remote_url.clone());
 
-        let env = ManagedEnvironment::push_new(flox, path_environment, owner, force)
+        let env = ManagedEnvironment::push_new(flox, path_environment, owner, remote_url, force)
             .map_err(|err| Self::convert_error(err, pointer, true))?;
 
         Ok(env)
@@ -179,31 +265,125 @@ impl Push {
     ///
     /// todo: add FloxHub base url when it's available
     fn push_existing_message(env: &ManagedPointer, force: bool) -> String {
-        let owner = &env.owner;
         let name = &env.name;
 
         let suffix = if force { " (forced)" } else { "" };
 
         formatdoc! {"
-            Updates to {name} successfully pushed to FloxHub{suffix}
+            Updates to {name} successfully pushed to {remote}{suffix}
 
-            Use 'flox pull {owner}/{name}' to get this environment in any other location.
-        "}
+            Use '{pull_command}' to get this environment in any other location.
+        ", remote = remote_name(env), pull_command = pull_command(env)}
     }
 
     /// construct a message for a newly created environment
     ///
     /// todo: add FloxHub base url when it's available
     fn push_new_message(env: &ManagedPointer, force: bool) -> String {
-        let owner = &env.owner;
         let name = &env.name;
 
         let suffix = if force { " (forced)" } else { "" };
 
         formatdoc! {"
-            {name} successfully pushed to FloxHub{suffix}
+            {name} successfully pushed to {remote}{suffix}
+
+            Use '{pull_command}' to get this environment in any other location.
+        ", remote = remote_name(env), pull_command = pull_command(env)}
+    }
+}
+
+/// Whether pushing failed because the environment diverged from upstream
+fn is_diverged(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<ManagedEnvironmentError>(),
+        Some(ManagedEnvironmentError::Diverged)
+    )
+}
+
+/// Name of the host of a managed environment for use in messages
+fn remote_name(pointer: &ManagedPointer) -> String {
+    match &pointer.remote_url {
+        Some(remote_url) => remote_url.to_string(),
+        None => "FloxHub".to_string(),
+    }
+}
+
+/// Command to pull the environment described by `pointer` into another directory
+fn pull_command(pointer: &ManagedPointer) -> String {
+    let owner = &pointer.owner;
+    let name = &pointer.name;
+    match &pointer.remote_url {
+        Some(remote_url) => format!("flox pull --remote-url {remote_url} {owner}/{name}"),
+        None => format!("flox pull {owner}/{name}"),
+    }
+}
+
+/// Parse the argument of `--remote-url`
+///
+/// Accepts URLs supported by git (`file://`, `ssh://`, `https://`, ...),
+/// scp-like remotes (`git@github.com:org/repo.git`), which are converted to `ssh://` URLs,
+/// as well as paths to local repositories, which are converted to `file://` URLs.
+pub(super) fn parse_remote_url(url: String) -> Result<Url> {
+    if let Some(ssh_url) = scp_to_ssh_url(&url) {
+        return Url::parse(&ssh_url).with_context(|| format!("'{url}' is not a valid remote"));
+    }
+
+    if let Ok(url) = Url::parse(&url) {
+        return Ok(url);
+    }
+
+    let path = Path::new(&url)
+        .canonicalize()
+        .with_context(|| format!("'{url}' is neither a URL nor an existing directory"))?;
+
+    Url::from_directory_path(&path)
+        .map_err(|_| anyhow::anyhow!("Could not convert '{}' to a URL", path.display()))
+}
+
+/// Convert the scp-like syntax accepted by git (`[user@]host:path`) to an `ssh://` URL
+///
+/// Like git, a remote is considered scp-like if it isn't a URL
+/// and has a colon before the first slash.
+/// The path is taken relative to the root of the host,
+/// which is how git hosting services interpret it.
+fn scp_to_ssh_url(url: &str) -> Option<String> {
+    if url.contains("://") {
+        return None;
+    }
+    let (host, path) = url.split_once(':')?;
+    if host.is_empty() || host.contains('/') {
+        return None;
+    }
+    Some(format!("ssh://{host}/{}", path.trim_start_matches('/')))
+}
 
-            Use 'flox pull {owner}/{name}' to get this environment in any other location.
-        "}
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn parses_remote_urls() {
+        assert_eq!(
+            parse_remote_url("git@github.com:org/repo.git".to_string()).unwrap(),
+            Url::parse("ssh://git@github.com/org/repo.git").unwrap()
+        );
+        assert_eq!(
+            parse_remote_url("example.com:/srv/envs.git".to_string()).unwrap(),
+            Url::parse("ssh://example.com/srv/envs.git").unwrap()
+        );
+        assert_eq!(
+            parse_remote_url("https://example.com/team/envs.git".to_string()).unwrap(),
+            Url::parse("https://example.com/team/envs.git").unwrap()
+        );
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().canonicalize().unwrap();
+        assert_eq!(
+            parse_remote_url(path.to_string_lossy().to_string()).unwrap(),
+            Url::from_directory_path(&path).unwrap()
+        );
+        assert!(parse_remote_url("./does/not/exist".to_string()).is_err());
     }
 }
@@ -0,0 +1,86 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use bpaf::Bpaf;
+use flox_rust_sdk::flox::Flox;
+use flox_rust_sdk::models::sbom::{Sbom as EnvironmentSbom, SbomFormat};
+use tracing::instrument;
+
+use super::list::List;
+use super::{environment_select, EnvironmentSelect};
+use crate::subcommand_metric;
+use crate::utils::message;
+
+// Generate a software bill of materials for an environment
+#[derive(Bpaf, Clone)]
+pub struct Sbom {
+    #[bpaf(external(environment_select), fallback(Default::default()))]
+    environment: EnvironmentSelect,
+
+    /// Only include packages for <system> (default: all systems)
+    #[bpaf(long, argument("system"))]
+    system: Option<String>,
+
+    /// Write the document to <path> instead of stdout
+    #[bpaf(short, long, argument("path"))]
+    output: Option<PathBuf>,
+
+    #[bpaf(external(sbom_document_format), fallback(SbomDocumentFormat::Spdx))]
+    format: SbomDocumentFormat,
+}
+
+#[derive(Bpaf, Clone, Debug, PartialEq)]
+pub enum SbomDocumentFormat {
+    /// Generate an SPDX 2.3 document (default)
+    #[bpaf(long)]
+    Spdx,
+
+    /// Generate a CycloneDX 1.5 document
+    #[bpaf(long)]
+    Cyclonedx,
+}
+
+impl From<SbomDocumentFormat> for SbomFormat {
+    fn from(format: SbomDocumentFormat) -> Self {
+        match format {
+            SbomDocumentFormat::Spdx => SbomFormat::Spdx,
+            SbomDocumentFormat::Cyclonedx => SbomFormat::CycloneDx,
+        }
+    }
+}
+
+impl Sbom {
+    #[instrument(name = "sbom", skip_all)]
+    pub async fn handle(self, flox: Flox) -> Result<()> {
+        subcommand_metric!("sbom");
+
+        let mut env = self
+            .environment
+            .detect_concrete_environment(&flox, "Generate SBOM for")?
+            .into_dyn_environment();
+
+        let lockfile = List::get_lockfile(&flox, &mut *env)?;
+        let mut sbom = EnvironmentSbom::from_lockfile(env.name().to_string(), &lockfile);
+        if let Some(system) = &self.system {
+            sbom.retain_system(system);
+        }
+
+        let format = SbomFormat::from(self.format);
+        let document = serde_json::to_string_pretty(&format.render(&sbom))?;
+
+        match self.output {
+            Some(path) => {
+                fs::write(&path, document)
+                    .with_context(|| format!("Could not write SBOM to '{}'", path.display()))?;
+                message::created(format!(
+                    "{} SBOM written to '{}'",
+                    format.specification(),
+                    path.display()
+                ));
+            },
+            None => println!("{document}"),
+        }
+        Ok(())
+    }
+}
@@ -15,16 +15,20 @@ use flox_rust_sdk::models::search::{
     ShowError,
     Subtree,
 };
+use flox_rust_sdk::models::search_index::{read_search_index, search_index_path};
 use indoc::formatdoc;
 use log::debug;
 use tracing::instrument;
 
+use crate::commands::index::{format_age, STALE_INDEX_AGE};
+use crate::commands::install::Install;
 use crate::config::features::Features;
 use crate::config::Config;
 use crate::subcommand_metric;
 use crate::utils::dialog::{Dialog, Spinner};
 use crate::utils::didyoumean::{DidYouMean, SearchSuggestion};
 use crate::utils::message;
+use crate::utils::package_browser::{BrowserOutcome, PackageBrowser, PackageEntry};
 use crate::utils::search::{
     construct_search_params,
     manifest_and_lockfile,
@@ -50,6 +54,17 @@ pub struct Search {
     #[bpaf(short, long)]
     pub all: bool,
 
+    /// Browse the results in a full-screen view and install the selected packages
+    #[bpaf(long, short)]
+    pub interactive: bool,
+
+    /// Search the local package index instead of the package sources
+    ///
+    /// Does not require network access.
+    /// Refresh the index with 'flox index refresh'.
+    #[bpaf(long)]
+    pub offline: bool,
+
     /// The package to search for in the format '<pkg-path>[@<semver-range>]' using 'node-semver' syntax.
     ///
     /// ex. python310Packages.pip
@@ -74,6 +89,20 @@ impl Search {
 
         debug!("performing search for term: {}", self.search_term);
 
+        let limit = if self.all {
+            None
+        } else {
+            config.flox.search_limit.or(DEFAULT_SEARCH_LIMIT)
+        };
+
+        if self.interactive {
+            return self.handle_interactive(config, flox).await;
+        }
+
+        if self.offline {
+            return self.handle_offline(&flox, limit);
+        }
+
         let (manifest, lockfile) = manifest_and_lockfile(&flox, "Search using")
             .context("failed while looking for manifest and lockfile")?;
 
@@ -81,12 +110,6 @@ impl Search {
         let lockfile = PathOrJson::Path(lockfile);
         let global_manifest: PathOrJson = global_manifest_path(&flox).try_into()?;
 
-        let limit = if self.all {
-            None
-        } else {
-            config.flox.search_limit.or(DEFAULT_SEARCH_LIMIT)
-        };
-
         let search_params = construct_search_params(
             &self.search_term,
             limit,
@@ -119,6 +142,7 @@ impl Search {
                 manifest,
                 global_manifest,
                 lockfile,
+                &config.flox.package_aliases,
             );
 
             if results.results.is_empty() {
@@ -162,6 +186,128 @@ impl Search {
     }
 }
 
+impl Search {
+    /// Search the local package index built by 'flox index refresh'
+    ///
+    /// Suggestions for similar packages are skipped,
+    /// as they would require searching the package sources.
+    fn handle_offline(self, flox: &Flox, limit: Option<u8>) -> Result<()> {
+        let Some(index) = read_search_index(search_index_path(flox))? else {
+            bail!(formatdoc! {"
+                No local package index found.
+
+                Create one with 'flox index refresh' while online.
+            "});
+        };
+
+        let age = index.age();
+        if age > STALE_INDEX_AGE {
+            message::warning(format!(
+                "The local package index was last refreshed {} ago. Refresh it with 'flox index refresh'.",
+                format_age(age)
+            ));
+        }
+
+        let query = Query::new(
+            &self.search_term,
+            Features::parse()?.search_strategy,
+            limit,
+            true,
+        )?;
+        let results = index.search(&query)?;
+
+        if self.json {
+            debug!("printing offline search results as JSON");
+            return render_search_results_json(results);
+        }
+
+        if results.results.is_empty() {
+            bail!(
+                "No packages matched this search term in the local package index: '{}'",
+                self.search_term
+            );
+        }
+
+        let results = DisplaySearchResults::from_search_results(&self.search_term, results)?;
+        println!("{results}");
+
+        let mut hints = String::new();
+        if let Some(hint) = results.search_results_truncated_hint() {
+            writeln!(&mut hints)?;
+            writeln!(&mut hints, "{hint}")?;
+        }
+        writeln!(&mut hints)?;
+        writeln!(&mut hints, "{FLOX_SHOW_HINT}")?;
+        message::plain(hints);
+
+        Ok(())
+    }
+}
+
+impl Search {
+    /// Browse all versions of the matching packages with a [PackageBrowser]
+    /// and install the selected packages into the detected environment
+    async fn handle_interactive(self, config: Config, flox: Flox) -> Result<()> {
+        if self.json {
+            bail!("'--interactive' can't be used with '--json'");
+        }
+        if !Dialog::can_prompt() {
+            bail!("'--interactive' requires a terminal");
+        }
+
+        // Search without deduplication to find all versions of each package
+        let query = Query::new(
+            &self.search_term,
+            Features::parse()?.search_strategy,
+            None,
+            false,
+        )?;
+        let results = if self.offline {
+            let Some(index) = read_search_index(search_index_path(&flox))? else {
+                bail!(formatdoc! {"
+                    No local package index found.
+
+                    Create one with 'flox index refresh' while online.
+                "});
+            };
+            index.search(&query)?
+        } else {
+            let (manifest, lockfile) = manifest_and_lockfile(&flox, "Search using")
+                .context("failed while looking for manifest and lockfile")?;
+            let search_params = SearchParams {
+                manifest: manifest.map(|p| p.try_into()).transpose()?,
+                global_manifest: global_manifest_path(&flox).try_into()?,
+                lockfile: PathOrJson::Path(lockfile),
+                query,
+            };
+            let (results, exit_status) = Dialog {
+                message: "Searching for packages...",
+                help_message: Some("This may take a while the first time you run it."),
+                typed: Spinner::new(|| do_search(&search_params)),
+            }
+            .spin_with_delay(Duration::from_secs(1))?;
+            debug!("search call exit status: {}", exit_status.to_string());
+            results
+        };
+
+        let entries = PackageEntry::from_search_results(results.results);
+        if entries.is_empty() {
+            bail!(
+                "No packages matched this search term: '{}'",
+                self.search_term
+            );
+        }
+
+        let title = format!("flox search {}", self.search_term);
+        match PackageBrowser::new(title, entries).run()? {
+            BrowserOutcome::Install(packages) => {
+                Install::packages(packages).handle(config, flox).await
+            },
+            BrowserOutcome::Cancel => Ok(()),
+        }
+    }
+}
+
 fn render_search_results_json(search_results: SearchResults) -> Result<()> {
     let json = serde_json::to_string(&search_results.results)?;
     println!("{}", json);
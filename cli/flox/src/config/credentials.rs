@@ -0,0 +1,507 @@
+//! Storage backends for FloxHub credentials
+//!
+//! By default the FloxHub token is stored in plaintext in the user config file.
+//! Alternatively it can be kept in the desktop keyring via the
+//! Secret Service API (using `secret-tool`),
+//! or handed to an external credential helper speaking the
+//! `git credential` protocol.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use toml_edit::Key;
+use url::Url;
+
+use super::{Config, FloxConfig, ReadWriteError, FLOX_CONFIG_FILE};
+
+/// Label under which secrets are stored in the Secret Service keyring
+const SECRET_SERVICE_NAME: &str = "flox";
+
+/// Backend selected by the `credential_store` config key
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CredentialStoreKind {
+    /// Store secrets in the user config file
+    #[default]
+    Plaintext,
+    /// Store secrets in the desktop keyring via the Secret Service API
+    SecretService,
+    /// Delegate to the command configured in `credential_helper`
+    Helper,
+}
+
+/// The secrets flox stores
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Secret {
+    FloxhubToken,
+    FloxhubRefreshToken,
+}
+
+impl Secret {
+    /// The config key under which the secret is stored in plaintext
+    pub fn config_key(&self) -> &'static str {
+        match self {
+            Secret::FloxhubToken => "floxhub_token",
+            Secret::FloxhubRefreshToken => "floxhub_refresh_token",
+        }
+    }
+
+    /// The environment variable that may provide the secret
+    pub fn env_var(&self) -> &'static str {
+        match self {
+            Secret::FloxhubToken => "FLOX_FLOXHUB_TOKEN",
+            Secret::FloxhubRefreshToken => "FLOX_FLOXHUB_REFRESH_TOKEN",
+        }
+    }
+
+    /// The value of the secret set in the environment
+    fn env_value(&self) -> Option<String> {
+        std::env::var(self.env_var()).ok().and_then(non_empty)
+    }
+
+    /// The value of the secret set in the config file or environment
+    fn config_value<'a>(&self, config: &'a FloxConfig) -> Option<&'a str> {
+        match self {
+            Secret::FloxhubToken => config.floxhub_token.as_deref(),
+            Secret::FloxhubRefreshToken => config.floxhub_refresh_token.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum CredentialStoreError {
+    #[error("'credential_store' is set to 'helper' but 'credential_helper' is not set")]
+    MissingHelper,
+    #[error("failed to run '{0}'")]
+    Spawn(String, #[source] std::io::Error),
+    #[error("'{command}' failed: {stderr}")]
+    Failed { command: String, stderr: String },
+    #[error("failed to update the config file")]
+    Config(#[source] ReadWriteError),
+}
+
+/// A backend to store FloxHub credentials in
+#[derive(Clone, Debug)]
+pub enum CredentialBackend {
+    /// Secrets are part of the user config file
+    Plaintext,
+    /// Secrets are stored in the keyring using `secret-tool`
+    SecretService { host: String },
+    /// Secrets are stored by a `git credential` compatible helper command
+    Helper { command: String, host: String },
+}
+
+/// The store for FloxHub credentials selected in the config
+///
+/// Secrets left in the user config file while a different backend is selected,
+/// e.g. from before switching the backend, are moved to that backend.
+#[derive(Clone, Debug)]
+pub struct CredentialStore {
+    backend: CredentialBackend,
+    /// Directory of the user config file
+    config_dir: PathBuf,
+    temp_dir: PathBuf,
+}
+
+impl CredentialStore {
+    /// Create the store selected in the config
+    ///
+    /// Secrets are scoped to the host of `floxhub_url`.
+    pub fn new(
+        config: &FloxConfig,
+        floxhub_url: &Url,
+        temp_dir: &Path,
+    ) -> Result<Self, CredentialStoreError> {
+        let host = floxhub_url
+            .host_str()
+            .unwrap_or(floxhub_url.as_str())
+            .to_string();
+
+        let backend = match config.credential_store.unwrap_or_default() {
+            CredentialStoreKind::Plaintext => CredentialBackend::Plaintext,
+            CredentialStoreKind::SecretService => CredentialBackend::SecretService { host },
+            CredentialStoreKind::Helper => CredentialBackend::Helper {
+                command: config
+                    .credential_helper
+                    .clone()
+                    .ok_or(CredentialStoreError::MissingHelper)?,
+                host,
+            },
+        };
+        Ok(CredentialStore {
+            backend,
+            config_dir: config.config_dir.clone(),
+            temp_dir: temp_dir.to_path_buf(),
+        })
+    }
+
+    /// Look up a secret
+    ///
+    /// A value set in the environment (e.g. `$FLOX_FLOXHUB_TOKEN`)
+    /// takes precedence over any backend.
+    /// If a backend other than the config file is selected,
+    /// a value left in the config file is moved to the backend
+    /// unless the backend already stores a value, which takes precedence.
+    pub fn get(
+        &self,
+        config: &FloxConfig,
+        secret: Secret,
+    ) -> Result<Option<String>, CredentialStoreError> {
+        if let Some(value) = secret.env_value() {
+            return Ok(Some(value));
+        }
+
+        let plaintext = secret.config_value(config);
+        if let CredentialBackend::Plaintext = self.backend {
+            return Ok(plaintext.map(str::to_string));
+        }
+
+        let stored = self.backend_get(secret)?;
+        let Some(plaintext) = plaintext else {
+            return Ok(stored);
+        };
+
+        debug!("moving {secret:?} from the config file to {self:?}");
+        if stored.is_none() {
+            self.backend_store(secret, plaintext)?;
+        }
+        self.erase_plaintext(secret)?;
+        Ok(stored.or(Some(plaintext.to_string())))
+    }
+
+    /// Look up a secret in a backend other than the config file
+    fn backend_get(&self, secret: Secret) -> Result<Option<String>, CredentialStoreError> {
+        match &self.backend {
+            CredentialBackend::Plaintext => Ok(None),
+            CredentialBackend::SecretService { host } => {
+                let output = run(
+                    Command::new("secret-tool")
+                        .arg("lookup")
+                        .args(secret_attributes(host, secret)),
+                    None,
+                    true,
+                )?;
+                Ok(non_empty(output))
+            },
+            CredentialBackend::Helper { command, host } => {
+                let output = run(
+                    &mut helper_command(command, "get"),
+                    Some(&helper_input(host, secret, None)),
+                    true,
+                )?;
+                Ok(output
+                    .lines()
+                    .find_map(|line| line.strip_prefix("password="))
+                    .map(str::to_string)
+                    .and_then(non_empty))
+            },
+        }
+    }
+
+    /// Store a secret, replacing any previous value
+    pub fn store(&self, secret: Secret, value: &str) -> Result<(), CredentialStoreError> {
+        debug!("storing {secret:?} in {self:?}");
+        if let CredentialBackend::Plaintext = self.backend {
+            return write_config(&self.config_dir, &self.temp_dir, secret, Some(value));
+        }
+        self.backend_store(secret, value)?;
+        // don't leave a stale copy in the config file
+        self.erase_plaintext(secret)
+    }
+
+    /// Store a secret in a backend other than the config file
+    fn backend_store(&self, secret: Secret, value: &str) -> Result<(), CredentialStoreError> {
+        match &self.backend {
+            CredentialBackend::Plaintext => Ok(()),
+            CredentialBackend::SecretService { host } => {
+                run(
+                    Command::new("secret-tool")
+                        .arg("store")
+                        .arg(format!("--label=FloxHub ({host}) {}", secret.config_key()))
+                        .args(secret_attributes(host, secret)),
+                    Some(value),
+                    false,
+                )?;
+                Ok(())
+            },
+            CredentialBackend::Helper { command, host } => {
+                run(
+                    &mut helper_command(command, "store"),
+                    Some(&helper_input(host, secret, Some(value))),
+                    false,
+                )?;
+                Ok(())
+            },
+        }
+    }
+
+    /// Remove a secret from the backend and the config file
+    pub fn erase(&self, secret: Secret) -> Result<(), CredentialStoreError> {
+        debug!("erasing {secret:?} from {self:?}");
+        match &self.backend {
+            CredentialBackend::Plaintext => {},
+            CredentialBackend::SecretService { host } => {
+                run(
+                    Command::new("secret-tool")
+                        .arg("clear")
+                        .args(secret_attributes(host, secret)),
+                    None,
+                    false,
+                )?;
+            },
+            CredentialBackend::Helper { command, host } => {
+                run(
+                    &mut helper_command(command, "erase"),
+                    Some(&helper_input(host, secret, None)),
+                    false,
+                )?;
+            },
+        }
+        self.erase_plaintext(secret)
+    }
+
+    /// Remove a secret from the config file
+    fn erase_plaintext(&self, secret: Secret) -> Result<(), CredentialStoreError> {
+        write_config(&self.config_dir, &self.temp_dir, secret, None)
+    }
+}
+
+fn write_config(
+    config_dir: &Path,
+    temp_dir: &Path,
+    secret: Secret,
+    value: Option<&str>,
+) -> Result<(), CredentialStoreError> {
+    let query = Key::parse(secret.config_key()).expect("secret keys are valid keys");
+    match Config::write_to_in(config_dir.join(FLOX_CONFIG_FILE), temp_dir, &query, value) {
+        // erasing a secret that is not stored is not an error
+        Err(ReadWriteError::NotAUserValue(_)) if value.is_none() => Ok(()),
+        result => result.map_err(CredentialStoreError::Config),
+    }
+}
+
+/// Attributes identifying a secret in the Secret Service keyring
+fn secret_attributes(host: &str, secret: Secret) -> [&str; 6] {
+    [
+        "service",
+        SECRET_SERVICE_NAME,
+        "host",
+        host,
+        "key",
+        secret.config_key(),
+    ]
+}
+
+/// The credential helper is run by the shell, like git's `!command` helpers,
+/// with the action appended as an argument.
+fn helper_command(command: &str, action: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(format!("{command} {action}"));
+    cmd
+}
+
+/// Describe a secret using the `git credential` input format
+///
+/// The secret is distinguished by the username so that helpers
+/// can store both the token and the refresh token for the same host.
+fn helper_input(host: &str, secret: Secret, value: Option<&str>) -> String {
+    let mut input = format!(
+        "protocol=https\nhost={host}\nusername={}\n",
+        secret.config_key()
+    );
+    if let Some(value) = value {
+        input.push_str(&format!("password={value}\n"));
+    }
+    input.push('\n');
+    input
+}
+
+/// Run a command, passing `stdin` as input, and return its stdout
+///
+/// If `lookup` is set, failing without output is treated as a missing secret.
+fn run(
+    command: &mut Command,
+    stdin: Option<&str>,
+    lookup: bool,
+) -> Result<String, CredentialStoreError> {
+    let display = format!("{command:?}");
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| CredentialStoreError::Spawn(display.clone(), e))?;
+
+    if let Some(input) = stdin {
+        let mut child_stdin = child.stdin.take().expect("stdin is piped");
+        match child_stdin.write_all(input.as_bytes()) {
+            // helpers may exit without reading all of their input
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {},
+            result => result.map_err(|e| CredentialStoreError::Spawn(display.clone(), e))?,
+        }
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| CredentialStoreError::Spawn(display.clone(), e))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    // e.g. `secret-tool lookup` exits with 1 and no output if the secret is missing
+    let missing = lookup && stdout.is_empty();
+    if !output.status.success() && !missing {
+        return Err(CredentialStoreError::Failed {
+            command: display,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(stdout)
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn helper_store(dir: &Path) -> CredentialStore {
+        // a minimal helper that stores a single password in a file
+        let file = dir.join("secret");
+        let command = format!(
+            r#"f() {{ case "$1" in
+                get) [ -f {file} ] && echo "password=$(cat {file})";;
+                store) sed -n 's/^password=//p' > {file};;
+                erase) rm -f {file};;
+            esac; }}; f"#,
+            file = file.display()
+        );
+        CredentialStore {
+            backend: CredentialBackend::Helper {
+                command,
+                host: "hub.flox.dev".to_string(),
+            },
+            config_dir: dir.to_path_buf(),
+            temp_dir: dir.to_path_buf(),
+        }
+    }
+
+    /// Write a plaintext token to the config file in `dir`
+    /// and return the config as it would be read from the file
+    fn write_plaintext_token(dir: &Path, token: &str) -> FloxConfig {
+        fs::write(
+            dir.join(FLOX_CONFIG_FILE),
+            format!("floxhub_token = \"{token}\"\n"),
+        )
+        .unwrap();
+        FloxConfig {
+            floxhub_token: Some(token.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn helper_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = helper_store(dir.path());
+        let config = FloxConfig::default();
+
+        assert_eq!(store.get(&config, Secret::FloxhubToken).unwrap(), None);
+
+        store.store(Secret::FloxhubToken, "secret-token").unwrap();
+        assert_eq!(
+            store.get(&config, Secret::FloxhubToken).unwrap().as_deref(),
+            Some("secret-token")
+        );
+
+        store.erase(Secret::FloxhubToken).unwrap();
+        assert_eq!(store.get(&config, Secret::FloxhubToken).unwrap(), None);
+    }
+
+    /// A plaintext token left in the config file doesn't shadow the stored one
+    /// and is removed from the config file
+    #[test]
+    fn stored_value_takes_precedence_over_plaintext() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = helper_store(dir.path());
+        store.store(Secret::FloxhubToken, "stored-token").unwrap();
+
+        let config = write_plaintext_token(dir.path(), "config-token");
+        assert_eq!(
+            store.get(&config, Secret::FloxhubToken).unwrap().as_deref(),
+            Some("stored-token")
+        );
+        let contents = fs::read_to_string(dir.path().join(FLOX_CONFIG_FILE)).unwrap();
+        assert!(!contents.contains("floxhub_token"));
+    }
+
+    /// A plaintext token is moved to the selected backend
+    #[test]
+    fn plaintext_value_is_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = helper_store(dir.path());
+
+        let config = write_plaintext_token(dir.path(), "config-token");
+        assert_eq!(
+            store.get(&config, Secret::FloxhubToken).unwrap().as_deref(),
+            Some("config-token")
+        );
+        let contents = fs::read_to_string(dir.path().join(FLOX_CONFIG_FILE)).unwrap();
+        assert!(!contents.contains("floxhub_token"));
+
+        assert_eq!(
+            store
+                .get(&FloxConfig::default(), Secret::FloxhubToken)
+                .unwrap()
+                .as_deref(),
+            Some("config-token")
+        );
+    }
+
+    /// Erasing a secret removes it from the backend and the config file
+    #[test]
+    fn erase_clears_backend_and_plaintext() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = helper_store(dir.path());
+        store.store(Secret::FloxhubToken, "stored-token").unwrap();
+        write_plaintext_token(dir.path(), "config-token");
+
+        store.erase(Secret::FloxhubToken).unwrap();
+        assert_eq!(
+            store
+                .get(&FloxConfig::default(), Secret::FloxhubToken)
+                .unwrap(),
+            None
+        );
+        let contents = fs::read_to_string(dir.path().join(FLOX_CONFIG_FILE)).unwrap();
+        assert!(!contents.contains("floxhub_token"));
+    }
+
+    #[test]
+    fn plaintext_writes_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CredentialStore {
+            backend: CredentialBackend::Plaintext,
+            config_dir: dir.path().to_path_buf(),
+            temp_dir: dir.path().to_path_buf(),
+        };
+
+        store.store(Secret::FloxhubRefreshToken, "refresh").unwrap();
+        let contents = fs::read_to_string(dir.path().join(FLOX_CONFIG_FILE)).unwrap();
+        assert!(contents.contains(r#"floxhub_refresh_token = "refresh""#));
+
+        store.erase(Secret::FloxhubRefreshToken).unwrap();
+        let contents = fs::read_to_string(dir.path().join(FLOX_CONFIG_FILE)).unwrap();
+        assert!(!contents.contains("floxhub_refresh_token"));
+
+        // erasing again is a no-op
+        store.erase(Secret::FloxhubRefreshToken).unwrap();
+    }
+}
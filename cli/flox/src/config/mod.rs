@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
@@ -7,6 +7,7 @@ use std::{env, fs};
 use anyhow::{Context, Result};
 use config::{Config as HierarchicalConfig, Environment};
 use flox_rust_sdk::flox::EnvironmentRef;
+use flox_rust_sdk::models::floxmeta::{CommitIdentity, CommitSigning, SigningFormat};
 use itertools::{Either, Itertools};
 use log::{debug, trace};
 use once_cell::sync::OnceCell;
@@ -17,6 +18,7 @@ use toml_edit::{DocumentMut, Item, Key, Table, TableLike};
 use url::Url;
 use xdg::BaseDirectories;
 
+use self::credentials::CredentialStoreKind;
 use self::features::Features;
 
 /// Name of flox managed directories (config, data, cache)
@@ -45,6 +47,9 @@ pub struct FloxConfig {
     /// Disable collecting and sending usage metrics
     #[serde(default)]
     pub disable_metrics: bool,
+    /// Send usage metrics to this endpoint instead of Flox,
+    /// an OTLP/HTTP collector or a `file://` URL to only write them locally
+    pub metrics_endpoint: Option<Url>,
     /// Directory where flox should store ephemeral data (default:
     /// `$XDG_CACHE_HOME/flox`)
     pub cache_dir: PathBuf,
@@ -59,25 +64,79 @@ pub struct FloxConfig {
     ///
     /// Note: This does _not_ use [flox_rust_sdk::flox::FloxhubToken] because
     /// parsing the token -- and thus parsing the config -- fails if the token is expired.
-    /// Instead parse as String (or some specialized enum to support keychains in the future)
-    /// and then validate the token as we build the [flox_rust_sdk::flox::Flox] instance.
+    /// Instead parse as String and then validate the token
+    /// as we build the [flox_rust_sdk::flox::Flox] instance.
+    /// Depending on `credential_store`, the token may be kept outside the config,
+    /// see [credentials::CredentialStore].
     pub floxhub_token: Option<String>,
 
+    /// OAuth refresh token used to renew `floxhub_token` before it expires
+    pub floxhub_refresh_token: Option<String>,
+
+    /// Where to store FloxHub credentials (default: `plaintext`)
+    pub credential_store: Option<CredentialStoreKind>,
+
+    /// Command implementing the `git credential` protocol,
+    /// used if `credential_store` is `helper`
+    pub credential_helper: Option<String>,
+
     /// How many items `flox search` should show by default
     pub search_limit: Option<u8>,
 
+    /// Additional "did you mean" suggestions for package names,
+    /// e.g. `node = "nodejs"`, taking precedence over the built-in suggestions
+    #[serde(default)]
+    pub package_aliases: BTreeMap<String, String>,
+
     /// Remote environments that are trusted for activation
     #[serde(default)]
     pub trusted_environments: HashMap<EnvironmentRef, EnvironmentTrust>,
 
+    /// Remote environments that are trusted for activation
+    /// as long as the hash of their hooks, profile scripts and variables matches
+    #[serde(default)]
+    pub trusted_hooks: HashMap<EnvironmentRef, String>,
+
+    /// Owners whose remote environments are trusted (or denied) for activation
+    #[serde(default)]
+    pub trusted_owners: HashMap<String, EnvironmentTrust>,
+
     /// The URL of the FloxHub instance to use
     pub floxhub_url: Option<Url>,
 
     /// Rule whether to change the shell prompt in activated environments
     pub shell_prompt: Option<EnvironmentPromptConfig>,
+
+    /// Name recorded as the author of generations of managed environments
+    /// (default: FloxHub handle)
+    pub git_author_name: Option<String>,
+
+    /// Email recorded as the author of generations of managed environments
+    pub git_author_email: Option<String>,
+
+    /// Sign generations of managed environments with this key,
+    /// a GPG key id or the path to an SSH private key
+    pub git_signing_key: Option<String>,
+
+    /// Format of `git_signing_key` (default: `openpgp`)
+    pub git_signing_format: Option<SigningFormat>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+impl FloxConfig {
+    /// The identity used to author and sign generations of managed environments
+    pub fn commit_identity(&self) -> CommitIdentity {
+        CommitIdentity {
+            name: self.git_author_name.clone(),
+            email: self.git_author_email.clone(),
+            signing: self.git_signing_key.clone().map(|key| CommitSigning {
+                key,
+                format: self.git_signing_format.unwrap_or_default(),
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum EnvironmentTrust {
     Trust,
@@ -103,6 +162,7 @@ pub struct NixConfig {
     pub access_tokens: HashMap<String, String>,
 }
 
+pub mod credentials;
 pub mod features;
 
 /// Error returned by [`Config::get()`]
@@ -15,6 +15,7 @@ use utils::{message, populate_default_nix_env_vars};
 
 use crate::utils::errors::{format_error, format_managed_error, format_remote_error};
 use crate::utils::metrics::Hub;
+use crate::utils::otlp::TraceExportGuard;
 
 mod build;
 mod commands;
@@ -74,6 +75,7 @@ fn main() -> ExitCode {
     init_logger(Some(verbosity));
 
     let _metrics_guard = Hub::global().try_guard().ok();
+    let _trace_guard = TraceExportGuard;
 
     // Pass down the verbosity level to all pkgdb calls
     std::env::set_var(
@@ -101,7 +103,8 @@ fn main() -> ExitCode {
                 return ExitCode::from(0);
             },
             bpaf::ParseFailure::Stderr(m) => {
-                message::error(format!("{m:80}"));
+                let error = commands::suggest_subcommand(env::args().skip(1), format!("{m:80}"));
+                message::error(commands::explain_changed_flag(env::args().skip(1), error));
                 return ExitCode::from(1);
             },
             bpaf::ParseFailure::Completion(c) => {
@@ -117,49 +120,50 @@ fn main() -> ExitCode {
     let runtime = tokio::runtime::Runtime::new().unwrap();
 
     // Run flox. Print errors and exit with status 1 on failure
-    let exit_code = match runtime.block_on(run(args)) {
+    match runtime.block_on(run(args)) {
         Ok(()) => ExitCode::from(0),
+        Err(e) => report_error(&e),
+    }
+}
 
-        Err(e) => {
-            // todo: figure out how to deal with context, properly
-            debug!("{:#}", e);
-
-            // Do not print any error if caused by wrapped flox (sh)
-            if e.is::<FloxShellErrorCode>() {
-                return e.downcast_ref::<FloxShellErrorCode>().unwrap().0;
-            }
+/// Print an error returned by a command and return the exit code for it
+pub(crate) fn report_error(e: &anyhow::Error) -> ExitCode {
+    // todo: figure out how to deal with context, properly
+    debug!("{:#}", e);
 
-            if let Some(e) = e.downcast_ref::<EnvironmentError>() {
-                message::error(format_error(e));
-                return ExitCode::from(1);
-            }
+    // Do not print any error if caused by wrapped flox (sh)
+    if e.is::<FloxShellErrorCode>() {
+        return e.downcast_ref::<FloxShellErrorCode>().unwrap().0;
+    }
 
-            if let Some(e) = e.downcast_ref::<ManagedEnvironmentError>() {
-                message::error(format_managed_error(e));
-                return ExitCode::from(1);
-            }
+    if let Some(e) = e.downcast_ref::<EnvironmentError>() {
+        message::error(format_error(e));
+        return ExitCode::from(1);
+    }
 
-            if let Some(e) = e.downcast_ref::<RemoteEnvironmentError>() {
-                message::error(format_remote_error(e));
-                return ExitCode::from(1);
-            }
+    if let Some(e) = e.downcast_ref::<ManagedEnvironmentError>() {
+        message::error(format_managed_error(e));
+        return ExitCode::from(1);
+    }
 
-            let err_str = e
-                .chain()
-                .skip(1)
-                .fold(e.to_string(), |acc, cause| format!("{}: {}", acc, cause));
+    if let Some(e) = e.downcast_ref::<RemoteEnvironmentError>() {
+        message::error(format_remote_error(e));
+        return ExitCode::from(1);
+    }
 
-            message::error(err_str);
+    let err_str = e
+        .chain()
+        .skip(1)
+        .fold(e.to_string(), |acc, cause| format!("{}: {}", acc, cause));
 
-            ExitCode::from(1)
-        },
-    };
+    message::error(err_str);
 
-    exit_code
+    ExitCode::from(1)
 }
 
+/// Exit with the wrapped code without printing an error
 #[derive(Debug)]
-struct FloxShellErrorCode(ExitCode);
+pub(crate) struct FloxShellErrorCode(pub ExitCode);
 impl Display for FloxShellErrorCode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         <Self as Debug>::fmt(self, f)
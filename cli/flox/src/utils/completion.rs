@@ -49,7 +49,7 @@ impl FloxCompletionExt for Flox {
             .expect("User must have a home directory")
             .join(".netrc");
 
-        let catalog_client = init_catalog_client(&config);
+        let catalog_client = init_catalog_client(&config, None);
 
         Ok(Flox {
             cache_dir: config.flox.cache_dir,
@@ -61,6 +61,7 @@ impl FloxCompletionExt for Flox {
             access_tokens,
             uuid: uuid::Uuid::nil(),
             floxhub_token: None,
+            commit_identity: Default::default(),
             floxhub: Floxhub::new(DEFAULT_FLOXHUB_URL.clone(), None)?,
             catalog_client,
         })
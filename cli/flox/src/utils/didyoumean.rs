@@ -1,11 +1,16 @@
+use std::collections::BTreeMap;
 use std::fmt::Display;
 
 use anyhow::Result;
 use flox_rust_sdk::flox::Flox;
 use flox_rust_sdk::models::environment::{global_manifest_path, Environment};
 use flox_rust_sdk::models::lockfile::LockedManifest;
-use flox_rust_sdk::models::search::{do_search, PathOrJson, SearchResults};
+use flox_rust_sdk::models::manifest::ManifestPackageDescriptor;
+use flox_rust_sdk::models::search::{do_search, PathOrJson, SearchResult, SearchResults};
 use log::debug;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use toml_edit::{DocumentMut, Item, TableLike};
 
 use super::search::{DisplayItems, DisplaySearchResults};
 use crate::utils::dialog::{Dialog, Spinner};
@@ -13,46 +18,62 @@ use crate::utils::search::construct_search_params;
 
 pub const SUGGESTION_SEARCH_LIMIT: u8 = 3;
 
+/// How many search results to rank by edit distance
+/// before picking the [SUGGESTION_SEARCH_LIMIT] closest ones
+const SUGGESTION_CANDIDATE_LIMIT: u8 = 12;
+
+/// Maximum edit distance for a fuzzy match to be suggested
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// Curated suggestions shipped with flox, see `didyoumean.toml`
+#[derive(Debug, Deserialize)]
+struct CuratedSuggestions {
+    /// Map from commonly used names to the package that provides them
+    packages: BTreeMap<String, String>,
+    /// Map from commonly used command names to flox subcommands
+    commands: BTreeMap<String, String>,
+}
+
+static CURATED_SUGGESTIONS: Lazy<CuratedSuggestions> = Lazy::new(|| {
+    toml_edit::de::from_str(include_str!("didyoumean.toml"))
+        .expect("curated suggestions should be valid")
+});
+
 /// Dynamically generate a "did you mean" message for a given search term.
 /// Will look up curated suggested search terms and query related search results.
 ///
+/// Curated terms are looked up in the user's `package_aliases`
+/// before the suggestions shipped with flox.
+///
 /// [DidYouMean] is parameterized by a type `S`,
 /// which is used to distinguish input types for the suggestion
 /// and specific suggestion output.
-pub struct DidYouMean<'a, S> {
-    searched_term: &'a str,
-    curated: Option<&'static str>,
+pub struct DidYouMean<S> {
+    searched_term: String,
+    curated: Option<String>,
     search_results: SearchResults,
     _suggestion: S,
 }
 
 pub struct InstallSuggestion;
 
-impl<S> DidYouMean<'_, S> {
+impl<S> DidYouMean<S> {
     pub fn has_suggestions(&self) -> bool {
         self.curated.is_some() || !self.search_results.results.is_empty()
     }
-}
 
-/// Suggestions for `install` subcommand
-impl<'a> DidYouMean<'a, InstallSuggestion> {
-    /// `install` specific curated terms
-    fn suggest_curated_package(input: &str) -> Option<&'static str> {
-        let suggestion = match input {
-            "java" => "jdk",
-            "node" => "nodejs",
-            "npm" => "nodejs",
-            "rust" => "cargo",
-            "sed" => "gnused",
-            "make" => "gnumake",
-            "awk" => "gawk",
-            "diff" => "diffutils",
-            "grep" => "gnugrep",
-            _ => return None,
-        };
-        Some(suggestion)
+    /// Look up a curated package for `input`,
+    /// preferring the user's `aliases` over the shipped suggestions.
+    fn suggest_curated_package(input: &str, aliases: &BTreeMap<String, String>) -> Option<String> {
+        aliases
+            .get(input)
+            .or_else(|| CURATED_SUGGESTIONS.packages.get(input))
+            .cloned()
     }
+}
 
+/// Suggestions for `install` subcommand
+impl DidYouMean<InstallSuggestion> {
     fn suggest_searched_packages(
         flox: &Flox,
         environment: &dyn Environment,
@@ -69,19 +90,22 @@ impl<'a> DidYouMean<'a, InstallSuggestion> {
 
         let search_params = construct_search_params(
             term,
-            Some(SUGGESTION_SEARCH_LIMIT),
+            Some(SUGGESTION_CANDIDATE_LIMIT),
             Some(environment.manifest_path(flox)?.try_into()?),
             global_manifest_path(flox).try_into()?,
             lockfile,
         )?;
 
-        let (results, _) = Dialog {
+        let (mut results, _) = Dialog {
             message: &format!("Could not find package for {term}. Looking for suggestions..."),
             help_message: None,
             typed: Spinner::new(|| do_search(&search_params)),
         }
         .spin()?;
 
+        rank_by_edit_distance(term, &mut results.results);
+        results.results.truncate(SUGGESTION_SEARCH_LIMIT.into());
+
         Ok(results)
     }
 
@@ -90,20 +114,25 @@ impl<'a> DidYouMean<'a, InstallSuggestion> {
     ///
     /// This will attempt to find curated suggestions for the given term,
     /// based on the lockfile of the given environment.
-    pub fn new(flox: &Flox, environment: &dyn Environment, term: &'a str) -> Self {
-        let curated = Self::suggest_curated_package(term);
-        let searched_term = curated.unwrap_or(term);
-        let search_results = match Self::suggest_searched_packages(flox, environment, searched_term)
-        {
-            Ok(results) => results,
-            Err(err) => {
-                debug!("failed to search for suggestions: {}", err);
-                SearchResults {
-                    results: Default::default(),
-                    count: Some(0),
-                }
-            },
-        };
+    pub fn new(
+        flox: &Flox,
+        environment: &dyn Environment,
+        term: &str,
+        aliases: &BTreeMap<String, String>,
+    ) -> Self {
+        let curated = Self::suggest_curated_package(term, aliases);
+        let searched_term = curated.clone().unwrap_or(term.to_string());
+        let search_results =
+            match Self::suggest_searched_packages(flox, environment, &searched_term) {
+                Ok(results) => results,
+                Err(err) => {
+                    debug!("failed to search for suggestions: {}", err);
+                    SearchResults {
+                        results: Default::default(),
+                        count: Some(0),
+                    }
+                },
+            };
         Self {
             searched_term,
             curated,
@@ -113,9 +142,9 @@ impl<'a> DidYouMean<'a, InstallSuggestion> {
     }
 }
 
-impl Display for DidYouMean<'_, InstallSuggestion> {
+impl Display for DidYouMean<InstallSuggestion> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let Some(curated) = self.curated {
+        if let Some(curated) = &self.curated {
             writeln!(
                 f,
                 "Try 'flox install {curated}' instead.",
@@ -154,21 +183,7 @@ impl Display for DidYouMean<'_, InstallSuggestion> {
 pub struct SearchSuggestion;
 
 /// Suggestions for `search` subcommand
-impl<'a> DidYouMean<'a, SearchSuggestion> {
-    /// `search` specific curated terms
-    fn suggest_curated_package(input: &str) -> Option<&'static str> {
-        let suggestion = match input {
-            "node" => "nodejs",
-            "java" => "jdk",
-            "npm" => "nodejs",
-            "rust" => "cargo",
-            "diff" => "diffutils",
-            "make" => "gnumake",
-            _ => return None,
-        };
-        Some(suggestion)
-    }
-
+impl DidYouMean<SearchSuggestion> {
     fn suggest_searched_packages(
         term: &str,
         manifest: Option<PathOrJson>,
@@ -177,19 +192,22 @@ impl<'a> DidYouMean<'a, SearchSuggestion> {
     ) -> Result<SearchResults> {
         let search_params = construct_search_params(
             term,
-            Some(SUGGESTION_SEARCH_LIMIT),
+            Some(SUGGESTION_CANDIDATE_LIMIT),
             manifest,
             global_manifest,
             lockfile,
         )?;
 
-        let (results, _) = Dialog {
+        let (mut results, _) = Dialog {
             message: "Looking for alternative suggestions...",
             help_message: None,
             typed: Spinner::new(|| do_search(&search_params)),
         }
         .spin()?;
 
+        rank_by_edit_distance(term, &mut results.results);
+        results.results.truncate(SUGGESTION_SEARCH_LIMIT.into());
+
         Ok(results)
     }
 
@@ -204,19 +222,20 @@ impl<'a> DidYouMean<'a, SearchSuggestion> {
     /// but still needs to be able to suggest search results
     /// based on an existing (global) manifest/lockfile.
     pub fn new(
-        term: &'a str,
+        term: &str,
         manifest: Option<PathOrJson>,
         global_manifest: PathOrJson,
         lockfile: PathOrJson,
+        aliases: &BTreeMap<String, String>,
     ) -> Self {
-        let curated = Self::suggest_curated_package(term);
+        let curated = Self::suggest_curated_package(term, aliases);
 
         let default_results = SearchResults {
             results: Default::default(),
             count: Some(0),
         };
 
-        let search_results = if let Some(curated) = curated {
+        let search_results = if let Some(curated) = &curated {
             match Self::suggest_searched_packages(curated, manifest, global_manifest, lockfile) {
                 Ok(results) => results,
                 Err(err) => {
@@ -229,7 +248,7 @@ impl<'a> DidYouMean<'a, SearchSuggestion> {
         };
 
         Self {
-            searched_term: term,
+            searched_term: term.to_string(),
             curated,
             search_results,
             _suggestion: SearchSuggestion,
@@ -237,9 +256,9 @@ impl<'a> DidYouMean<'a, SearchSuggestion> {
     }
 }
 
-impl Display for DidYouMean<'_, SearchSuggestion> {
+impl Display for DidYouMean<SearchSuggestion> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let Some(curated) = self.curated else {
+        let Some(curated) = &self.curated else {
             debug!("no curated suggestions");
             return Ok(());
         };
@@ -259,3 +278,257 @@ impl Display for DidYouMean<'_, SearchSuggestion> {
         Ok(())
     }
 }
+
+/// Sort search results by the edit distance between `term`
+/// and the pname (or attribute name) of each result.
+///
+/// The sort is stable, so results at the same distance keep the order
+/// they were returned in by the search.
+fn rank_by_edit_distance(term: &str, results: &mut [SearchResult]) {
+    results.sort_by_cached_key(|result| {
+        let name = result
+            .pname
+            .as_deref()
+            .or(result.rel_path.last().map(String::as_str))
+            .unwrap_or_default();
+        strsim::damerau_levenshtein(term, name)
+    });
+}
+
+/// Find the candidate closest to `input`,
+/// if it is within [MAX_SUGGESTION_DISTANCE] edits
+/// and shares more than it differs from `input`.
+fn closest_match<'c>(
+    input: &str,
+    candidates: impl IntoIterator<Item = &'c str>,
+) -> Option<&'c str> {
+    candidates
+        .into_iter()
+        .map(|candidate| (strsim::damerau_levenshtein(input, candidate), candidate))
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE && *distance < input.len())
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Suggest one of `commands` for a mistyped or unknown subcommand.
+///
+/// Curated commands take precedence over fuzzy matches,
+/// so that e.g. `shell` suggests `activate` rather than `show`.
+pub fn suggest_command<'c>(input: &str, commands: &[&'c str]) -> Option<&'c str> {
+    if let Some(curated) = CURATED_SUGGESTIONS.commands.get(input) {
+        if let Some(command) = commands.iter().find(|command| *command == curated) {
+            return Some(command);
+        }
+    }
+    closest_match(input, commands.iter().copied())
+}
+
+/// Keys that flox knows for a table in the manifest,
+/// identified by the path of the table
+fn known_manifest_keys(table_path: &[&str]) -> Option<Vec<&'static str>> {
+    let keys: &[&str] = match table_path {
+        [] => &[
+            "version", "install", "vars", "hook", "profile", "options", "registry",
+        ],
+        ["install", _] => {
+            // keys handled by pkgdb that the descriptor doesn't model
+            let unmodeled = ["abs-path", "name", "semver", "pkg-group", "input"];
+            let mut keys = ManifestPackageDescriptor::keys().to_vec();
+            keys.extend(unmodeled);
+            return Some(keys);
+        },
+        ["hook"] => &["on-activate", "script"],
+        ["profile"] => &["common", "bash", "zsh", "fish", "tcsh"],
+        ["options"] => &["systems", "allow", "semver"],
+        ["options", "allow"] => &["unfree", "broken", "licenses"],
+        ["options", "semver"] => &["prefer-pre-releases"],
+        _ => return None,
+    };
+    Some(keys.to_vec())
+}
+
+/// An unknown key in a manifest and the known key it likely meant
+#[derive(Debug, PartialEq)]
+pub struct ManifestKeySuggestion {
+    pub key: String,
+    pub suggestion: String,
+}
+
+impl Display for ManifestKeySuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Unknown manifest key '{}', did you mean '{}'?",
+            self.key, self.suggestion
+        )
+    }
+}
+
+/// Find keys in the manifest that are unknown to flox
+/// but close to a known key, e.g. `[instal]` or `options.alow`.
+///
+/// Invalid TOML yields no suggestions,
+/// as the parse error is reported elsewhere.
+pub fn suggest_manifest_keys(contents: &str) -> Vec<ManifestKeySuggestion> {
+    fn visit(
+        table: &dyn TableLike,
+        path: &mut Vec<String>,
+        suggestions: &mut Vec<ManifestKeySuggestion>,
+    ) {
+        let path_refs = path.iter().map(String::as_str).collect::<Vec<_>>();
+        let Some(known_keys) = known_manifest_keys(&path_refs) else {
+            // descend into the tables of [install] which are keyed by install ID
+            if path_refs == ["install"] {
+                for (key, item) in table.iter() {
+                    if let Some(descriptor) = item.as_table_like() {
+                        path.push(key.to_string());
+                        visit(descriptor, path, suggestions);
+                        path.pop();
+                    }
+                }
+            }
+            return;
+        };
+
+        for (key, item) in table.iter() {
+            if known_keys.contains(&key) {
+                if let Some(child) = item.as_table_like() {
+                    path.push(key.to_string());
+                    visit(child, path, suggestions);
+                    path.pop();
+                }
+                continue;
+            }
+
+            if let Some(suggestion) = closest_match(key, known_keys.iter().copied()) {
+                let prefix = path.iter().map(|p| format!("{p}.")).collect::<String>();
+                suggestions.push(ManifestKeySuggestion {
+                    key: format!("{prefix}{key}"),
+                    suggestion: format!("{prefix}{suggestion}"),
+                });
+            }
+        }
+    }
+
+    let Ok(document) = contents.parse::<DocumentMut>() else {
+        return vec![];
+    };
+
+    let mut suggestions = vec![];
+    if let Item::Table(root) = document.as_item() {
+        visit(root, &mut vec![], &mut suggestions);
+    }
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use flox_rust_sdk::models::search::Subtree;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn curated_suggestions_are_valid() {
+        assert_eq!(CURATED_SUGGESTIONS.packages.get("node").unwrap(), "nodejs");
+        assert_eq!(CURATED_SUGGESTIONS.commands.get("add").unwrap(), "install");
+    }
+
+    #[test]
+    fn user_aliases_take_precedence() {
+        let aliases = BTreeMap::from([
+            ("node".to_string(), "nodejs_20".to_string()),
+            ("k8s".to_string(), "kubectl".to_string()),
+        ]);
+
+        let suggest = DidYouMean::<InstallSuggestion>::suggest_curated_package;
+        assert_eq!(suggest("node", &aliases), Some("nodejs_20".to_string()));
+        assert_eq!(suggest("k8s", &aliases), Some("kubectl".to_string()));
+        assert_eq!(suggest("sed", &aliases), Some("gnused".to_string()));
+        assert_eq!(suggest("ripgrep", &aliases), None);
+    }
+
+    #[test]
+    fn ranks_search_results_by_edit_distance() {
+        let result = |pname: &str| SearchResult {
+            input: "nixpkgs".to_string(),
+            abs_path: vec![],
+            subtree: Subtree::LegacyPackages,
+            system: "x86_64-linux".to_string(),
+            rel_path: vec![pname.to_string()],
+            pname: Some(pname.to_string()),
+            version: None,
+            description: None,
+            broken: None,
+            unfree: None,
+            license: None,
+            id: 0,
+        };
+        let mut results = vec![result("python3-full"), result("pythn"), result("python")];
+        rank_by_edit_distance("pyhton", &mut results);
+        let ranked = results
+            .iter()
+            .map(|r| r.pname.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(ranked, vec!["python", "pythn", "python3-full"]);
+    }
+
+    #[test]
+    fn suggests_commands() {
+        let commands = ["init", "activate", "install", "show", "upgrade", "list"];
+        assert_eq!(suggest_command("upgarde", &commands), Some("upgrade"));
+        assert_eq!(suggest_command("instal", &commands), Some("install"));
+        assert_eq!(suggest_command("shell", &commands), Some("activate"));
+        assert_eq!(suggest_command("ls", &commands), Some("list"));
+        assert_eq!(suggest_command("frobnicate", &commands), None);
+    }
+
+    #[test]
+    fn suggests_manifest_keys() {
+        let manifest = indoc! {r#"
+            version = 1
+
+            [instal]
+            hello.pkg-path = "hello"
+
+            [install]
+            ripgrep.pkgpath = "ripgrep"
+            curl.pkg-path = "curl"
+            curl.held = true
+            gcc.pkg-path = "gcc"
+            gcc.hled = true
+
+            [vars]
+            INSTAL = "not a key"
+
+            [options]
+            systems = ["x86_64-linux"]
+            alow.unfree = true
+            semver.prefer-prereleases = true
+        "#};
+
+        assert_eq!(suggest_manifest_keys(manifest), vec![
+            ManifestKeySuggestion {
+                key: "instal".to_string(),
+                suggestion: "install".to_string(),
+            },
+            ManifestKeySuggestion {
+                key: "install.ripgrep.pkgpath".to_string(),
+                suggestion: "install.ripgrep.pkg-path".to_string(),
+            },
+            ManifestKeySuggestion {
+                key: "install.gcc.hled".to_string(),
+                suggestion: "install.gcc.held".to_string(),
+            },
+            ManifestKeySuggestion {
+                key: "options.alow".to_string(),
+                suggestion: "options.allow".to_string(),
+            },
+            ManifestKeySuggestion {
+                key: "options.semver.prefer-prereleases".to_string(),
+                suggestion: "options.semver.prefer-pre-releases".to_string(),
+            },
+        ]);
+    }
+}
@@ -10,8 +10,10 @@ use flox_rust_sdk::models::environment::{
     ENVIRONMENT_POINTER_FILENAME,
 };
 use flox_rust_sdk::models::lockfile::LockedManifestError;
+use flox_rust_sdk::models::manifest::{ManifestMergeError, MergeConflict};
 use flox_rust_sdk::models::pkgdb::{error_codes, CallPkgDbError, ContextMsgError, PkgDbError};
 use indoc::formatdoc;
+use itertools::Itertools;
 use log::{debug, trace};
 
 /// Convert to an error variant that directs the user to the docs if the provided error is
@@ -402,6 +404,9 @@ pub fn format_managed_error(err: &ManagedEnvironmentError) -> String {
             This can happen if the environment is modified and pushed from another machine.
 
             To resolve this issue, either
+             * run 'flox pull --merge'
+               to merge the upstream changes into your local changes,
+               then publish the merged environment with 'flox push'.
              * run 'flox pull --force'
                to discard local changes
                and reset the environment to the latest upstream version.
@@ -466,6 +471,29 @@ pub fn format_managed_error(err: &ManagedEnvironmentError) -> String {
             format_core_error(core_environment_error)
         },
         ManagedEnvironmentError::Registry(_) => display_chain(err),
+        ManagedEnvironmentError::Merge(ManifestMergeError::Conflicts(conflicts)) => formatdoc! {"
+            Could not merge upstream changes, both sides changed:
+
+            {conflicts}
+
+            Run 'flox pull --merge' in an interactive terminal to pick a side for each change,
+            or run 'flox pull --force' to discard local changes.
+        ", conflicts = conflicts.iter().map(format_merge_conflict).join("\n")},
+        ManagedEnvironmentError::Merge(_) => display_chain(err),
+    }
+}
+
+/// Format a conflict of a manifest merge for display,
+/// showing the local and upstream change to the conflicting key
+pub fn format_merge_conflict(conflict: &MergeConflict) -> String {
+    let value = |value: &Option<String>| value.clone().unwrap_or_else(|| "(removed)".to_string());
+    formatdoc! {"
+        {key}
+          local:    {ours}
+          upstream: {theirs}",
+        key = conflict.key(),
+        ours = indent::indent_by(12, value(&conflict.ours)),
+        theirs = indent::indent_by(12, value(&conflict.theirs)),
     }
 }
 
@@ -668,9 +696,6 @@ pub fn format_locked_manifest_error(err: &LockedManifestError) -> String {
         LockedManifestError::UpdateFailed(pkgdb_error) => {
             format_pkgdb_error(pkgdb_error, err, "Failed to update environment.")
         },
-        LockedManifestError::CheckLockfile(pkgdb_error) => {
-            format_pkgdb_error(pkgdb_error, err, "Failed to check environment.")
-        },
         // endregion
 
         // this is a bug, but likely needs some formatting
@@ -687,6 +712,10 @@ pub fn format_locked_manifest_error(err: &LockedManifestError) -> String {
             This is likely due to a corrupt environment.
         "},
 
+        LockedManifestError::ParseManifest(toml_error) => formatdoc! {"
+            Failed to parse manifest: {toml_error}
+        "},
+
         LockedManifestError::SerializeGlobalLockfile(_) => display_chain(err),
 
         // todo: add global-manifest.lock(1) manual entry and reference it here
@@ -695,8 +724,6 @@ pub fn format_locked_manifest_error(err: &LockedManifestError) -> String {
 
             Please ensure that you have write permissions to '~/.config/flox/global-manifest.lock'.
         "},
-
-        LockedManifestError::ParseCheckWarnings(_) => display_chain(err),
     }
 }
 
@@ -1,3 +1,4 @@
+use flox_rust_sdk::flox::FloxhubToken;
 use flox_rust_sdk::providers::catalog::{CatalogClient, Client, MockClient};
 use tracing::debug;
 
@@ -7,8 +8,12 @@ use crate::config::Config;
 ///
 /// - Return [None] if the Catalog API is disabled through the feature flag
 /// - Initialize a mock client if the `_FLOX_USE_CATALOG_MOCK` environment variable is set to `true`
-/// - Initialize a real client otherwise
-pub fn init_catalog_client(config: &Config) -> Option<Client> {
+/// - Initialize a real client otherwise,
+///   authenticated with the FloxHub token if one is available
+pub fn init_catalog_client(
+    config: &Config,
+    floxhub_token: Option<&FloxhubToken>,
+) -> Option<Client> {
     // Do not initialize a client if the Catalog API is disabled
     if !config.features.clone().unwrap_or_default().use_catalog {
         debug!("catalog feature is disabled, skipping client initialization");
@@ -23,6 +28,10 @@ pub fn init_catalog_client(config: &Config) -> Option<Client> {
         Some(MockClient.into())
     } else {
         debug!("Using catalog client");
-        Some(Client::Catalog(CatalogClient::default()))
+        let client = match floxhub_token {
+            Some(token) => CatalogClient::with_floxhub_token(token),
+            None => CatalogClient::default(),
+        };
+        Some(Client::Catalog(client))
     }
 }
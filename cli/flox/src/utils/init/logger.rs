@@ -7,6 +7,8 @@ use tracing_subscriber::{EnvFilter, Registry};
 
 use crate::commands::Verbosity;
 use crate::utils::metrics::MetricsLayer;
+use crate::utils::otlp::OtlpTraceLayer;
+use crate::utils::profile::ProfileLayer;
 use crate::utils::TERMINAL_STDERR;
 
 struct LockingTerminalStderr;
@@ -102,6 +104,8 @@ pub fn create_registry_and_filter_reload_handle() -> (
         .with_filter(filter);
     let metrics_layer = MetricsLayer::new();
     let sentry_layer = sentry::integrations::tracing::layer();
+    let otlp_layer = OtlpTraceLayer::from_env();
+    let profile_layer = ProfileLayer::new();
     // Filtered layer must come first.
     // This appears to be the only way to avoid logs of the `flox_command` trace
     // which is processed by the `log_layer` irrepective of the filter applied to it.
@@ -110,7 +114,9 @@ pub fn create_registry_and_filter_reload_handle() -> (
     let registry = tracing_subscriber::registry()
         .with(log_layer)
         .with(metrics_layer)
-        .with(sentry_layer);
+        .with(sentry_layer)
+        .with(otlp_layer)
+        .with(profile_layer);
 
     (registry, filter_reload_handle)
 }
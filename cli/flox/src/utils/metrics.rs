@@ -7,7 +7,7 @@ use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Duration as TimeoutDuration;
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use flox_rust_sdk::flox::FLOX_VERSION;
 use fslock::LockFile;
 use indoc::indoc;
@@ -17,6 +17,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use time::format_description::well_known::Iso8601;
 use time::{Duration, OffsetDateTime};
+use url::Url;
 use uuid::Uuid;
 
 use super::TRAILING_NETWORK_CALL_TIMEOUT;
@@ -149,6 +150,21 @@ impl MetricEntry {
             empty_flags: vec![],
         }
     }
+
+    /// The subcommand the entry was recorded for
+    pub fn subcommand(&self) -> Option<&str> {
+        self.subcommand.as_deref()
+    }
+
+    /// Additional data recorded with the subcommand
+    pub fn extras(&self) -> &HashMap<String, String> {
+        &self.extras
+    }
+
+    /// When the entry was recorded
+    pub fn timestamp(&self) -> OffsetDateTime {
+        self.timestamp
+    }
 }
 
 /// A representation of the metrics buffer
@@ -259,6 +275,12 @@ impl MetricsBuffer {
     }
 }
 
+/// Read the entries that are currently buffered and not yet sent
+pub fn read_buffered_metrics(cache_dir: &Path) -> Result<Vec<MetricEntry>> {
+    let buffer = MetricsBuffer::read(cache_dir)?;
+    Ok(buffer.iter().cloned().collect())
+}
+
 pub fn read_metrics_uuid(config: &Config) -> Result<Uuid> {
     let data_dir = &config.flox.data_dir;
     let uuid_path = data_dir.join(METRICS_UUID_FILE_NAME);
@@ -491,6 +513,169 @@ impl Default for AWSDatalakeConnection {
     }
 }
 
+/// Connection to an OpenTelemetry collector
+///
+/// Sends metrics as OTLP/HTTP JSON log records,
+/// e.g. to `http://localhost:4318/v1/logs`.
+#[derive(Debug)]
+pub struct OtlpHttpConnection {
+    pub timeout: TimeoutDuration,
+    pub endpoint_url: Url,
+}
+
+impl OtlpHttpConnection {
+    /// Convert entries to an OTLP `ExportLogsServiceRequest`
+    fn payload(entries: &[&MetricEntry]) -> Result<serde_json::Value> {
+        fn attribute(key: &str, value: impl Into<Option<String>>) -> Option<serde_json::Value> {
+            Some(json!({ "key": key, "value": { "stringValue": value.into()? } }))
+        }
+
+        let records = entries
+            .iter()
+            .map(|entry| {
+                let timestamp = entry.timestamp.unix_timestamp_nanos().to_string();
+                let attributes =
+                    [
+                        attribute("subcommand", entry.subcommand.clone()),
+                        attribute("flox.uuid", entry.uuid.to_string()),
+                        attribute("os.type", entry.os_family.clone()),
+                        attribute("os.version", entry.os_family_release.clone()),
+                        attribute("os.name", entry.os.clone()),
+                        attribute("os.release", entry.os_version.clone()),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .chain(entry.extras.iter().filter_map(|(key, value)| {
+                        attribute(&format!("extras.{key}"), value.clone())
+                    }))
+                    .collect::<Vec<_>>();
+
+                Ok(json!({
+                    "timeUnixNano": timestamp,
+                    "observedTimeUnixNano": timestamp,
+                    "severityText": "INFO",
+                    "body": { "stringValue": "cli-invocation" },
+                    "attributes": attributes,
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(json!({
+            "resourceLogs": [{
+                "resource": {
+                    "attributes": [
+                        attribute("service.name", "flox-cli".to_string()),
+                        attribute("service.version", FLOX_VERSION.to_string()),
+                    ],
+                },
+                "scopeLogs": [{
+                    "scope": { "name": "flox.metrics" },
+                    "logRecords": records,
+                }],
+            }],
+        }))
+    }
+}
+
+impl Connection for OtlpHttpConnection {
+    fn send(&mut self, entries: Vec<&MetricEntry>) -> Result<()> {
+        let payload = Self::payload(&entries)?;
+
+        debug!("Sending metrics to {}", &self.endpoint_url);
+        debug!("Metrics: {payload:#}");
+
+        reqwest::blocking::Client::new()
+            .post(self.endpoint_url.clone())
+            .header("user-agent", format!("flox-cli/{}", &*FLOX_VERSION))
+            .json(&payload)
+            .timeout(self.timeout)
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// A connection that only appends metrics to a local file, one JSON entry per line
+///
+/// Allows inspecting and shipping usage data with other tools
+/// without sending anything over the network.
+#[derive(Debug)]
+pub struct FileConnection {
+    pub path: PathBuf,
+}
+
+impl Connection for FileConnection {
+    fn send(&mut self, entries: Vec<&MetricEntry>) -> Result<()> {
+        debug!("Writing metrics to {}", self.path.display());
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("could not open metrics file {}", self.path.display()))?;
+
+        let mut lines = String::new();
+        for entry in entries {
+            lines.push_str(&serde_json::to_string(entry)?);
+            lines.push('\n');
+        }
+        file.write_all(lines.as_bytes())?;
+        Ok(())
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// The telemetry backend selected by the `metrics_endpoint` config key
+///
+/// * no endpoint: the Flox telemetry backend
+/// * `file://` URLs: [FileConnection]
+/// * other URLs: [OtlpHttpConnection]
+pub fn connection_from_config(config: &Config) -> Result<Box<dyn Connection>> {
+    let Some(endpoint) = &config.flox.metrics_endpoint else {
+        return Ok(AWSDatalakeConnection::default().boxed());
+    };
+
+    let connection = match endpoint.scheme() {
+        "file" => FileConnection {
+            path: endpoint
+                .to_file_path()
+                .map_err(|_| anyhow!("invalid metrics file path: {endpoint}"))?,
+        }
+        .boxed(),
+        "http" | "https" => OtlpHttpConnection {
+            timeout: TRAILING_NETWORK_CALL_TIMEOUT,
+            endpoint_url: endpoint.clone(),
+        }
+        .boxed(),
+        scheme => bail!(
+            "unsupported metrics endpoint scheme '{scheme}', expected 'file', 'http' or 'https'"
+        ),
+    };
+    Ok(connection)
+}
+
+/// A human readable description of where metrics are sent
+pub fn describe_metrics_endpoint(config: &Config) -> String {
+    match &config.flox.metrics_endpoint {
+        None => "Flox".to_string(),
+        Some(endpoint) if endpoint.scheme() == "file" => endpoint
+            .to_file_path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|_| endpoint.to_string()),
+        Some(endpoint) => endpoint.to_string(),
+    }
+}
+
 #[derive(Debug)]
 pub struct Client {
     pub uuid: Uuid,
@@ -501,14 +686,14 @@ pub struct Client {
 
 impl Client {
     /// Create a new client with defaults read from the config
-    pub fn new_with_config(config: &Config, connection: impl Connection + 'static) -> Result<Self> {
+    pub fn new_with_config(config: &Config, connection: Box<dyn Connection>) -> Result<Self> {
         let uuid = read_metrics_uuid(config)?;
         let metrics_dir = config.flox.cache_dir.clone();
         Ok(Client {
             uuid,
             metrics_dir,
             max_age: DEFAULT_BUFFER_EXPIRY,
-            connection: connection.boxed(),
+            connection,
         })
     }
 
@@ -736,7 +921,7 @@ mod tests {
             ..Default::default()
         };
 
-        let client = Client::new_with_config(&config, TestConnection::default()).unwrap();
+        let client = Client::new_with_config(&config, TestConnection::default().boxed()).unwrap();
 
         assert_eq!(client.uuid, uuid);
         assert_eq!(client.metrics_dir, cache_dir);
@@ -797,6 +982,63 @@ mod tests {
         assert_eq!(entry_bar.subcommand, event_bar.subcommand);
     }
 
+    /// Test that [FileConnection] appends entries as JSON lines
+    #[test]
+    fn test_file_connection() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("metrics").join("events.json");
+        let mut connection = FileConnection { path: path.clone() };
+
+        let entry_foo = make_entry("foo");
+        let entry_bar = make_entry("bar");
+        connection.send(vec![&entry_foo]).unwrap();
+        connection.send(vec![&entry_bar]).unwrap();
+
+        let written = fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<MetricEntry>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(written, vec![entry_foo, entry_bar]);
+    }
+
+    /// Test that [OtlpHttpConnection] produces one log record per entry
+    #[test]
+    fn test_otlp_payload() {
+        let mut entry = make_entry("foo");
+        entry.extras.insert("key".to_string(), "value".to_string());
+
+        let payload = OtlpHttpConnection::payload(&[&entry]).unwrap();
+        let records = &payload["resourceLogs"][0]["scopeLogs"][0]["logRecords"];
+        assert_eq!(records.as_array().unwrap().len(), 1);
+
+        let attributes = records[0]["attributes"].as_array().unwrap();
+        assert!(attributes.contains(&json!({
+            "key": "subcommand",
+            "value": { "stringValue": "foo" }
+        })));
+        assert!(attributes.contains(&json!({
+            "key": "extras.key",
+            "value": { "stringValue": "value" }
+        })));
+    }
+
+    /// Test that the metrics endpoint selects the connection
+    #[test]
+    fn test_connection_from_config() {
+        let mut config = Config::default();
+        config.flox.metrics_endpoint = Some(Url::parse("file:///tmp/metrics.json").unwrap());
+        let connection: Box<FileConnection> = connection_from_config(&config)
+            .unwrap()
+            .into_any()
+            .downcast()
+            .unwrap();
+        assert_eq!(connection.path, PathBuf::from("/tmp/metrics.json"));
+
+        config.flox.metrics_endpoint = Some(Url::parse("ftp://example.com").unwrap());
+        assert!(connection_from_config(&config).is_err());
+    }
+
     /// Test that [Hub::try_guard] returns a guard as expected
     /// And that the guard flushes the metrics on drop when the buffer is expired.
     /// And that the guard does not flush the metrics when the buffer is not expired.
@@ -15,6 +15,9 @@ pub mod init;
 pub mod message;
 pub mod metrics;
 pub mod openers;
+pub mod otlp;
+pub mod package_browser;
+pub mod profile;
 pub mod search;
 
 pub static TERMINAL_STDERR: Lazy<Mutex<Stderr>> = Lazy::new(|| Mutex::new(std::io::stderr()));
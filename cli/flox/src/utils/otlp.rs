@@ -0,0 +1,374 @@
+//! Export [tracing] spans to an OpenTelemetry collector
+//!
+//! Exporting is enabled by setting `OTEL_EXPORTER_OTLP_ENDPOINT`
+//! (or `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`),
+//! e.g. to `http://localhost:4318` for a local collector.
+//! Spans of flox and the flox SDK are collected while the command runs
+//! and sent as OTLP/HTTP JSON once the command finishes,
+//! or right before `flox activate` replaces itself with a shell.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use flox_rust_sdk::flox::FLOX_VERSION;
+use log::debug;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use url::Url;
+use uuid::Uuid;
+
+use super::TRAILING_NETWORK_CALL_TIMEOUT;
+
+/// Base URL of the collector, `/v1/traces` is appended
+pub const OTLP_ENDPOINT_VAR: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+/// Full URL traces are sent to, takes precedence over [OTLP_ENDPOINT_VAR]
+pub const OTLP_TRACES_ENDPOINT_VAR: &str = "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT";
+
+static EXPORTER: Lazy<Mutex<Option<Exporter>>> = Lazy::new(|| Mutex::new(None));
+
+/// A span as recorded by the [OtlpTraceLayer]
+#[derive(Debug, Clone)]
+struct SpanRecord {
+    span_id: String,
+    parent_span_id: Option<String>,
+    name: String,
+    start: SystemTime,
+    end: Option<SystemTime>,
+    attributes: Vec<(String, Value)>,
+}
+
+/// Spans of the current invocation, waiting to be sent
+#[derive(Debug)]
+struct Exporter {
+    endpoint: Url,
+    trace_id: String,
+    open: HashMap<u64, SpanRecord>,
+    finished: Vec<SpanRecord>,
+}
+
+/// Determine the traces endpoint from the standard OpenTelemetry variables
+fn endpoint_from_env() -> Option<Url> {
+    let (url, append_path) = match std::env::var(OTLP_TRACES_ENDPOINT_VAR) {
+        Ok(url) if !url.is_empty() => (url, false),
+        _ => match std::env::var(OTLP_ENDPOINT_VAR) {
+            Ok(url) if !url.is_empty() => (url, true),
+            _ => return None,
+        },
+    };
+
+    let url = if append_path {
+        format!("{}/v1/traces", url.trim_end_matches('/'))
+    } else {
+        url
+    };
+
+    match Url::parse(&url) {
+        Ok(url) => Some(url),
+        Err(e) => {
+            debug!("Ignoring invalid OTLP endpoint {url}: {e}");
+            None
+        },
+    }
+}
+
+/// A [tracing_subscriber::Layer] that records the spans of flox and the SDK
+/// for export to an OpenTelemetry collector.
+///
+/// Spans of other crates are skipped,
+/// their children are attached to the closest recorded ancestor.
+pub struct OtlpTraceLayer {}
+
+impl OtlpTraceLayer {
+    /// Create the layer if an OTLP endpoint is configured in the environment
+    pub fn from_env() -> Option<Self> {
+        let endpoint = endpoint_from_env()?;
+        debug!("Exporting traces to {endpoint}");
+
+        *EXPORTER.lock().ok()? = Some(Exporter {
+            endpoint,
+            trace_id: Uuid::new_v4().simple().to_string(),
+            open: HashMap::new(),
+            finished: Vec::new(),
+        });
+        Some(OtlpTraceLayer {})
+    }
+}
+
+impl<S> tracing_subscriber::Layer<S> for OtlpTraceLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let metadata = attrs.metadata();
+        if !metadata.target().starts_with("flox") {
+            return;
+        }
+        let Ok(mut exporter) = EXPORTER.lock() else {
+            return;
+        };
+        let Some(exporter) = exporter.as_mut() else {
+            return;
+        };
+
+        let parent_span_id = ctx.span(id).and_then(|span| {
+            span.scope()
+                .skip(1)
+                .find_map(|parent| exporter.open.get(&parent.id().into_u64()))
+                .map(|parent| parent.span_id.clone())
+        });
+
+        let mut attributes = Vec::new();
+        attrs.record(&mut AttributeVisitor(&mut attributes));
+
+        exporter.open.insert(id.into_u64(), SpanRecord {
+            span_id: Uuid::new_v4().simple().to_string()[..16].to_string(),
+            parent_span_id,
+            name: metadata.name().to_string(),
+            start: SystemTime::now(),
+            end: None,
+            attributes,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        let Ok(mut exporter) = EXPORTER.lock() else {
+            return;
+        };
+        let Some(span) = exporter
+            .as_mut()
+            .and_then(|exporter| exporter.open.get_mut(&id.into_u64()))
+        else {
+            return;
+        };
+        values.record(&mut AttributeVisitor(&mut span.attributes));
+    }
+
+    fn on_close(&self, id: Id, _ctx: Context<'_, S>) {
+        let Ok(mut exporter) = EXPORTER.lock() else {
+            return;
+        };
+        let Some(exporter) = exporter.as_mut() else {
+            return;
+        };
+        if let Some(mut span) = exporter.open.remove(&id.into_u64()) {
+            span.end = Some(SystemTime::now());
+            exporter.finished.push(span);
+        }
+    }
+}
+
+/// Collects span fields as OTLP attributes
+struct AttributeVisitor<'a>(&'a mut Vec<(String, Value)>);
+
+impl AttributeVisitor<'_> {
+    fn set(&mut self, field: &Field, value: Value) {
+        self.0.retain(|(key, _)| key != field.name());
+        self.0.push((field.name().to_string(), value));
+    }
+}
+
+impl Visit for AttributeVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field, json!({ "stringValue": value }));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.set(field, json!({ "intValue": value.to_string() }));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.set(field, json!({ "intValue": value.to_string() }));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.set(field, json!({ "boolValue": value }));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.set(field, json!({ "stringValue": format!("{value:?}") }));
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
+
+/// Convert spans to an OTLP `ExportTraceServiceRequest`
+///
+/// Spans that are still open end at `now`.
+fn payload(trace_id: &str, spans: &[SpanRecord], now: SystemTime) -> Value {
+    let spans = spans
+        .iter()
+        .map(|span| {
+            let attributes = span
+                .attributes
+                .iter()
+                .map(|(key, value)| json!({ "key": key, "value": value }))
+                .collect::<Vec<_>>();
+            let mut otlp_span = json!({
+                "traceId": trace_id,
+                "spanId": span.span_id,
+                "name": span.name,
+                // SPAN_KIND_INTERNAL
+                "kind": 1,
+                "startTimeUnixNano": unix_nanos(span.start),
+                "endTimeUnixNano": unix_nanos(span.end.unwrap_or(now)),
+                "attributes": attributes,
+            });
+            if let Some(parent) = &span.parent_span_id {
+                otlp_span["parentSpanId"] = json!(parent);
+            }
+            otlp_span
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    { "key": "service.name", "value": { "stringValue": "flox-cli" } },
+                    { "key": "service.version", "value": { "stringValue": &*FLOX_VERSION } },
+                ],
+            },
+            "scopeSpans": [{
+                "scope": { "name": "flox" },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+/// Take all recorded spans and stop recording
+///
+/// Returns the endpoint and payload to send, if any spans were recorded.
+fn take_export() -> Option<(Url, Value)> {
+    let exporter = EXPORTER.lock().ok()?.take()?;
+    let mut spans = exporter.finished;
+    spans.extend(exporter.open.into_values());
+    if spans.is_empty() {
+        return None;
+    }
+    let payload = payload(&exporter.trace_id, &spans, SystemTime::now());
+    Some((exporter.endpoint, payload))
+}
+
+/// Send all recorded spans to the collector
+///
+/// Spans that are still open are sent as ending now,
+/// which allows exporting a trace before `exec`ing into another process.
+/// Exporting stops afterwards, so if `exec` fails,
+/// [TraceExportGuard] does not send the same spans again.
+pub fn flush_traces() {
+    let Some((endpoint, payload)) = take_export() else {
+        return;
+    };
+
+    // The blocking client must not run on an async runtime thread
+    let result = std::thread::spawn(move || send(&endpoint, &payload)).join();
+    match result {
+        Ok(Err(e)) => debug!("Failed to export traces: {e}"),
+        Err(_) => debug!("Failed to export traces: sender panicked"),
+        Ok(Ok(())) => {},
+    }
+}
+
+fn send(endpoint: &Url, payload: &Value) -> Result<()> {
+    debug!("Sending traces to {endpoint}");
+    reqwest::blocking::Client::new()
+        .post(endpoint.clone())
+        .header("user-agent", format!("flox-cli/{}", &*FLOX_VERSION))
+        .json(payload)
+        .timeout(TRAILING_NETWORK_CALL_TIMEOUT)
+        .send()?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Flushes recorded spans when dropped
+pub struct TraceExportGuard;
+
+impl Drop for TraceExportGuard {
+    fn drop(&mut self) {
+        flush_traces();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn payload_links_spans() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+        let spans = [
+            SpanRecord {
+                span_id: "0000000000000001".to_string(),
+                parent_span_id: None,
+                name: "install".to_string(),
+                start,
+                end: Some(start + Duration::from_secs(2)),
+                attributes: vec![(
+                    "environment_type".to_string(),
+                    json!({ "stringValue": "path" }),
+                )],
+            },
+            SpanRecord {
+                span_id: "0000000000000002".to_string(),
+                parent_span_id: Some("0000000000000001".to_string()),
+                name: "pkgdb".to_string(),
+                start,
+                end: None,
+                attributes: vec![],
+            },
+        ];
+
+        let payload = payload("trace", &spans, start + Duration::from_secs(3));
+        let spans = &payload["resourceSpans"][0]["scopeSpans"][0]["spans"];
+
+        assert_eq!(spans[0]["traceId"], "trace");
+        assert_eq!(spans[0]["endTimeUnixNano"], "3000000000");
+        assert!(spans[0].get("parentSpanId").is_none());
+        assert_eq!(spans[0]["attributes"][0]["key"], "environment_type");
+        assert_eq!(spans[1]["parentSpanId"], "0000000000000001");
+        // open spans end at the time of export
+        assert_eq!(spans[1]["endTimeUnixNano"], "4000000000");
+    }
+
+    #[test]
+    fn spans_are_exported_once() {
+        let start = SystemTime::UNIX_EPOCH;
+        *EXPORTER.lock().unwrap() = Some(Exporter {
+            endpoint: Url::parse("http://localhost:4318/v1/traces").unwrap(),
+            trace_id: "trace".to_string(),
+            open: HashMap::from([(1, SpanRecord {
+                span_id: "0000000000000001".to_string(),
+                parent_span_id: None,
+                name: "activate".to_string(),
+                start,
+                end: None,
+                attributes: vec![],
+            })]),
+            finished: vec![],
+        });
+
+        let (_, payload) = take_export().expect("spans should be exported");
+        assert_eq!(
+            payload["resourceSpans"][0]["scopeSpans"][0]["spans"][0]["name"],
+            "activate"
+        );
+        // e.g. dropping the guard after a failed exec
+        assert!(take_export().is_none());
+    }
+}
@@ -0,0 +1,515 @@
+//! A full-screen browser for search results used by `flox search --interactive`
+//!
+//! The browser lists the packages found by a search,
+//! narrows them down as the user types,
+//! shows the details `flox show` would print for the highlighted package,
+//! and returns the packages the user selected for installation.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{self, Write};
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+use flox_rust_sdk::models::search::{SearchResult, Subtree};
+
+use super::search::{DEFAULT_DESCRIPTION, SEARCH_INPUT_SEPARATOR};
+
+/// Packages are listed left of the details if the terminal is at least this wide
+const MIN_WIDTH_FOR_DETAILS: usize = 60;
+
+const HELP: &str = "↑/↓ move  tab select  enter install  esc cancel";
+
+/// A package found by the search, with all of its versions
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageEntry {
+    /// The package as it is passed to `flox install`,
+    /// i.e. `<rel_path>` or `<input>:<rel_path>` if multiple inputs provide it
+    pub name: String,
+    pub description: Option<String>,
+    pub license: Option<String>,
+    /// Versions of the package, in the order the search ranked them
+    pub versions: Vec<String>,
+    pub unfree: bool,
+    pub broken: bool,
+}
+
+impl PackageEntry {
+    /// Group search results by package, keeping the order of the results
+    ///
+    /// The search has to be run without deduplication to find all versions.
+    pub fn from_search_results(results: Vec<SearchResult>) -> Vec<PackageEntry> {
+        let mut inputs_by_path: HashMap<&[String], HashSet<&str>> = HashMap::new();
+        for result in &results {
+            inputs_by_path
+                .entry(&result.rel_path)
+                .or_default()
+                .insert(&result.input);
+        }
+        let names = results
+            .iter()
+            .map(|result| {
+                let rel_path = result.rel_path.join(".");
+                if inputs_by_path[result.rel_path.as_slice()].len() > 1 {
+                    format!("{}{SEARCH_INPUT_SEPARATOR}{rel_path}", result.input)
+                } else {
+                    rel_path
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut entries: Vec<PackageEntry> = Vec::new();
+        for (result, name) in results.into_iter().zip(names) {
+            let index = match entries.iter().position(|entry| entry.name == name) {
+                Some(index) => index,
+                None => {
+                    entries.push(PackageEntry {
+                        name,
+                        description: result.description.map(|d| d.replace('\n', " ")),
+                        license: result.license,
+                        versions: Vec::new(),
+                        unfree: result.unfree.unwrap_or_default(),
+                        broken: result.broken.unwrap_or_default(),
+                    });
+                    entries.len() - 1
+                },
+            };
+
+            // Like `flox show`, skip the "latest" alias of catalog packages
+            let is_latest = result.subtree == Subtree::Catalog
+                && result.abs_path.last().is_some_and(|attr| attr == "latest");
+            if let Some(version) = result.version.filter(|_| !is_latest) {
+                if !entries[index].versions.contains(&version) {
+                    entries[index].versions.push(version);
+                }
+            }
+        }
+        entries
+    }
+
+    /// Whether every word of `filter` occurs in the name or description
+    fn matches(&self, filter: &str) -> bool {
+        let name = self.name.to_lowercase();
+        let description = self.description.as_deref().unwrap_or("").to_lowercase();
+        filter
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| name.contains(word) || description.contains(word))
+    }
+}
+
+/// What the user decided in the browser
+#[derive(Debug, PartialEq)]
+pub enum BrowserOutcome {
+    /// Install the packages with these names
+    Install(Vec<String>),
+    Cancel,
+}
+
+/// The state of the package browser, independent of the terminal
+#[derive(Debug)]
+pub struct PackageBrowser {
+    title: String,
+    entries: Vec<PackageEntry>,
+    filter: String,
+    /// Indices of the entries matching [Self::filter]
+    visible: Vec<usize>,
+    /// Position of the highlighted entry in [Self::visible]
+    cursor: usize,
+    /// Position of the first listed entry in [Self::visible]
+    offset: usize,
+    /// Indices of the selected entries
+    selected: BTreeSet<usize>,
+}
+
+impl PackageBrowser {
+    pub fn new(title: impl Into<String>, entries: Vec<PackageEntry>) -> Self {
+        let mut browser = PackageBrowser {
+            title: title.into(),
+            visible: Vec::new(),
+            entries,
+            filter: String::new(),
+            cursor: 0,
+            offset: 0,
+            selected: BTreeSet::new(),
+        };
+        browser.apply_filter();
+        browser
+    }
+
+    /// Run the browser on the alternate screen of the terminal until the user
+    /// installs or cancels
+    ///
+    /// The browser is drawn to stderr, which has to be a terminal.
+    pub fn run(mut self) -> Result<BrowserOutcome> {
+        let mut stderr = io::stderr();
+        let _guard = TerminalGuard::enter(&mut stderr)?;
+
+        loop {
+            let (width, height) = terminal::size()?;
+            self.draw(&mut stderr, width.into(), height.into())?;
+            stderr.flush()?;
+
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => {
+                    let list_height = Self::list_height(height.into());
+                    if let Some(outcome) = self.handle_key(key, list_height) {
+                        return Ok(outcome);
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    /// Update the state for a key press,
+    /// returning an outcome if the browser should be closed
+    fn handle_key(&mut self, key: KeyEvent, list_height: usize) -> Option<BrowserOutcome> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return Some(BrowserOutcome::Cancel),
+            KeyCode::Char('c') if ctrl => return Some(BrowserOutcome::Cancel),
+            KeyCode::Enter => {
+                let mut selected = self.selected.iter().copied().collect::<Vec<_>>();
+                if selected.is_empty() {
+                    selected.extend(self.current());
+                }
+                if !selected.is_empty() {
+                    let names = selected
+                        .into_iter()
+                        .map(|index| self.entries[index].name.clone())
+                        .collect();
+                    return Some(BrowserOutcome::Install(names));
+                }
+            },
+            KeyCode::Up => self.move_cursor(-1),
+            KeyCode::Char('p') if ctrl => self.move_cursor(-1),
+            KeyCode::Down => self.move_cursor(1),
+            KeyCode::Char('n') if ctrl => self.move_cursor(1),
+            KeyCode::PageUp => self.move_cursor(-(list_height as isize)),
+            KeyCode::PageDown => self.move_cursor(list_height as isize),
+            KeyCode::Tab => {
+                self.toggle_current();
+                self.move_cursor(1);
+            },
+            KeyCode::BackTab => {
+                self.toggle_current();
+                self.move_cursor(-1);
+            },
+            KeyCode::Backspace => {
+                self.filter.pop();
+                self.apply_filter();
+            },
+            KeyCode::Char('u') if ctrl => {
+                self.filter.clear();
+                self.apply_filter();
+            },
+            KeyCode::Char(c) if !ctrl => {
+                self.filter.push(c);
+                self.apply_filter();
+            },
+            _ => {},
+        }
+        self.scroll_to_cursor(list_height);
+        None
+    }
+
+    /// The index of the highlighted entry
+    fn current(&self) -> Option<usize> {
+        self.visible.get(self.cursor).copied()
+    }
+
+    fn apply_filter(&mut self) {
+        self.visible = (0..self.entries.len())
+            .filter(|index| self.entries[*index].matches(&self.filter))
+            .collect();
+        self.cursor = 0;
+        self.offset = 0;
+    }
+
+    fn move_cursor(&mut self, by: isize) {
+        let last = self.visible.len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(by).min(last);
+    }
+
+    fn toggle_current(&mut self) {
+        if let Some(index) = self.current() {
+            if !self.selected.remove(&index) {
+                self.selected.insert(index);
+            }
+        }
+    }
+
+    fn scroll_to_cursor(&mut self, list_height: usize) {
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if self.cursor >= self.offset + list_height {
+            self.offset = self.cursor + 1 - list_height;
+        }
+    }
+
+    /// Lines available for the list,
+    /// below the filter and a separator and above the help line
+    fn list_height(height: usize) -> usize {
+        height.saturating_sub(3).max(1)
+    }
+
+    fn draw(&self, out: &mut impl Write, width: usize, height: usize) -> Result<()> {
+        let list_height = Self::list_height(height);
+        let (list_width, details_width) = if width >= MIN_WIDTH_FOR_DETAILS {
+            let list_width = width * 11 / 20;
+            (list_width, width - list_width - 3)
+        } else {
+            (width, 0)
+        };
+
+        queue!(out, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+
+        let count = format!(
+            "{}/{} ({} selected)",
+            self.visible.len(),
+            self.entries.len(),
+            self.selected.len()
+        );
+        let prompt = format!("{} > {}", self.title, self.filter);
+        let padding = width.saturating_sub(prompt.chars().count() + count.chars().count());
+        queue!(
+            out,
+            Print(truncate(&prompt, width)),
+            Print(" ".repeat(padding)),
+            SetAttribute(Attribute::Dim),
+            Print(if padding > 0 { count } else { String::new() }),
+            SetAttribute(Attribute::Reset),
+            cursor::MoveTo(0, 1),
+            Print("─".repeat(width)),
+        )?;
+
+        let details = self
+            .current()
+            .map(|index| details_lines(&self.entries[index], details_width))
+            .unwrap_or_default();
+
+        for line in 0..list_height {
+            queue!(out, cursor::MoveTo(0, (line + 2) as u16))?;
+
+            let position = self.offset + line;
+            if let Some(index) = self.visible.get(position).copied() {
+                let marker = if self.selected.contains(&index) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                let text = format!(" {marker} {}", self.entries[index].name);
+                let text = format!("{:<list_width$}", truncate(&text, list_width));
+                if position == self.cursor {
+                    queue!(
+                        out,
+                        SetAttribute(Attribute::Reverse),
+                        Print(text),
+                        SetAttribute(Attribute::Reset)
+                    )?;
+                } else {
+                    queue!(out, Print(text))?;
+                }
+            } else if line == 0 {
+                queue!(
+                    out,
+                    Print(truncate("  No packages match the filter", list_width))
+                )?;
+            }
+
+            if details_width > 0 {
+                queue!(
+                    out,
+                    cursor::MoveTo(list_width as u16, (line + 2) as u16),
+                    Print(" │ "),
+                    Print(details.get(line).map(String::as_str).unwrap_or_default()),
+                )?;
+            }
+        }
+
+        queue!(
+            out,
+            cursor::MoveTo(0, height.saturating_sub(1) as u16),
+            SetAttribute(Attribute::Dim),
+            Print(truncate(HELP, width)),
+            SetAttribute(Attribute::Reset),
+        )?;
+        Ok(())
+    }
+}
+
+/// The details of a package as shown by `flox show`, wrapped to `width`
+fn details_lines(entry: &PackageEntry, width: usize) -> Vec<String> {
+    if width == 0 {
+        return Vec::new();
+    }
+    let mut lines = vec![truncate(&entry.name, width), String::new()];
+    lines.extend(wrap(
+        entry.description.as_deref().unwrap_or(DEFAULT_DESCRIPTION),
+        width,
+    ));
+    lines.push(String::new());
+
+    let mut license = entry.license.clone().unwrap_or("N/A".to_string());
+    if entry.unfree {
+        license.push_str(" (unfree)");
+    }
+    if entry.broken {
+        license.push_str(" (broken)");
+    }
+    lines.extend(wrap(&format!("License: {license}"), width));
+
+    let versions = if entry.versions.is_empty() {
+        "N/A".to_string()
+    } else {
+        entry.versions.join(", ")
+    };
+    lines.extend(wrap(&format!("Versions: {versions}"), width));
+    lines
+}
+
+/// Break `text` into lines of at most `width` characters at whitespace
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+        .into_iter()
+        .map(|line| truncate(&line, width))
+        .collect()
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+/// Switches the terminal to raw mode and the alternate screen,
+/// and restores it when dropped, also if the browser fails
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter(out: &mut impl Write) -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        let guard = TerminalGuard;
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stderr(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn result(input: &str, rel_path: &str, version: &str) -> SearchResult {
+        SearchResult {
+            input: input.to_string(),
+            rel_path: rel_path.split('.').map(String::from).collect(),
+            version: Some(version.to_string()),
+            description: Some(format!("{rel_path}\ndescription")),
+            ..Default::default()
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn groups_versions_and_disambiguates_inputs() {
+        let entries = PackageEntry::from_search_results(vec![
+            result("nixpkgs", "hello", "2.12.1"),
+            result("nixpkgs", "python3Packages.hello", "1.0"),
+            result("nixpkgs", "hello", "2.12"),
+            result("other", "python3Packages.hello", "1.0"),
+            result("nixpkgs", "hello", "2.12.1"),
+        ]);
+
+        let names = entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![
+            "hello",
+            "nixpkgs:python3Packages.hello",
+            "other:python3Packages.hello"
+        ]);
+        assert_eq!(entries[0].versions, vec!["2.12.1", "2.12"]);
+        assert_eq!(entries[0].description.as_deref(), Some("hello description"));
+    }
+
+    #[test]
+    fn filters_selects_and_installs() {
+        let entries = PackageEntry::from_search_results(vec![
+            result("nixpkgs", "hello", "2.12.1"),
+            result("nixpkgs", "hello-wayland", "1.0"),
+            result("nixpkgs", "python3Packages.hello", "1.0"),
+        ]);
+        let mut browser = PackageBrowser::new("hello", entries);
+
+        // without a selection, the highlighted package is installed
+        browser.handle_key(key(KeyCode::Down), 10);
+        assert_eq!(
+            browser.handle_key(key(KeyCode::Enter), 10),
+            Some(BrowserOutcome::Install(vec!["hello-wayland".to_string()]))
+        );
+
+        browser.handle_key(key(KeyCode::Up), 10);
+        browser.handle_key(key(KeyCode::Tab), 10);
+        for c in "python".chars() {
+            browser.handle_key(key(KeyCode::Char(c)), 10);
+        }
+        assert_eq!(browser.visible, vec![2]);
+        browser.handle_key(key(KeyCode::Tab), 10);
+
+        assert_eq!(
+            browser.handle_key(key(KeyCode::Enter), 10),
+            Some(BrowserOutcome::Install(vec![
+                "hello".to_string(),
+                "python3Packages.hello".to_string()
+            ]))
+        );
+        assert_eq!(
+            browser.handle_key(key(KeyCode::Esc), 10),
+            Some(BrowserOutcome::Cancel)
+        );
+    }
+
+    #[test]
+    fn draws_list_and_details() {
+        let entries = PackageEntry::from_search_results(vec![
+            result("nixpkgs", "hello", "2.12.1"),
+            result("nixpkgs", "hello", "2.12"),
+        ]);
+        let browser = PackageBrowser::new("hello", entries);
+
+        let mut out = Vec::new();
+        browser.draw(&mut out, 80, 10).unwrap();
+        let screen = String::from_utf8(out).unwrap();
+        assert!(screen.contains("[ ] hello"));
+        assert!(screen.contains("Versions: 2.12.1, 2.12"));
+        assert!(screen.contains("License: N/A"));
+    }
+}
@@ -0,0 +1,274 @@
+//! Timing profiles of flox commands
+//!
+//! While profiling is enabled the [ProfileLayer] records
+//! when each span of flox and the flox SDK starts and ends.
+//! The recorded phases can then be printed as a summary
+//! or written as a Chrome trace (`chrome://tracing`, Perfetto).
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use serde_json::json;
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context as LayerContext;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Enables profiling, the value is either `1` or a path to write a Chrome trace to
+pub const FLOX_PROFILE_VAR: &str = "FLOX_PROFILE";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static PROFILE: Lazy<Mutex<Profile>> = Lazy::new(|| {
+    Mutex::new(Profile {
+        start: Instant::now(),
+        phases: Vec::new(),
+        open: HashMap::new(),
+    })
+});
+
+/// A span recorded by the [ProfileLayer]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phase {
+    pub name: String,
+    /// Index of the enclosing phase
+    pub parent: Option<usize>,
+    /// Time since profiling was enabled
+    pub start: Duration,
+    /// [None] while the phase is running
+    pub duration: Option<Duration>,
+}
+
+#[derive(Debug)]
+struct Profile {
+    start: Instant,
+    phases: Vec<Phase>,
+    /// Indices into `phases` of spans that have not been closed yet
+    open: HashMap<u64, usize>,
+}
+
+/// Where to report the profile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileOutput {
+    /// Print a summary to stderr
+    Summary,
+    /// Write a Chrome trace JSON file
+    ChromeTrace(std::path::PathBuf),
+}
+
+impl ProfileOutput {
+    /// Read the output from `$FLOX_PROFILE`
+    pub fn from_env() -> Option<Self> {
+        match std::env::var(FLOX_PROFILE_VAR).ok()?.as_str() {
+            "" | "0" | "false" => None,
+            "1" | "true" => Some(ProfileOutput::Summary),
+            path => Some(ProfileOutput::ChromeTrace(path.into())),
+        }
+    }
+}
+
+/// Start recording spans
+pub fn enable_profiling() {
+    if let Ok(mut profile) = PROFILE.lock() {
+        profile.start = Instant::now();
+        profile.phases.clear();
+        profile.open.clear();
+    }
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// The phases recorded so far, phases that are still running are included
+/// with their duration up to now
+pub fn recorded_phases() -> Vec<Phase> {
+    let Ok(profile) = PROFILE.lock() else {
+        return Vec::new();
+    };
+    let now = profile.start.elapsed();
+    profile
+        .phases
+        .iter()
+        .cloned()
+        .map(|mut phase| {
+            phase
+                .duration
+                .get_or_insert(now.saturating_sub(phase.start));
+            phase
+        })
+        .collect()
+}
+
+/// Report the recorded phases
+pub fn report(output: &ProfileOutput) -> Result<()> {
+    let phases = recorded_phases();
+    match output {
+        ProfileOutput::Summary => {
+            let mut stderr = crate::utils::TERMINAL_STDERR.lock().unwrap();
+            std::io::Write::write_all(&mut *stderr, format_summary(&phases).as_bytes())?;
+        },
+        ProfileOutput::ChromeTrace(path) => write_chrome_trace(&phases, path)?,
+    }
+    Ok(())
+}
+
+/// Render phases as an indented table
+pub fn format_summary(phases: &[Phase]) -> String {
+    let depth = |mut index: usize| {
+        let mut depth = 0;
+        while let Some(parent) = phases[index].parent {
+            depth += 1;
+            index = parent;
+        }
+        depth
+    };
+
+    let rows = phases
+        .iter()
+        .enumerate()
+        .map(|(index, phase)| {
+            (
+                format!("{}{}", "  ".repeat(depth(index)), phase.name),
+                phase.duration.unwrap_or_default(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+
+    let mut summary = String::from("Profile:\n");
+    for (name, duration) in rows {
+        let _ = writeln!(
+            summary,
+            "  {name:<width$}  {:>9.3}ms",
+            duration.as_secs_f64() * 1000.0
+        );
+    }
+    summary
+}
+
+/// Write phases in the Chrome trace event format
+pub fn write_chrome_trace(phases: &[Phase], path: &Path) -> Result<()> {
+    let pid = std::process::id();
+    let events = phases
+        .iter()
+        .map(|phase| {
+            json!({
+                "name": phase.name,
+                "cat": "flox",
+                // complete event
+                "ph": "X",
+                "ts": phase.start.as_micros() as u64,
+                "dur": phase.duration.unwrap_or_default().as_micros() as u64,
+                "pid": pid,
+                "tid": 1,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let trace = json!({ "traceEvents": events, "displayTimeUnit": "ms" });
+    std::fs::write(path, serde_json::to_string_pretty(&trace)?)
+        .with_context(|| format!("could not write profile to {}", path.display()))?;
+    Ok(())
+}
+
+/// A [tracing_subscriber::Layer] that records span timings while profiling is enabled
+pub struct ProfileLayer {}
+
+impl ProfileLayer {
+    pub fn new() -> Self {
+        ProfileLayer {}
+    }
+}
+
+impl<S> tracing_subscriber::Layer<S> for ProfileLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: LayerContext<'_, S>) {
+        if !ENABLED.load(Ordering::Relaxed) || !attrs.metadata().target().starts_with("flox") {
+            return;
+        }
+        let Ok(mut profile) = PROFILE.lock() else {
+            return;
+        };
+
+        let parent = ctx.span(id).and_then(|span| {
+            span.scope()
+                .skip(1)
+                .find_map(|parent| profile.open.get(&parent.id().into_u64()).copied())
+        });
+        let phase = Phase {
+            name: attrs.metadata().name().to_string(),
+            parent,
+            start: profile.start.elapsed(),
+            duration: None,
+        };
+        profile.phases.push(phase);
+        let index = profile.phases.len() - 1;
+        profile.open.insert(id.into_u64(), index);
+    }
+
+    fn on_close(&self, id: Id, _ctx: LayerContext<'_, S>) {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return;
+        }
+        let Ok(mut profile) = PROFILE.lock() else {
+            return;
+        };
+        let Some(index) = profile.open.remove(&id.into_u64()) else {
+            return;
+        };
+        let end = profile.start.elapsed();
+        let phase = &mut profile.phases[index];
+        phase.duration = Some(end.saturating_sub(phase.start));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phases() -> Vec<Phase> {
+        vec![
+            Phase {
+                name: "activate".to_string(),
+                parent: None,
+                start: Duration::ZERO,
+                duration: Some(Duration::from_millis(1500)),
+            },
+            Phase {
+                name: "build".to_string(),
+                parent: Some(0),
+                start: Duration::from_millis(10),
+                duration: Some(Duration::from_millis(1200)),
+            },
+        ]
+    }
+
+    #[test]
+    fn summary_indents_nested_phases() {
+        let summary = format_summary(&phases());
+        assert_eq!(
+            summary,
+            "Profile:\n  activate   1500.000ms\n    build    1200.000ms\n"
+        );
+    }
+
+    #[test]
+    fn chrome_trace_uses_microseconds() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trace.json");
+        write_chrome_trace(&phases(), &path).unwrap();
+
+        let trace: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let build = &trace["traceEvents"][1];
+        assert_eq!(build["name"], "build");
+        assert_eq!(build["ph"], "X");
+        assert_eq!(build["ts"], 10_000);
+        assert_eq!(build["dur"], 1_200_000);
+    }
+}
@@ -117,6 +117,14 @@ public:
    */
   std::optional<unsigned> priority;
 
+  /**
+   * Exclude the package's group from upgrades.
+   *
+   * This is only recorded here, held packages are skipped by `flox upgrade`
+   * and it does not affect locking.
+   */
+  std::optional<bool> held;
+
   /**
    * @brief Ensure that a raw descriptor's fields are valid or
    *        throws an exception if the descriptor is invalid.
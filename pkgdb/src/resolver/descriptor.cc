@@ -54,6 +54,7 @@ ManifestDescriptorRaw::clear()
   this->pkgGroup          = std::nullopt;
   this->packageRepository = std::nullopt;
   this->priority          = std::nullopt;
+  this->held              = std::nullopt;
 }
 
 
@@ -413,6 +414,19 @@ from_json( const nlohmann::json & jfrom, ManifestDescriptorRaw & descriptor )
                 flox::extract_json_errmsg( e ) );
             }
         }
+      else if ( key == "held" )
+        {
+          try
+            {
+              value.get_to( descriptor.held );
+            }
+          catch ( nlohmann::json::exception & e )
+            {
+              throw ParseManifestDescriptorRawException(
+                "couldn't interpret field 'held'",
+                flox::extract_json_errmsg( e ) );
+            }
+        }
       else
         {
           throw ParseManifestDescriptorRawException(
@@ -459,6 +473,7 @@ to_json( nlohmann::json & jto, const ManifestDescriptorRaw & descriptor )
     {
       jto["priority"] = *descriptor.priority;
     }
+  if ( descriptor.held.has_value() ) { jto["held"] = *descriptor.held; }
 }
 
 /* -------------------------------------------------------------------------- */